@@ -0,0 +1,174 @@
+// Compares the price-level book with the skip list of orders it replaced, on
+// one side of a book with 100k resting orders. Run with `cargo bench`.
+use std::time::{Duration, Instant};
+
+use rusty_prism::exchange::book::BookSide;
+use rusty_prism::order::{Order, Side};
+use rusty_prism::price::Price;
+use skiplist::ordered_skiplist::OrderedSkipList;
+
+const RESTING_ORDERS: usize = 100_000;
+const PRICE_LEVELS: u32 = 500;
+const LOOKUPS: usize = 100;
+const CANCELS: usize = 100;
+const FILLS: usize = 10_000;
+const DEPTH_SNAPSHOTS: usize = 10;
+
+// xorshift, so every run works on the same orders
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+fn resting_orders() -> Vec<Order> {
+    let mut random = Random(0x2545f4914f6cdd1d);
+    (0..RESTING_ORDERS)
+        .map(|_| {
+            let price = Price::from(100 + random.next(PRICE_LEVELS as usize) as i64);
+            let quantity = 100 * (1 + random.next(10) as u32);
+            Order::new("AAPL", quantity, price, Side::Sell)
+        })
+        .collect()
+}
+
+fn time(mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    run();
+    start.elapsed()
+}
+
+fn report(name: &str, operations: usize, skip_list: Duration, price_levels: Duration) {
+    let per_operation = |elapsed: Duration| elapsed.as_nanos() as f64 / operations as f64;
+    println!(
+        "{:<14} {:>8} ops {:>14.0} ns/op {:>14.0} ns/op {:>10.1}x",
+        name,
+        operations,
+        per_operation(skip_list),
+        per_operation(price_levels),
+        per_operation(skip_list) / per_operation(price_levels).max(1.0),
+    );
+}
+
+// Aggregates a side the way depth was taken from the skip list, by walking
+// every order
+fn skip_list_depth(orders: &OrderedSkipList<Order>) -> Vec<(Price, u64)> {
+    let mut levels: Vec<(Price, u64)> = Vec::new();
+    for order in orders.iter() {
+        match levels.last_mut() {
+            Some((price, quantity)) if *price == order.price => *quantity += order.quantity as u64,
+            _ => levels.push((order.price, order.quantity as u64)),
+        }
+    }
+    levels
+}
+
+fn main() {
+    let orders = resting_orders();
+    let mut random = Random(0x9e3779b97f4a7c15);
+    let picks: Vec<u32> = (0..CANCELS.max(LOOKUPS))
+        .map(|_| orders[random.next(orders.len())].id)
+        .collect();
+
+    println!(
+        "{} resting orders over {} price levels",
+        RESTING_ORDERS, PRICE_LEVELS
+    );
+    println!(
+        "{:<14} {:>12} {:>20} {:>20} {:>11}",
+        "operation", "", "skip list", "price levels", "speedup"
+    );
+
+    let mut skip_list = OrderedSkipList::new();
+    let mut book = BookSide::new(Side::Sell);
+    report(
+        "add",
+        RESTING_ORDERS,
+        time(|| {
+            orders
+                .iter()
+                .for_each(|order| skip_list.insert(order.clone()))
+        }),
+        time(|| orders.iter().for_each(|order| book.insert(order.clone()))),
+    );
+
+    // The skip list can only find an order by id by walking it
+    report(
+        "find by id",
+        LOOKUPS,
+        time(|| {
+            for id in &picks[..LOOKUPS] {
+                std::hint::black_box(skip_list.iter().find(|order| order.id == *id));
+            }
+        }),
+        time(|| {
+            for id in &picks[..LOOKUPS] {
+                std::hint::black_box(book.get(*id));
+            }
+        }),
+    );
+
+    report(
+        "depth",
+        DEPTH_SNAPSHOTS,
+        time(|| {
+            for _ in 0..DEPTH_SNAPSHOTS {
+                std::hint::black_box(skip_list_depth(&skip_list));
+            }
+        }),
+        time(|| {
+            for _ in 0..DEPTH_SNAPSHOTS {
+                std::hint::black_box(book.levels().collect::<Vec<_>>());
+            }
+        }),
+    );
+
+    // A partial fill at the top of the book: pop and re-insert for the skip
+    // list, in place for the price levels
+    report(
+        "fill at top",
+        FILLS,
+        time(|| {
+            for _ in 0..FILLS {
+                if let Some(mut order) = skip_list.pop_front() {
+                    order.quantity -= 1;
+                    if order.quantity > 0 {
+                        skip_list.insert(order);
+                    }
+                }
+            }
+        }),
+        time(|| {
+            for _ in 0..FILLS {
+                if let Some(id) = book.front().map(|order| order.id) {
+                    book.fill(id, 1);
+                }
+            }
+        }),
+    );
+
+    // Cancelling by order id, as a cancel request does
+    report(
+        "cancel",
+        CANCELS,
+        time(|| {
+            for id in &picks[..CANCELS] {
+                let order = skip_list.iter().find(|order| order.id == *id).cloned();
+                if let Some(order) = order {
+                    skip_list.remove(&order);
+                }
+            }
+        }),
+        time(|| {
+            for id in &picks[..CANCELS] {
+                book.remove(*id);
+            }
+        }),
+    );
+    assert_eq!(skip_list.len(), book.len());
+}
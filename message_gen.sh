@@ -1,4 +1,7 @@
 echo "Generating $2 FIX messages to $1..."
+if [ -n "$3" ]; then
+    RANDOM=$3  # Seed, so the same messages are generated every time
+fi
 tickers=("AAPL" "GOOGL" "MSFT" "AMZN" "FB")  # List of tickers
 sides=("1" "2")  # Buy (1) and Sell (2) sides
 
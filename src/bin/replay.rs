@@ -0,0 +1,89 @@
+// Rebuilds the books and executions of the exchange from its journal, on top
+// of the snapshot taken before it if there is one, and prints them:
+//
+//   cargo run --bin replay -- [journal.log] [snapshot.dat]
+//
+// The exchange is configured from the same files as the server, which have to
+// be the ones the runs were started with.
+#[macro_use]
+extern crate rusty_prism;
+use rusty_prism::exchange::exchange::{Exchange, ExchangeConfig};
+use rusty_prism::exchange::journal;
+use rusty_prism::exchange::snapshot::Snapshot;
+use rusty_prism::order::Side;
+use std::{env, fs};
+
+fn print_exchange(exchange: &Exchange) {
+    let mut symbols: Vec<String> = exchange
+        .get_reference_data()
+        .get_instruments()
+        .iter()
+        .map(|instrument| instrument.symbol.to_owned())
+        .collect();
+    symbols.sort();
+    for symbol in symbols {
+        println!("{} {}", symbol, exchange.get_trading_phase(&symbol));
+        // Every resting order, hidden ones included
+        for order in exchange.get_open_orders(&symbol) {
+            let side = match order.side {
+                Side::Buy => "Bid",
+                Side::Sell => "Offer",
+            };
+            println!(
+                "  {} {} {} @ {} {}{}",
+                side,
+                order.id,
+                order.quantity,
+                order.price,
+                order.participant,
+                if order.hidden { " hidden" } else { "" }
+            );
+        }
+    }
+    for (buy_order_id, sell_order_id, price, quantity) in exchange.get_fills() {
+        println!(
+            "Fill buy {} sell {} {} @ {}",
+            buy_order_id, sell_order_id, quantity, price
+        );
+    }
+}
+
+fn main() {
+    let journal_file = env::args().nth(1).unwrap_or("./journal.log".to_string());
+    let config = match ExchangeConfig::load(
+        "./instruments.cfg",
+        "./risk.cfg",
+        "./schedule.cfg",
+        "./fees.cfg",
+    ) {
+        Ok(config) => config,
+        Err(e) => {
+            log_error!("{}", e);
+            return;
+        }
+    };
+    let snapshot_file = env::args().nth(2).unwrap_or("./snapshot.dat".to_string());
+    let snapshot = match fs::metadata(&snapshot_file) {
+        Ok(_) => match Snapshot::load(&snapshot_file) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                log_error!("{}", e);
+                return;
+            }
+        },
+        Err(_) => None,
+    };
+    let exchanges = match journal::read_entries(&journal_file)
+        .and_then(|entries| journal::replay(snapshot.as_ref(), &entries, &config))
+    {
+        Ok(exchanges) => exchanges,
+        Err(e) => {
+            log_error!("Failed to replay {}: {}", journal_file, e);
+            return;
+        }
+    };
+    for (shard, exchange) in exchanges.iter().enumerate() {
+        println!("Shard {}", shard);
+        print_exchange(exchange);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+
+use super::obligation::Obligations;
+use crate::exchange::trade::TradeReport;
+use crate::order::Side;
+use crate::price::Price;
+
+// What a participant owes or is owed for one side of a trade once the CCP
+// has stepped in between the two sides
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contract {
+    pub trade_id: String,
+    pub participant: String,
+    pub account: String,
+    // Always the CCP
+    pub counterparty: String,
+    pub side: Side,
+    pub symbol: String,
+    pub currency: String,
+    pub price: Price,
+    pub quantity: u32,
+    pub settlement_date: NaiveDate,
+}
+
+impl Contract {
+    fn new(report: &TradeReport, ccp: &str) -> Contract {
+        Contract {
+            trade_id: report.trade_id.to_owned(),
+            participant: report.side.participant.to_owned(),
+            account: report.side.account.to_owned(),
+            counterparty: ccp.to_string(),
+            side: report.side.side.clone(),
+            symbol: report.symbol.to_owned(),
+            currency: report.currency.to_owned(),
+            price: report.price,
+            quantity: report.quantity,
+            settlement_date: report.settlement_date,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClearingReject {
+    // The side was already reported, or the trade already cleared
+    DuplicateReport(String),
+    // The two sides don't agree on the terms of the trade
+    MismatchedSides(String),
+}
+
+impl Display for ClearingReject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClearingReject::DuplicateReport(trade_id) => {
+                write!(f, "Trade {} was already reported", trade_id)
+            }
+            ClearingReject::MismatchedSides(trade_id) => {
+                write!(f, "The sides of trade {} don't match", trade_id)
+            }
+        }
+    }
+}
+
+// A central counterparty, which takes on both sides of every trade reported
+// to it and nets what each participant owes it
+#[derive(Debug, Clone)]
+pub struct ClearingHouse {
+    ccp: String,
+    // Sides waiting for the other side of their trade, by trade id
+    pending: HashMap<String, TradeReport>,
+    cleared: HashSet<String>,
+    contracts: Vec<Contract>,
+    obligations: Obligations,
+}
+
+impl ClearingHouse {
+    pub fn new(ccp: &str) -> ClearingHouse {
+        ClearingHouse {
+            ccp: ccp.to_string(),
+            pending: HashMap::new(),
+            cleared: HashSet::new(),
+            contracts: Vec::new(),
+            obligations: Obligations::new(),
+        }
+    }
+
+    pub fn get_ccp(&self) -> &str {
+        &self.ccp
+    }
+
+    // Registers one side of a trade. Once the other side is in as well, the
+    // trade is novated into a contract between each side and the CCP, which
+    // are returned and netted into the settlement obligations.
+    pub fn register(&mut self, report: TradeReport) -> Result<Vec<Contract>, ClearingReject> {
+        if self.cleared.contains(&report.trade_id) {
+            return Err(ClearingReject::DuplicateReport(report.trade_id));
+        }
+        let other = match self.pending.remove(&report.trade_id) {
+            Some(other) => other,
+            None => {
+                self.pending.insert(report.trade_id.to_owned(), report);
+                return Ok(Vec::new());
+            }
+        };
+        if other.side.side == report.side.side {
+            let trade_id = report.trade_id.to_owned();
+            self.pending.insert(trade_id.to_owned(), other);
+            return Err(ClearingReject::DuplicateReport(trade_id));
+        }
+        let matches = other.symbol == report.symbol
+            && other.currency == report.currency
+            && other.price == report.price
+            && other.quantity == report.quantity
+            && other.settlement_date == report.settlement_date;
+        if !matches {
+            let trade_id = report.trade_id.to_owned();
+            self.pending.insert(trade_id.to_owned(), other);
+            return Err(ClearingReject::MismatchedSides(trade_id));
+        }
+
+        self.cleared.insert(report.trade_id.to_owned());
+        let contracts = vec![
+            Contract::new(&other, &self.ccp),
+            Contract::new(&report, &self.ccp),
+        ];
+        for contract in &contracts {
+            self.obligations.net(contract);
+        }
+        self.contracts.extend(contracts.iter().cloned());
+        Ok(contracts)
+    }
+
+    // Sides whose other side has not been reported, which are not cleared
+    pub fn get_pending(&self) -> Vec<&TradeReport> {
+        let mut pending: Vec<&TradeReport> = self.pending.values().collect();
+        pending.sort_by(|a, b| a.trade_id.cmp(&b.trade_id));
+        pending
+    }
+
+    // Every contract the CCP is party to, in the order the trades cleared
+    pub fn get_contracts(&self) -> &[Contract] {
+        &self.contracts
+    }
+
+    pub fn get_obligations(&self) -> &Obligations {
+        &self.obligations
+    }
+}
+
+#[cfg(test)]
+fn trade_report(trade_id: &str, side: Side, participant: &str) -> TradeReport {
+    use crate::exchange::fee::Liquidity;
+    use crate::exchange::trade::TradeSide;
+    TradeReport {
+        trade_id: trade_id.to_string(),
+        symbol: "AAPL".to_string(),
+        currency: "USD".to_string(),
+        price: Price::from(150),
+        quantity: 100,
+        trade_date: NaiveDate::from_ymd_opt(2024, 3, 7).unwrap(),
+        settlement_date: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+        side: TradeSide {
+            side,
+            participant: participant.to_string(),
+            account: String::new(),
+            order_id: 1,
+            cl_ord_id: String::new(),
+            leaves_quantity: 0,
+            liquidity: Liquidity::Auction,
+            fee: Price::ZERO,
+        },
+    }
+}
+
+#[test]
+fn test_novation() {
+    let mut clearing_house = ClearingHouse::new("CCP");
+    let buy = trade_report("AAPL-1", Side::Buy, "FIRM1");
+    let sell = trade_report("AAPL-1", Side::Sell, "FIRM2");
+    assert_eq!(clearing_house.register(buy.clone()), Ok(Vec::new()));
+    assert_eq!(clearing_house.get_pending(), vec![&buy]);
+
+    let contracts = clearing_house.register(sell.clone()).unwrap();
+    assert_eq!(contracts.len(), 2);
+    assert_eq!(contracts[0].participant, "FIRM1");
+    assert_eq!(contracts[0].side, Side::Buy);
+    assert_eq!(contracts[1].participant, "FIRM2");
+    assert_eq!(contracts[1].side, Side::Sell);
+    assert!(contracts
+        .iter()
+        .all(|contract| contract.counterparty == "CCP"));
+    assert!(clearing_house.get_pending().is_empty());
+    assert_eq!(clearing_house.get_contracts().len(), 2);
+
+    assert_eq!(
+        clearing_house.register(sell),
+        Err(ClearingReject::DuplicateReport("AAPL-1".to_string()))
+    );
+}
+
+#[test]
+fn test_mismatched_sides() {
+    let mut clearing_house = ClearingHouse::new("CCP");
+    let buy = trade_report("AAPL-1", Side::Buy, "FIRM1");
+    clearing_house.register(buy.clone()).unwrap();
+    assert_eq!(
+        clearing_house.register(buy.clone()),
+        Err(ClearingReject::DuplicateReport("AAPL-1".to_string()))
+    );
+    let mut sell = trade_report("AAPL-1", Side::Sell, "FIRM2");
+    sell.quantity = 50;
+    assert_eq!(
+        clearing_house.register(sell),
+        Err(ClearingReject::MismatchedSides("AAPL-1".to_string()))
+    );
+    // The first side still waits for a matching one
+    assert_eq!(clearing_house.get_pending(), vec![&buy]);
+    assert!(clearing_house.get_contracts().is_empty());
+}
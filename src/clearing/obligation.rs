@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+
+use chrono::NaiveDate;
+
+use super::clearinghouse::Contract;
+use crate::order::Side;
+use crate::price::Price;
+
+// What a participant has to settle with the CCP in one symbol on one date,
+// netted over all of its contracts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Obligation {
+    pub settlement_date: NaiveDate,
+    pub participant: String,
+    pub counterparty: String,
+    pub symbol: String,
+    pub currency: String,
+    // Securities to receive when positive, to deliver when negative
+    pub quantity: i64,
+    // Cash to receive when positive, to pay when negative, in raw price
+    // units times quantity
+    cash: i128,
+    pub contracts: usize,
+}
+
+impl Obligation {
+    pub fn cash(&self) -> Price {
+        Price::from_raw(self.cash.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+// The obligations of every participant, by settlement date, participant and
+// symbol
+#[derive(Debug, Clone, Default)]
+pub struct Obligations {
+    obligations: BTreeMap<(NaiveDate, String, String), Obligation>,
+}
+
+impl Obligations {
+    pub fn new() -> Obligations {
+        Obligations::default()
+    }
+
+    // Adds `contract` to what its participant settles. Buying receives the
+    // securities and pays for them, selling the other way around.
+    pub fn net(&mut self, contract: &Contract) {
+        let obligation = self
+            .obligations
+            .entry((
+                contract.settlement_date,
+                contract.participant.to_owned(),
+                contract.symbol.to_owned(),
+            ))
+            .or_insert_with(|| Obligation {
+                settlement_date: contract.settlement_date,
+                participant: contract.participant.to_owned(),
+                counterparty: contract.counterparty.to_owned(),
+                symbol: contract.symbol.to_owned(),
+                currency: contract.currency.to_owned(),
+                quantity: 0,
+                cash: 0,
+                contracts: 0,
+            });
+        let quantity = match contract.side {
+            Side::Buy => contract.quantity as i64,
+            Side::Sell => -(contract.quantity as i64),
+        };
+        obligation.quantity += quantity;
+        obligation.cash -= contract.price.raw() as i128 * quantity as i128;
+        obligation.contracts += 1;
+    }
+
+    pub fn get(
+        &self,
+        participant: &str,
+        symbol: &str,
+        settlement_date: NaiveDate,
+    ) -> Option<&Obligation> {
+        self.obligations
+            .get(&(settlement_date, participant.to_string(), symbol.to_string()))
+    }
+
+    pub fn get_obligations(&self) -> Vec<&Obligation> {
+        self.obligations.values().collect()
+    }
+
+    // Writes the obligations as CSV, one per line after a header
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        fs::write(file_path, self.to_string())
+            .map_err(|e| format!("Failed to write {}: {}", file_path, e))
+    }
+}
+
+impl Display for Obligations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "settlement_date,participant,counterparty,symbol,currency,quantity,cash,contracts"
+        )?;
+        for obligation in self.obligations.values() {
+            writeln!(
+                f,
+                "{},{},{},{},{},{},{},{}",
+                obligation.settlement_date,
+                obligation.participant,
+                obligation.counterparty,
+                obligation.symbol,
+                obligation.currency,
+                obligation.quantity,
+                obligation.cash(),
+                obligation.contracts
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_netting() {
+    let settlement_date = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+    let contract = |participant: &str, side, quantity, price| Contract {
+        trade_id: String::new(),
+        participant: participant.to_string(),
+        account: String::new(),
+        counterparty: "CCP".to_string(),
+        side,
+        symbol: "AAPL".to_string(),
+        currency: "USD".to_string(),
+        price: Price::from(price),
+        quantity,
+        settlement_date,
+    };
+    let mut obligations = Obligations::new();
+    obligations.net(&contract("FIRM1", Side::Buy, 100, 150));
+    obligations.net(&contract("FIRM2", Side::Sell, 100, 150));
+    obligations.net(&contract("FIRM1", Side::Sell, 40, 155));
+    obligations.net(&contract("FIRM2", Side::Buy, 40, 155));
+
+    let firm1 = obligations.get("FIRM1", "AAPL", settlement_date).unwrap();
+    assert_eq!(firm1.quantity, 60);
+    assert_eq!(firm1.cash(), Price::from(-8800));
+    assert_eq!(firm1.contracts, 2);
+    let firm2 = obligations.get("FIRM2", "AAPL", settlement_date).unwrap();
+    assert_eq!(firm2.quantity, -60);
+    assert_eq!(firm2.cash(), Price::from(8800));
+
+    // Contracts settling on another date are netted apart
+    let mut later = contract("FIRM1", Side::Buy, 10, 150);
+    later.settlement_date = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+    obligations.net(&later);
+    assert_eq!(obligations.get_obligations().len(), 3);
+    assert_eq!(
+        obligations.to_string(),
+        "settlement_date,participant,counterparty,symbol,currency,quantity,cash,contracts\n\
+         2024-03-11,FIRM1,CCP,AAPL,USD,60,-8800,2\n\
+         2024-03-11,FIRM2,CCP,AAPL,USD,-60,8800,2\n\
+         2024-03-12,FIRM1,CCP,AAPL,USD,10,-1500,1\n"
+    );
+}
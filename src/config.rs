@@ -0,0 +1,110 @@
+use std::str::FromStr;
+
+// Section name matching every participant or symbol
+pub const ANY: &str = "*";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigEntry {
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+impl ConfigEntry {
+    pub fn parse<T: FromStr>(&self) -> Result<T, String> {
+        self.value.parse::<T>().map_err(|_| self.invalid())
+    }
+
+    pub fn invalid(&self) -> String {
+        format!("Line {}: invalid value for {}", self.line, self.key)
+    }
+
+    pub fn unknown(&self) -> String {
+        format!("Line {}: unknown field {}", self.line, self.key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSection {
+    pub name: String,
+    pub entries: Vec<ConfigEntry>,
+}
+
+pub fn read_sections(file_path: &str) -> Result<Vec<ConfigSection>, String> {
+    let contents = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    parse_sections(&contents)
+}
+
+// Splits an INI style file into `[name]` sections of `key = value` entries.
+// Blank lines and lines starting with '#' or ';' are ignored.
+pub fn parse_sections(contents: &str) -> Result<Vec<ConfigSection>, String> {
+    let mut sections: Vec<ConfigSection> = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            sections.push(ConfigSection {
+                name: name.trim().to_string(),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let section = sections
+            .last_mut()
+            .ok_or(format!("Line {}: field outside of a section", line_number))?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(format!("Line {}: expected key = value", line_number))?;
+        section.entries.push(ConfigEntry {
+            line: line_number,
+            key: key.trim().to_string(),
+            value: value.trim().to_string(),
+        });
+    }
+    Ok(sections)
+}
+
+#[test]
+fn test_parse_sections() {
+    let sections = parse_sections(
+        "# comment
+        [AAPL]
+        lot_size = 100
+        ; another comment
+
+        [MSFT]
+        [GOOG]
+        currency=USD",
+    )
+    .unwrap();
+    assert_eq!(sections.len(), 3);
+    assert_eq!(sections[0].name, "AAPL");
+    assert_eq!(
+        sections[0].entries,
+        vec![ConfigEntry {
+            line: 3,
+            key: "lot_size".to_string(),
+            value: "100".to_string(),
+        }]
+    );
+    assert!(sections[1].entries.is_empty());
+    assert_eq!(sections[2].entries[0].value, "USD");
+    assert_eq!(sections[0].entries[0].parse::<u32>(), Ok(100));
+    assert_eq!(
+        sections[2].entries[0].parse::<u32>(),
+        Err("Line 8: invalid value for currency".to_string())
+    );
+}
+
+#[test]
+fn test_parse_sections_errors() {
+    assert!(parse_sections("lot_size = 100").is_err());
+    assert!(parse_sections("[AAPL]\nlot_size").is_err());
+}
@@ -0,0 +1,174 @@
+use crate::order::Order;
+use crate::price::Price;
+
+// The single price a call auction uncrosses at, with the volume that trades
+// there and the imbalance left over. A positive surplus is unfilled buying
+// interest, a negative one unfilled selling interest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Equilibrium {
+    pub price: Price,
+    pub volume: u64,
+    pub surplus: i64,
+}
+
+// Finds the equilibrium price among the limit prices of the orders:
+//
+//   1. the price with the maximum executable volume
+//   2. then the minimum surplus
+//   3. then market pressure, the highest price if every remaining surplus is
+//      on the buy side or the lowest if every one is on the sell side
+//   4. then the price closest to the reference price, or to the middle of the
+//      remaining prices without one
+//
+// Returns None when the orders do not cross.
+pub fn equilibrium_price<'a>(
+    buy_orders: impl Iterator<Item = &'a Order>,
+    sell_orders: impl Iterator<Item = &'a Order>,
+    reference_price: Option<Price>,
+) -> Option<Equilibrium> {
+    let buy_orders: Vec<&Order> = buy_orders.collect();
+    let sell_orders: Vec<&Order> = sell_orders.collect();
+    let mut prices: Vec<Price> = buy_orders
+        .iter()
+        .chain(sell_orders.iter())
+        .map(|order| order.price)
+        .collect();
+    prices.sort();
+    prices.dedup();
+
+    let mut candidates: Vec<Equilibrium> = prices
+        .into_iter()
+        .map(|price| {
+            let buy_volume: u64 = buy_orders
+                .iter()
+                .filter(|order| order.price >= price)
+                .map(|order| order.quantity as u64)
+                .sum();
+            let sell_volume: u64 = sell_orders
+                .iter()
+                .filter(|order| order.price <= price)
+                .map(|order| order.quantity as u64)
+                .sum();
+            Equilibrium {
+                price,
+                volume: buy_volume.min(sell_volume),
+                surplus: buy_volume as i64 - sell_volume as i64,
+            }
+        })
+        .collect();
+
+    let max_volume = candidates.iter().map(|candidate| candidate.volume).max()?;
+    if max_volume == 0 {
+        return None;
+    }
+    candidates.retain(|candidate| candidate.volume == max_volume);
+
+    let min_surplus = candidates
+        .iter()
+        .map(|candidate| candidate.surplus.unsigned_abs())
+        .min()?;
+    candidates.retain(|candidate| candidate.surplus.unsigned_abs() == min_surplus);
+
+    // Candidates are in ascending price order
+    if candidates.iter().all(|candidate| candidate.surplus > 0) {
+        return candidates.last().copied();
+    }
+    if candidates.iter().all(|candidate| candidate.surplus < 0) {
+        return candidates.first().copied();
+    }
+
+    let reference_price = reference_price.unwrap_or_else(|| {
+        let low = candidates
+            .first()
+            .map_or(0, |candidate| candidate.price.raw());
+        let high = candidates
+            .last()
+            .map_or(0, |candidate| candidate.price.raw());
+        Price::from_raw(low + (high - low) / 2)
+    });
+    candidates
+        .into_iter()
+        .min_by_key(|candidate| (candidate.price.raw() - reference_price.raw()).abs())
+}
+
+#[cfg(test)]
+fn test_orders(orders: &[(u32, i64)], side: crate::order::Side) -> Vec<Order> {
+    orders
+        .iter()
+        .map(|(quantity, price)| Order::new("AAPL", *quantity, Price::from(*price), side.clone()))
+        .collect()
+}
+
+#[test]
+fn test_equilibrium_max_volume() {
+    use crate::order::Side;
+    let buy_orders = test_orders(&[(100, 103), (200, 102), (300, 100)], Side::Buy);
+    let sell_orders = test_orders(&[(200, 99), (200, 101), (300, 104)], Side::Sell);
+    let equilibrium = equilibrium_price(buy_orders.iter(), sell_orders.iter(), None).unwrap();
+    assert_eq!(equilibrium.price, Price::from(101));
+    assert_eq!(equilibrium.volume, 300);
+    assert_eq!(equilibrium.surplus, -100);
+}
+
+#[test]
+fn test_equilibrium_min_surplus() {
+    use crate::order::Side;
+    // 200 trades at both 100 and 101, with a surplus of 100 at 100 and 0 at 101
+    let buy_orders = test_orders(&[(200, 101), (100, 100)], Side::Buy);
+    let sell_orders = test_orders(&[(200, 99), (100, 102)], Side::Sell);
+    let equilibrium = equilibrium_price(buy_orders.iter(), sell_orders.iter(), None).unwrap();
+    assert_eq!(equilibrium.price, Price::from(101));
+    assert_eq!(equilibrium.volume, 200);
+    assert_eq!(equilibrium.surplus, 0);
+}
+
+#[test]
+fn test_equilibrium_market_pressure() {
+    use crate::order::Side;
+    let buy_orders = test_orders(&[(300, 102)], Side::Buy);
+    let sell_orders = test_orders(&[(100, 100), (100, 101)], Side::Sell);
+    let equilibrium = equilibrium_price(buy_orders.iter(), sell_orders.iter(), None).unwrap();
+    assert_eq!(equilibrium.price, Price::from(102));
+    assert_eq!(equilibrium.surplus, 100);
+
+    let buy_orders = test_orders(&[(100, 102), (100, 101)], Side::Buy);
+    let sell_orders = test_orders(&[(300, 100)], Side::Sell);
+    let equilibrium = equilibrium_price(buy_orders.iter(), sell_orders.iter(), None).unwrap();
+    assert_eq!(equilibrium.price, Price::from(100));
+    assert_eq!(equilibrium.surplus, -100);
+}
+
+#[test]
+fn test_equilibrium_reference_price() {
+    use crate::order::Side;
+    let buy_orders = test_orders(&[(100, 104)], Side::Buy);
+    let sell_orders = test_orders(&[(100, 100)], Side::Sell);
+    let equilibrium = equilibrium_price(
+        buy_orders.iter(),
+        sell_orders.iter(),
+        Some(Price::from(101)),
+    )
+    .unwrap();
+    assert_eq!(equilibrium.price, Price::from(100));
+    let equilibrium = equilibrium_price(
+        buy_orders.iter(),
+        sell_orders.iter(),
+        Some(Price::from(110)),
+    )
+    .unwrap();
+    assert_eq!(equilibrium.price, Price::from(104));
+    let equilibrium = equilibrium_price(buy_orders.iter(), sell_orders.iter(), None).unwrap();
+    assert_eq!(equilibrium.volume, 100);
+}
+
+#[test]
+fn test_equilibrium_no_cross() {
+    use crate::order::Side;
+    let buy_orders = test_orders(&[(100, 99)], Side::Buy);
+    let sell_orders = test_orders(&[(100, 100)], Side::Sell);
+    assert_eq!(
+        equilibrium_price(buy_orders.iter(), sell_orders.iter(), None),
+        None
+    );
+    assert_eq!(equilibrium_price([].iter(), sell_orders.iter(), None), None);
+}
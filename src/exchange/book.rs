@@ -0,0 +1,450 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use super::depth::PriceLevel;
+use crate::order::{Order, Side};
+use crate::price::Price;
+
+// A resting order and its neighbours in the queue at its price
+#[derive(Debug, Clone)]
+struct Node {
+    order: Order,
+    prev: Option<u32>,
+    next: Option<u32>,
+}
+
+// The FIFO queue of orders at one price, by order id, with its totals kept up
+// to date as orders join, fill and leave. The totals include the hidden
+// orders, which are also counted on their own. The displayed orders queue
+// first, and the last of them is kept apart so a displayed order can join
+// without walking past the hidden ones.
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    head: u32,
+    tail: u32,
+    displayed_tail: Option<u32>,
+    quantity: u64,
+    order_count: usize,
+    hidden_quantity: u64,
+    hidden_count: usize,
+}
+
+impl Level {
+    fn add(&mut self, order: &Order, quantity: u64) {
+        self.quantity += quantity;
+        if order.hidden {
+            self.hidden_quantity += quantity;
+        }
+    }
+
+    fn subtract(&mut self, order: &Order, quantity: u64) {
+        self.quantity -= quantity;
+        if order.hidden {
+            self.hidden_quantity -= quantity;
+        }
+    }
+}
+
+// Displayed orders queue ahead of hidden ones, then by time
+fn priority(order: &Order) -> (bool, u32) {
+    (order.hidden, order.sequence)
+}
+
+// One side of an instrument's book: a sorted map of price levels, each a FIFO
+// queue linked through an index of the orders by id. Finding, filling or
+// cancelling an order by id is O(1), and adding an order or emptying a level
+// O(log levels).
+#[derive(Clone)]
+pub struct BookSide {
+    side: Side,
+    levels: BTreeMap<Price, Level>,
+    orders: HashMap<u32, Node>,
+}
+
+impl BookSide {
+    pub fn new(side: Side) -> BookSide {
+        BookSide {
+            side,
+            levels: BTreeMap::new(),
+            orders: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    // Queues `order` at its price, behind every order there with an earlier
+    // sequence. That is the back of the displayed or hidden orders unless the
+    // order was created before the orders already resting there.
+    pub fn insert(&mut self, order: Order) {
+        self.remove(order.id);
+        let (id, price, quantity) = (order.id, order.price, order.quantity as u64);
+        let Some(level) = self.levels.get_mut(&price) else {
+            let (hidden_quantity, hidden_count) = match order.hidden {
+                true => (quantity, 1),
+                false => (0, 0),
+            };
+            self.levels.insert(
+                price,
+                Level {
+                    head: id,
+                    tail: id,
+                    displayed_tail: (!order.hidden).then_some(id),
+                    quantity,
+                    order_count: 1,
+                    hidden_quantity,
+                    hidden_count,
+                },
+            );
+            self.orders.insert(
+                id,
+                Node {
+                    order,
+                    prev: None,
+                    next: None,
+                },
+            );
+            return;
+        };
+
+        let mut prev = match order.hidden {
+            true => Some(level.tail),
+            false => level.displayed_tail,
+        };
+        while let Some(node) = prev.and_then(|prev| self.orders.get(&prev)) {
+            if priority(&node.order) < priority(&order) {
+                break;
+            }
+            prev = node.prev;
+        }
+        let next = match prev {
+            Some(prev) => self.orders.get(&prev).and_then(|node| node.next),
+            None => Some(level.head),
+        };
+        match prev.and_then(|prev| self.orders.get_mut(&prev)) {
+            Some(node) => node.next = Some(id),
+            None => level.head = id,
+        }
+        match next.and_then(|next| self.orders.get_mut(&next)) {
+            Some(node) => node.prev = Some(id),
+            None => level.tail = id,
+        }
+        if !order.hidden && prev == level.displayed_tail {
+            level.displayed_tail = Some(id);
+        }
+        level.add(&order, quantity);
+        level.order_count += 1;
+        level.hidden_count += order.hidden as usize;
+        self.orders.insert(id, Node { order, prev, next });
+    }
+
+    // Takes order `order_id` off the book
+    pub fn remove(&mut self, order_id: u32) -> Option<Order> {
+        let node = self.orders.remove(&order_id)?;
+        let price = node.order.price;
+        let level = self.levels.get_mut(&price)?;
+        match node.prev.and_then(|prev| self.orders.get_mut(&prev)) {
+            Some(prev) => prev.next = node.next,
+            None => level.head = node.next.unwrap_or(level.head),
+        }
+        match node.next.and_then(|next| self.orders.get_mut(&next)) {
+            Some(next) => next.prev = node.prev,
+            None => level.tail = node.prev.unwrap_or(level.tail),
+        }
+        if level.displayed_tail == Some(order_id) {
+            level.displayed_tail = node.prev;
+        }
+        level.subtract(&node.order, node.order.quantity as u64);
+        level.order_count -= 1;
+        level.hidden_count -= node.order.hidden as usize;
+        if level.order_count == 0 {
+            self.levels.remove(&price);
+        }
+        Some(node.order)
+    }
+
+    pub fn get(&self, order_id: u32) -> Option<&Order> {
+        self.orders.get(&order_id).map(|node| &node.order)
+    }
+
+    // Sets the open quantity of order `order_id` in place, so it keeps its
+    // priority, and returns the order as it now is. A quantity of 0 takes it
+    // off the book.
+    pub fn update_quantity(&mut self, order_id: u32, quantity: u32) -> Option<Order> {
+        if quantity == 0 {
+            let mut removed = self.remove(order_id)?;
+            removed.quantity = 0;
+            return Some(removed);
+        }
+        let node = self.orders.get_mut(&order_id)?;
+        let level = self.levels.get_mut(&node.order.price)?;
+        level.subtract(&node.order, node.order.quantity as u64);
+        level.add(&node.order, quantity as u64);
+        node.order.quantity = quantity;
+        Some(node.order.clone())
+    }
+
+    // Fills `quantity` of order `order_id`, taking it off the book once
+    // nothing is left
+    pub fn fill(&mut self, order_id: u32, quantity: u32) -> Option<Order> {
+        let open_quantity = self.get(order_id)?.quantity;
+        self.update_quantity(order_id, open_quantity.saturating_sub(quantity))
+    }
+
+    pub fn best_price(&self) -> Option<Price> {
+        self.best_level().map(|(price, _)| *price)
+    }
+
+    // The best price of the orders `include` holds for
+    pub fn best_price_of(&self, include: impl Fn(&Order) -> bool) -> Option<Price> {
+        self.price_order()
+            .find(|(_, level)| self.queue(Some(level.head)).any(&include))
+            .map(|(price, _)| *price)
+    }
+
+    // The order with the highest priority
+    pub fn front(&self) -> Option<&Order> {
+        self.best_level()
+            .and_then(|(_, level)| self.get(level.head))
+    }
+
+    // The order with the lowest priority
+    pub fn back(&self) -> Option<&Order> {
+        let worst = match self.side {
+            Side::Buy => self.levels.first_key_value(),
+            Side::Sell => self.levels.last_key_value(),
+        };
+        worst.and_then(|(_, level)| self.get(level.tail))
+    }
+
+    // The displayed totals at `price`
+    pub fn level(&self, price: Price) -> Option<PriceLevel> {
+        self.levels
+            .get(&price)
+            .and_then(|level| price_level(&price, level))
+    }
+
+    // The displayed totals of every price level with a displayed order, best
+    // price first
+    pub fn levels(&self) -> impl Iterator<Item = PriceLevel> + '_ {
+        self.price_order()
+            .filter_map(|(price, level)| price_level(price, level))
+    }
+
+    // The orders at `price` in priority order, the displayed ones first
+    pub fn level_orders(&self, price: Price) -> impl Iterator<Item = &Order> + '_ {
+        self.queue(self.levels.get(&price).map(|level| level.head))
+    }
+
+    // Every order in priority order
+    pub fn iter(&self) -> impl Iterator<Item = &Order> + '_ {
+        self.price_order()
+            .flat_map(move |(_, level)| self.queue(Some(level.head)))
+    }
+
+    fn best_level(&self) -> Option<(&Price, &Level)> {
+        match self.side {
+            Side::Buy => self.levels.last_key_value(),
+            Side::Sell => self.levels.first_key_value(),
+        }
+    }
+
+    fn price_order(&self) -> Box<dyn Iterator<Item = (&Price, &Level)> + '_> {
+        match self.side {
+            Side::Buy => Box::new(self.levels.iter().rev()),
+            Side::Sell => Box::new(self.levels.iter()),
+        }
+    }
+
+    fn queue(&self, head: Option<u32>) -> impl Iterator<Item = &Order> + '_ {
+        std::iter::successors(head.and_then(|id| self.orders.get(&id)), move |node| {
+            node.next.and_then(|next| self.orders.get(&next))
+        })
+        .map(|node| &node.order)
+    }
+}
+
+fn price_level(price: &Price, level: &Level) -> Option<PriceLevel> {
+    (level.order_count > level.hidden_count).then(|| PriceLevel {
+        price: *price,
+        quantity: level.quantity - level.hidden_quantity,
+        order_count: level.order_count - level.hidden_count,
+    })
+}
+
+impl fmt::Debug for BookSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Sides are equal when they hold the same orders in the same priority
+impl PartialEq for BookSide {
+    fn eq(&self, other: &BookSide) -> bool {
+        self.side == other.side
+            && self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(order, other)| order == other && order.quantity == other.quantity)
+    }
+}
+
+#[test]
+fn test_book_side_priority() {
+    let mut bids = BookSide::new(Side::Buy);
+    let orders = [(100, 101), (200, 102), (50, 101), (300, 100)]
+        .map(|(quantity, price)| Order::new("AAPL", quantity, Price::from(price), Side::Buy));
+    for order in &orders {
+        bids.insert(order.clone());
+    }
+    let ids: Vec<u32> = bids.iter().map(|order| order.id).collect();
+    assert_eq!(
+        ids,
+        vec![orders[1].id, orders[0].id, orders[2].id, orders[3].id]
+    );
+    assert_eq!(bids.front(), Some(&orders[1]));
+    assert_eq!(bids.back(), Some(&orders[3]));
+    assert_eq!(bids.best_price(), Some(Price::from(102)));
+
+    let mut asks = BookSide::new(Side::Sell);
+    for order in &orders {
+        let mut ask = order.clone();
+        ask.side = Side::Sell;
+        asks.insert(ask);
+    }
+    assert_eq!(asks.front(), Some(&orders[3]));
+    assert_eq!(asks.back(), Some(&orders[1]));
+
+    // An older order queues ahead of newer ones at its price
+    let late = orders[0].clone();
+    let mut early = Order::new("AAPL", 10, Price::from(101), Side::Buy);
+    early.sequence = late.sequence - 1;
+    bids.remove(late.id);
+    bids.insert(early.clone());
+    bids.insert(late.clone());
+    let level: Vec<&Order> = bids.level_orders(Price::from(101)).collect();
+    assert_eq!(level, vec![&early, &late, &orders[2]]);
+}
+
+#[test]
+fn test_book_side_level_totals() {
+    let mut asks = BookSide::new(Side::Sell);
+    let orders = [(100, 101), (200, 101), (50, 102)]
+        .map(|(quantity, price)| Order::new("AAPL", quantity, Price::from(price), Side::Sell));
+    for order in &orders {
+        asks.insert(order.clone());
+    }
+    assert_eq!(
+        asks.level(Price::from(101)),
+        Some(PriceLevel {
+            price: Price::from(101),
+            quantity: 300,
+            order_count: 2
+        })
+    );
+
+    let filled = asks.fill(orders[0].id, 40).unwrap();
+    assert_eq!(filled.quantity, 60);
+    assert_eq!(asks.front(), Some(&orders[0]));
+    assert_eq!(asks.level(Price::from(101)).unwrap().quantity, 260);
+    assert_eq!(asks.fill(orders[0].id, 60).unwrap().quantity, 0);
+    assert_eq!(asks.get(orders[0].id), None);
+    assert_eq!(asks.front(), Some(&orders[1]));
+    assert_eq!(asks.level(Price::from(101)).unwrap().order_count, 1);
+
+    assert_eq!(asks.remove(orders[1].id), Some(orders[1].clone()));
+    assert_eq!(asks.level(Price::from(101)), None);
+    let levels: Vec<PriceLevel> = asks.levels().collect();
+    assert_eq!(levels.len(), 1);
+    assert_eq!(levels[0].quantity, 50);
+    assert_eq!(asks.remove(orders[1].id), None);
+
+    asks.remove(orders[2].id);
+    assert!(asks.is_empty());
+    assert_eq!(asks.front(), None);
+}
+
+#[test]
+fn test_book_side_remove_middle() {
+    let mut bids = BookSide::new(Side::Buy);
+    let orders =
+        [100, 200, 300].map(|quantity| Order::new("AAPL", quantity, Price::from(150), Side::Buy));
+    for order in &orders {
+        bids.insert(order.clone());
+    }
+    bids.remove(orders[1].id);
+    let level: Vec<&Order> = bids.level_orders(Price::from(150)).collect();
+    assert_eq!(level, vec![&orders[0], &orders[2]]);
+    assert_eq!(bids.back(), Some(&orders[2]));
+    bids.remove(orders[2].id);
+    assert_eq!(bids.back(), Some(&orders[0]));
+    bids.insert(orders[1].clone());
+    assert_eq!(bids.back(), Some(&orders[1]));
+    assert_eq!(bids.level(Price::from(150)).unwrap().quantity, 300);
+}
+
+#[test]
+fn test_book_side_hidden_orders() {
+    let mut asks = BookSide::new(Side::Sell);
+    let mut hidden = Order::new("AAPL", 500, Price::from(101), Side::Sell);
+    hidden.hidden = true;
+    asks.insert(hidden.clone());
+    assert_eq!(asks.level(Price::from(101)), None);
+    assert_eq!(asks.levels().count(), 0);
+    assert_eq!(asks.best_price(), Some(Price::from(101)));
+
+    // A later displayed order queues ahead of the hidden one
+    let displayed = Order::new("AAPL", 100, Price::from(101), Side::Sell);
+    asks.insert(displayed.clone());
+    let level: Vec<&Order> = asks.level_orders(Price::from(101)).collect();
+    assert_eq!(level, vec![&displayed, &hidden]);
+    assert_eq!(
+        asks.level(Price::from(101)),
+        Some(PriceLevel {
+            price: Price::from(101),
+            quantity: 100,
+            order_count: 1
+        })
+    );
+
+    asks.fill(hidden.id, 200);
+    assert_eq!(asks.level(Price::from(101)).unwrap().quantity, 100);
+    asks.remove(displayed.id);
+    assert_eq!(asks.level(Price::from(101)), None);
+    assert_eq!(asks.front().unwrap().quantity, 300);
+}
+
+#[test]
+fn test_book_side_displayed_tail() {
+    let mut bids = BookSide::new(Side::Buy);
+    let [displayed1, hidden1, displayed2, hidden2, displayed3] = [false, true, false, true, false]
+        .map(|hidden| {
+            let mut order = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+            order.hidden = hidden;
+            order
+        });
+    for order in [&displayed1, &hidden1, &displayed2, &hidden2] {
+        bids.insert(order.clone());
+    }
+    let level: Vec<&Order> = bids.level_orders(Price::from(100)).collect();
+    assert_eq!(level, vec![&displayed1, &displayed2, &hidden1, &hidden2]);
+
+    // Displayed orders keep joining behind the last displayed one as the
+    // displayed orders ahead of them leave
+    bids.remove(displayed2.id);
+    bids.insert(displayed3.clone());
+    let level: Vec<&Order> = bids.level_orders(Price::from(100)).collect();
+    assert_eq!(level, vec![&displayed1, &displayed3, &hidden1, &hidden2]);
+    bids.remove(displayed1.id);
+    bids.remove(displayed3.id);
+    bids.insert(displayed2.clone());
+    let level: Vec<&Order> = bids.level_orders(Price::from(100)).collect();
+    assert_eq!(level, vec![&displayed2, &hidden1, &hidden2]);
+    assert_eq!(bids.back(), Some(&hidden2));
+}
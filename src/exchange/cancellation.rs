@@ -0,0 +1,106 @@
+use std::fmt::Display;
+
+use crate::order::{Order, SelfTradePrevention, Side};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CancelReason {
+    SelfTradePrevention(SelfTradePrevention),
+}
+
+impl Display for CancelReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancelReason::SelfTradePrevention(mode) => {
+                let mode = match mode {
+                    SelfTradePrevention::CancelNewest => "cancel newest",
+                    SelfTradePrevention::CancelOldest => "cancel oldest",
+                    SelfTradePrevention::CancelBoth => "cancel both",
+                    SelfTradePrevention::DecrementAndCancel => "decrement and cancel",
+                };
+                write!(f, "Self-trade prevention, {}", mode)
+            }
+        }
+    }
+}
+
+// Quantity taken off the book by the exchange rather than by the owner of the
+// order. `order` is the order as it rested before the cancellation, and
+// `execution_count` how many executions the order book had made by then.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cancellation {
+    pub order: Order,
+    pub quantity: u32,
+    pub reason: CancelReason,
+    pub execution_count: usize,
+}
+
+impl Cancellation {
+    pub fn leaves_quantity(&self) -> u32 {
+        self.order.quantity - self.quantity
+    }
+
+    // The order left on the book, if any quantity is left
+    pub fn remaining_order(&self) -> Option<Order> {
+        let mut order = self.order.clone();
+        order.quantity = self.leaves_quantity();
+        (order.quantity > 0).then_some(order)
+    }
+}
+
+// The resting orders a mass cancel applies to: every order of `participant`,
+// narrowed down to one symbol or side when they are given
+#[derive(Debug, Clone, PartialEq)]
+pub struct MassCancel {
+    pub participant: String,
+    pub symbol: Option<String>,
+    pub side: Option<Side>,
+}
+
+impl MassCancel {
+    pub fn matches(&self, order: &Order) -> bool {
+        order.participant == self.participant
+            && self
+                .symbol
+                .as_ref()
+                .is_none_or(|symbol| *symbol == order.symbol)
+            && self.side.as_ref().is_none_or(|side| *side == order.side)
+    }
+}
+
+#[test]
+fn test_cancellation() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let cancellation = Cancellation {
+        order: Order::new("AAPL", 100, Price::from(150), Side::Buy),
+        quantity: 40,
+        reason: CancelReason::SelfTradePrevention(SelfTradePrevention::DecrementAndCancel),
+        execution_count: 0,
+    };
+    assert_eq!(cancellation.leaves_quantity(), 60);
+    assert_eq!(cancellation.remaining_order().unwrap().quantity, 60);
+    assert_eq!(
+        cancellation.reason.to_string(),
+        "Self-trade prevention, decrement and cancel"
+    );
+}
+
+#[test]
+fn test_mass_cancel_matches() {
+    use crate::price::Price;
+    let mut order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order.participant = "FIRM1".to_string();
+    let mut mass_cancel = MassCancel {
+        participant: "FIRM1".to_string(),
+        symbol: None,
+        side: None,
+    };
+    assert!(mass_cancel.matches(&order));
+    mass_cancel.symbol = Some("AAPL".to_string());
+    mass_cancel.side = Some(Side::Sell);
+    assert!(!mass_cancel.matches(&order));
+    mass_cancel.side = Some(Side::Buy);
+    assert!(mass_cancel.matches(&order));
+    mass_cancel.participant = "FIRM2".to_string();
+    assert!(!mass_cancel.matches(&order));
+}
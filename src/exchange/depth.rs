@@ -0,0 +1,77 @@
+use super::book::BookSide;
+use crate::order::Order;
+use crate::price::Price;
+
+// The resting orders at one price, aggregated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceLevel {
+    pub price: Price,
+    pub quantity: u64,
+    pub order_count: usize,
+}
+
+// Level 1, the best bid and offer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopOfBook {
+    pub bid: Option<PriceLevel>,
+    pub ask: Option<PriceLevel>,
+}
+
+// Level 2, both sides aggregated by price, best price first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarketDepth {
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+// Level 3, every resting order in priority order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderDepth {
+    pub bids: Vec<Order>,
+    pub asks: Vec<Order>,
+}
+
+impl From<&MarketDepth> for TopOfBook {
+    fn from(depth: &MarketDepth) -> TopOfBook {
+        TopOfBook {
+            bid: depth.bids.first().copied(),
+            ask: depth.asks.first().copied(),
+        }
+    }
+}
+
+// The first `max_levels` price levels of one side of the book, best price
+// first, or every level when it is 0. The totals are kept by the book, so
+// this does not walk the orders.
+pub fn take_levels(orders: &BookSide, max_levels: usize) -> Vec<PriceLevel> {
+    let max_levels = if max_levels == 0 {
+        usize::MAX
+    } else {
+        max_levels
+    };
+    orders.levels().take(max_levels).collect()
+}
+
+#[test]
+fn test_take_levels() {
+    use crate::order::Side;
+    let mut bids = BookSide::new(Side::Buy);
+    for (quantity, price) in [(100, 102), (200, 102), (50, 101), (300, 100)] {
+        bids.insert(Order::new("AAPL", quantity, Price::from(price), Side::Buy));
+    }
+    let levels = take_levels(&bids, 0);
+    assert_eq!(levels.len(), 3);
+    assert_eq!(
+        levels[0],
+        PriceLevel {
+            price: Price::from(102),
+            quantity: 300,
+            order_count: 2
+        }
+    );
+    assert_eq!(levels[2].quantity, 300);
+    let levels = take_levels(&bids, 2);
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[1].price, Price::from(101));
+    assert!(take_levels(&BookSide::new(Side::Sell), 1).is_empty());
+}
@@ -1,27 +1,1220 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
+
+use super::auction::{self, Equilibrium};
+use super::book::BookSide;
+use super::cancellation::{Cancellation, MassCancel};
+use super::depth::{self, MarketDepth, OrderDepth, TopOfBook};
+use super::fee::{FeeSchedule, Fees};
+use super::instrument::{Instrument, ReferenceData};
+use super::journal::{Command, Journal};
+use super::marketdata::{MarketDataEntryType, MarketDataFeed, MarketDataUpdate};
+use super::matching::{Fifo, MatchingAlgorithm};
 use super::orderbook::OrderBook;
-use crate::order::Order;
+use super::orderrecord::{OrderRecord, OrderStatus};
+use super::phase::{PhaseChange, TradingPhase, TradingSchedule};
+use super::position::{Position, Positions};
+use super::quote::{Quote, QuoteCancel, QuoteEntry};
+use super::reject::RejectReason;
+use super::risk::{RiskLimits, RiskManager};
+use super::snapshot::{Execution, Snapshot};
+use super::trade::{self, AmendmentType, Trade, TradeAmendment, TradeSide};
+use crate::config::ANY;
+use crate::order::{Order, OrderIds, Side};
+use crate::price::Price;
+
+// Everything an exchange is started with, loaded once at startup
+#[derive(Debug, Clone, Default)]
+pub struct ExchangeConfig {
+    pub reference_data: ReferenceData,
+    pub risk_manager: RiskManager,
+    pub schedule: TradingSchedule,
+    pub order_ids: OrderIds,
+    pub fee_schedule: FeeSchedule,
+}
+
+impl ExchangeConfig {
+    pub fn load(
+        instruments_file: &str,
+        risk_file: &str,
+        schedule_file: &str,
+        fees_file: &str,
+    ) -> Result<ExchangeConfig, String> {
+        Ok(ExchangeConfig {
+            reference_data: ReferenceData::load(instruments_file)
+                .map_err(|e| format!("Failed to load instruments: {}", e))?,
+            risk_manager: RiskManager::load(risk_file)
+                .map_err(|e| format!("Failed to load risk limits: {}", e))?,
+            schedule: TradingSchedule::load(schedule_file)
+                .map_err(|e| format!("Failed to load trading schedule: {}", e))?,
+            order_ids: OrderIds::default(),
+            fee_schedule: FeeSchedule::load(fees_file)
+                .map_err(|e| format!("Failed to load fee schedule: {}", e))?,
+        })
+    }
+}
 
 pub struct Exchange {
     orderbook: OrderBook,
+    // Order ids by participant and ClOrdID, which is only unique within a
+    // participant
+    cl_ord_ids: HashMap<(String, String), u32>,
+    reference_data: ReferenceData,
+    risk_manager: RiskManager,
+    positions: Positions,
+    last_trade_prices: HashMap<String, Price>,
+    cancellations: Vec<Cancellation>,
+    // Trades not yet handed over for clearing
+    trades: Vec<Trade>,
+    // Busted and corrected trades not yet reported
+    amendments: Vec<TradeAmendment>,
+    fee_schedule: FeeSchedule,
+    // What every participant was charged over the day
+    fees: Fees,
+    auctions: HashSet<String>,
+    phases: HashMap<String, TradingPhase>,
+    default_phase: TradingPhase,
+    schedule: TradingSchedule,
+    // Time since the exchange started, as of the last schedule update
+    now: Duration,
+    // When each running volatility auction ends
+    volatility_auctions: HashMap<String, Duration>,
+    // The phase each halted instrument resumes into
+    halted_phases: HashMap<String, TradingPhase>,
+    phase_changes: Vec<PhaseChange>,
+    market_data: MarketDataFeed,
+    // Every order ever entered, by order id, including rejected ones
+    order_records: HashMap<u32, OrderRecord>,
+    order_ids: OrderIds,
+    journal: Option<Journal>,
+    // The time of the last clock entry in the journal
+    journaled_clock: Duration,
 }
 
 impl Exchange {
     pub fn new() -> Self {
+        Exchange::with_config(ExchangeConfig::default())
+    }
+
+    // Instruments trade continuously unless there is a schedule, in which case
+    // they start closed and wait for it
+    pub fn with_config(config: ExchangeConfig) -> Self {
+        let default_phase = if config.schedule.is_empty() {
+            TradingPhase::Continuous
+        } else {
+            TradingPhase::Closed
+        };
+        let mut orderbook = OrderBook::new();
+        // Executions are numbered apart from orders, but interleaved across
+        // the shards the same way
+        orderbook.executions.set_ids(config.order_ids);
         Exchange {
-            orderbook: OrderBook::new(),
+            orderbook,
+            cl_ord_ids: HashMap::new(),
+            reference_data: config.reference_data,
+            risk_manager: config.risk_manager,
+            positions: Positions::new(),
+            last_trade_prices: HashMap::new(),
+            cancellations: Vec::new(),
+            trades: Vec::new(),
+            amendments: Vec::new(),
+            fee_schedule: config.fee_schedule,
+            fees: Fees::new(),
+            auctions: HashSet::new(),
+            phases: HashMap::new(),
+            default_phase,
+            schedule: config.schedule,
+            now: Duration::ZERO,
+            volatility_auctions: HashMap::new(),
+            halted_phases: HashMap::new(),
+            phase_changes: Vec::new(),
+            market_data: MarketDataFeed::new(),
+            order_records: HashMap::new(),
+            order_ids: config.order_ids,
+            journal: None,
+            journaled_clock: Duration::ZERO,
+        }
+    }
+
+    // Journals every state-changing input from here on, before applying it
+    pub fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    fn journal(&mut self, command: impl FnOnce() -> Command) {
+        if self.journal.is_none() {
+            return;
+        }
+        self.journal_clock();
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.append(command()) {
+                log_error!("{}", e);
+            }
+        }
+    }
+
+    // Commands are replayed at the time they came in, so the clock is
+    // journaled whenever it has moved on by the next command
+    fn journal_clock(&mut self) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        if self.now == self.journaled_clock {
+            return;
+        }
+        match journal.append(Command::Clock(self.now)) {
+            Ok(_) => self.journaled_clock = self.now,
+            Err(e) => log_error!("{}", e),
+        }
+    }
+
+    // Numbers `order` from this exchange's ids, which are the same on every
+    // run given the same orders, and gives it time priority from the same
+    // counter
+    pub fn assign_order_id(&mut self, order: &mut Order) {
+        order.id = self.order_ids.next_id();
+        order.sequence = order.id;
+    }
+
+    pub fn add_instrument(&mut self, instrument: Instrument) {
+        self.reference_data.add_instrument(instrument);
+    }
+
+    pub fn get_reference_data(&self) -> &ReferenceData {
+        &self.reference_data
+    }
+
+    // Only symbols defined in the reference data can be traded, and only in
+    // a phase that takes orders
+    fn validate_order(&self, order: &Order) -> Result<(), RejectReason> {
+        let instrument = self
+            .reference_data
+            .get_instrument(&order.symbol)
+            .ok_or(RejectReason::UnknownSymbol(order.symbol.to_owned()))?;
+        match &order.peg {
+            // Pegs follow the touch, so only a limit has to be a valid price
+            Some(peg) => match peg.limit {
+                Some(limit) => instrument.validate(order.quantity, limit)?,
+                None => instrument.validate_quantity(order.quantity)?,
+            },
+            None => instrument.validate(order.quantity, order.price)?,
+        }
+        let phase = self.get_trading_phase(&order.symbol);
+        if !phase.allows_order_entry() {
+            return Err(RejectReason::TradingPhase(phase));
+        }
+        Ok(())
+    }
+
+    // Prices a pegged order from the current touch
+    fn price_peg(&self, order: &mut Order) -> Result<(), RejectReason> {
+        if let Some(peg) = order.peg {
+            let (bid, ask) = self.orderbook.get_touch(&order.symbol);
+            let instrument = self.reference_data.get_instrument(&order.symbol);
+            let reference = peg
+                .reference(&order.side, bid, ask, |price| {
+                    instrument.map_or(Price::from_raw(1), |instrument| instrument.tick_size(price))
+                })
+                .ok_or(RejectReason::NoPegReference)?;
+            order.price =
+                peg.price_at(&order.side, reference)
+                    .ok_or(RejectReason::InvalidPegPrice {
+                        reference,
+                        offset: peg.offset,
+                    })?;
         }
+        Ok(())
+    }
+
+    fn check_risk(&self, order: &Order, replacing: Option<&Order>) -> Result<(), RejectReason> {
+        self.risk_manager
+            .check(order, replacing, self.get_reference_price(&order.symbol))
+            .map_err(RejectReason::RiskBreach)
+    }
+
+    // Price deviation is measured from the last trade, or from the reference
+    // price in the instrument definition before the first trade
+    pub fn get_reference_price(&self, symbol: &str) -> Option<Price> {
+        self.last_trade_prices.get(symbol).copied().or_else(|| {
+            self.reference_data
+                .get_instrument(symbol)
+                .and_then(|instrument| instrument.reference_price)
+        })
+    }
+
+    // The price positions in `symbol` are marked at
+    pub fn get_last_trade_price(&self, symbol: &str) -> Option<Price> {
+        self.last_trade_prices.get(symbol).copied()
     }
 
-    pub fn execute_order(&mut self, order: Order) {
+    // The positions of `participant` from the fills in this exchange, in
+    // `account` and `symbol` when given
+    pub fn get_positions(
+        &self,
+        participant: &str,
+        account: Option<&str>,
+        symbol: Option<&str>,
+    ) -> Vec<&Position> {
+        self.positions.get_positions(participant, account, symbol)
+    }
+
+    // Replaces the limits of `participant` in `symbol`, either of which may
+    // be '*'
+    pub fn set_risk_limits(&mut self, participant: &str, symbol: &str, limits: RiskLimits) {
+        self.journal(|| Command::SetRiskLimits {
+            participant: participant.to_string(),
+            symbol: symbol.to_string(),
+            limits: limits.clone(),
+        });
+        self.risk_manager.set_limits(participant, symbol, limits);
+    }
+
+    pub fn get_risk_manager(&self) -> &RiskManager {
+        &self.risk_manager
+    }
+
+    pub fn execute_order(&mut self, mut order: Order) -> Result<(), RejectReason> {
+        self.journal(|| Command::NewOrder(order.clone()));
+        self.order_ids.skip_past(order.id.max(order.sequence));
+        let checked = self
+            .validate_order(&order)
+            .and_then(|()| self.price_peg(&mut order))
+            .and_then(|()| self.check_risk(&order, None));
+        if let Err(reason) = checked {
+            // A rejected order keeps its record, without taking over the
+            // ClOrdID of an order that was accepted
+            if !order.cl_ord_id.is_empty() {
+                self.cl_ord_ids
+                    .entry(cl_ord_id_key(&order))
+                    .or_insert(order.id);
+            }
+            self.order_records
+                .insert(order.id, OrderRecord::rejected(&order, Utc::now()));
+            return Err(reason);
+        }
         let symbol = &order.symbol.to_owned();
+        if !order.cl_ord_id.is_empty() {
+            self.cl_ord_ids.insert(cl_ord_id_key(&order), order.id);
+        }
+        self.order_records
+            .insert(order.id, OrderRecord::new(&order, Utc::now()));
+        self.risk_manager.order_added(&order);
         self.orderbook.add_order(order);
-        self.orderbook.match_orders(symbol);
+        self.market_data.book_changed(symbol);
+        self.match_orders(symbol);
+        Ok(())
+    }
+
+    // Runs continuous matching for `symbol`, unless it is in a call auction or
+    // a phase without matching. The pegged orders are repriced once the book
+    // no longer crosses, and matched again when they move.
+    fn match_orders(&mut self, symbol: &str) {
+        loop {
+            self.match_book(symbol);
+            if !self.reprice_pegged_orders(symbol) {
+                break;
+            }
+        }
+    }
+
+    // Keeps the pegged orders of `symbol` at their peg, returning whether any
+    // of them moved
+    fn reprice_pegged_orders(&mut self, symbol: &str) -> bool {
+        let order_ids = &mut self.order_ids;
+        let instrument = self.reference_data.get_instrument(symbol);
+        let repriced = self.orderbook.reprice_pegged_orders(
+            symbol,
+            |price| instrument.map_or(Price::from_raw(1), |instrument| instrument.tick_size(price)),
+            || order_ids.next_id(),
+        );
+        for (resting, moved) in &repriced {
+            self.risk_manager.order_removed(resting);
+            self.risk_manager.order_added(moved);
+            self.update_record(moved.id, |record, _| {
+                record.order.price = moved.price;
+                record.order.sequence = moved.sequence;
+            });
+        }
+        if !repriced.is_empty() {
+            self.market_data.book_changed(symbol);
+        }
+        !repriced.is_empty()
+    }
+
+    // One round of continuous matching. A trade outside the instrument's
+    // dynamic band is not made and interrupts trading with a volatility
+    // auction instead.
+    fn match_book(&mut self, symbol: &str) {
+        if self.auctions.contains(symbol) || !self.get_trading_phase(symbol).allows_matching() {
+            return;
+        }
+        let executions_before = self.orderbook.executions.len();
+        let price_band = self.get_dynamic_band(symbol);
+        let algorithm = self.get_matching_algorithm(symbol);
+        let breach = self
+            .orderbook
+            .match_orders(symbol, algorithm.as_ref(), price_band);
+        self.apply_matches(symbol, executions_before);
+
+        if let Some(price) = breach {
+            log_warn!(
+                "{} would have traded at {} outside its band, interrupting trading",
+                symbol,
+                price
+            );
+            match self.change_phase(symbol, TradingPhase::VolatilityAuction) {
+                Ok(changes) => self.phase_changes.extend(changes),
+                Err(reason) => log_error!("Failed to interrupt {}: {}", symbol, reason),
+            }
+        }
+    }
+
+    fn get_matching_algorithm(&self, symbol: &str) -> Box<dyn MatchingAlgorithm> {
+        self.reference_data
+            .get_instrument(symbol)
+            .map_or(Box::new(Fifo), Instrument::matching_algorithm)
+    }
+
+    // The prices continuous trading in `symbol` is limited to, around the
+    // last trade or reference price
+    pub fn get_dynamic_band(&self, symbol: &str) -> Option<(Price, Price)> {
+        self.reference_data
+            .get_instrument(symbol)?
+            .dynamic_band(self.get_reference_price(symbol)?)
+    }
+
+    // Applies the executions after the first `executions_before` and any
+    // cancellations to the risk exposures and the last trade price, and
+    // queues the trades for clearing. They are applied in the order they
+    // happened, as each one works on the order as the one before it left it.
+    fn apply_matches(&mut self, symbol: &str, executions_before: usize) {
+        let execution_ids = self
+            .orderbook
+            .executions
+            .get_ids(executions_before)
+            .to_vec();
+        let changed = !execution_ids.is_empty() || !self.orderbook.cancellations.is_empty();
+        let mut cancellations = std::mem::take(&mut self.orderbook.cancellations)
+            .into_iter()
+            .peekable();
+        for (index, &execution_id) in execution_ids.iter().enumerate() {
+            while let Some(cancellation) = cancellations
+                .next_if(|cancellation| cancellation.execution_count <= executions_before + index)
+            {
+                self.apply_cancellation(cancellation);
+            }
+            self.apply_execution(symbol, execution_id);
+        }
+        for cancellation in cancellations {
+            self.apply_cancellation(cancellation);
+        }
+        if changed {
+            self.market_data.book_changed(symbol);
+        }
+    }
+
+    fn apply_execution(&mut self, symbol: &str, execution_id: usize) {
+        let (buy_order, sell_order) = match self.orderbook.executions.matches.get(&execution_id) {
+            Some(execution) => execution.to_owned(),
+            None => return,
+        };
+        let quantity = self
+            .orderbook
+            .executions
+            .get_quantity(execution_id)
+            .unwrap_or(0);
+        self.risk_manager.order_filled(&buy_order, quantity);
+        self.risk_manager.order_filled(&sell_order, quantity);
+        if let Some(trade_price) = self.orderbook.executions.get_price(execution_id) {
+            self.positions.fill(&buy_order, trade_price, quantity);
+            self.positions.fill(&sell_order, trade_price, quantity);
+            self.last_trade_prices
+                .insert(symbol.to_string(), trade_price);
+            self.market_data.trade(symbol, trade_price, quantity);
+            let trade = self.trade(execution_id, &buy_order, &sell_order, trade_price, quantity);
+            for side in [&trade.buyer, &trade.seller] {
+                self.fees
+                    .charge(&side.participant, side.liquidity, quantity, side.fee);
+            }
+            self.trades.push(trade);
+            for order_id in [buy_order.id, sell_order.id] {
+                self.update_record(order_id, |record, now| {
+                    record.fill(quantity, trade_price, now)
+                });
+            }
+        }
+    }
+
+    fn apply_cancellation(&mut self, cancellation: Cancellation) {
+        self.update_record(cancellation.order.id, |record, now| {
+            record.cancel(cancellation.quantity, now)
+        });
+        self.risk_manager.order_removed(&cancellation.order);
+        if let Some(remaining) = cancellation.remaining_order() {
+            self.risk_manager.order_added(&remaining);
+        }
+        self.cancellations.push(cancellation);
+    }
+
+    // Execution `execution_id` as a trade, settling after the instrument's
+    // settlement cycle from today, with each side's fee from the schedule
+    fn trade(
+        &self,
+        execution_id: usize,
+        buy_order: &Order,
+        sell_order: &Order,
+        price: Price,
+        quantity: u32,
+    ) -> Trade {
+        let instrument = self.reference_data.get_instrument(&buy_order.symbol);
+        let trade_date = Utc::now().date_naive();
+        Trade {
+            trade_id: format!("{}-{}", buy_order.symbol, execution_id),
+            symbol: buy_order.symbol.to_owned(),
+            currency: instrument
+                .map_or("USD", |instrument| &instrument.currency)
+                .to_string(),
+            price,
+            quantity,
+            trade_date,
+            settlement_date: trade::settlement_date(
+                trade_date,
+                instrument.map_or(2, |instrument| instrument.settlement_days),
+            ),
+            buyer: self.trade_side(execution_id, buy_order, quantity),
+            seller: self.trade_side(execution_id, sell_order, quantity),
+        }
+    }
+
+    fn trade_side(&self, execution_id: usize, order: &Order, quantity: u32) -> TradeSide {
+        let liquidity = self
+            .orderbook
+            .executions
+            .get_liquidity(execution_id, &order.side);
+        let fee = self
+            .fee_schedule
+            .get_fee(&order.participant, &order.symbol, liquidity, quantity);
+        TradeSide::new(order, quantity, liquidity, fee)
+    }
+
+    // What every participant was charged for its fills so far
+    pub fn get_fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    // Puts `symbol` into a call auction. Orders are collected without matching
+    // until the auction is uncrossed.
+    pub fn start_auction(&mut self, symbol: &str) {
+        self.auctions.insert(symbol.to_string());
+    }
+
+    pub fn is_in_auction(&self, symbol: &str) -> bool {
+        self.auctions.contains(symbol)
+    }
+
+    // The price and volume the auction would uncross at right now
+    pub fn get_indicative_equilibrium(&self, symbol: &str) -> Option<Equilibrium> {
+        let buy_orders = self.orderbook.buy_orders.get(symbol)?;
+        let sell_orders = self.orderbook.sell_orders.get(symbol)?;
+        auction::equilibrium_price(
+            buy_orders.iter(),
+            sell_orders.iter(),
+            self.get_reference_price(symbol),
+        )
+    }
+
+    // Ends the call auction for `symbol`, executing every crossing order at
+    // the equilibrium price, and returns to continuous matching
+    pub fn uncross(&mut self, symbol: &str) -> Option<Equilibrium> {
+        self.auctions.remove(symbol);
+        let equilibrium = self.get_indicative_equilibrium(symbol)?;
+        let executions_before = self.orderbook.executions.len();
+        let algorithm = self.get_matching_algorithm(symbol);
+        self.orderbook
+            .uncross(symbol, equilibrium.price, algorithm.as_ref());
+        self.apply_matches(symbol, executions_before);
+        Some(equilibrium)
+    }
+
+    pub fn get_trading_phase(&self, symbol: &str) -> TradingPhase {
+        self.phases
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.default_phase)
+    }
+
+    // The phase of the market as a whole, when every instrument is in it
+    pub fn get_market_phase(&self) -> Option<TradingPhase> {
+        let mut phases = self
+            .reference_data
+            .get_instruments()
+            .into_iter()
+            .map(|instrument| self.get_trading_phase(&instrument.symbol));
+        let phase = phases.next()?;
+        phases.all(|other| other == phase).then_some(phase)
+    }
+
+    // Moves `symbol`, or every instrument for '*', into `phase`. Entering an
+    // auction starts collecting orders, and moving on from one into
+    // continuous trading or the close uncrosses it. Nothing changes if any
+    // instrument cannot make the transition.
+    pub fn set_trading_phase(
+        &mut self,
+        symbol: &str,
+        phase: TradingPhase,
+    ) -> Result<Vec<PhaseChange>, RejectReason> {
+        self.journal(|| Command::SetTradingPhase {
+            symbol: symbol.to_string(),
+            phase,
+        });
+        self.change_phase(symbol, phase)
+    }
+
+    fn change_phase(
+        &mut self,
+        symbol: &str,
+        phase: TradingPhase,
+    ) -> Result<Vec<PhaseChange>, RejectReason> {
+        let symbols = self.resolve_symbols(symbol)?;
+
+        for symbol in &symbols {
+            let from = self.get_trading_phase(symbol);
+            if from != phase && !from.can_transition_to(phase) {
+                return Err(RejectReason::InvalidPhaseTransition { from, to: phase });
+            }
+        }
+        Ok(self.enter_phase(symbols, phase))
+    }
+
+    // Moves `symbols` into `phase` without checking that they can make the
+    // transition
+    fn enter_phase(&mut self, symbols: Vec<String>, phase: TradingPhase) -> Vec<PhaseChange> {
+        let mut changes = Vec::new();
+        for symbol in symbols {
+            let from = self.get_trading_phase(&symbol);
+            if from == phase {
+                continue;
+            }
+            self.phases.insert(symbol.to_owned(), phase);
+
+            self.volatility_auctions.remove(&symbol);
+            if phase == TradingPhase::VolatilityAuction {
+                let duration = self
+                    .reference_data
+                    .get_instrument(&symbol)
+                    .map(|instrument| instrument.volatility_auction_duration)
+                    .unwrap_or_default();
+                self.volatility_auctions
+                    .insert(symbol.to_owned(), self.now + duration);
+            }
+            if phase == TradingPhase::Halted {
+                self.halted_phases.insert(symbol.to_owned(), from);
+            } else {
+                self.halted_phases.remove(&symbol);
+            }
+
+            let mut equilibrium = None;
+            if phase.is_auction() {
+                self.start_auction(&symbol);
+            } else if phase == TradingPhase::Continuous || phase == TradingPhase::Closed {
+                // An auction interrupted by a halt is abandoned, not uncrossed
+                if from == TradingPhase::Halted {
+                    self.auctions.remove(&symbol);
+                } else if self.is_in_auction(&symbol) {
+                    equilibrium = self.uncross(&symbol);
+                }
+                let entry_type = match (from, phase) {
+                    (TradingPhase::OpeningAuction, _) => Some(MarketDataEntryType::OpeningPrice),
+                    (TradingPhase::ClosingAuction, TradingPhase::Closed) => {
+                        Some(MarketDataEntryType::ClosingPrice)
+                    }
+                    _ => None,
+                };
+                if let Some((entry_type, equilibrium)) = entry_type.zip(equilibrium) {
+                    self.market_data.auction_price(
+                        &symbol,
+                        entry_type,
+                        equilibrium.price,
+                        equilibrium.volume,
+                    );
+                }
+                self.match_orders(&symbol);
+            }
+            changes.push(PhaseChange {
+                symbol,
+                from,
+                to: phase,
+                equilibrium,
+            });
+        }
+        changes
+    }
+
+    fn resolve_symbols(&self, symbol: &str) -> Result<Vec<String>, RejectReason> {
+        if symbol == ANY {
+            Ok(self
+                .reference_data
+                .get_instruments()
+                .iter()
+                .map(|instrument| instrument.symbol.to_owned())
+                .collect())
+        } else if self.reference_data.get_instrument(symbol).is_some() {
+            Ok(vec![symbol.to_string()])
+        } else {
+            Err(RejectReason::UnknownSymbol(symbol.to_string()))
+        }
+    }
+
+    // Halts `symbol`, or the whole market for '*'
+    pub fn halt(&mut self, symbol: &str) -> Result<Vec<PhaseChange>, RejectReason> {
+        self.journal(|| Command::Halt {
+            symbol: symbol.to_string(),
+        });
+        self.change_phase(symbol, TradingPhase::Halted)
+    }
+
+    // Returns halted instruments to the phase they were halted in, which is
+    // the only way out of a halt other than the close. A volatility auction
+    // restarts with its full duration.
+    pub fn resume(&mut self, symbol: &str) -> Result<Vec<PhaseChange>, RejectReason> {
+        self.journal(|| Command::Resume {
+            symbol: symbol.to_string(),
+        });
+        let mut changes = Vec::new();
+        for symbol in self.resolve_symbols(symbol)? {
+            if let Some(phase) = self.halted_phases.get(&symbol).copied() {
+                changes.extend(self.enter_phase(vec![symbol], phase));
+            }
+        }
+        Ok(changes)
+    }
+
+    // Returns the phase changes made by the exchange itself since the last
+    // call, such as volatility interruptions
+    pub fn take_phase_changes(&mut self) -> Vec<PhaseChange> {
+        std::mem::take(&mut self.phase_changes)
+    }
+
+    // Applies the scheduled transitions that have come due `elapsed` after
+    // the exchange started, after ending any volatility auctions that have run
+    // their course
+    pub fn apply_schedule(&mut self, elapsed: Duration) -> Vec<PhaseChange> {
+        self.now = elapsed;
+        let mut changes = Vec::new();
+        let mut ended: Vec<String> = self
+            .volatility_auctions
+            .iter()
+            .filter(|(_, end)| **end <= elapsed)
+            .map(|(symbol, _)| symbol.to_owned())
+            .collect();
+        if !ended.is_empty() || self.schedule.is_due(elapsed) {
+            self.journal_clock();
+        }
+        ended.sort();
+        for symbol in ended {
+            match self.change_phase(&symbol, TradingPhase::Continuous) {
+                Ok(applied) => changes.extend(applied),
+                Err(reason) => log_warn!("Failed to resume {}: {}", symbol, reason),
+            }
+        }
+        for entry in self.schedule.take_due(elapsed) {
+            // Instruments that cannot make the transition, such as halted
+            // ones, are skipped without holding up the others
+            let symbols = match self.resolve_symbols(&entry.symbol) {
+                Ok(symbols) => symbols,
+                Err(reason) => {
+                    log_warn!("Skipped scheduled {}: {}", entry.phase, reason);
+                    continue;
+                }
+            };
+            for symbol in symbols {
+                match self.change_phase(&symbol, entry.phase) {
+                    Ok(applied) => changes.extend(applied),
+                    Err(reason) => log_warn!(
+                        "Skipped scheduled {} for {}: {}",
+                        entry.phase,
+                        symbol,
+                        reason
+                    ),
+                }
+            }
+        }
+        changes
+    }
+
+    // True once every instrument is closed and nothing more is scheduled
+    pub fn is_closed(&self) -> bool {
+        self.schedule.is_finished() && self.get_market_phase() == Some(TradingPhase::Closed)
+    }
+
+    // The trades made since the last call, for clearing
+    pub fn take_trades(&mut self) -> Vec<Trade> {
+        std::mem::take(&mut self.trades)
+    }
+
+    // Returns the trades busted or corrected since the last call, so both
+    // sides can be told
+    pub fn take_amendments(&mut self) -> Vec<TradeAmendment> {
+        std::mem::take(&mut self.amendments)
+    }
+
+    // Busts trade `trade_id` as if it had never happened. What the orders
+    // filled is not put back on the book.
+    pub fn bust_trade(&mut self, trade_id: &str) -> Result<(), RejectReason> {
+        let execution_id = self.find_trade(trade_id)?;
+        self.journal(|| Command::BustTrade {
+            trade_id: trade_id.to_string(),
+        });
+        self.amend_trade(execution_id, None);
+        Ok(())
+    }
+
+    // Corrects the price and quantity of trade `trade_id`. The quantity can
+    // only be corrected down, since the orders may have nothing left to fill.
+    pub fn correct_trade(
+        &mut self,
+        trade_id: &str,
+        price: Price,
+        quantity: u32,
+    ) -> Result<(), RejectReason> {
+        let execution_id = self.find_trade(trade_id)?;
+        let traded_quantity = self
+            .orderbook
+            .executions
+            .get_quantity(execution_id)
+            .unwrap_or(0);
+        if quantity == 0 || quantity > traded_quantity {
+            return Err(RejectReason::InvalidCorrection {
+                quantity,
+                traded_quantity,
+            });
+        }
+        self.journal(|| Command::CorrectTrade {
+            trade_id: trade_id.to_string(),
+            price,
+            quantity,
+        });
+        self.amend_trade(execution_id, Some((price, quantity)));
+        Ok(())
+    }
+
+    fn find_trade(&self, trade_id: &str) -> Result<usize, RejectReason> {
+        trade::parse_trade_id(trade_id)
+            .filter(|(symbol, execution_id)| {
+                self.orderbook
+                    .executions
+                    .matches
+                    .get(execution_id)
+                    .is_some_and(|(buy_order, _)| buy_order.symbol == *symbol)
+            })
+            .map(|(_, execution_id)| execution_id)
+            .ok_or_else(|| RejectReason::UnknownTrade(trade_id.to_string()))
+    }
+
+    // Takes execution `execution_id` back out of the risk exposures, fees,
+    // order records and market data, and puts the correction in if there is
+    // one. Positions and the last trade price are built again from what is
+    // left.
+    fn amend_trade(&mut self, execution_id: usize, correction: Option<(Price, u32)>) {
+        let executions = &self.orderbook.executions;
+        let (Some((buy_order, sell_order)), Some(price), Some(quantity)) = (
+            executions.matches.get(&execution_id).cloned(),
+            executions.get_price(execution_id),
+            executions.get_quantity(execution_id),
+        ) else {
+            return;
+        };
+        let symbol = buy_order.symbol.to_owned();
+        let traded = self.trade(execution_id, &buy_order, &sell_order, price, quantity);
+        let corrected = correction.unwrap_or((Price::ZERO, 0));
+        for (order, side) in [(&buy_order, &traded.buyer), (&sell_order, &traded.seller)] {
+            self.risk_manager.fill_busted(order, quantity);
+            self.fees
+                .refund(&side.participant, side.liquidity, quantity, side.fee);
+            self.update_record(order.id, |record, now| {
+                record.correct_fill((quantity, price), (corrected.1, corrected.0), now)
+            });
+        }
+        self.market_data.trade_busted(&symbol, price, quantity);
+
+        let (amendment_type, trade) = match correction {
+            Some((price, quantity)) => {
+                self.orderbook
+                    .executions
+                    .correct(execution_id, price, quantity);
+                let trade = self.trade(execution_id, &buy_order, &sell_order, price, quantity);
+                for (order, side) in [(&buy_order, &trade.buyer), (&sell_order, &trade.seller)] {
+                    self.risk_manager.fill_restored(order, quantity);
+                    self.fees
+                        .charge(&side.participant, side.liquidity, quantity, side.fee);
+                }
+                self.market_data.trade(&symbol, price, quantity);
+                (AmendmentType::Correction, trade)
+            }
+            None => {
+                self.orderbook.executions.remove(execution_id);
+                (AmendmentType::Bust, traded)
+            }
+        };
+        self.replay_executions(&symbol);
+
+        let record = |order: &Order| {
+            self.order_records
+                .get(&order.id)
+                .cloned()
+                .unwrap_or_else(|| OrderRecord::new(order, Utc::now()))
+        };
+        let amendment = TradeAmendment {
+            amendment_id: format!("{}-{}", symbol, self.orderbook.executions.next_id()),
+            amendment_type,
+            trade,
+            buy_record: record(&buy_order),
+            sell_record: record(&sell_order),
+        };
+        self.amendments.push(amendment);
+    }
+
+    // Builds the positions in `symbol` and its last trade price again from
+    // the executions left after one was busted or corrected
+    fn replay_executions(&mut self, symbol: &str) {
+        self.positions.remove_symbol(symbol);
+        let executions = &self.orderbook.executions;
+        let mut last_trade_price = None;
+        for &execution_id in executions.get_ids(0) {
+            let (Some((buy_order, sell_order)), Some(price), Some(quantity)) = (
+                executions.matches.get(&execution_id),
+                executions.get_price(execution_id),
+                executions.get_quantity(execution_id),
+            ) else {
+                continue;
+            };
+            if buy_order.symbol != symbol {
+                continue;
+            }
+            self.positions.fill(buy_order, price, quantity);
+            self.positions.fill(sell_order, price, quantity);
+            last_trade_price = Some(price);
+        }
+        match last_trade_price {
+            Some(price) => self.last_trade_prices.insert(symbol.to_string(), price),
+            None => self.last_trade_prices.remove(symbol),
+        };
+    }
+
+    // Returns the orders cancelled by the exchange since the last call, so
+    // their owners can be told
+    pub fn take_cancellations(&mut self) -> Vec<Cancellation> {
+        std::mem::take(&mut self.cancellations)
+    }
+
+    // Amends the resting order `order_id` with the quantity, price and ClOrdID
+    // of `replacement`, whose quantity is the new total quantity of the order
+    // including what has been filled. Reducing it at the same price keeps time
+    // priority; a price change or quantity increase requeues the order. Either
+    // way matching is re-run, since the amended order may now be marketable,
+    // or small enough for an all-or-none condition to be met.
+    pub fn replace_order(
+        &mut self,
+        order_id: u32,
+        mut replacement: Order,
+    ) -> Result<Order, RejectReason> {
+        self.journal(|| Command::ReplaceOrder {
+            order_id,
+            replacement: replacement.clone(),
+        });
+        self.validate_order(&replacement)?;
+        self.price_peg(&mut replacement)?;
+        let resting = self
+            .orderbook
+            .get_order(&replacement.symbol, &replacement.side, order_id)
+            .ok_or(RejectReason::UnknownOrder)?
+            .to_owned();
+        // Participants only see their own orders
+        if resting.participant != replacement.participant {
+            return Err(RejectReason::UnknownOrder);
+        }
+        // The quantity is the new total, of which what has been filled stays
+        // filled and the rest is left open
+        let cum_quantity = self
+            .order_records
+            .get(&order_id)
+            .map_or(0, |record| record.cum_quantity);
+        let order_quantity = replacement.quantity;
+        if order_quantity <= cum_quantity {
+            return Err(RejectReason::QuantityNotAboveFilled {
+                quantity: replacement.quantity,
+                cum_quantity,
+            });
+        }
+        replacement.quantity -= cum_quantity;
+        // Priority is kept unless the open quantity grows beyond what rests,
+        // which self-trade prevention may have cut below the order's leaves
+        let keeps_priority = replacement.price == resting.price
+            && replacement.quantity <= resting.quantity
+            && replacement.peg == resting.peg
+            && replacement.hidden == resting.hidden
+            && replacement.min_quantity == resting.min_quantity
+            && replacement.all_or_none == resting.all_or_none;
+        self.check_risk(&replacement, Some(&resting))?;
+
+        let replaced = if keeps_priority {
+            let mut reduced = self
+                .orderbook
+                .reduce_order(&resting, replacement.quantity)
+                .ok_or(RejectReason::UnknownOrder)?;
+            reduced.cl_ord_id = replacement.cl_ord_id.to_owned();
+            self.risk_manager.order_removed(&resting);
+            self.risk_manager.order_added(&reduced);
+            self.update_record(order_id, |record, now| {
+                record.replace(&reduced, order_quantity, now)
+            });
+            reduced
+        } else {
+            let mut requeued = self
+                .orderbook
+                .remove_order(resting)
+                .ok_or(RejectReason::UnknownOrder)?;
+            self.risk_manager.order_removed(&requeued);
+            requeued.quantity = replacement.quantity;
+            requeued.price = replacement.price;
+            requeued.sequence = self.order_ids.next_id();
+            requeued.cl_ord_id = replacement.cl_ord_id.to_owned();
+            requeued.peg = replacement.peg;
+            requeued.hidden = replacement.hidden;
+            requeued.min_quantity = replacement.min_quantity;
+            requeued.all_or_none = replacement.all_or_none;
+            self.risk_manager.order_added(&requeued);
+            self.update_record(order_id, |record, now| {
+                record.replace(&requeued, order_quantity, now)
+            });
+            self.orderbook.add_order(requeued.clone());
+            requeued
+        };
+        self.match_orders(&replaced.symbol);
+
+        if !replaced.cl_ord_id.is_empty() {
+            self.cl_ord_ids
+                .insert(cl_ord_id_key(&replaced), replaced.id);
+        }
+        self.market_data.book_changed(&replaced.symbol);
+        Ok(replaced)
+    }
+
+    pub fn get_order_id(&self, participant: &str, cl_ord_id: &str) -> Option<u32> {
+        self.cl_ord_ids
+            .get(&(participant.to_string(), cl_ord_id.to_string()))
+            .copied()
+    }
+
+    // Takes `order` off the book, returning it as it rested
+    pub fn cancel_order(&mut self, order: Order) -> Option<Order> {
+        self.journal(|| Command::CancelOrder(order.clone()));
+        self.remove_order(order)
+    }
+
+    fn remove_order(&mut self, order: Order) -> Option<Order> {
+        let removed = self.orderbook.remove_order(order)?;
+        self.update_record(removed.id, |record, now| {
+            record.cancel(removed.quantity, now)
+        });
+        self.risk_manager.order_removed(&removed);
+        self.market_data.book_changed(&removed.symbol);
+        // Pegged orders follow the touch if the order was on it
+        self.match_orders(&removed.symbol);
+        Some(removed)
+    }
+
+    // Cancels every resting order `mass_cancel` applies to, by symbol and in
+    // priority order on each side, and returns them
+    pub fn mass_cancel(&mut self, mass_cancel: &MassCancel) -> Vec<Order> {
+        self.journal(|| Command::MassCancel(mass_cancel.clone()));
+        let mut symbols: Vec<String> = self.get_active_symbols().into_iter().collect();
+        symbols.sort();
+        let orders: Vec<Order> = symbols
+            .iter()
+            .flat_map(|symbol| self.get_open_orders(symbol))
+            .filter(|order| mass_cancel.matches(order))
+            .cloned()
+            .collect();
+        orders
+            .into_iter()
+            .filter_map(|order| self.remove_order(order))
+            .collect()
+    }
+
+    // Replaces the quote of the entry's participant in its symbol with both
+    // sides of the entry at once. Nothing matches until both legs are in
+    // place, and if either is rejected the previous quote stands. The legs
+    // keep their order ids from one quote to the next, and a side requoted at
+    // the same price for no more size keeps its time priority.
+    pub fn quote(&mut self, entry: QuoteEntry) -> Result<(), RejectReason> {
+        self.journal(|| Command::Quote(entry.clone()));
+        let symbol = &entry.symbol;
+        if self.reference_data.get_instrument(symbol).is_none() {
+            return Err(RejectReason::UnknownSymbol(symbol.to_owned()));
+        }
+        entry.check_spread()?;
+        let quote = self
+            .orderbook
+            .get_quote(&entry.participant, symbol)
+            .cloned();
+        let mut legs = Vec::new();
+        for side in [Side::Buy, Side::Sell] {
+            let resting = quote.as_ref().and_then(|quote| {
+                self.orderbook
+                    .get_order(symbol, &side, quote.leg_id(&side))
+                    .cloned()
+            });
+            let leg = entry.leg(side, 0);
+            if let Some(leg) = &leg {
+                self.validate_order(leg)?;
+                self.check_risk(leg, resting.as_ref())?;
+            }
+            legs.push((resting, leg));
+        }
+
+        let quote = match quote {
+            Some(quote) => quote,
+            None => {
+                let quote = Quote {
+                    participant: entry.participant.to_owned(),
+                    symbol: symbol.to_owned(),
+                    bid_id: self.order_ids.next_id(),
+                    offer_id: self.order_ids.next_id(),
+                };
+                self.orderbook.add_quote(quote.clone());
+                quote
+            }
+        };
+        for (resting, leg) in legs {
+            let leg = leg.map(|mut leg| {
+                leg.id = quote.leg_id(&leg.side);
+                leg
+            });
+            self.requote(resting, leg);
+        }
+        self.market_data.book_changed(symbol);
+        self.match_orders(symbol);
+        Ok(())
     }
 
-    pub fn cancel_order(&mut self, order: Order) {
-        self.orderbook.remove_order(order);
+    // Puts `leg` on the book in place of the `resting` leg of a quote, or
+    // just takes the resting leg off. A leg keeps one record under its order
+    // id, so one coming back after it filled or was pulled carries on the
+    // record of the leg before it, fills included.
+    fn requote(&mut self, resting: Option<Order>, leg: Option<Order>) {
+        let resting = resting.and_then(|resting| self.orderbook.remove_order(resting));
+        if let Some(resting) = &resting {
+            self.risk_manager.order_removed(resting);
+        }
+        match (resting, leg) {
+            (None, None) => {}
+            (Some(resting), None) => self.update_record(resting.id, |record, now| {
+                record.cancel(resting.quantity, now)
+            }),
+            (resting, Some(mut leg)) => {
+                leg.sequence = match resting {
+                    Some(resting)
+                        if leg.price == resting.price && leg.quantity <= resting.quantity =>
+                    {
+                        resting.sequence
+                    }
+                    _ => self.order_ids.next_id(),
+                };
+                match self.order_records.get_mut(&leg.id) {
+                    Some(record) => {
+                        record.replace(&leg, record.cum_quantity + leg.quantity, Utc::now())
+                    }
+                    None => {
+                        self.order_records
+                            .insert(leg.id, OrderRecord::new(&leg, Utc::now()));
+                    }
+                }
+                self.risk_manager.order_added(&leg);
+                self.orderbook.add_order(leg);
+            }
+        }
+    }
+
+    // Pulls both legs of every quote `quote_cancel` applies to, by symbol,
+    // and returns the legs that were resting. The quotes keep their order
+    // ids for the next time they are quoted.
+    pub fn cancel_quotes(&mut self, quote_cancel: &QuoteCancel) -> Vec<Order> {
+        self.journal(|| Command::CancelQuotes(quote_cancel.clone()));
+        let mut quotes: Vec<Quote> = self
+            .orderbook
+            .get_quotes()
+            .filter(|quote| quote_cancel.matches(quote))
+            .cloned()
+            .collect();
+        quotes.sort_by(|quote, other| quote.symbol.cmp(&other.symbol));
+        let legs: Vec<Order> = quotes
+            .iter()
+            .flat_map(|quote| {
+                [Side::Buy, Side::Sell].into_iter().filter_map(|side| {
+                    self.orderbook
+                        .get_order(&quote.symbol, &side, quote.leg_id(&side))
+                        .cloned()
+                })
+            })
+            .collect();
+        legs.into_iter()
+            .filter_map(|leg| self.remove_order(leg))
+            .collect()
+    }
+
+    // The quote of `participant` in `symbol`, whether or not either leg is
+    // resting
+    pub fn get_quote(&self, participant: &str, symbol: &str) -> Option<&Quote> {
+        self.orderbook.get_quote(participant, symbol)
+    }
+
+    fn update_record(
+        &mut self,
+        order_id: u32,
+        update: impl FnOnce(&mut OrderRecord, DateTime<Utc>),
+    ) {
+        match self.order_records.get_mut(&order_id) {
+            Some(record) => update(record, Utc::now()),
+            None => log_warn!("No record of order {}", order_id),
+        }
+    }
+
+    // The record of order `order_id`, which outlives the order on the book
+    pub fn get_order_record(&self, order_id: u32) -> Option<&OrderRecord> {
+        self.order_records.get(&order_id)
+    }
+
+    // The record of `order_id`, only when the order is `participant`'s own
+    pub fn get_participant_order_record(
+        &self,
+        participant: &str,
+        order_id: u32,
+    ) -> Option<&OrderRecord> {
+        self.get_order_record(order_id)
+            .filter(|record| record.order.participant == participant)
+    }
+
+    // Publishes the market data changes since the last call, one update per
+    // changed symbol
+    pub fn take_market_data(&mut self) -> Vec<MarketDataUpdate> {
+        let books = self
+            .market_data
+            .changed_symbols()
+            .into_iter()
+            .map(|symbol| {
+                let depth = self.get_depth(&symbol, 0);
+                (symbol, depth)
+            })
+            .collect();
+        self.market_data.publish(books)
+    }
+
+    // The sequence number of the last market data update for `symbol`
+    pub fn get_market_data_sequence(&self, symbol: &str) -> u64 {
+        self.market_data.get_sequence(symbol)
     }
 
     pub fn check_execution(&self, order_id: u32) -> HashSet<(Order, Order)> {
@@ -37,20 +1230,195 @@ impl Exchange {
             .collect()
     }
 
+    pub fn get_fills(&self) -> Vec<(u32, u32, Price, u32)> {
+        self.orderbook.executions.get_fills()
+    }
+
     pub fn get_open_orders(&self, symbol: &str) -> Vec<&Order> {
         let mut orders = Vec::new();
 
         if let Some(buy_list) = self.orderbook.buy_orders.get(symbol) {
-            orders.extend(buy_list.iter().map(|order| order));
+            orders.extend(buy_list.iter());
         }
 
         if let Some(sell_list) = self.orderbook.sell_orders.get(symbol) {
-            orders.extend(sell_list.iter().map(|order| order));
+            orders.extend(sell_list.iter());
         }
 
         orders
     }
 
+    pub fn get_top_of_book(&self, symbol: &str) -> TopOfBook {
+        TopOfBook::from(&self.get_depth(symbol, 1))
+    }
+
+    // The price levels on each side of `symbol`, up to `levels` of them or
+    // the whole book for 0
+    pub fn get_depth(&self, symbol: &str, levels: usize) -> MarketDepth {
+        MarketDepth {
+            bids: self
+                .orderbook
+                .buy_orders
+                .get(symbol)
+                .map(|orders| depth::take_levels(orders, levels))
+                .unwrap_or_default(),
+            asks: self
+                .orderbook
+                .sell_orders
+                .get(symbol)
+                .map(|orders| depth::take_levels(orders, levels))
+                .unwrap_or_default(),
+        }
+    }
+
+    // The displayed orders on each side of `symbol`
+    pub fn get_order_depth(&self, symbol: &str) -> OrderDepth {
+        let displayed = |orders: &BookSide| {
+            orders
+                .iter()
+                .filter(|order| !order.hidden)
+                .cloned()
+                .collect()
+        };
+        OrderDepth {
+            bids: self
+                .orderbook
+                .buy_orders
+                .get(symbol)
+                .map(displayed)
+                .unwrap_or_default(),
+            asks: self
+                .orderbook
+                .sell_orders
+                .get(symbol)
+                .map(displayed)
+                .unwrap_or_default(),
+        }
+    }
+
+    // The books, order records, quotes, executions and last trade prices, to
+    // be restored in the next run
+    pub fn snapshot(&self) -> Snapshot {
+        let mut symbols: Vec<String> = self.get_active_symbols().into_iter().collect();
+        symbols.sort();
+        let orders: Vec<Order> = symbols
+            .iter()
+            .flat_map(|symbol| self.get_open_orders(symbol))
+            .cloned()
+            .collect();
+        let mut records: Vec<OrderRecord> = self.order_records.values().cloned().collect();
+        records.sort_by_key(|record| record.order.id);
+        let executions = &self.orderbook.executions;
+        let executions: Vec<Execution> = executions
+            .get_ids(0)
+            .iter()
+            .filter_map(|&execution_id| {
+                let (buy_order, sell_order) = executions.matches.get(&execution_id)?.to_owned();
+                Some(Execution {
+                    execution_id,
+                    buy_order,
+                    sell_order,
+                    price: executions.get_price(execution_id)?,
+                    quantity: executions.get_quantity(execution_id)?,
+                    aggressor: executions.get_aggressor(execution_id).cloned(),
+                })
+            })
+            .collect();
+        let mut quotes: Vec<Quote> = self.orderbook.get_quotes().cloned().collect();
+        quotes.sort_by(|quote, other| {
+            (&quote.symbol, &quote.participant).cmp(&(&other.symbol, &other.participant))
+        });
+        let last_order_id = orders
+            .iter()
+            .chain(records.iter().map(|record| &record.order))
+            .map(|order| order.id.max(order.sequence))
+            .chain(quotes.iter().map(|quote| quote.bid_id.max(quote.offer_id)))
+            .max()
+            .unwrap_or(0);
+        Snapshot {
+            sequence: 0,
+            last_order_id,
+            last_execution_id: self.orderbook.executions.get_last_id(),
+            orders,
+            records,
+            quotes,
+            executions,
+            last_trade_prices: self
+                .last_trade_prices
+                .iter()
+                .map(|(symbol, price)| (symbol.to_owned(), *price))
+                .collect(),
+        }
+    }
+
+    // Restores `snapshot` into an exchange that has not taken any orders
+    // yet. Resting orders without a record, as in a snapshot written by hand,
+    // are given a new one.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.order_ids.skip_past(snapshot.last_order_id);
+        for record in &snapshot.records {
+            let order = &record.order;
+            if !order.cl_ord_id.is_empty() {
+                // As when they came in, rejected orders don't take over the
+                // ClOrdID of an accepted one
+                if record.status == OrderStatus::Rejected {
+                    self.cl_ord_ids
+                        .entry(cl_ord_id_key(order))
+                        .or_insert(order.id);
+                } else {
+                    self.cl_ord_ids.insert(cl_ord_id_key(order), order.id);
+                }
+            }
+            self.order_records.insert(order.id, record.clone());
+        }
+        for order in &snapshot.orders {
+            self.order_records
+                .entry(order.id)
+                .or_insert_with(|| OrderRecord::new(order, Utc::now()));
+            self.risk_manager.order_added(order);
+            self.orderbook.add_order(order.clone());
+            self.market_data.book_changed(&order.symbol);
+        }
+        for quote in &snapshot.quotes {
+            self.orderbook.add_quote(quote.clone());
+        }
+        self.orderbook
+            .executions
+            .skip_past(snapshot.last_execution_id);
+        for execution in &snapshot.executions {
+            let execution_id = execution.execution_id;
+            self.orderbook.executions.insert(
+                execution_id,
+                (execution.buy_order.clone(), execution.sell_order.clone()),
+                execution.price,
+                execution.quantity,
+                execution.aggressor.clone(),
+            );
+            self.risk_manager
+                .fill_restored(&execution.buy_order, execution.quantity);
+            self.risk_manager
+                .fill_restored(&execution.sell_order, execution.quantity);
+            for order in [&execution.buy_order, &execution.sell_order] {
+                self.positions
+                    .fill(order, execution.price, execution.quantity);
+                // Already reported, but still part of the day's fees
+                let side = self.trade_side(execution_id, order, execution.quantity);
+                self.fees.charge(
+                    &side.participant,
+                    side.liquidity,
+                    execution.quantity,
+                    side.fee,
+                );
+            }
+        }
+        self.last_trade_prices.extend(
+            snapshot
+                .last_trade_prices
+                .iter()
+                .map(|(symbol, price)| (symbol.to_owned(), *price)),
+        );
+    }
+
     pub fn get_active_symbols(&self) -> HashSet<String> {
         let mut symbols = HashSet::new();
         symbols.extend(self.orderbook.buy_orders.keys().cloned());
@@ -59,6 +1427,16 @@ impl Exchange {
     }
 }
 
+impl Default for Exchange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn cl_ord_id_key(order: &Order) -> (String, String) {
+    (order.participant.to_owned(), order.cl_ord_id.to_owned())
+}
+
 #[test]
 fn test_new_exchange() {
     let exchange = Exchange::new();
@@ -68,9 +1446,11 @@ fn test_new_exchange() {
 #[test]
 fn test_add_orders() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut exchange = Exchange::new();
-    let order = Order::new("AAPL", 100, 150.0, Side::Buy);
-    exchange.execute_order(order.clone());
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order.clone()).unwrap();
     assert_eq!(exchange.get_open_orders("AAPL").len(), 1);
     assert_eq!(exchange.get_open_orders("AAPL")[0], &order);
 }
@@ -78,20 +1458,24 @@ fn test_add_orders() {
 #[test]
 fn test_match_orders() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut exchange = Exchange::new();
-    let order1 = Order::new("AAPL", 10, 100.0, Side::Buy);
-    let order2 = Order::new("AAPL", 10, 100.0, Side::Sell);
-    exchange.execute_order(order1);
-    exchange.execute_order(order2);
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 10, Price::from(100), Side::Buy);
+    let order2 = Order::new("AAPL", 10, Price::from(100), Side::Sell);
+    exchange.execute_order(order1).unwrap();
+    exchange.execute_order(order2).unwrap();
     assert_eq!(exchange.get_open_orders("AAPL").len(), 0);
 }
 
 #[test]
 fn test_cancel_orders() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut exchange = Exchange::new();
-    let order = Order::new("AAPL", 100, 150.0, Side::Buy);
-    exchange.execute_order(order.clone());
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order.clone()).unwrap();
     exchange.cancel_order(order);
     assert!(exchange.get_open_orders("AAPL").is_empty());
 }
@@ -99,11 +1483,13 @@ fn test_cancel_orders() {
 #[test]
 fn test_get_executions() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut exchange = Exchange::new();
-    let order1 = Order::new("AAPL", 10, 100.0, Side::Buy);
-    let order2 = Order::new("AAPL", 10, 100.0, Side::Sell);
-    exchange.execute_order(order1.clone());
-    exchange.execute_order(order2.clone());
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 10, Price::from(100), Side::Buy);
+    let order2 = Order::new("AAPL", 10, Price::from(100), Side::Sell);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
     assert_eq!(exchange.get_executions().len(), 1);
     assert_eq!(exchange.get_executions()[0], (order1, order2));
 }
@@ -111,24 +1497,1579 @@ fn test_get_executions() {
 #[test]
 fn test_get_open_orders() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut exchange = Exchange::new();
-    let order1 = Order::new("AAPL", 10, 100.0, Side::Buy);
-    let order2 = Order::new("AAPL", 10, 100.0, Side::Sell);
-    exchange.execute_order(order1.clone());
-    exchange.execute_order(order2.clone());
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 10, Price::from(100), Side::Buy);
+    let order2 = Order::new("AAPL", 10, Price::from(100), Side::Sell);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
     assert_eq!(exchange.get_open_orders("AAPL").len(), 0);
 }
 
 #[test]
 fn test_get_active_symbols() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut exchange = Exchange::new();
-    let order1 = Order::new("AAPL", 10, 100.0, Side::Buy);
-    let order2 = Order::new("GOOG", 10, 100.0, Side::Sell);
-    exchange.execute_order(order1.clone());
-    exchange.execute_order(order2.clone());
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange.add_instrument(Instrument::new("GOOG"));
+    let order1 = Order::new("AAPL", 10, Price::from(100), Side::Buy);
+    let order2 = Order::new("GOOG", 10, Price::from(100), Side::Sell);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
     let mut symbols = HashSet::new();
     symbols.insert("AAPL".to_string());
     symbols.insert("GOOG".to_string());
     assert_eq!(exchange.get_active_symbols(), symbols);
 }
+
+#[test]
+fn test_replace_reduce_quantity_keeps_priority() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
+    let replacement = Order::new("AAPL", 60, Price::from(150), Side::Buy);
+    let replaced = exchange.replace_order(order1.id, replacement).unwrap();
+    assert_eq!(replaced.id, order1.id);
+    assert_eq!(replaced.quantity, 60);
+    let open_orders = exchange.get_open_orders("AAPL");
+    assert_eq!(open_orders[0], &order1);
+    assert_eq!(open_orders[0].quantity, 60);
+    assert_eq!(open_orders[1], &order2);
+}
+
+#[test]
+fn test_replace_increase_quantity_loses_priority() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
+    let replacement = Order::new("AAPL", 200, Price::from(150), Side::Buy);
+    exchange.replace_order(order1.id, replacement).unwrap();
+    let open_orders = exchange.get_open_orders("AAPL");
+    assert_eq!(open_orders[0], &order2);
+    assert_eq!(open_orders[1], &order1);
+    assert_eq!(open_orders[1].quantity, 200);
+}
+
+#[test]
+fn test_replace_price_change_loses_priority() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
+    let replacement = Order::new("AAPL", 50, Price::from(151), Side::Sell);
+    exchange.replace_order(order1.id, replacement).unwrap();
+    let replacement = Order::new("AAPL", 50, Price::from(150), Side::Sell);
+    exchange.replace_order(order1.id, replacement).unwrap();
+    let open_orders = exchange.get_open_orders("AAPL");
+    assert_eq!(open_orders[0], &order2);
+    assert_eq!(open_orders[1], &order1);
+}
+
+#[test]
+fn test_replace_marketable_order_matches() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(140), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
+    assert_eq!(exchange.get_open_orders("AAPL").len(), 2);
+    let replacement = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.replace_order(order1.id, replacement).unwrap();
+    assert!(exchange.get_open_orders("AAPL").is_empty());
+    assert_eq!(exchange.check_execution(order1.id).len(), 1);
+}
+
+#[test]
+fn test_replace_partially_filled_order() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
+    exchange
+        .execute_order(Order::new("AAPL", 60, Price::from(150), Side::Sell))
+        .unwrap();
+
+    // The same total quantity leaves the 40 still open, in the same place
+    let replacement = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let replaced = exchange.replace_order(order1.id, replacement).unwrap();
+    assert_eq!(replaced.quantity, 40);
+    let record = exchange.get_order_record(order1.id).unwrap();
+    assert_eq!((record.order.quantity, record.leaves_quantity), (100, 40));
+    let open_orders = exchange.get_open_orders("AAPL");
+    assert_eq!(open_orders[0], &order1);
+    assert_eq!(open_orders[1], &order2);
+
+    let replacement = Order::new("AAPL", 60, Price::from(150), Side::Buy);
+    assert_eq!(
+        exchange.replace_order(order1.id, replacement),
+        Err(RejectReason::QuantityNotAboveFilled {
+            quantity: 60,
+            cum_quantity: 60,
+        })
+    );
+
+    let replacement = Order::new("AAPL", 100, Price::from(151), Side::Buy);
+    let replaced = exchange.replace_order(order1.id, replacement).unwrap();
+    assert_eq!(replaced.quantity, 40);
+    exchange
+        .execute_order(Order::new("AAPL", 100, Price::from(151), Side::Sell))
+        .unwrap();
+    let record = exchange.get_order_record(order1.id).unwrap();
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (100, 0));
+    assert_eq!(exchange.get_open_orders("AAPL")[1].quantity, 60);
+}
+
+#[test]
+fn test_replace_unknown_order() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order1.clone()).unwrap();
+    let replacement = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    assert_eq!(
+        exchange.replace_order(order1.id, replacement),
+        Err(RejectReason::UnknownOrder)
+    );
+    let replacement = Order::new("AAPL", 0, Price::from(150), Side::Buy);
+    assert_eq!(
+        exchange.replace_order(order1.id, replacement),
+        Err(RejectReason::InvalidQuantity)
+    );
+    // Another participant's order cannot be amended
+    let mut replacement = Order::new("AAPL", 50, Price::from(150), Side::Buy);
+    replacement.participant = "FIRM2".to_string();
+    assert_eq!(
+        exchange.replace_order(order1.id, replacement),
+        Err(RejectReason::UnknownOrder)
+    );
+    assert_eq!(exchange.get_open_orders("AAPL")[0].quantity, 100);
+}
+
+#[test]
+fn test_get_order_id() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order1.cl_ord_id = "ORD1".to_string();
+    exchange.execute_order(order1.clone()).unwrap();
+    assert_eq!(exchange.get_order_id("", "ORD1"), Some(order1.id));
+    let mut replacement = Order::new("AAPL", 50, Price::from(150), Side::Buy);
+    replacement.cl_ord_id = "ORD2".to_string();
+    exchange.replace_order(order1.id, replacement).unwrap();
+    assert_eq!(exchange.get_order_id("", "ORD2"), Some(order1.id));
+    assert_eq!(exchange.get_order_id("", "ORD3"), None);
+
+    // ClOrdIDs are only unique per participant
+    let mut order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order2.cl_ord_id = "ORD2".to_string();
+    order2.participant = "FIRM2".to_string();
+    exchange.execute_order(order2.clone()).unwrap();
+    assert_eq!(exchange.get_order_id("", "ORD2"), Some(order1.id));
+    assert_eq!(exchange.get_order_id("FIRM2", "ORD2"), Some(order2.id));
+}
+
+#[test]
+fn test_reject_off_tick_orders() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    let mut instrument = Instrument::new("AAPL");
+    instrument.tick_sizes = vec![(Price::ZERO, "0.05".parse().unwrap())];
+    exchange.add_instrument(instrument);
+    exchange.add_instrument(Instrument::new("GOOG"));
+    let order1 = Order::new("AAPL", 100, "150.05".parse().unwrap(), Side::Buy);
+    exchange.execute_order(order1.clone()).unwrap();
+    let order2 = Order::new("AAPL", 100, "150.01".parse().unwrap(), Side::Buy);
+    assert_eq!(
+        exchange.execute_order(order2),
+        Err(RejectReason::OffTick {
+            price: "150.01".parse().unwrap(),
+            tick_size: "0.05".parse().unwrap(),
+        })
+    );
+    let replacement = Order::new("AAPL", 100, "150.07".parse().unwrap(), Side::Buy);
+    assert!(exchange.replace_order(order1.id, replacement).is_err());
+    assert_eq!(exchange.get_open_orders("AAPL").len(), 1);
+    let order3 = Order::new("GOOG", 100, "150.01".parse().unwrap(), Side::Buy);
+    assert!(exchange.execute_order(order3).is_ok());
+}
+
+#[test]
+fn test_match_orders_exact_prices() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order1 = Order::new("AAPL", 10, "0.3".parse().unwrap(), Side::Buy);
+    let order2 = Order::new(
+        "AAPL",
+        10,
+        "0.1".parse::<Price>().unwrap() + "0.2".parse::<Price>().unwrap(),
+        Side::Sell,
+    );
+    exchange.execute_order(order1).unwrap();
+    exchange.execute_order(order2).unwrap();
+    assert_eq!(exchange.get_executions().len(), 1);
+}
+
+#[test]
+fn test_reject_unknown_symbol() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order = Order::new("TSLA", 100, Price::from(150), Side::Buy);
+    assert_eq!(
+        exchange.execute_order(order),
+        Err(RejectReason::UnknownSymbol("TSLA".to_string()))
+    );
+    assert!(exchange.get_active_symbols().is_empty());
+}
+
+#[test]
+fn test_reject_invalid_quantity() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut instrument = Instrument::new("AAPL");
+    instrument.lot_size = 100;
+    instrument.max_quantity = 1000;
+    let mut reference_data = ReferenceData::new();
+    reference_data.add_instrument(instrument);
+    let mut exchange = Exchange::with_config(ExchangeConfig {
+        reference_data,
+        ..Default::default()
+    });
+    let order = Order::new("AAPL", 50, Price::from(150), Side::Buy);
+    assert!(matches!(
+        exchange.execute_order(order),
+        Err(RejectReason::OddLot { .. })
+    ));
+    let order = Order::new("AAPL", 2000, Price::from(150), Side::Buy);
+    assert!(matches!(
+        exchange.execute_order(order),
+        Err(RejectReason::QuantityOutOfRange { .. })
+    ));
+    assert!(exchange.get_open_orders("AAPL").is_empty());
+}
+
+#[test]
+fn test_reject_risk_breach() {
+    use super::risk::RiskBreach;
+    use crate::config::ANY;
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange.set_risk_limits(
+        "FIRM1",
+        ANY,
+        RiskLimits {
+            max_order_quantity: Some(1000),
+            max_open_orders: Some(1),
+            ..Default::default()
+        },
+    );
+    let mut order1 = Order::new("AAPL", 2000, Price::from(150), Side::Buy);
+    order1.participant = "FIRM1".to_string();
+    assert_eq!(
+        exchange.execute_order(order1.clone()),
+        Err(RejectReason::RiskBreach(RiskBreach::MaxOrderQuantity {
+            quantity: 2000,
+            limit: 1000
+        }))
+    );
+    order1.quantity = 500;
+    exchange.execute_order(order1.clone()).unwrap();
+    let mut order2 = Order::new("AAPL", 500, Price::from(140), Side::Buy);
+    order2.participant = "FIRM1".to_string();
+    assert_eq!(
+        exchange.execute_order(order2.clone()),
+        Err(RejectReason::RiskBreach(RiskBreach::MaxOpenOrders {
+            limit: 1
+        }))
+    );
+    let mut replacement = Order::new("AAPL", 400, Price::from(150), Side::Buy);
+    replacement.participant = "FIRM1".to_string();
+    assert!(exchange.replace_order(order1.id, replacement).is_ok());
+    exchange.cancel_order(order1);
+    assert!(exchange.execute_order(order2).is_ok());
+}
+
+#[test]
+fn test_risk_positions_and_price_deviation() {
+    use crate::config::ANY;
+    use crate::order::Side;
+    let mut instrument = Instrument::new("AAPL");
+    instrument.reference_price = Some(Price::from(100));
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(instrument);
+    exchange.set_risk_limits(
+        ANY,
+        ANY,
+        RiskLimits {
+            max_price_deviation_bps: Some(1000),
+            ..Default::default()
+        },
+    );
+    let mut buy = Order::new("AAPL", 100, Price::from(105), Side::Buy);
+    buy.participant = "FIRM1".to_string();
+    let mut sell = Order::new("AAPL", 60, Price::from(105), Side::Sell);
+    sell.participant = "FIRM2".to_string();
+    exchange.execute_order(buy).unwrap();
+    exchange.execute_order(sell).unwrap();
+    assert_eq!(exchange.get_reference_price("AAPL"), Some(Price::from(105)));
+    let risk_manager = exchange.get_risk_manager();
+    assert_eq!(risk_manager.get_net_position("FIRM1", "AAPL"), 60);
+    assert_eq!(risk_manager.get_net_position("FIRM2", "AAPL"), -60);
+    assert_eq!(risk_manager.get_open_orders("FIRM1", "AAPL"), 1);
+    assert_eq!(risk_manager.get_open_orders("FIRM2", "AAPL"), 0);
+    let order = Order::new("AAPL", 100, Price::from(116), Side::Sell);
+    assert!(matches!(
+        exchange.execute_order(order),
+        Err(RejectReason::RiskBreach(_))
+    ));
+}
+
+#[test]
+fn test_self_trade_prevention() {
+    use super::cancellation::CancelReason;
+    use crate::order::{SelfTradePrevention, Side};
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order1 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    order1.participant = "FIRM1".to_string();
+    let mut order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order2.participant = "FIRM1".to_string();
+    order2.self_trade_prevention = Some(SelfTradePrevention::CancelOldest);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2.clone()).unwrap();
+    assert!(exchange.get_executions().is_empty());
+    let cancellations = exchange.take_cancellations();
+    assert_eq!(cancellations.len(), 1);
+    assert_eq!(cancellations[0].order, order1);
+    assert_eq!(
+        cancellations[0].reason,
+        CancelReason::SelfTradePrevention(SelfTradePrevention::CancelOldest)
+    );
+    assert!(exchange.take_cancellations().is_empty());
+    assert_eq!(exchange.get_open_orders("AAPL"), vec![&order2]);
+    let risk_manager = exchange.get_risk_manager();
+    assert_eq!(risk_manager.get_open_orders("FIRM1", "AAPL"), 1);
+    assert_eq!(risk_manager.get_net_position("FIRM1", "AAPL"), 0);
+}
+
+#[test]
+fn test_self_trade_prevention_decrement_and_cancel() {
+    use crate::order::{SelfTradePrevention, Side};
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order1 = Order::new("AAPL", 30, Price::from(100), Side::Sell);
+    order1.participant = "FIRM1".to_string();
+    let mut order2 = Order::new("AAPL", 70, Price::from(100), Side::Sell);
+    order2.participant = "FIRM2".to_string();
+    let mut order3 = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    order3.participant = "FIRM1".to_string();
+    order3.self_trade_prevention = Some(SelfTradePrevention::DecrementAndCancel);
+    for order in [&order1, &order2, &order3] {
+        exchange.execute_order(order.clone()).unwrap();
+    }
+
+    // 30 is cancelled off both FIRM1 orders before the rest of the buy
+    // fills against FIRM2
+    let cancellations = exchange.take_cancellations();
+    assert_eq!(cancellations.len(), 2);
+    assert_eq!(cancellations[0].order, order3);
+    assert_eq!(cancellations[0].leaves_quantity(), 70);
+    assert_eq!(cancellations[1].order, order1);
+    assert_eq!(cancellations[1].leaves_quantity(), 0);
+    assert_eq!(
+        exchange.get_fills(),
+        vec![(order3.id, order2.id, Price::from(100), 70)]
+    );
+    assert!(exchange.get_open_orders("AAPL").is_empty());
+
+    let record = exchange.get_order_record(order3.id).unwrap();
+    assert_eq!(record.status, OrderStatus::Filled);
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (70, 0));
+    assert_eq!(
+        exchange.get_order_record(order1.id).unwrap().status,
+        OrderStatus::Canceled
+    );
+    let risk_manager = exchange.get_risk_manager();
+    assert_eq!(risk_manager.get_open_orders("FIRM1", "AAPL"), 0);
+    assert_eq!(risk_manager.get_net_position("FIRM1", "AAPL"), 70);
+    assert_eq!(risk_manager.get_net_position("FIRM2", "AAPL"), -70);
+}
+
+#[test]
+fn test_replace_after_self_trade_decrement() {
+    use crate::order::{SelfTradePrevention, Side};
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order1 = Order::new("AAPL", 100, Price::from(100), Side::Sell);
+    order1.participant = "FIRM1".to_string();
+    let mut order2 = Order::new("AAPL", 100, Price::from(100), Side::Sell);
+    order2.participant = "FIRM2".to_string();
+    let mut order3 = Order::new("AAPL", 30, Price::from(100), Side::Buy);
+    order3.participant = "FIRM1".to_string();
+    order3.self_trade_prevention = Some(SelfTradePrevention::DecrementAndCancel);
+    for order in [&order1, &order2, &order3] {
+        exchange.execute_order(order.clone()).unwrap();
+    }
+    assert_eq!(exchange.get_open_orders("AAPL")[0].quantity, 70);
+
+    // Sending the original total back reopens the 30 and loses priority
+    let mut replacement = Order::new("AAPL", 100, Price::from(100), Side::Sell);
+    replacement.participant = "FIRM1".to_string();
+    let replaced = exchange.replace_order(order1.id, replacement).unwrap();
+    assert_eq!(replaced.quantity, 100);
+    let open_orders = exchange.get_open_orders("AAPL");
+    assert_eq!(open_orders[0], &order2);
+    assert_eq!(open_orders[1], &order1);
+    assert_eq!(open_orders[1].quantity, 100);
+    assert_eq!(
+        exchange.get_risk_manager().get_open_orders("FIRM1", "AAPL"),
+        1
+    );
+}
+
+#[test]
+fn test_self_trade_prevention_cancel_both() {
+    use super::cancellation::CancelReason;
+    use crate::order::{SelfTradePrevention, Side};
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order1 = Order::new("AAPL", 50, Price::from(100), Side::Sell);
+    order1.participant = "FIRM1".to_string();
+    let mut order2 = Order::new("AAPL", 50, Price::from(100), Side::Sell);
+    order2.participant = "FIRM2".to_string();
+    let mut order3 = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    order3.participant = "FIRM1".to_string();
+    order3.self_trade_prevention = Some(SelfTradePrevention::CancelBoth);
+    for order in [&order1, &order2, &order3] {
+        exchange.execute_order(order.clone()).unwrap();
+    }
+
+    // Both FIRM1 orders go in full, so FIRM2 is left untouched
+    let cancellations = exchange.take_cancellations();
+    assert_eq!(cancellations.len(), 2);
+    assert_eq!(cancellations[0].order, order3);
+    assert_eq!(cancellations[1].order, order1);
+    assert!(cancellations.iter().all(|cancellation| {
+        cancellation.leaves_quantity() == 0
+            && cancellation.reason
+                == CancelReason::SelfTradePrevention(SelfTradePrevention::CancelBoth)
+    }));
+    assert!(exchange.get_executions().is_empty());
+    assert_eq!(exchange.get_open_orders("AAPL"), vec![&order2]);
+    for order in [&order1, &order3] {
+        assert_eq!(
+            exchange.get_order_record(order.id).unwrap().status,
+            OrderStatus::Canceled
+        );
+    }
+    let risk_manager = exchange.get_risk_manager();
+    assert_eq!(risk_manager.get_open_orders("FIRM1", "AAPL"), 0);
+    assert_eq!(risk_manager.get_open_orders("FIRM2", "AAPL"), 1);
+}
+
+#[test]
+fn test_call_auction() {
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange.start_auction("AAPL");
+    assert!(exchange.is_in_auction("AAPL"));
+    let order1 = Order::new("AAPL", 100, Price::from(103), Side::Buy);
+    let order2 = Order::new("AAPL", 200, Price::from(102), Side::Buy);
+    let order3 = Order::new("AAPL", 300, Price::from(100), Side::Buy);
+    let order4 = Order::new("AAPL", 200, Price::from(99), Side::Sell);
+    let order5 = Order::new("AAPL", 200, Price::from(101), Side::Sell);
+    let order6 = Order::new("AAPL", 300, Price::from(104), Side::Sell);
+    for order in [&order1, &order2, &order3, &order4, &order5, &order6] {
+        exchange.execute_order(order.clone()).unwrap();
+    }
+    assert!(exchange.get_executions().is_empty());
+    assert_eq!(exchange.get_open_orders("AAPL").len(), 6);
+
+    let equilibrium = exchange.get_indicative_equilibrium("AAPL").unwrap();
+    assert_eq!(exchange.uncross("AAPL"), Some(equilibrium));
+    assert_eq!(equilibrium.price, Price::from(101));
+    assert_eq!(equilibrium.volume, 300);
+    assert!(!exchange.is_in_auction("AAPL"));
+    let traded: u32 = exchange
+        .get_executions()
+        .iter()
+        .map(|(buy_order, sell_order)| buy_order.quantity.min(sell_order.quantity))
+        .sum();
+    assert_eq!(traded, 300);
+    assert_eq!(exchange.get_reference_price("AAPL"), Some(Price::from(101)));
+    let open_orders = exchange.get_open_orders("AAPL");
+    assert_eq!(open_orders.len(), 3);
+    assert_eq!(open_orders[0], &order3);
+    assert_eq!(open_orders[1], &order5);
+    assert_eq!(open_orders[1].quantity, 100);
+
+    // Back in continuous trading
+    let order7 = Order::new("AAPL", 100, Price::from(101), Side::Buy);
+    exchange.execute_order(order7).unwrap();
+    assert_eq!(exchange.get_open_orders("AAPL").len(), 2);
+}
+
+#[test]
+fn test_trading_phases() {
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange.add_instrument(Instrument::new("GOOG"));
+    assert_eq!(exchange.get_market_phase(), Some(TradingPhase::Continuous));
+    assert_eq!(
+        exchange.set_trading_phase("AAPL", TradingPhase::OpeningAuction),
+        Err(RejectReason::InvalidPhaseTransition {
+            from: TradingPhase::Continuous,
+            to: TradingPhase::OpeningAuction
+        })
+    );
+    assert!(exchange
+        .set_trading_phase("TSLA", TradingPhase::Halted)
+        .is_err());
+
+    let changes = exchange
+        .set_trading_phase(ANY, TradingPhase::Closed)
+        .unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(exchange.get_market_phase(), Some(TradingPhase::Closed));
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    assert_eq!(
+        exchange.execute_order(order),
+        Err(RejectReason::TradingPhase(TradingPhase::Closed))
+    );
+
+    exchange
+        .set_trading_phase(ANY, TradingPhase::PreOpen)
+        .unwrap();
+    exchange
+        .set_trading_phase(ANY, TradingPhase::OpeningAuction)
+        .unwrap();
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(149), Side::Sell);
+    exchange.execute_order(order1).unwrap();
+    exchange.execute_order(order2).unwrap();
+    assert!(exchange.get_executions().is_empty());
+
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::Halted)
+        .unwrap();
+    assert_eq!(exchange.get_market_phase(), None);
+    // A halt is only lifted by resuming
+    assert_eq!(
+        exchange.set_trading_phase(ANY, TradingPhase::Continuous),
+        Err(RejectReason::InvalidPhaseTransition {
+            from: TradingPhase::Halted,
+            to: TradingPhase::Continuous
+        })
+    );
+    let changes = exchange.resume("AAPL").unwrap();
+    assert_eq!(changes[0].from, TradingPhase::Halted);
+    assert_eq!(changes[0].to, TradingPhase::OpeningAuction);
+    let changes = exchange
+        .set_trading_phase(ANY, TradingPhase::Continuous)
+        .unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].equilibrium.unwrap().volume, 100);
+    assert_eq!(changes[1].equilibrium, None);
+    assert_eq!(exchange.get_executions().len(), 1);
+}
+
+#[test]
+fn test_close_halted_auction() {
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::Closed)
+        .unwrap();
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::PreOpen)
+        .unwrap();
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::OpeningAuction)
+        .unwrap();
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(149), Side::Sell);
+    exchange.execute_order(order1).unwrap();
+    exchange.execute_order(order2).unwrap();
+
+    // Closing the halted instrument abandons the auction instead of uncrossing it
+    exchange.halt("AAPL").unwrap();
+    let changes = exchange
+        .set_trading_phase("AAPL", TradingPhase::Closed)
+        .unwrap();
+    assert_eq!(changes[0].from, TradingPhase::Halted);
+    assert_eq!(changes[0].equilibrium, None);
+    assert!(exchange.get_executions().is_empty());
+    assert!(!exchange.is_in_auction("AAPL"));
+}
+
+#[test]
+fn test_trading_schedule() {
+    use crate::order::Side;
+    let mut reference_data = ReferenceData::new();
+    reference_data.add_instrument(Instrument::new("AAPL"));
+    let mut exchange = Exchange::with_config(ExchangeConfig {
+        reference_data,
+        schedule: TradingSchedule::parse(
+            "[*]
+            0 = pre_open
+            1 = continuous
+            2 = closing_auction
+            3 = closed",
+        )
+        .unwrap(),
+        ..Default::default()
+    });
+    assert_eq!(exchange.get_trading_phase("AAPL"), TradingPhase::Closed);
+    assert!(!exchange.is_closed());
+    assert_eq!(exchange.apply_schedule(Duration::ZERO).len(), 1);
+    assert_eq!(exchange.get_trading_phase("AAPL"), TradingPhase::PreOpen);
+    // Orders are taken and can be cancelled before the open, but don't match
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    exchange.execute_order(order.clone()).unwrap();
+    exchange
+        .execute_order(Order::new("AAPL", 100, Price::from(150), Side::Sell))
+        .unwrap();
+    assert!(exchange.get_executions().is_empty());
+    assert!(exchange.cancel_order(order).is_some());
+
+    exchange.apply_schedule(Duration::from_millis(2500));
+    assert_eq!(
+        exchange.get_trading_phase("AAPL"),
+        TradingPhase::ClosingAuction
+    );
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    exchange.execute_order(order1).unwrap();
+    exchange.execute_order(order2).unwrap();
+    assert!(exchange.get_executions().is_empty());
+    let changes = exchange.apply_schedule(Duration::from_secs(3));
+    assert_eq!(changes[0].to, TradingPhase::Closed);
+    assert_eq!(
+        changes[0].equilibrium.map(|equilibrium| equilibrium.price),
+        Some(Price::from(150))
+    );
+    assert!(exchange.is_closed());
+}
+
+#[test]
+fn test_schedule_does_not_lift_halt() {
+    let mut reference_data = ReferenceData::new();
+    reference_data.add_instrument(Instrument::new("AAPL"));
+    reference_data.add_instrument(Instrument::new("GOOG"));
+    let mut exchange = Exchange::with_config(ExchangeConfig {
+        reference_data,
+        schedule: TradingSchedule::parse(
+            "[*]
+            0 = pre_open
+            1 = continuous
+            2 = closed",
+        )
+        .unwrap(),
+        ..Default::default()
+    });
+    exchange.apply_schedule(Duration::ZERO);
+    exchange.halt("AAPL").unwrap();
+
+    let changes = exchange.apply_schedule(Duration::from_secs(1));
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].symbol, "GOOG");
+    assert_eq!(exchange.get_trading_phase("AAPL"), TradingPhase::Halted);
+    // The close still ends the day of a halted instrument
+    assert_eq!(exchange.apply_schedule(Duration::from_secs(2)).len(), 2);
+    assert!(exchange.is_closed());
+}
+
+#[test]
+fn test_volatility_interruption() {
+    use crate::order::Side;
+    let mut instrument = Instrument::new("AAPL");
+    instrument.reference_price = Some(Price::from(100));
+    instrument.dynamic_band_bps = Some(500);
+    instrument.volatility_auction_duration = Duration::from_secs(2);
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(instrument);
+    assert_eq!(
+        exchange.get_dynamic_band("AAPL"),
+        Some((Price::from(95), Price::from(105)))
+    );
+
+    let order1 = Order::new("AAPL", 100, Price::from(101), Side::Sell);
+    let order2 = Order::new("AAPL", 100, Price::from(130), Side::Sell);
+    exchange.execute_order(order1).unwrap();
+    exchange.execute_order(order2).unwrap();
+    // A fat-fingered buy trades inside the band, then stops before 130
+    let order3 = Order::new("AAPL", 200, Price::from(150), Side::Buy);
+    exchange.execute_order(order3).unwrap();
+    assert_eq!(exchange.get_executions().len(), 1);
+    assert_eq!(
+        exchange.get_trading_phase("AAPL"),
+        TradingPhase::VolatilityAuction
+    );
+    let changes = exchange.take_phase_changes();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].from, TradingPhase::Continuous);
+    assert_eq!(changes[0].to, TradingPhase::VolatilityAuction);
+    assert!(exchange.take_phase_changes().is_empty());
+
+    assert!(exchange.apply_schedule(Duration::from_secs(1)).is_empty());
+    let changes = exchange.apply_schedule(Duration::from_secs(2));
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].to, TradingPhase::Continuous);
+    assert_eq!(
+        changes[0].equilibrium.map(|equilibrium| equilibrium.price),
+        Some(Price::from(130))
+    );
+    assert_eq!(exchange.get_executions().len(), 2);
+    assert_eq!(exchange.get_reference_price("AAPL"), Some(Price::from(130)));
+}
+
+#[test]
+fn test_market_wide_halt() {
+    use crate::order::Side;
+    let mut instrument = Instrument::new("AAPL");
+    instrument.reference_price = Some(Price::from(100));
+    instrument.dynamic_band_bps = Some(500);
+    instrument.volatility_auction_duration = Duration::from_secs(2);
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(instrument);
+    exchange.add_instrument(Instrument::new("GOOG"));
+    exchange
+        .execute_order(Order::new("AAPL", 100, Price::from(120), Side::Sell))
+        .unwrap();
+    exchange
+        .execute_order(Order::new("AAPL", 100, Price::from(120), Side::Buy))
+        .unwrap();
+    assert_eq!(
+        exchange.get_trading_phase("AAPL"),
+        TradingPhase::VolatilityAuction
+    );
+
+    assert_eq!(exchange.halt(ANY).unwrap().len(), 2);
+    assert_eq!(exchange.get_market_phase(), Some(TradingPhase::Halted));
+    let order = Order::new("GOOG", 100, Price::from(150), Side::Buy);
+    assert_eq!(
+        exchange.execute_order(order),
+        Err(RejectReason::TradingPhase(TradingPhase::Halted))
+    );
+    // The volatility auction does not end while halted
+    assert!(exchange.apply_schedule(Duration::from_secs(3)).is_empty());
+
+    let changes = exchange.resume(ANY).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(
+        exchange.get_trading_phase("AAPL"),
+        TradingPhase::VolatilityAuction
+    );
+    assert_eq!(exchange.get_trading_phase("GOOG"), TradingPhase::Continuous);
+    assert!(exchange.resume("GOOG").unwrap().is_empty());
+    assert!(exchange.resume("TSLA").is_err());
+
+    // Resuming restarts the volatility auction in full
+    assert!(exchange.apply_schedule(Duration::from_secs(4)).is_empty());
+    let changes = exchange.apply_schedule(Duration::from_secs(5));
+    assert_eq!(changes[0].to, TradingPhase::Continuous);
+    assert_eq!(exchange.get_executions().len(), 1);
+}
+
+#[cfg(test)]
+fn allocate_with(instrument: Instrument) -> Vec<(u32, u32, Price, u32)> {
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(instrument);
+    let mut order_ids = Vec::new();
+    for (quantity, participant) in [(30, "A"), (50, "MM"), (20, "C")] {
+        let mut order = Order::new("ES", quantity, Price::from(100), Side::Sell);
+        order.participant = participant.to_string();
+        order_ids.push(order.id);
+        exchange.execute_order(order).unwrap();
+    }
+    let order = Order::new("ES", 50, Price::from(101), Side::Buy);
+    order_ids.push(order.id);
+    exchange.execute_order(order).unwrap();
+    // Order ids renumbered by arrival, so runs can be compared
+    exchange
+        .get_fills()
+        .into_iter()
+        .map(|(buy_order_id, sell_order_id, price, quantity)| {
+            let position = |id| {
+                order_ids
+                    .iter()
+                    .position(|order_id| *order_id == id)
+                    .unwrap()
+            };
+            (
+                position(buy_order_id) as u32,
+                position(sell_order_id) as u32,
+                price,
+                quantity,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_matching_algorithms() {
+    use super::matching::MatchingRule;
+    let mut instrument = Instrument::new("ES");
+    let price = Price::from(100);
+    assert_eq!(
+        allocate_with(instrument.clone()),
+        vec![(3, 0, price, 30), (3, 1, price, 20)]
+    );
+
+    instrument.matching_rule = MatchingRule::ProRata;
+    let fills = allocate_with(instrument.clone());
+    assert_eq!(
+        fills,
+        vec![(3, 0, price, 15), (3, 1, price, 25), (3, 2, price, 10)]
+    );
+    assert_eq!(allocate_with(instrument.clone()), fills);
+
+    instrument.matching_rule = MatchingRule::ProRataTopOrder;
+    assert_eq!(
+        allocate_with(instrument.clone()),
+        vec![(3, 0, price, 30), (3, 1, price, 15), (3, 2, price, 5)]
+    );
+
+    instrument.matching_rule = MatchingRule::LeadMarketMaker;
+    instrument.lead_market_makers = vec!["MM".to_string()];
+    instrument.lmm_allocation_percent = 40;
+    // 20 to MM, then 30 over 30, 30 and 20
+    assert_eq!(
+        allocate_with(instrument),
+        vec![(3, 0, price, 12), (3, 1, price, 20 + 11), (3, 2, price, 7)]
+    );
+}
+
+#[test]
+fn test_book_depth() {
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    assert_eq!(exchange.get_top_of_book("AAPL"), TopOfBook::default());
+    let orders = [
+        Order::new("AAPL", 100, Price::from(99), Side::Buy),
+        Order::new("AAPL", 200, Price::from(99), Side::Buy),
+        Order::new("AAPL", 100, Price::from(98), Side::Buy),
+        Order::new("AAPL", 300, Price::from(101), Side::Sell),
+    ];
+    for order in &orders {
+        exchange.execute_order(order.clone()).unwrap();
+    }
+
+    let top_of_book = exchange.get_top_of_book("AAPL");
+    let bid = top_of_book.bid.unwrap();
+    assert_eq!(
+        (bid.price, bid.quantity, bid.order_count),
+        (Price::from(99), 300, 2)
+    );
+    assert_eq!(top_of_book.ask.unwrap().quantity, 300);
+
+    let depth = exchange.get_depth("AAPL", 0);
+    assert_eq!(depth.bids.len(), 2);
+    assert_eq!(depth.asks.len(), 1);
+    assert_eq!(exchange.get_depth("AAPL", 1).bids.len(), 1);
+
+    let order_depth = exchange.get_order_depth("AAPL");
+    assert_eq!(order_depth.bids, orders[..3].to_vec());
+    assert_eq!(order_depth.asks, orders[3..].to_vec());
+}
+
+#[test]
+fn test_market_data_updates() {
+    use super::marketdata::UpdateAction;
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::Closed)
+        .unwrap();
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::PreOpen)
+        .unwrap();
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::OpeningAuction)
+        .unwrap();
+    let order1 = Order::new("AAPL", 100, Price::from(101), Side::Buy);
+    let order2 = Order::new("AAPL", 60, Price::from(100), Side::Sell);
+    exchange.execute_order(order1.clone()).unwrap();
+    exchange.execute_order(order2).unwrap();
+    let updates = exchange.take_market_data();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].sequence, 1);
+    assert_eq!(updates[0].entries.len(), 2);
+    assert!(exchange.take_market_data().is_empty());
+
+    exchange
+        .set_trading_phase("AAPL", TradingPhase::Continuous)
+        .unwrap();
+    let updates = exchange.take_market_data();
+    let entry_types: Vec<(MarketDataEntryType, UpdateAction)> = updates[0]
+        .entries
+        .iter()
+        .map(|entry| (entry.entry_type, entry.action))
+        .collect();
+    assert_eq!(
+        entry_types,
+        vec![
+            (MarketDataEntryType::Trade, UpdateAction::New),
+            (MarketDataEntryType::TradeVolume, UpdateAction::Change),
+            (MarketDataEntryType::OpeningPrice, UpdateAction::New),
+            (MarketDataEntryType::Bid, UpdateAction::Change),
+            (MarketDataEntryType::Offer, UpdateAction::Delete),
+        ]
+    );
+    assert_eq!(updates[0].entries[3].size, 40);
+
+    let mut resting = order1;
+    resting.quantity = 40;
+    exchange.cancel_order(resting);
+    let updates = exchange.take_market_data();
+    assert_eq!(updates[0].sequence, 3);
+    assert_eq!(updates[0].entries[0].action, UpdateAction::Delete);
+    assert_eq!(exchange.get_market_data_sequence("AAPL"), 3);
+}
+
+#[test]
+fn test_participant_order_record() {
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    order.participant = "FIRM1".to_string();
+    exchange.execute_order(order.clone()).unwrap();
+    let record = exchange.get_participant_order_record("FIRM1", order.id);
+    assert_eq!(record.unwrap().order.id, order.id);
+    // Another participant cannot look the order up by its id
+    assert!(exchange
+        .get_participant_order_record("FIRM2", order.id)
+        .is_none());
+    assert!(exchange.get_participant_order_record("FIRM1", 0).is_none());
+}
+
+#[test]
+fn test_order_records() {
+    use super::orderrecord::OrderStatus;
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut order1 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    order1.cl_ord_id = "ORD1".to_string();
+    let order2 = Order::new("AAPL", 40, Price::from(151), Side::Buy);
+    let order3 = Order::new("AAPL", 10, Price::from(150), Side::Buy);
+    exchange.execute_order(order1.clone()).unwrap();
+    assert_eq!(
+        exchange.get_order_record(order1.id).unwrap().status,
+        OrderStatus::New
+    );
+    exchange.execute_order(order2.clone()).unwrap();
+    exchange.execute_order(order3.clone()).unwrap();
+
+    let record = exchange.get_order_record(order1.id).unwrap();
+    assert_eq!(record.status, OrderStatus::PartiallyFilled);
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (50, 50));
+    assert_eq!(record.average_price(), Price::from(150));
+    // Filled orders are off the book but still on record
+    let record = exchange.get_order_record(order2.id).unwrap();
+    assert_eq!(record.status, OrderStatus::Filled);
+    assert_eq!(record.leaves_quantity, 0);
+
+    let replacement = Order::new("AAPL", 80, Price::from(150), Side::Sell);
+    exchange.replace_order(order1.id, replacement).unwrap();
+    let record = exchange.get_order_record(order1.id).unwrap();
+    assert_eq!(record.status, OrderStatus::Replaced);
+    assert_eq!(record.order.quantity, 80);
+    assert_eq!(record.leaves_quantity, 30);
+    assert_eq!(record.original_quantity, 100);
+    let mut resting = order1.clone();
+    resting.quantity = 30;
+    exchange.cancel_order(resting);
+    let record = exchange.get_order_record(order1.id).unwrap();
+    assert_eq!(record.status, OrderStatus::Canceled);
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (50, 0));
+    assert!(record.updated_at >= record.created_at);
+
+    let order4 = Order::new("AAPL", 0, Price::from(150), Side::Buy);
+    assert!(exchange.execute_order(order4.clone()).is_err());
+    assert_eq!(
+        exchange.get_order_record(order4.id).unwrap().status,
+        OrderStatus::Rejected
+    );
+}
+
+#[test]
+fn test_mass_cancel() {
+    use super::orderrecord::OrderStatus;
+    use crate::order::Side;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange.add_instrument(Instrument::new("GOOG"));
+    let mut orders = Vec::new();
+    for (symbol, side, participant) in [
+        ("AAPL", Side::Buy, "FIRM1"),
+        ("AAPL", Side::Sell, "FIRM1"),
+        ("GOOG", Side::Buy, "FIRM1"),
+        ("AAPL", Side::Buy, "FIRM2"),
+    ] {
+        let price = match side {
+            Side::Buy => Price::from(100),
+            Side::Sell => Price::from(110),
+        };
+        let mut order = Order::new(symbol, 100, price, side);
+        order.participant = participant.to_string();
+        exchange.execute_order(order.clone()).unwrap();
+        orders.push(order);
+    }
+
+    let mut mass_cancel = MassCancel {
+        participant: "FIRM1".to_string(),
+        symbol: Some("AAPL".to_string()),
+        side: Some(Side::Sell),
+    };
+    assert_eq!(exchange.mass_cancel(&mass_cancel), vec![orders[1].clone()]);
+    assert_eq!(
+        exchange.get_order_record(orders[1].id).unwrap().status,
+        OrderStatus::Canceled
+    );
+
+    mass_cancel.symbol = None;
+    mass_cancel.side = None;
+    assert_eq!(
+        exchange.mass_cancel(&mass_cancel),
+        vec![orders[0].clone(), orders[2].clone()]
+    );
+    assert!(exchange.mass_cancel(&mass_cancel).is_empty());
+    assert_eq!(exchange.get_open_orders("AAPL"), vec![&orders[3]]);
+    assert_eq!(
+        exchange.get_risk_manager().get_open_orders("FIRM1", "AAPL"),
+        0
+    );
+}
+
+#[cfg(test)]
+fn pegged_order(side: Side, peg_type: PegType, offset: &str) -> Order {
+    let mut order = Order::new("AAPL", 100, Price::ZERO, side);
+    order.peg = Some(Peg {
+        peg_type,
+        offset: offset.parse().unwrap(),
+        limit: None,
+    });
+    order
+}
+
+#[cfg(test)]
+use crate::order::{Peg, PegType};
+
+#[test]
+fn test_pegged_orders_follow_touch() {
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    assert_eq!(
+        exchange.execute_order(pegged_order(Side::Buy, PegType::Primary, "0")),
+        Err(RejectReason::NoPegReference)
+    );
+    let bid = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    let ask = Order::new("AAPL", 100, Price::from(101), Side::Sell);
+    exchange.execute_order(bid.clone()).unwrap();
+    exchange.execute_order(ask.clone()).unwrap();
+
+    let peg = pegged_order(Side::Buy, PegType::Primary, "-0.5");
+    exchange.execute_order(peg.clone()).unwrap();
+    let depth = exchange.get_order_depth("AAPL");
+    assert_eq!(depth.bids[1], peg);
+    assert_eq!(depth.bids[1].price, "99.5".parse().unwrap());
+
+    // A better bid moves the peg up, behind the orders at its new price
+    let better = Order::new("AAPL", 100, "100.5".parse().unwrap(), Side::Buy);
+    let joined = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    exchange.execute_order(better.clone()).unwrap();
+    exchange.execute_order(joined.clone()).unwrap();
+    let bids = exchange.get_order_depth("AAPL").bids;
+    assert_eq!(bids, vec![better.clone(), bid.clone(), joined, peg.clone()]);
+    assert_eq!(
+        exchange.get_order_record(peg.id).unwrap().order.price,
+        Price::from(100)
+    );
+
+    exchange.cancel_order(better);
+    let bids = exchange.get_order_depth("AAPL").bids;
+    assert_eq!(bids[2], peg);
+    assert_eq!(bids[2].price, "99.5".parse().unwrap());
+    assert!(exchange.get_fills().is_empty());
+
+    // A market peg takes the offer as soon as it arrives
+    let market = pegged_order(Side::Buy, PegType::Market, "0");
+    exchange.execute_order(market.clone()).unwrap();
+    assert_eq!(
+        exchange.get_fills(),
+        vec![(market.id, ask.id, Price::from(101), 100)]
+    );
+
+    // An offset that takes the price out of range rejects the order
+    let wide = pegged_order(Side::Buy, PegType::Primary, "9223372036854");
+    assert_eq!(
+        exchange.execute_order(wide),
+        Err(RejectReason::InvalidPegPrice {
+            reference: Price::from(100),
+            offset: "9223372036854".parse().unwrap(),
+        })
+    );
+}
+
+#[test]
+fn test_midpoint_pegs_trade_at_mid() {
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    exchange
+        .execute_order(Order::new("AAPL", 100, Price::from(100), Side::Buy))
+        .unwrap();
+    let ask = Order::new("AAPL", 100, Price::from(102), Side::Sell);
+    exchange.execute_order(ask.clone()).unwrap();
+
+    let buy = pegged_order(Side::Buy, PegType::Midpoint, "0");
+    let sell = pegged_order(Side::Sell, PegType::Midpoint, "0");
+    exchange.execute_order(buy.clone()).unwrap();
+    assert_eq!(
+        exchange.get_order_depth("AAPL").bids[0].price,
+        Price::from(101)
+    );
+    exchange.execute_order(sell.clone()).unwrap();
+    assert_eq!(
+        exchange.get_fills(),
+        vec![(buy.id, sell.id, Price::from(101), 100)]
+    );
+
+    // An offset through the touch trades with it
+    let buy = pegged_order(Side::Buy, PegType::Midpoint, "1");
+    exchange.execute_order(buy.clone()).unwrap();
+    assert_eq!(
+        exchange.get_fills()[1],
+        (buy.id, ask.id, Price::from(102), 100)
+    );
+    assert!(exchange.get_order_depth("AAPL").asks.is_empty());
+}
+
+#[test]
+fn test_midpoint_pegs_rest_on_tick() {
+    let mut instrument = Instrument::new("AAPL");
+    instrument.tick_sizes = vec![(Price::ZERO, "0.01".parse().unwrap())];
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(instrument);
+    let bid = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    exchange.execute_order(bid).unwrap();
+    let ask = Order::new("AAPL", 100, "100.03".parse().unwrap(), Side::Sell);
+    exchange.execute_order(ask).unwrap();
+
+    // The midpoint of 100.015 is rounded to the tick on the passive side
+    let buy = pegged_order(Side::Buy, PegType::Midpoint, "0");
+    exchange.execute_order(buy.clone()).unwrap();
+    let price = exchange.get_order_record(buy.id).unwrap().order.price;
+    assert_eq!(price, "100.01".parse().unwrap());
+    let sell = pegged_order(Side::Sell, PegType::Midpoint, "0");
+    exchange.execute_order(sell.clone()).unwrap();
+    let price = exchange.get_order_record(sell.id).unwrap().order.price;
+    assert_eq!(price, "100.02".parse().unwrap());
+    assert!(exchange.get_fills().is_empty());
+}
+
+#[test]
+fn test_hidden_orders() {
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut hidden = Order::new("AAPL", 100, Price::from(101), Side::Sell);
+    hidden.hidden = true;
+    exchange.execute_order(hidden.clone()).unwrap();
+    assert_eq!(exchange.get_depth("AAPL", 0), MarketDepth::default());
+    assert!(exchange.get_order_depth("AAPL").asks.is_empty());
+    assert!(exchange.take_market_data().is_empty());
+
+    // A displayed order at the same price trades first, even though it came
+    // later, and a hidden midpoint peg is priced from the displayed orders
+    let displayed = Order::new("AAPL", 100, Price::from(101), Side::Sell);
+    exchange.execute_order(displayed.clone()).unwrap();
+    exchange
+        .execute_order(Order::new("AAPL", 100, Price::from(99), Side::Buy))
+        .unwrap();
+    let mut midpoint = pegged_order(Side::Buy, PegType::Midpoint, "0");
+    midpoint.hidden = true;
+    exchange.execute_order(midpoint.clone()).unwrap();
+    assert_eq!(exchange.get_top_of_book("AAPL").bid.unwrap().quantity, 100);
+    assert_eq!(exchange.get_open_orders("AAPL")[0].price, Price::from(100));
+
+    let buy = Order::new("AAPL", 150, Price::from(101), Side::Buy);
+    exchange.execute_order(buy.clone()).unwrap();
+    assert_eq!(
+        exchange.get_fills(),
+        vec![
+            (buy.id, displayed.id, Price::from(101), 100),
+            (buy.id, hidden.id, Price::from(101), 50)
+        ]
+    );
+    assert!(exchange.get_depth("AAPL", 0).asks.is_empty());
+}
+
+#[test]
+fn test_positions_from_fills() {
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let order = |participant: &str, quantity, price, side| {
+        let mut order = Order::new("AAPL", quantity, Price::from(price), side);
+        order.participant = participant.to_string();
+        order.account = "ACC1".to_string();
+        order
+    };
+    exchange
+        .execute_order(order("FIRM1", 100, 150, Side::Buy))
+        .unwrap();
+    exchange
+        .execute_order(order("FIRM2", 100, 150, Side::Sell))
+        .unwrap();
+    exchange
+        .execute_order(order("FIRM1", 40, 155, Side::Sell))
+        .unwrap();
+    exchange
+        .execute_order(order("FIRM2", 40, 155, Side::Buy))
+        .unwrap();
+
+    let positions = exchange.get_positions("FIRM1", None, None);
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].net_quantity, 60);
+    assert_eq!(positions[0].average_cost(), Some(Price::from(150)));
+    assert_eq!(positions[0].realized_pnl(), Price::from(200));
+    let mark_price = exchange.get_last_trade_price("AAPL").unwrap();
+    assert_eq!(positions[0].unrealized_pnl(mark_price), Price::from(300));
+    let short = exchange.get_positions("FIRM2", Some("ACC1"), Some("AAPL"));
+    assert_eq!(short[0].net_quantity, -60);
+    assert_eq!(short[0].realized_pnl(), Price::from(-200));
+
+    // Positions are rebuilt from the executions of a snapshot
+    let mut restored = Exchange::new();
+    restored.add_instrument(Instrument::new("AAPL"));
+    restored.restore(&exchange.snapshot());
+    assert_eq!(
+        restored.get_positions("FIRM1", None, None),
+        exchange.get_positions("FIRM1", None, None)
+    );
+}
+
+#[test]
+fn test_trades_for_clearing() {
+    let mut instrument = Instrument::new("AAPL");
+    instrument.settlement_days = 1;
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(instrument);
+    let mut sell = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    sell.participant = "FIRM2".to_string();
+    exchange.assign_order_id(&mut sell);
+    exchange.execute_order(sell.clone()).unwrap();
+    let mut buy = Order::new("AAPL", 60, Price::from(151), Side::Buy);
+    buy.participant = "FIRM1".to_string();
+    buy.account = "ACC1".to_string();
+    exchange.assign_order_id(&mut buy);
+    exchange.execute_order(buy.clone()).unwrap();
+
+    let trades = exchange.take_trades();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].trade_id, "AAPL-1");
+    assert_eq!(trades[0].price, Price::from(150));
+    assert_eq!(trades[0].quantity, 60);
+    assert_eq!(trades[0].currency, "USD");
+    assert_eq!(
+        trades[0].settlement_date,
+        trade::settlement_date(trades[0].trade_date, 1)
+    );
+    assert_eq!(trades[0].buyer.participant, "FIRM1");
+    assert_eq!(trades[0].buyer.account, "ACC1");
+    assert_eq!(trades[0].buyer.order_id, buy.id);
+    assert_eq!(trades[0].seller.participant, "FIRM2");
+    assert_eq!(trades[0].seller.side, Side::Sell);
+    assert!(exchange.take_trades().is_empty());
+}
+
+#[test]
+fn test_maker_taker_fees() {
+    use super::fee::Liquidity;
+    let mut exchange = Exchange::with_config(ExchangeConfig {
+        fee_schedule: FeeSchedule::parse(
+            "[*]
+            maker_fee = -0.002
+            taker_fee = 0.003",
+        )
+        .unwrap(),
+        ..Default::default()
+    });
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut sell = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    sell.participant = "FIRM2".to_string();
+    exchange.assign_order_id(&mut sell);
+    exchange.execute_order(sell).unwrap();
+    let mut buy = Order::new("AAPL", 60, Price::from(151), Side::Buy);
+    buy.participant = "FIRM1".to_string();
+    exchange.assign_order_id(&mut buy);
+    exchange.execute_order(buy).unwrap();
+
+    let trades = exchange.take_trades();
+    let buyer = &trades[0].buyer;
+    assert_eq!(buyer.liquidity, Liquidity::Removed);
+    assert_eq!(buyer.fee, "0.18".parse().unwrap());
+    assert_eq!(buyer.leaves_quantity, 0);
+    let seller = &trades[0].seller;
+    assert_eq!(seller.liquidity, Liquidity::Added);
+    assert_eq!(seller.fee, "-0.12".parse().unwrap());
+    assert_eq!(seller.leaves_quantity, 40);
+
+    let fees = exchange.get_fees();
+    assert_eq!(
+        fees.get("FIRM1").unwrap().taker_fees,
+        "0.18".parse().unwrap()
+    );
+    assert_eq!(
+        fees.get("FIRM2").unwrap().maker_fees,
+        "-0.12".parse().unwrap()
+    );
+}
+
+#[test]
+fn test_bust_and_correct_trades() {
+    let mut exchange = Exchange::with_config(ExchangeConfig {
+        fee_schedule: FeeSchedule::parse("[*]\ntaker_fee = 0.01").unwrap(),
+        ..Default::default()
+    });
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let mut orders = Vec::new();
+    for (participant, quantity, price, side) in [
+        ("FIRM2", 100, 150, Side::Sell),
+        ("FIRM1", 60, 151, Side::Buy),
+        ("FIRM1", 40, 152, Side::Buy),
+    ] {
+        let mut order = Order::new("AAPL", quantity, Price::from(price), side);
+        order.participant = participant.to_string();
+        exchange.assign_order_id(&mut order);
+        exchange.execute_order(order.clone()).unwrap();
+        orders.push(order);
+    }
+    let trade_ids: Vec<String> = exchange
+        .take_trades()
+        .into_iter()
+        .map(|trade| trade.trade_id)
+        .collect();
+    assert_eq!(trade_ids, ["AAPL-1", "AAPL-2"]);
+
+    exchange.bust_trade("AAPL-1").unwrap();
+    assert_eq!(
+        exchange.get_fills(),
+        vec![(orders[2].id, orders[0].id, Price::from(150), 40)]
+    );
+    assert_eq!(
+        exchange
+            .positions
+            .get("FIRM1", "", "AAPL")
+            .unwrap()
+            .net_quantity,
+        40
+    );
+    assert_eq!(exchange.risk_manager.get_net_position("FIRM2", "AAPL"), -40);
+    let buy = exchange.get_order_record(orders[1].id).unwrap();
+    assert_eq!(buy.status, OrderStatus::Canceled);
+    assert_eq!(
+        exchange
+            .get_order_record(orders[0].id)
+            .unwrap()
+            .cum_quantity,
+        40
+    );
+    assert_eq!(exchange.get_fees().get("FIRM1").unwrap().fills, 1);
+    let amendments = exchange.take_amendments();
+    assert_eq!(amendments.len(), 1);
+    assert_eq!(amendments[0].amendment_type, AmendmentType::Bust);
+    assert_eq!(amendments[0].amendment_id, "AAPL-3");
+    assert_eq!(amendments[0].trade.trade_id, "AAPL-1");
+
+    exchange
+        .correct_trade("AAPL-2", Price::from(149), 30)
+        .unwrap();
+    let position = exchange.positions.get("FIRM1", "", "AAPL").unwrap();
+    assert_eq!(position.net_quantity, 30);
+    assert_eq!(position.average_cost(), Some(Price::from(149)));
+    assert_eq!(
+        exchange.get_last_trade_price("AAPL"),
+        Some(Price::from(149))
+    );
+    assert_eq!(
+        exchange.get_fees().get("FIRM1").unwrap().taker_fees,
+        "0.3".parse().unwrap()
+    );
+    let amendments = exchange.take_amendments();
+    assert_eq!(amendments[0].amendment_type, AmendmentType::Correction);
+    assert_eq!(amendments[0].trade.quantity, 30);
+    assert_eq!(amendments[0].sell_record.cum_quantity, 30);
+
+    assert_eq!(
+        exchange.bust_trade("AAPL-1"),
+        Err(RejectReason::UnknownTrade("AAPL-1".to_string()))
+    );
+    assert_eq!(
+        exchange.bust_trade("MSFT-2"),
+        Err(RejectReason::UnknownTrade("MSFT-2".to_string()))
+    );
+    assert_eq!(
+        exchange.correct_trade("AAPL-2", Price::from(149), 31),
+        Err(RejectReason::InvalidCorrection {
+            quantity: 31,
+            traded_quantity: 30
+        })
+    );
+
+    // Busted ids are not handed out again, not even after a restore
+    let mut restored = Exchange::new();
+    restored.add_instrument(Instrument::new("AAPL"));
+    restored.restore(&exchange.snapshot());
+    for side in [Side::Sell, Side::Buy] {
+        let mut order = Order::new("AAPL", 10, Price::from(150), side);
+        restored.assign_order_id(&mut order);
+        restored.execute_order(order).unwrap();
+    }
+    assert_eq!(restored.take_trades()[0].trade_id, "AAPL-5");
+}
+
+#[test]
+fn test_mass_quotes() {
+    let mut exchange = Exchange::new();
+    exchange.add_instrument(Instrument::new("AAPL"));
+    let entry = |bid: Option<(i64, u32)>, offer: Option<(i64, u32)>| QuoteEntry {
+        participant: "MM1".to_string(),
+        account: String::new(),
+        quote_set_id: "S1".to_string(),
+        quote_entry_id: "Q1".to_string(),
+        symbol: "AAPL".to_string(),
+        bid: bid.map(|(price, size)| (Price::from(price), size)),
+        offer: offer.map(|(price, size)| (Price::from(price), size)),
+    };
+    let mut resting = Order::new("AAPL", 100, Price::from(149), Side::Buy);
+    exchange.assign_order_id(&mut resting);
+    exchange.execute_order(resting.clone()).unwrap();
+
+    exchange
+        .quote(entry(Some((149, 100)), Some((151, 100))))
+        .unwrap();
+    let quote = exchange.get_quote("MM1", "AAPL").unwrap().clone();
+    let leg = |exchange: &Exchange, side: Side| {
+        exchange
+            .orderbook
+            .get_order("AAPL", &side, quote.leg_id(&side))
+            .cloned()
+    };
+    let bid = leg(&exchange, Side::Buy).unwrap();
+    assert_eq!(
+        (bid.participant.as_str(), bid.cl_ord_id.as_str()),
+        ("MM1", "Q1")
+    );
+    assert_eq!(exchange.get_open_orders("AAPL").len(), 3);
+
+    // Less size at the same price keeps its place behind the resting order,
+    // a new price goes to the back, and neither gets a new order id
+    exchange
+        .quote(entry(Some((149, 50)), Some((152, 100))))
+        .unwrap();
+    assert_eq!(exchange.get_quote("MM1", "AAPL"), Some(&quote));
+    let requoted = leg(&exchange, Side::Buy).unwrap();
+    assert_eq!((requoted.sequence, requoted.quantity), (bid.sequence, 50));
+    assert_eq!(exchange.get_order_depth("AAPL").bids[1].id, quote.bid_id);
+    let offer = leg(&exchange, Side::Sell).unwrap();
+    assert_eq!(offer.price, Price::from(152));
+
+    // A crossed or unknown quote leaves the last one standing
+    assert_eq!(
+        exchange.quote(entry(Some((152, 100)), Some((152, 100)))),
+        Err(RejectReason::CrossedQuote {
+            bid: Price::from(152),
+            offer: Price::from(152),
+        })
+    );
+    let mut unknown = entry(Some((149, 100)), None);
+    unknown.symbol = "MSFT".to_string();
+    assert_eq!(
+        exchange.quote(unknown),
+        Err(RejectReason::UnknownSymbol("MSFT".to_string()))
+    );
+    assert_eq!(leg(&exchange, Side::Sell), Some(offer.clone()));
+
+    // The offer trades like an order and comes back under the same id
+    let mut buy = Order::new("AAPL", 100, Price::from(152), Side::Buy);
+    exchange.assign_order_id(&mut buy);
+    exchange.execute_order(buy.clone()).unwrap();
+    assert_eq!(
+        exchange.get_fills(),
+        vec![(buy.id, quote.offer_id, Price::from(152), 100)]
+    );
+    assert_eq!(leg(&exchange, Side::Sell), None);
+    exchange.quote(entry(None, Some((153, 20)))).unwrap();
+    assert_eq!(leg(&exchange, Side::Buy), None);
+    assert_eq!(leg(&exchange, Side::Sell).unwrap().quantity, 20);
+    // Its record keeps the earlier fill
+    let record = exchange.get_order_record(quote.offer_id).unwrap();
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (100, 20));
+    assert_eq!(record.order.quantity, 120);
+    assert_eq!(record.average_price(), Price::from(152));
+    assert_eq!(
+        exchange.get_risk_manager().get_open_orders("MM1", "AAPL"),
+        1
+    );
+
+    let cancel = QuoteCancel {
+        participant: "MM1".to_string(),
+        symbol: None,
+    };
+    let pulled = exchange.cancel_quotes(&cancel);
+    assert_eq!(pulled.len(), 1);
+    assert_eq!(pulled[0].id, quote.offer_id);
+    assert!(exchange.cancel_quotes(&cancel).is_empty());
+    assert_eq!(exchange.get_open_orders("AAPL"), vec![&resting]);
+
+    // Quotes and their ids carry over into the next run
+    let mut restored = Exchange::new();
+    restored.add_instrument(Instrument::new("AAPL"));
+    restored.restore(&exchange.snapshot());
+    restored
+        .quote(entry(Some((148, 10)), Some((150, 10))))
+        .unwrap();
+    assert_eq!(restored.get_quote("MM1", "AAPL"), Some(&quote));
+    let mut order = Order::new("AAPL", 1, Price::from(140), Side::Buy);
+    restored.assign_order_id(&mut order);
+    assert!(order.id > quote.bid_id.max(quote.offer_id));
+}
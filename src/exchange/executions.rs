@@ -1,11 +1,24 @@
 use std::collections::{HashMap, HashSet};
 
-use crate::order::Order;
+use super::fee::Liquidity;
+use crate::order::{Order, OrderIds, Side};
+use crate::price::Price;
 
+// Executions by id. Ids are never reused, so one stays the same when others
+// are busted.
 #[derive(Debug, Clone)]
 pub struct ExecutionList {
     pub lookup: HashMap<u32, HashSet<usize>>,
     pub matches: HashMap<usize, (Order, Order)>,
+    pub prices: HashMap<usize, Price>,
+    pub quantities: HashMap<usize, u32>,
+    // The side of the incoming order, for executions outside of auctions
+    pub aggressors: HashMap<usize, Side>,
+    // Every execution id in the order the executions happened
+    sequence: Vec<usize>,
+    ids: OrderIds,
+    // The highest id handed out, busted or not
+    last_id: usize,
 }
 
 impl ExecutionList {
@@ -13,19 +26,141 @@ impl ExecutionList {
         ExecutionList {
             lookup: HashMap::new(),
             matches: HashMap::new(),
+            prices: HashMap::new(),
+            quantities: HashMap::new(),
+            aggressors: HashMap::new(),
+            sequence: Vec::new(),
+            ids: OrderIds::default(),
+            last_id: 0,
         }
     }
 
-    pub fn insert(&mut self, execution_id: usize, execution: (Order, Order)) {
+    // Numbers executions with `ids`, as order ids are numbered, so the
+    // executions of different shards never share an id
+    pub fn set_ids(&mut self, ids: OrderIds) {
+        self.ids = ids;
+    }
+
+    pub fn next_id(&mut self) -> usize {
+        let execution_id = self.ids.next_id() as usize;
+        self.last_id = self.last_id.max(execution_id);
+        execution_id
+    }
+
+    // Moves past `execution_id`, which was handed out elsewhere
+    pub fn skip_past(&mut self, execution_id: usize) {
+        self.ids.skip_past(execution_id as u32);
+        self.last_id = self.last_id.max(execution_id);
+    }
+
+    pub fn get_last_id(&self) -> usize {
+        self.last_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.sequence.len()
+    }
+
+    // The ids of the executions from the `from`th one on, in the order they
+    // happened
+    pub fn get_ids(&self, from: usize) -> &[usize] {
+        self.sequence.get(from..).unwrap_or_default()
+    }
+
+    // `execution` holds both orders as they were before the fill, and
+    // `aggressor` the side that took liquidity, if either did
+    pub fn insert(
+        &mut self,
+        execution_id: usize,
+        execution: (Order, Order),
+        price: Price,
+        quantity: u32,
+        aggressor: Option<Side>,
+    ) {
         self.lookup
             .entry(execution.0.id)
-            .or_insert_with(HashSet::new)
+            .or_default()
             .insert(execution_id);
         self.lookup
             .entry(execution.1.id)
-            .or_insert_with(HashSet::new)
+            .or_default()
             .insert(execution_id);
         self.matches.insert(execution_id, execution);
+        self.prices.insert(execution_id, price);
+        self.quantities.insert(execution_id, quantity);
+        if let Some(aggressor) = aggressor {
+            self.aggressors.insert(execution_id, aggressor);
+        }
+        self.sequence.push(execution_id);
+        self.skip_past(execution_id);
+    }
+
+    // Takes a busted execution out, returning both orders as they were
+    // before it
+    pub fn remove(&mut self, execution_id: usize) -> Option<(Order, Order)> {
+        let execution = self.matches.remove(&execution_id)?;
+        for order_id in [execution.0.id, execution.1.id] {
+            if let Some(ids) = self.lookup.get_mut(&order_id) {
+                ids.remove(&execution_id);
+                if ids.is_empty() {
+                    self.lookup.remove(&order_id);
+                }
+            }
+        }
+        self.prices.remove(&execution_id);
+        self.quantities.remove(&execution_id);
+        self.aggressors.remove(&execution_id);
+        self.sequence.retain(|id| *id != execution_id);
+        Some(execution)
+    }
+
+    // Corrects the price and quantity of an execution, which keeps its id
+    // and its place in the sequence
+    pub fn correct(&mut self, execution_id: usize, price: Price, quantity: u32) -> bool {
+        if !self.matches.contains_key(&execution_id) {
+            return false;
+        }
+        self.prices.insert(execution_id, price);
+        self.quantities.insert(execution_id, quantity);
+        true
+    }
+
+    pub fn get_price(&self, execution_id: usize) -> Option<Price> {
+        self.prices.get(&execution_id).copied()
+    }
+
+    pub fn get_quantity(&self, execution_id: usize) -> Option<u32> {
+        self.quantities.get(&execution_id).copied()
+    }
+
+    pub fn get_aggressor(&self, execution_id: usize) -> Option<&Side> {
+        self.aggressors.get(&execution_id)
+    }
+
+    // How the order on `side` of the execution traded
+    pub fn get_liquidity(&self, execution_id: usize, side: &Side) -> Liquidity {
+        match self.get_aggressor(execution_id) {
+            Some(aggressor) if aggressor == side => Liquidity::Removed,
+            Some(_) => Liquidity::Added,
+            None => Liquidity::Auction,
+        }
+    }
+
+    // (buy order id, sell order id, price, quantity) of every execution in the
+    // order they happened, for comparing the outcome of matching runs
+    pub fn get_fills(&self) -> Vec<(u32, u32, Price, u32)> {
+        self.sequence
+            .iter()
+            .filter_map(|&execution_id| {
+                let (buy_order, sell_order) = self.matches.get(&execution_id)?;
+                Some((
+                    buy_order.id,
+                    sell_order.id,
+                    self.get_price(execution_id)?,
+                    self.get_quantity(execution_id)?,
+                ))
+            })
+            .collect()
     }
 
     pub fn get_matches_for_id(&self, order_id: u32) -> HashSet<(Order, Order)> {
@@ -49,27 +184,85 @@ fn test_new_execution_list() {
 #[test]
 fn test_insert_execution() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut executions = ExecutionList::new();
-    let order1 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
     let execution = (order1.clone(), order2.clone());
-    executions.insert(1, execution.clone());
+    executions.insert(1, execution.clone(), Price::from(150), 100, None);
     assert_eq!(executions.lookup.len(), 2);
     assert_eq!(executions.matches.len(), 1);
     assert_eq!(executions.lookup.get(&order1.id).unwrap().len(), 1);
     assert_eq!(executions.lookup.get(&order2.id).unwrap().len(), 1);
     assert_eq!(executions.matches.get(&1).unwrap().0.id, execution.0.id);
     assert_eq!(executions.matches.get(&1).unwrap().1.id, execution.1.id);
+    assert_eq!(executions.get_price(1), Some(Price::from(150)));
+    assert_eq!(executions.get_price(2), None);
+    assert_eq!(executions.get_quantity(1), Some(100));
+    assert_eq!(
+        executions.get_fills(),
+        vec![(order1.id, order2.id, Price::from(150), 100)]
+    );
+    assert_eq!(executions.get_liquidity(1, &Side::Buy), Liquidity::Auction);
+    executions.insert(
+        2,
+        execution.clone(),
+        Price::from(150),
+        100,
+        Some(Side::Sell),
+    );
+    assert_eq!(executions.get_liquidity(2, &Side::Buy), Liquidity::Added);
+    assert_eq!(executions.get_liquidity(2, &Side::Sell), Liquidity::Removed);
+}
+
+#[test]
+fn test_stable_execution_ids() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let mut executions = ExecutionList::new();
+    executions.set_ids(OrderIds::new(2, 3));
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    for _ in 0..3 {
+        let execution_id = executions.next_id();
+        let execution = (order1.clone(), order2.clone());
+        executions.insert(execution_id, execution, Price::from(150), 10, None);
+    }
+    assert_eq!(executions.get_ids(0), &[2, 5, 8]);
+
+    assert!(executions.remove(5).is_some());
+    assert!(executions.remove(5).is_none());
+    assert_eq!(executions.get_ids(0), &[2, 8]);
+    assert_eq!(executions.get_ids(1), &[8]);
+    assert_eq!(executions.get_matches_for_id(order1.id).len(), 1);
+    // A busted id is not handed out again
+    assert_eq!(executions.get_last_id(), 8);
+    assert_eq!(executions.next_id(), 11);
+
+    assert!(executions.correct(8, Price::from(149), 5));
+    assert!(!executions.correct(5, Price::from(149), 5));
+    assert_eq!(
+        executions.get_fills(),
+        vec![
+            (order1.id, order2.id, Price::from(150), 10),
+            (order1.id, order2.id, Price::from(149), 5)
+        ]
+    );
+    executions.remove(2);
+    executions.remove(8);
+    assert!(executions.get_ids(0).is_empty());
+    assert!(executions.lookup.is_empty());
 }
 
 #[test]
 fn test_lookup_with_order_id() {
     use crate::order::Side;
+    use crate::price::Price;
     let mut executions = ExecutionList::new();
-    let order1 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
     let execution = (order1.clone(), order2.clone());
-    executions.insert(1, execution.clone());
+    executions.insert(1, execution.clone(), Price::from(150), 100, None);
     assert_eq!(
         executions.get_matches_for_id(order1.id).get(&execution),
         Some(&execution)
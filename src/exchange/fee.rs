@@ -0,0 +1,333 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::fs;
+
+use crate::config::{self, ConfigSection, ANY};
+use crate::price::Price;
+
+// How one side of a fill traded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    // The resting order, which made the liquidity
+    Added,
+    // The incoming order, which took it
+    Removed,
+    // Either side of an auction uncross, where neither took from the other
+    Auction,
+}
+
+impl Display for Liquidity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Liquidity::Added => write!(f, "maker"),
+            Liquidity::Removed => write!(f, "taker"),
+            Liquidity::Auction => write!(f, "auction"),
+        }
+    }
+}
+
+// What a fill costs per unit of quantity, by how it traded. Negative rates
+// are rebates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeeRates {
+    pub maker: Price,
+    pub taker: Price,
+    pub auction: Price,
+}
+
+impl FeeRates {
+    pub fn get_rate(&self, liquidity: Liquidity) -> Price {
+        match liquidity {
+            Liquidity::Added => self.maker,
+            Liquidity::Removed => self.taker,
+            Liquidity::Auction => self.auction,
+        }
+    }
+}
+
+// Fee rates per participant tier and symbol, with the tier of each
+// participant. Participants without a tier are in '*'.
+#[derive(Debug, Clone, Default)]
+pub struct FeeSchedule {
+    tiers: HashMap<String, String>,
+    rates: HashMap<(String, String), FeeRates>,
+}
+
+impl FeeSchedule {
+    pub fn new() -> FeeSchedule {
+        FeeSchedule::default()
+    }
+
+    pub fn load(file_path: &str) -> Result<FeeSchedule, String> {
+        FeeSchedule::from_sections(config::read_sections(file_path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<FeeSchedule, String> {
+        FeeSchedule::from_sections(config::parse_sections(contents)?)
+    }
+
+    // Builds the schedule from [TIER] or [TIER:SYMBOL] sections, where either
+    // part may be '*'. Rates are per unit of quantity, and `participants`
+    // puts participants in the tier:
+    //
+    //   [market_maker]
+    //   participants = FIRM1, FIRM2
+    //   maker_fee = -0.003
+    //   taker_fee = 0.003
+    //   auction_fee = 0.001
+    fn from_sections(sections: Vec<ConfigSection>) -> Result<FeeSchedule, String> {
+        let mut schedule = FeeSchedule::new();
+
+        for section in sections {
+            let (tier, symbol) = section.name.split_once(':').unwrap_or((&section.name, ANY));
+            let (tier, symbol) = (tier.trim(), symbol.trim());
+            let mut rates = FeeRates::default();
+            for entry in &section.entries {
+                match entry.key.as_str() {
+                    "participants" => {
+                        for participant in entry.value.split(',') {
+                            let participant = participant.trim();
+                            if !participant.is_empty() {
+                                schedule.set_tier(participant, tier);
+                            }
+                        }
+                    }
+                    "maker_fee" => rates.maker = entry.parse()?,
+                    "taker_fee" => rates.taker = entry.parse()?,
+                    "auction_fee" => rates.auction = entry.parse()?,
+                    _ => return Err(entry.unknown()),
+                }
+            }
+            schedule.set_rates(tier, symbol, rates);
+        }
+        Ok(schedule)
+    }
+
+    pub fn set_tier(&mut self, participant: &str, tier: &str) {
+        self.tiers.insert(participant.to_string(), tier.to_string());
+    }
+
+    pub fn get_tier(&self, participant: &str) -> &str {
+        self.tiers.get(participant).map_or(ANY, String::as_str)
+    }
+
+    pub fn set_rates(&mut self, tier: &str, symbol: &str, rates: FeeRates) {
+        self.rates
+            .insert((tier.to_string(), symbol.to_string()), rates);
+    }
+
+    // The most specific rates for the participant's tier and symbol, falling
+    // back to the '*' wildcards
+    pub fn get_rates(&self, participant: &str, symbol: &str) -> Option<&FeeRates> {
+        let tier = self.get_tier(participant);
+        [(tier, symbol), (tier, ANY), (ANY, symbol), (ANY, ANY)]
+            .iter()
+            .find_map(|(tier, symbol)| self.rates.get(&(tier.to_string(), symbol.to_string())))
+    }
+
+    // The fee for a fill of `quantity`, nothing without a schedule
+    pub fn get_fee(
+        &self,
+        participant: &str,
+        symbol: &str,
+        liquidity: Liquidity,
+        quantity: u32,
+    ) -> Price {
+        self.get_rates(participant, symbol)
+            .map_or(Price::ZERO, |rates| {
+                Price::from_raw(
+                    rates
+                        .get_rate(liquidity)
+                        .raw()
+                        .saturating_mul(quantity as i64),
+                )
+            })
+    }
+}
+
+// The fees of one participant over the day
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeeSummary {
+    pub participant: String,
+    pub fills: usize,
+    pub quantity: u64,
+    pub maker_fees: Price,
+    pub taker_fees: Price,
+    pub auction_fees: Price,
+}
+
+impl FeeSummary {
+    // What the participant owes, or is owed when negative
+    pub fn total(&self) -> Price {
+        self.maker_fees + self.taker_fees + self.auction_fees
+    }
+}
+
+// The fee summaries of every participant
+#[derive(Debug, Clone, Default)]
+pub struct Fees {
+    summaries: BTreeMap<String, FeeSummary>,
+}
+
+impl Fees {
+    pub fn new() -> Fees {
+        Fees::default()
+    }
+
+    pub fn charge(&mut self, participant: &str, liquidity: Liquidity, quantity: u32, fee: Price) {
+        let summary = self
+            .summaries
+            .entry(participant.to_string())
+            .or_insert_with(|| FeeSummary {
+                participant: participant.to_string(),
+                ..FeeSummary::default()
+            });
+        summary.fills += 1;
+        summary.quantity += quantity as u64;
+        let fees = match liquidity {
+            Liquidity::Added => &mut summary.maker_fees,
+            Liquidity::Removed => &mut summary.taker_fees,
+            Liquidity::Auction => &mut summary.auction_fees,
+        };
+        *fees = *fees + fee;
+    }
+
+    // Takes back the fee of a busted fill
+    pub fn refund(&mut self, participant: &str, liquidity: Liquidity, quantity: u32, fee: Price) {
+        let Some(summary) = self.summaries.get_mut(participant) else {
+            return;
+        };
+        summary.fills = summary.fills.saturating_sub(1);
+        summary.quantity = summary.quantity.saturating_sub(quantity as u64);
+        let fees = match liquidity {
+            Liquidity::Added => &mut summary.maker_fees,
+            Liquidity::Removed => &mut summary.taker_fees,
+            Liquidity::Auction => &mut summary.auction_fees,
+        };
+        *fees = *fees - fee;
+    }
+
+    // Adds the fees charged on another shard
+    pub fn merge(&mut self, other: Fees) {
+        for (participant, other) in other.summaries {
+            let summary = self
+                .summaries
+                .entry(participant)
+                .or_insert_with(|| FeeSummary {
+                    participant: other.participant.to_owned(),
+                    ..FeeSummary::default()
+                });
+            summary.fills += other.fills;
+            summary.quantity += other.quantity;
+            summary.maker_fees = summary.maker_fees + other.maker_fees;
+            summary.taker_fees = summary.taker_fees + other.taker_fees;
+            summary.auction_fees = summary.auction_fees + other.auction_fees;
+        }
+    }
+
+    pub fn get(&self, participant: &str) -> Option<&FeeSummary> {
+        self.summaries.get(participant)
+    }
+
+    pub fn get_summaries(&self) -> Vec<&FeeSummary> {
+        self.summaries.values().collect()
+    }
+
+    // Writes the summaries as CSV, one participant per line after a header
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        fs::write(file_path, self.to_string())
+            .map_err(|e| format!("Failed to write {}: {}", file_path, e))
+    }
+}
+
+impl Display for Fees {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "participant,fills,quantity,maker_fees,taker_fees,auction_fees,total"
+        )?;
+        for summary in self.summaries.values() {
+            writeln!(
+                f,
+                "{},{},{},{},{},{},{}",
+                summary.participant,
+                summary.fills,
+                summary.quantity,
+                summary.maker_fees,
+                summary.taker_fees,
+                summary.auction_fees,
+                summary.total()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_fee_schedule_parse() {
+    let schedule = FeeSchedule::parse(
+        "[*]
+        maker_fee = -0.002
+        taker_fee = 0.003
+
+        [market_maker]
+        participants = FIRM1, FIRM2
+        maker_fee = -0.003
+        taker_fee = 0.0025
+
+        [market_maker:AAPL]
+        maker_fee = -0.004
+        ",
+    )
+    .unwrap();
+    assert_eq!(schedule.get_tier("FIRM2"), "market_maker");
+    assert_eq!(schedule.get_tier("FIRM3"), ANY);
+    assert_eq!(
+        schedule.get_fee("FIRM1", "MSFT", Liquidity::Added, 100),
+        "-0.3".parse().unwrap()
+    );
+    assert_eq!(
+        schedule.get_fee("FIRM1", "AAPL", Liquidity::Added, 100),
+        "-0.4".parse().unwrap()
+    );
+    // The most specific section applies as a whole
+    assert_eq!(
+        schedule.get_fee("FIRM1", "AAPL", Liquidity::Removed, 100),
+        Price::ZERO
+    );
+    assert_eq!(
+        schedule.get_fee("FIRM3", "AAPL", Liquidity::Removed, 100),
+        "0.3".parse().unwrap()
+    );
+    assert_eq!(
+        FeeSchedule::new().get_fee("FIRM3", "AAPL", Liquidity::Removed, 100),
+        Price::ZERO
+    );
+    assert!(FeeSchedule::parse("[*]\nmaker_fee = abc").is_err());
+    assert!(FeeSchedule::parse("[*]\nexchange_fee = 1").is_err());
+}
+
+#[test]
+fn test_fee_summaries() {
+    let mut fees = Fees::new();
+    fees.charge("FIRM1", Liquidity::Removed, 100, "0.3".parse().unwrap());
+    fees.charge("FIRM2", Liquidity::Added, 100, "-0.2".parse().unwrap());
+    let mut other_shard = Fees::new();
+    other_shard.charge("FIRM1", Liquidity::Added, 50, "-0.1".parse().unwrap());
+    other_shard.charge("FIRM1", Liquidity::Auction, 10, "0.01".parse().unwrap());
+    fees.merge(other_shard);
+
+    fees.charge("FIRM2", Liquidity::Added, 10, "-0.02".parse().unwrap());
+    fees.refund("FIRM2", Liquidity::Added, 10, "-0.02".parse().unwrap());
+
+    let firm1 = fees.get("FIRM1").unwrap();
+    assert_eq!(firm1.fills, 3);
+    assert_eq!(firm1.quantity, 160);
+    assert_eq!(firm1.total(), "0.21".parse().unwrap());
+    assert_eq!(
+        fees.to_string(),
+        "participant,fills,quantity,maker_fees,taker_fees,auction_fees,total\n\
+         FIRM1,3,160,-0.1,0.3,0.01,0.21\n\
+         FIRM2,1,100,-0.2,0,0,-0.2\n"
+    );
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::order::{Order, Peg};
+
+// The key=value fields of a journal or snapshot line, as written with
+// `write_fields`. Tabs, newlines and backslashes in values are escaped.
+pub(super) struct Fields<'a>(HashMap<&'a str, String>);
+
+impl<'a> Fields<'a> {
+    pub(super) fn parse(parts: impl Iterator<Item = &'a str>) -> Result<Fields<'a>, ()> {
+        let mut fields = HashMap::new();
+        for part in parts {
+            let (key, value) = part.split_once('=').ok_or(())?;
+            fields.insert(key, unescape(value)?);
+        }
+        Ok(Fields(fields))
+    }
+
+    pub(super) fn get(&self, key: &str) -> Result<String, ()> {
+        self.0.get(key).cloned().ok_or(())
+    }
+
+    pub(super) fn value<T: FromStr>(&self, key: &str) -> Result<T, ()> {
+        self.0.get(key).ok_or(())?.parse().map_err(|_| ())
+    }
+
+    pub(super) fn optional<T: FromStr>(&self, key: &str) -> Result<Option<T>, ()> {
+        match self.0.get(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| ()),
+            None => Ok(None),
+        }
+    }
+
+    // The order written with `order_fields` under `prefix`
+    pub(super) fn order(&self, prefix: &str) -> Result<Order, ()> {
+        let key = |key: &str| format!("{}{}", prefix, key);
+        let mut order = Order::new(
+            &self.get(&key("symbol"))?,
+            self.value(&key("quantity"))?,
+            self.value(&key("price"))?,
+            self.value(&key("side"))?,
+        );
+        order.id = self.value(&key("id"))?;
+        order.sequence = self.value(&key("sequence"))?;
+        order.cl_ord_id = self.get(&key("cl_ord_id")).unwrap_or_default();
+        order.participant = self.get(&key("participant")).unwrap_or_default();
+        order.account = self.get(&key("account")).unwrap_or_default();
+        order.stp_group = self.get(&key("stp_group")).unwrap_or_default();
+        order.self_trade_prevention = self.optional(&key("stp"))?;
+        if let Some(peg_type) = self.optional(&key("peg_type"))? {
+            order.peg = Some(Peg {
+                peg_type,
+                offset: self.value(&key("peg_offset"))?,
+                limit: self.optional(&key("peg_limit"))?,
+            });
+        }
+        order.hidden = self.optional(&key("hidden"))?.unwrap_or(false);
+        order.min_quantity = self.optional(&key("min_quantity"))?.unwrap_or(0);
+        order.all_or_none = self.optional(&key("all_or_none"))?.unwrap_or(false);
+        Ok(order)
+    }
+}
+
+pub(super) fn order_fields(prefix: &str, order: &Order, fields: &mut Vec<(String, String)>) {
+    let mut push = |key: &str, value: String| fields.push((format!("{}{}", prefix, key), value));
+    push("id", order.id.to_string());
+    push("symbol", order.symbol.to_owned());
+    push("quantity", order.quantity.to_string());
+    push("price", order.price.to_string());
+    push("side", order.side.to_string());
+    push("sequence", order.sequence.to_string());
+    for (key, value) in [
+        ("cl_ord_id", &order.cl_ord_id),
+        ("participant", &order.participant),
+        ("account", &order.account),
+        ("stp_group", &order.stp_group),
+    ] {
+        if !value.is_empty() {
+            push(key, value.to_owned());
+        }
+    }
+    if let Some(mode) = order.self_trade_prevention {
+        push("stp", mode.to_string());
+    }
+    if let Some(peg) = &order.peg {
+        push("peg_type", peg.peg_type.to_string());
+        push("peg_offset", peg.offset.to_string());
+        if let Some(limit) = peg.limit {
+            push("peg_limit", limit.to_string());
+        }
+    }
+    if order.hidden {
+        push("hidden", true.to_string());
+    }
+    if order.min_quantity > 0 {
+        push("min_quantity", order.min_quantity.to_string());
+    }
+    if order.all_or_none {
+        push("all_or_none", true.to_string());
+    }
+}
+
+pub(super) fn write_fields(
+    f: &mut std::fmt::Formatter<'_>,
+    fields: &[(String, String)],
+) -> std::fmt::Result {
+    for (key, value) in fields {
+        write!(f, "\t{}={}", key, escape(value))?;
+    }
+    Ok(())
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> Result<String, ()> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            _ => return Err(()),
+        }
+    }
+    Ok(unescaped)
+}
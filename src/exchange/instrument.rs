@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::matching::{
+    Fifo, LeadMarketMaker, MatchingAlgorithm, MatchingRule, ProRata, ProRataTopOrder,
+};
+use super::reject::RejectReason;
+use crate::config::{self, ConfigSection};
+use crate::price::Price;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    pub symbol: String,
+    pub security_id: String,
+    pub currency: String,
+    pub lot_size: u32,
+    pub min_quantity: u32,
+    pub max_quantity: u32,
+    // (start price, tick size) pairs in ascending start price order
+    pub tick_sizes: Vec<(Price, Price)>,
+    pub low_limit_price: Option<Price>,
+    pub high_limit_price: Option<Price>,
+    pub reference_price: Option<Price>,
+    // Continuous trading stops before a trade further than this from the last
+    // trade or reference price, in basis points
+    pub dynamic_band_bps: Option<u32>,
+    pub volatility_auction_duration: Duration,
+    pub matching_rule: MatchingRule,
+    // Participants given a guaranteed share of each fill under the lmm rule
+    pub lead_market_makers: Vec<String>,
+    pub lmm_allocation_percent: u32,
+    // Business days from trade date to settlement date
+    pub settlement_days: u32,
+}
+
+impl Instrument {
+    // Creates an instrument that accepts any positive quantity on the finest
+    // representable price grid
+    pub fn new(symbol: &str) -> Instrument {
+        Instrument {
+            symbol: symbol.to_string(),
+            security_id: symbol.to_string(),
+            currency: "USD".to_string(),
+            lot_size: 1,
+            min_quantity: 1,
+            max_quantity: u32::MAX,
+            tick_sizes: vec![(Price::ZERO, Price::from_raw(1))],
+            low_limit_price: None,
+            high_limit_price: None,
+            reference_price: None,
+            dynamic_band_bps: None,
+            volatility_auction_duration: Duration::from_secs(5),
+            matching_rule: MatchingRule::Fifo,
+            lead_market_makers: Vec::new(),
+            lmm_allocation_percent: 0,
+            settlement_days: 2,
+        }
+    }
+
+    pub fn matching_algorithm(&self) -> Box<dyn MatchingAlgorithm> {
+        match self.matching_rule {
+            MatchingRule::Fifo => Box::new(Fifo),
+            MatchingRule::ProRata => Box::new(ProRata),
+            MatchingRule::ProRataTopOrder => Box::new(ProRataTopOrder),
+            MatchingRule::LeadMarketMaker => Box::new(LeadMarketMaker {
+                participants: self.lead_market_makers.clone(),
+                allocation_percent: self.lmm_allocation_percent,
+            }),
+        }
+    }
+
+    // The (low, high) prices continuous trading may print at around
+    // `reference_price`
+    pub fn dynamic_band(&self, reference_price: Price) -> Option<(Price, Price)> {
+        let band_bps = self.dynamic_band_bps?;
+        let width = reference_price.raw().abs() as i128 * band_bps as i128 / 10_000;
+        let width = Price::from_raw(width as i64);
+        Some((reference_price - width, reference_price + width))
+    }
+
+    pub fn tick_size(&self, price: Price) -> Price {
+        self.tick_sizes
+            .iter()
+            .take_while(|(start, _)| *start <= price)
+            .last()
+            .or(self.tick_sizes.first())
+            .map_or(Price::from_raw(1), |(_, tick_size)| *tick_size)
+    }
+
+    pub fn validate(&self, quantity: u32, price: Price) -> Result<(), RejectReason> {
+        self.validate_quantity(quantity)?;
+
+        let tick_size = self.tick_size(price);
+        if !price.is_on_tick(tick_size) {
+            return Err(RejectReason::OffTick { price, tick_size });
+        }
+
+        let below_band = self.low_limit_price.is_some_and(|low| price < low);
+        let above_band = self.high_limit_price.is_some_and(|high| price > high);
+        if below_band || above_band {
+            return Err(RejectReason::PriceOutOfBand {
+                price,
+                low_limit_price: self.low_limit_price,
+                high_limit_price: self.high_limit_price,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn validate_quantity(&self, quantity: u32) -> Result<(), RejectReason> {
+        if quantity == 0 {
+            return Err(RejectReason::InvalidQuantity);
+        }
+        if !quantity.is_multiple_of(self.lot_size) {
+            return Err(RejectReason::OddLot {
+                quantity,
+                lot_size: self.lot_size,
+            });
+        }
+        if quantity < self.min_quantity || quantity > self.max_quantity {
+            return Err(RejectReason::QuantityOutOfRange {
+                quantity,
+                min_quantity: self.min_quantity,
+                max_quantity: self.max_quantity,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceData {
+    instruments: BTreeMap<String, Instrument>,
+}
+
+impl ReferenceData {
+    pub fn new() -> ReferenceData {
+        ReferenceData {
+            instruments: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(file_path: &str) -> Result<ReferenceData, String> {
+        ReferenceData::from_sections(config::read_sections(file_path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<ReferenceData, String> {
+        ReferenceData::from_sections(config::parse_sections(contents)?)
+    }
+
+    // Builds the instruments from one [SYMBOL] section each:
+    //
+    //   [AAPL]
+    //   security_id = US0378331005
+    //   currency = USD
+    //   lot_size = 100
+    //   min_quantity = 100
+    //   max_quantity = 100000
+    //   tick_sizes = 0:0.0001, 1:0.01
+    //   low_limit_price = 1
+    //   high_limit_price = 1000
+    //   reference_price = 50
+    //   dynamic_band_bps = 500
+    //   volatility_auction_seconds = 5
+    //   matching_algorithm = lmm
+    //   lead_market_makers = FIRM1, FIRM2
+    //   lmm_allocation_percent = 40
+    //   settlement_days = 2
+    fn from_sections(sections: Vec<ConfigSection>) -> Result<ReferenceData, String> {
+        let mut reference_data = ReferenceData::new();
+
+        for section in sections {
+            let mut instrument = Instrument::new(&section.name);
+            for entry in &section.entries {
+                match entry.key.as_str() {
+                    "security_id" => instrument.security_id = entry.value.to_owned(),
+                    "currency" => instrument.currency = entry.value.to_owned(),
+                    "lot_size" => instrument.lot_size = entry.parse()?,
+                    "min_quantity" => instrument.min_quantity = entry.parse()?,
+                    "max_quantity" => instrument.max_quantity = entry.parse()?,
+                    "tick_sizes" => {
+                        instrument.tick_sizes =
+                            parse_tick_sizes(&entry.value).ok_or_else(|| entry.invalid())?
+                    }
+                    "low_limit_price" => instrument.low_limit_price = Some(entry.parse()?),
+                    "high_limit_price" => instrument.high_limit_price = Some(entry.parse()?),
+                    "reference_price" => instrument.reference_price = Some(entry.parse()?),
+                    "dynamic_band_bps" => instrument.dynamic_band_bps = Some(entry.parse()?),
+                    "volatility_auction_seconds" => {
+                        instrument.volatility_auction_duration = entry
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                            .ok_or_else(|| entry.invalid())?
+                    }
+                    "matching_algorithm" => instrument.matching_rule = entry.parse()?,
+                    "lead_market_makers" => {
+                        instrument.lead_market_makers = entry
+                            .value
+                            .split(',')
+                            .map(|participant| participant.trim().to_string())
+                            .filter(|participant| !participant.is_empty())
+                            .collect()
+                    }
+                    "lmm_allocation_percent" => {
+                        instrument.lmm_allocation_percent = entry.parse()?;
+                        if instrument.lmm_allocation_percent > 100 {
+                            return Err(entry.invalid());
+                        }
+                    }
+                    "settlement_days" => instrument.settlement_days = entry.parse()?,
+                    _ => return Err(entry.unknown()),
+                }
+                if instrument.lot_size == 0 {
+                    return Err(entry.invalid());
+                }
+            }
+            reference_data.add_instrument(instrument);
+        }
+        Ok(reference_data)
+    }
+
+    pub fn add_instrument(&mut self, instrument: Instrument) {
+        self.instruments
+            .insert(instrument.symbol.to_owned(), instrument);
+    }
+
+    pub fn get_instrument(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(symbol)
+    }
+
+    pub fn get_instruments(&self) -> Vec<&Instrument> {
+        self.instruments.values().collect()
+    }
+}
+
+fn parse_tick_sizes(value: &str) -> Option<Vec<(Price, Price)>> {
+    let mut tick_sizes = value
+        .split(',')
+        .map(|band| {
+            let (start, tick_size) = band.trim().split_once(':')?;
+            let start = start.trim().parse::<Price>().ok()?;
+            let tick_size = tick_size.trim().parse::<Price>().ok()?;
+            (tick_size > Price::ZERO).then_some((start, tick_size))
+        })
+        .collect::<Option<Vec<(Price, Price)>>>()?;
+    tick_sizes.sort();
+    (!tick_sizes.is_empty()).then_some(tick_sizes)
+}
+
+#[test]
+fn test_instrument_tick_size() {
+    let mut instrument = Instrument::new("AAPL");
+    instrument.tick_sizes = vec![
+        (Price::ZERO, "0.0001".parse().unwrap()),
+        (Price::from(1), "0.01".parse().unwrap()),
+        (Price::from(1000), "0.05".parse().unwrap()),
+    ];
+    assert_eq!(
+        instrument.tick_size("0.5".parse().unwrap()),
+        "0.0001".parse().unwrap()
+    );
+    assert_eq!(
+        instrument.tick_size(Price::from(1)),
+        "0.01".parse().unwrap()
+    );
+    assert_eq!(
+        instrument.tick_size(Price::from(1500)),
+        "0.05".parse().unwrap()
+    );
+}
+
+#[test]
+fn test_instrument_validate() {
+    let mut instrument = Instrument::new("AAPL");
+    instrument.lot_size = 100;
+    instrument.min_quantity = 100;
+    instrument.max_quantity = 10000;
+    instrument.tick_sizes = vec![(Price::ZERO, "0.01".parse().unwrap())];
+    instrument.low_limit_price = Some(Price::from(1));
+    instrument.high_limit_price = Some(Price::from(1000));
+
+    assert!(instrument.validate(100, "150.25".parse().unwrap()).is_ok());
+    assert_eq!(
+        instrument.validate(150, Price::from(150)),
+        Err(RejectReason::OddLot {
+            quantity: 150,
+            lot_size: 100
+        })
+    );
+    assert_eq!(
+        instrument.validate(20000, Price::from(150)),
+        Err(RejectReason::QuantityOutOfRange {
+            quantity: 20000,
+            min_quantity: 100,
+            max_quantity: 10000
+        })
+    );
+    assert!(matches!(
+        instrument.validate(100, "150.255".parse().unwrap()),
+        Err(RejectReason::OffTick { .. })
+    ));
+    assert!(matches!(
+        instrument.validate(100, Price::from(1001)),
+        Err(RejectReason::PriceOutOfBand { .. })
+    ));
+}
+
+#[test]
+fn test_reference_data_parse() {
+    let reference_data = ReferenceData::parse(
+        "# test instruments
+        [AAPL]
+        security_id = US0378331005
+        currency = USD
+        lot_size = 100
+        min_quantity = 100
+        max_quantity = 100000
+        tick_sizes = 1:0.01, 0:0.0001
+        low_limit_price = 1
+        high_limit_price = 1000
+        reference_price = 150.5
+        dynamic_band_bps = 500
+        volatility_auction_seconds = 2.5
+        matching_algorithm = lmm
+        lead_market_makers = FIRM1, FIRM2
+        lmm_allocation_percent = 40
+        settlement_days = 1
+
+        [MSFT]
+        ",
+    )
+    .unwrap();
+
+    let aapl = reference_data.get_instrument("AAPL").unwrap();
+    assert_eq!(aapl.security_id, "US0378331005");
+    assert_eq!(aapl.lot_size, 100);
+    assert_eq!(aapl.max_quantity, 100000);
+    assert_eq!(aapl.tick_sizes[0], (Price::ZERO, "0.0001".parse().unwrap()));
+    assert_eq!(aapl.high_limit_price, Some(Price::from(1000)));
+    assert_eq!(aapl.reference_price, Some("150.5".parse().unwrap()));
+    assert_eq!(aapl.dynamic_band_bps, Some(500));
+    assert_eq!(aapl.matching_rule, MatchingRule::LeadMarketMaker);
+    assert_eq!(aapl.lead_market_makers, vec!["FIRM1", "FIRM2"]);
+    assert_eq!(aapl.lmm_allocation_percent, 40);
+    assert_eq!(aapl.settlement_days, 1);
+    assert_eq!(
+        aapl.volatility_auction_duration,
+        Duration::from_millis(2500)
+    );
+    assert_eq!(
+        reference_data.get_instrument("MSFT"),
+        Some(&Instrument::new("MSFT"))
+    );
+    assert_eq!(reference_data.get_instrument("GOOG"), None);
+    assert_eq!(reference_data.get_instruments().len(), 2);
+}
+
+#[test]
+fn test_reference_data_parse_errors() {
+    assert!(ReferenceData::parse("lot_size = 100").is_err());
+    assert!(ReferenceData::parse("[AAPL]\nlot_size = abc").is_err());
+    assert!(ReferenceData::parse("[AAPL]\nlot_size = 0").is_err());
+    assert!(ReferenceData::parse("[AAPL]\ntick_sizes = 0:0").is_err());
+    assert!(ReferenceData::parse("[AAPL]\ncolour = blue").is_err());
+    assert!(ReferenceData::parse("[AAPL]\nlot_size").is_err());
+    assert!(ReferenceData::parse("[AAPL]\nvolatility_auction_seconds = -1").is_err());
+    assert!(ReferenceData::parse("[AAPL]\nmatching_algorithm = random").is_err());
+    assert!(ReferenceData::parse("[AAPL]\nlmm_allocation_percent = 101").is_err());
+}
+
+#[test]
+fn test_instrument_dynamic_band() {
+    let mut instrument = Instrument::new("AAPL");
+    assert_eq!(instrument.dynamic_band(Price::from(100)), None);
+    instrument.dynamic_band_bps = Some(500);
+    assert_eq!(
+        instrument.dynamic_band(Price::from(100)),
+        Some((Price::from(95), Price::from(105)))
+    );
+}
@@ -0,0 +1,699 @@
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::cancellation::MassCancel;
+use super::exchange::{Exchange, ExchangeConfig};
+use super::fields::{order_fields, write_fields, Fields};
+use super::phase::TradingPhase;
+use super::quote::{QuoteCancel, QuoteEntry, QuoteSide};
+use super::risk::RiskLimits;
+use super::shard::ShardPlan;
+use super::snapshot::Snapshot;
+use crate::order::Order;
+use crate::price::Price;
+
+// A state-changing input to an exchange, journaled before it is applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    // A run of the exchange starting, split into `shard_count` shards
+    Start {
+        shard_count: usize,
+    },
+    // The time since the run started, as of the commands that follow
+    Clock(Duration),
+    NewOrder(Order),
+    CancelOrder(Order),
+    ReplaceOrder {
+        order_id: u32,
+        replacement: Order,
+    },
+    MassCancel(MassCancel),
+    Quote(QuoteEntry),
+    CancelQuotes(QuoteCancel),
+    SetTradingPhase {
+        symbol: String,
+        phase: TradingPhase,
+    },
+    Halt {
+        symbol: String,
+    },
+    Resume {
+        symbol: String,
+    },
+    BustTrade {
+        trade_id: String,
+    },
+    CorrectTrade {
+        trade_id: String,
+        price: Price,
+        quantity: u32,
+    },
+    SetRiskLimits {
+        participant: String,
+        symbol: String,
+        limits: RiskLimits,
+    },
+}
+
+// One line of the journal: the command and the shard it was applied to,
+// numbered across every shard in the order they were written
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub sequence: u64,
+    pub shard: usize,
+    pub command: Command,
+}
+
+// Entries are written one per line as tab separated fields:
+//
+//   <sequence> <shard> <command> <key>=<value> ...
+//
+// with tabs, newlines and backslashes in values escaped
+impl Display for JournalEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        let mut push = |key: &str, value: String| fields.push((key.to_string(), value));
+        let name = match &self.command {
+            Command::Start { shard_count } => {
+                push("shards", shard_count.to_string());
+                "start"
+            }
+            Command::Clock(elapsed) => {
+                push("nanos", elapsed.as_nanos().to_string());
+                "clock"
+            }
+            Command::NewOrder(order) => {
+                order_fields("", order, &mut fields);
+                "new"
+            }
+            Command::CancelOrder(order) => {
+                order_fields("", order, &mut fields);
+                "cancel"
+            }
+            Command::ReplaceOrder {
+                order_id,
+                replacement,
+            } => {
+                push("order_id", order_id.to_string());
+                order_fields("", replacement, &mut fields);
+                "replace"
+            }
+            Command::MassCancel(mass_cancel) => {
+                push("participant", mass_cancel.participant.to_owned());
+                if let Some(symbol) = &mass_cancel.symbol {
+                    push("symbol", symbol.to_owned());
+                }
+                if let Some(side) = &mass_cancel.side {
+                    push("side", side.to_string());
+                }
+                "mass_cancel"
+            }
+            Command::Quote(entry) => {
+                push("participant", entry.participant.to_owned());
+                push("symbol", entry.symbol.to_owned());
+                for (key, value) in [
+                    ("account", &entry.account),
+                    ("quote_set_id", &entry.quote_set_id),
+                    ("quote_entry_id", &entry.quote_entry_id),
+                ] {
+                    if !value.is_empty() {
+                        push(key, value.to_owned());
+                    }
+                }
+                for (prefix, quote_side) in [("bid", entry.bid), ("offer", entry.offer)] {
+                    if let Some((price, size)) = quote_side {
+                        push(&format!("{}_price", prefix), price.to_string());
+                        push(&format!("{}_size", prefix), size.to_string());
+                    }
+                }
+                "quote"
+            }
+            Command::CancelQuotes(quote_cancel) => {
+                push("participant", quote_cancel.participant.to_owned());
+                if let Some(symbol) = &quote_cancel.symbol {
+                    push("symbol", symbol.to_owned());
+                }
+                "cancel_quotes"
+            }
+            Command::SetTradingPhase { symbol, phase } => {
+                push("symbol", symbol.to_owned());
+                push("phase", phase.to_string());
+                "set_phase"
+            }
+            Command::Halt { symbol } => {
+                push("symbol", symbol.to_owned());
+                "halt"
+            }
+            Command::Resume { symbol } => {
+                push("symbol", symbol.to_owned());
+                "resume"
+            }
+            Command::BustTrade { trade_id } => {
+                push("trade_id", trade_id.to_owned());
+                "bust_trade"
+            }
+            Command::CorrectTrade {
+                trade_id,
+                price,
+                quantity,
+            } => {
+                push("trade_id", trade_id.to_owned());
+                push("price", price.to_string());
+                push("quantity", quantity.to_string());
+                "correct_trade"
+            }
+            Command::SetRiskLimits {
+                participant,
+                symbol,
+                limits,
+            } => {
+                push("participant", participant.to_owned());
+                push("symbol", symbol.to_owned());
+                // Named as in risk.cfg, and left out when not set
+                for (key, limit) in [
+                    (
+                        "max_order_quantity",
+                        limits.max_order_quantity.map(|limit| limit.to_string()),
+                    ),
+                    (
+                        "max_notional",
+                        limits.max_notional.map(|limit| limit.to_string()),
+                    ),
+                    (
+                        "max_price_deviation_bps",
+                        limits
+                            .max_price_deviation_bps
+                            .map(|limit| limit.to_string()),
+                    ),
+                    (
+                        "max_open_orders",
+                        limits.max_open_orders.map(|limit| limit.to_string()),
+                    ),
+                    (
+                        "max_gross_position",
+                        limits.max_gross_position.map(|limit| limit.to_string()),
+                    ),
+                    (
+                        "max_net_position",
+                        limits.max_net_position.map(|limit| limit.to_string()),
+                    ),
+                ] {
+                    if let Some(limit) = limit {
+                        push(key, limit);
+                    }
+                }
+                "set_risk_limits"
+            }
+        };
+        write!(f, "{}\t{}\t{}", self.sequence, self.shard, name)?;
+        write_fields(f, &fields)
+    }
+}
+
+impl FromStr for JournalEntry {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('\t');
+        let sequence = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        let shard = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        let name = parts.next().ok_or(())?;
+        let fields = Fields::parse(parts)?;
+        let command = match name {
+            "start" => Command::Start {
+                shard_count: fields.value("shards")?,
+            },
+            "clock" => Command::Clock(Duration::from_nanos(fields.value("nanos")?)),
+            "new" => Command::NewOrder(fields.order("")?),
+            "cancel" => Command::CancelOrder(fields.order("")?),
+            "replace" => Command::ReplaceOrder {
+                order_id: fields.value("order_id")?,
+                replacement: fields.order("")?,
+            },
+            "mass_cancel" => Command::MassCancel(MassCancel {
+                participant: fields.get("participant")?,
+                symbol: fields.get("symbol").ok(),
+                side: fields.optional("side")?,
+            }),
+            "quote" => {
+                let quote_side = |prefix: &str| -> Result<Option<QuoteSide>, ()> {
+                    match fields.optional(&format!("{}_price", prefix))? {
+                        Some(price) => {
+                            Ok(Some((price, fields.value(&format!("{}_size", prefix))?)))
+                        }
+                        None => Ok(None),
+                    }
+                };
+                Command::Quote(QuoteEntry {
+                    participant: fields.get("participant")?,
+                    account: fields.get("account").unwrap_or_default(),
+                    quote_set_id: fields.get("quote_set_id").unwrap_or_default(),
+                    quote_entry_id: fields.get("quote_entry_id").unwrap_or_default(),
+                    symbol: fields.get("symbol")?,
+                    bid: quote_side("bid")?,
+                    offer: quote_side("offer")?,
+                })
+            }
+            "cancel_quotes" => Command::CancelQuotes(QuoteCancel {
+                participant: fields.get("participant")?,
+                symbol: fields.get("symbol").ok(),
+            }),
+            "set_phase" => Command::SetTradingPhase {
+                symbol: fields.get("symbol")?,
+                phase: fields.value("phase")?,
+            },
+            "halt" => Command::Halt {
+                symbol: fields.get("symbol")?,
+            },
+            "resume" => Command::Resume {
+                symbol: fields.get("symbol")?,
+            },
+            "bust_trade" => Command::BustTrade {
+                trade_id: fields.get("trade_id")?,
+            },
+            "correct_trade" => Command::CorrectTrade {
+                trade_id: fields.get("trade_id")?,
+                price: fields.value("price")?,
+                quantity: fields.value("quantity")?,
+            },
+            "set_risk_limits" => Command::SetRiskLimits {
+                participant: fields.get("participant")?,
+                symbol: fields.get("symbol")?,
+                limits: RiskLimits {
+                    max_order_quantity: fields.optional("max_order_quantity")?,
+                    max_notional: fields.optional("max_notional")?,
+                    max_price_deviation_bps: fields.optional("max_price_deviation_bps")?,
+                    max_open_orders: fields.optional("max_open_orders")?,
+                    max_gross_position: fields.optional("max_gross_position")?,
+                    max_net_position: fields.optional("max_net_position")?,
+                },
+            },
+            _ => return Err(()),
+        };
+        Ok(JournalEntry {
+            sequence,
+            shard,
+            command,
+        })
+    }
+}
+
+struct JournalFile {
+    file: File,
+    next_sequence: u64,
+}
+
+// Append-only journal shared by the shards of an exchange, each writing
+// through a handle of its own. Every entry is written out before the command
+// is applied.
+#[derive(Clone)]
+pub struct Journal {
+    file: Arc<Mutex<JournalFile>>,
+    shard: usize,
+}
+
+impl Journal {
+    // Appends to the journal at `file_path`, numbering on from its last entry
+    pub fn open(file_path: &str) -> Result<Journal, String> {
+        let next_sequence = match File::open(file_path) {
+            Ok(_) => read_entries(file_path)?
+                .last()
+                .map_or(1, |entry| entry.sequence + 1),
+            Err(_) => 1,
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)
+            .map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+        Ok(Journal {
+            file: Arc::new(Mutex::new(JournalFile {
+                file,
+                next_sequence,
+            })),
+            shard: 0,
+        })
+    }
+
+    // A handle on the same journal for the shard `shard`
+    pub fn for_shard(&self, shard: usize) -> Journal {
+        Journal {
+            file: Arc::clone(&self.file),
+            shard,
+        }
+    }
+
+    // Numbers on from `sequence` if the journal is behind it, as when it was
+    // cleared after a snapshot up to `sequence` was taken
+    pub fn continue_after(&self, sequence: u64) -> Result<(), String> {
+        let mut journal = self
+            .file
+            .lock()
+            .map_err(|_| "Journal lock poisoned".to_string())?;
+        journal.next_sequence = journal.next_sequence.max(sequence + 1);
+        Ok(())
+    }
+
+    // Writes `command` out, returning its sequence number
+    pub fn append(&self, command: Command) -> Result<u64, String> {
+        let mut journal = self
+            .file
+            .lock()
+            .map_err(|_| "Journal lock poisoned".to_string())?;
+        let entry = JournalEntry {
+            sequence: journal.next_sequence,
+            shard: self.shard,
+            command,
+        };
+        writeln!(journal.file, "{}", entry)
+            .map_err(|e| format!("Failed to write journal entry {}: {}", entry.sequence, e))?;
+        journal.next_sequence += 1;
+        Ok(entry.sequence)
+    }
+}
+
+pub fn read_entries(file_path: &str) -> Result<Vec<JournalEntry>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let mut entries = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+        if line.is_empty() {
+            continue;
+        }
+        let entry = line
+            .parse()
+            .map_err(|_| format!("Line {}: invalid journal entry", index + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+// Rebuilds the shards of the exchange from `snapshot`, or from nothing, and
+// the entries of the journal after it. Each run starts from the state the
+// last one left, spread across its shards, and every command is applied to
+// its shard in journal order. Order ids and priorities are handed out by each
+// shard as they were in the run.
+pub fn replay(
+    snapshot: Option<&Snapshot>,
+    entries: &[JournalEntry],
+    config: &ExchangeConfig,
+) -> Result<Vec<Exchange>, String> {
+    let snapshot = snapshot.cloned().unwrap_or_default();
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    let mut started = false;
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.sequence > snapshot.sequence)
+    {
+        if let Command::Start { shard_count } = entry.command {
+            let state = if started {
+                Snapshot::merge(exchanges.iter().map(Exchange::snapshot))
+            } else {
+                snapshot.clone()
+            };
+            exchanges = restore(&state, config, shard_count);
+            started = true;
+            continue;
+        }
+        if !started {
+            return Err(format!(
+                "Entry {} comes before the start of a run",
+                entry.sequence
+            ));
+        }
+        let shard_count = exchanges.len();
+        let exchange = exchanges.get_mut(entry.shard).ok_or(format!(
+            "Entry {} is for shard {} of {}",
+            entry.sequence, entry.shard, shard_count
+        ))?;
+        apply(exchange, entry.command.clone());
+    }
+    if !started {
+        return Ok(restore(&snapshot, config, 1));
+    }
+    Ok(exchanges)
+}
+
+// The shards of a run split into `shard_count` shards, starting from `state`
+pub fn restore(state: &Snapshot, config: &ExchangeConfig, shard_count: usize) -> Vec<Exchange> {
+    let plan = ShardPlan::new(config, shard_count);
+    plan.get_configs()
+        .iter()
+        .zip(state.split(&plan))
+        .map(|(config, state)| {
+            let mut exchange = Exchange::with_config(config.clone());
+            exchange.restore(&state);
+            exchange
+        })
+        .collect()
+}
+
+// Applies a journaled command the way it was applied when it was written.
+// Rejections are not reported, since they were when the command came in.
+fn apply(exchange: &mut Exchange, command: Command) {
+    match command {
+        Command::Start { .. } => {}
+        Command::Clock(elapsed) => {
+            exchange.apply_schedule(elapsed);
+        }
+        Command::NewOrder(order) => {
+            let _ = exchange.execute_order(order);
+        }
+        Command::CancelOrder(order) => {
+            exchange.cancel_order(order);
+        }
+        Command::ReplaceOrder {
+            order_id,
+            replacement,
+        } => {
+            let _ = exchange.replace_order(order_id, replacement);
+        }
+        Command::MassCancel(mass_cancel) => {
+            exchange.mass_cancel(&mass_cancel);
+        }
+        Command::Quote(entry) => {
+            let _ = exchange.quote(entry);
+        }
+        Command::CancelQuotes(quote_cancel) => {
+            exchange.cancel_quotes(&quote_cancel);
+        }
+        Command::SetTradingPhase { symbol, phase } => {
+            let _ = exchange.set_trading_phase(&symbol, phase);
+        }
+        Command::Halt { symbol } => {
+            let _ = exchange.halt(&symbol);
+        }
+        Command::Resume { symbol } => {
+            let _ = exchange.resume(&symbol);
+        }
+        Command::BustTrade { trade_id } => {
+            let _ = exchange.bust_trade(&trade_id);
+        }
+        Command::CorrectTrade {
+            trade_id,
+            price,
+            quantity,
+        } => {
+            let _ = exchange.correct_trade(&trade_id, price, quantity);
+        }
+        Command::SetRiskLimits {
+            participant,
+            symbol,
+            limits,
+        } => {
+            exchange.set_risk_limits(&participant, &symbol, limits);
+        }
+    }
+}
+
+#[test]
+fn test_journal_entry_round_trip() {
+    use crate::order::{Peg, PegType, SelfTradePrevention, Side};
+    use crate::price::Price;
+    let mut order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order.cl_ord_id = "a\tb\\c\n".to_string();
+    order.participant = "FIRM1".to_string();
+    order.account = "ACC1".to_string();
+    order.self_trade_prevention = Some(SelfTradePrevention::CancelBoth);
+    order.peg = Some(Peg {
+        peg_type: PegType::Primary,
+        offset: "-0.01".parse().unwrap(),
+        limit: Some(Price::from(150)),
+    });
+    order.hidden = true;
+    order.min_quantity = 10;
+    order.all_or_none = true;
+    let entry_peg = order.peg;
+    let commands = [
+        Command::Start { shard_count: 4 },
+        Command::Clock(Duration::from_millis(1500)),
+        Command::NewOrder(order.clone()),
+        Command::CancelOrder(order.clone()),
+        Command::ReplaceOrder {
+            order_id: 7,
+            replacement: order,
+        },
+        Command::MassCancel(MassCancel {
+            participant: "FIRM1".to_string(),
+            symbol: None,
+            side: Some(Side::Sell),
+        }),
+        Command::Quote(QuoteEntry {
+            participant: "MM1".to_string(),
+            account: String::new(),
+            quote_set_id: "S1".to_string(),
+            quote_entry_id: "Q1".to_string(),
+            symbol: "AAPL".to_string(),
+            bid: Some((Price::from(149), 100)),
+            offer: None,
+        }),
+        Command::CancelQuotes(QuoteCancel {
+            participant: "MM1".to_string(),
+            symbol: Some("AAPL".to_string()),
+        }),
+        Command::SetTradingPhase {
+            symbol: "AAPL".to_string(),
+            phase: TradingPhase::OpeningAuction,
+        },
+        Command::Halt {
+            symbol: "*".to_string(),
+        },
+        Command::Resume {
+            symbol: "*".to_string(),
+        },
+        Command::BustTrade {
+            trade_id: "AAPL-3".to_string(),
+        },
+        Command::CorrectTrade {
+            trade_id: "AAPL-4".to_string(),
+            price: Price::from(149),
+            quantity: 50,
+        },
+        Command::SetRiskLimits {
+            participant: "FIRM1".to_string(),
+            symbol: "*".to_string(),
+            limits: RiskLimits {
+                max_order_quantity: Some(1000),
+                max_notional: Some("1000000.5".parse().unwrap()),
+                max_net_position: Some(500),
+                ..Default::default()
+            },
+        },
+    ];
+    for (index, command) in commands.into_iter().enumerate() {
+        let entry = JournalEntry {
+            sequence: index as u64 + 1,
+            shard: index % 2,
+            command,
+        };
+        let line = entry.to_string();
+        assert!(!line.contains('\n'));
+        let decoded: JournalEntry = line.parse().unwrap();
+        assert_eq!(decoded, entry);
+        if let Command::NewOrder(order) = &decoded.command {
+            assert_eq!(order.cl_ord_id, "a\tb\\c\n");
+            assert_eq!(order.quantity, 100);
+            assert_eq!(order.account, "ACC1");
+            assert_eq!(order.peg, entry_peg);
+            assert!(order.hidden);
+            assert_eq!(order.min_quantity, 10);
+            assert!(order.all_or_none);
+        }
+    }
+    assert!("1\t0\tunknown".parse::<JournalEntry>().is_err());
+    assert!("1\t0\tnew\tid=1".parse::<JournalEntry>().is_err());
+}
+
+#[test]
+fn test_journal_replay() {
+    use super::instrument::{Instrument, ReferenceData};
+    use crate::order::Side;
+    use crate::price::Price;
+    let file_path = std::env::temp_dir().join(format!("journal_replay_{}.log", std::process::id()));
+    let file_path = file_path.to_str().unwrap();
+    let _ = std::fs::remove_file(file_path);
+
+    let mut reference_data = ReferenceData::new();
+    for symbol in ["AAPL", "GOOGL", "MSFT"] {
+        reference_data.add_instrument(Instrument::new(symbol));
+    }
+    let config = ExchangeConfig {
+        reference_data,
+        ..Default::default()
+    };
+    let journal = Journal::open(file_path).unwrap();
+    journal.append(Command::Start { shard_count: 2 }).unwrap();
+    let plan = ShardPlan::new(&config, 2);
+    let mut exchanges: Vec<Exchange> = plan
+        .get_configs()
+        .iter()
+        .enumerate()
+        .map(|(index, config)| {
+            let mut exchange = Exchange::with_config(config.clone());
+            exchange.set_journal(journal.for_shard(index));
+            exchange
+        })
+        .collect();
+
+    let orders = [
+        ("AAPL", 100, 150, Side::Buy),
+        ("GOOGL", 50, 90, Side::Sell),
+        ("AAPL", 60, 149, Side::Sell),
+        ("MSFT", 10, 300, Side::Buy),
+        ("GOOGL", 80, 91, Side::Buy),
+        ("AAPL", 30, 151, Side::Buy),
+    ];
+    for (symbol, quantity, price, side) in orders {
+        let exchange = &mut exchanges[plan.get_shard(symbol)];
+        let mut order = Order::new(symbol, quantity, Price::from(price), side);
+        exchange.assign_order_id(&mut order);
+        exchange.execute_order(order).unwrap();
+    }
+    let shard = plan.get_shard("AAPL");
+    let resting = exchanges[shard].get_open_orders("AAPL")[0].clone();
+    exchanges[shard].cancel_order(resting);
+    exchanges[plan.get_shard("MSFT")].halt("MSFT").unwrap();
+    let limits = RiskLimits {
+        max_order_quantity: Some(50),
+        ..Default::default()
+    };
+    let shard = plan.get_shard("GOOGL");
+    exchanges[shard].set_risk_limits("*", "GOOGL", limits.clone());
+    let mut order = Order::new("GOOGL", 60, Price::from(89), Side::Buy);
+    exchanges[shard].assign_order_id(&mut order);
+    assert!(exchanges[shard].execute_order(order).is_err());
+    // The ids of a shard don't depend on the process-wide counter
+    Order::new("AAPL", 1, Price::from(1), Side::Buy);
+
+    let replayed = replay(None, &read_entries(file_path).unwrap(), &config).unwrap();
+    std::fs::remove_file(file_path).unwrap();
+    assert_eq!(replayed.len(), exchanges.len());
+    for (exchange, replayed) in exchanges.iter().zip(&replayed) {
+        assert_eq!(replayed.get_fills(), exchange.get_fills());
+        for symbol in ["AAPL", "GOOGL", "MSFT"] {
+            assert_eq!(
+                replayed.get_order_depth(symbol),
+                exchange.get_order_depth(symbol)
+            );
+            assert_eq!(
+                replayed.get_trading_phase(symbol),
+                exchange.get_trading_phase(symbol)
+            );
+        }
+    }
+    assert!(!exchanges[plan.get_shard("GOOGL")].get_fills().is_empty());
+    assert_eq!(
+        replayed[plan.get_shard("MSFT")].get_trading_phase("MSFT"),
+        TradingPhase::Halted
+    );
+    assert_eq!(
+        replayed[shard]
+            .get_risk_manager()
+            .get_limits("FIRM1", "GOOGL"),
+        Some(&limits)
+    );
+}
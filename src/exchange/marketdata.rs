@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::depth::{MarketDepth, PriceLevel};
+use crate::price::Price;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketDataEntryType {
+    Bid,
+    Offer,
+    Trade,
+    OpeningPrice,
+    ClosingPrice,
+    TradeVolume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateAction {
+    New,
+    Change,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketDataEntry {
+    pub action: UpdateAction,
+    pub entry_type: MarketDataEntryType,
+    pub price: Option<Price>,
+    pub size: u64,
+    pub order_count: Option<usize>,
+}
+
+// Everything that changed for one symbol since its last update. Sequence
+// numbers start at 1 and go up by one with every update of the symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketDataUpdate {
+    pub symbol: String,
+    pub sequence: u64,
+    pub entries: Vec<MarketDataEntry>,
+}
+
+// Turns book changes, trades and auction prices into incremental updates.
+// Book changes are published as the difference between the price levels last
+// published and the current ones.
+#[derive(Debug, Clone, Default)]
+pub struct MarketDataFeed {
+    books: HashMap<String, MarketDepth>,
+    changed: BTreeSet<String>,
+    pending: BTreeMap<String, Vec<MarketDataEntry>>,
+    volumes: HashMap<String, u64>,
+    sequences: HashMap<String, u64>,
+}
+
+impl MarketDataFeed {
+    pub fn new() -> MarketDataFeed {
+        MarketDataFeed::default()
+    }
+
+    pub fn book_changed(&mut self, symbol: &str) {
+        self.changed.insert(symbol.to_string());
+    }
+
+    // A trade, followed by the day's traded volume including it
+    pub fn trade(&mut self, symbol: &str, price: Price, quantity: u32) {
+        let volume = self.volumes.entry(symbol.to_string()).or_default();
+        *volume += quantity as u64;
+        let volume = *volume;
+        let entries = self.pending.entry(symbol.to_string()).or_default();
+        entries.push(MarketDataEntry {
+            action: UpdateAction::New,
+            entry_type: MarketDataEntryType::Trade,
+            price: Some(price),
+            size: quantity as u64,
+            order_count: None,
+        });
+        entries.push(MarketDataEntry {
+            action: UpdateAction::Change,
+            entry_type: MarketDataEntryType::TradeVolume,
+            price: None,
+            size: volume,
+            order_count: None,
+        });
+    }
+
+    // A busted trade, deleted along with its quantity from the day's volume
+    pub fn trade_busted(&mut self, symbol: &str, price: Price, quantity: u32) {
+        let volume = self.volumes.entry(symbol.to_string()).or_default();
+        *volume = volume.saturating_sub(quantity as u64);
+        let volume = *volume;
+        let entries = self.pending.entry(symbol.to_string()).or_default();
+        entries.push(MarketDataEntry {
+            action: UpdateAction::Delete,
+            entry_type: MarketDataEntryType::Trade,
+            price: Some(price),
+            size: quantity as u64,
+            order_count: None,
+        });
+        entries.push(MarketDataEntry {
+            action: UpdateAction::Change,
+            entry_type: MarketDataEntryType::TradeVolume,
+            price: None,
+            size: volume,
+            order_count: None,
+        });
+    }
+
+    // The opening or closing price set by an auction
+    pub fn auction_price(
+        &mut self,
+        symbol: &str,
+        entry_type: MarketDataEntryType,
+        price: Price,
+        volume: u64,
+    ) {
+        self.pending
+            .entry(symbol.to_string())
+            .or_default()
+            .push(MarketDataEntry {
+                action: UpdateAction::New,
+                entry_type,
+                price: Some(price),
+                size: volume,
+                order_count: None,
+            });
+    }
+
+    // Symbols whose book changed since the last publish
+    pub fn changed_symbols(&self) -> Vec<String> {
+        self.changed.iter().cloned().collect()
+    }
+
+    pub fn get_sequence(&self, symbol: &str) -> u64 {
+        self.sequences.get(symbol).copied().unwrap_or(0)
+    }
+
+    // Publishes the pending trades and prices, then the level changes between
+    // the books last published and `books`, which holds the full depth of
+    // every changed symbol
+    pub fn publish(&mut self, books: Vec<(String, MarketDepth)>) -> Vec<MarketDataUpdate> {
+        let mut entries = std::mem::take(&mut self.pending);
+        self.changed.clear();
+        for (symbol, book) in books {
+            let previous = self.books.remove(&symbol).unwrap_or_default();
+            let symbol_entries = entries.entry(symbol.to_owned()).or_default();
+            symbol_entries.extend(level_changes(
+                MarketDataEntryType::Bid,
+                &previous.bids,
+                &book.bids,
+            ));
+            symbol_entries.extend(level_changes(
+                MarketDataEntryType::Offer,
+                &previous.asks,
+                &book.asks,
+            ));
+            if !book.bids.is_empty() || !book.asks.is_empty() {
+                self.books.insert(symbol, book);
+            }
+        }
+
+        entries
+            .into_iter()
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(symbol, entries)| {
+                let sequence = self.sequences.entry(symbol.to_owned()).or_default();
+                *sequence += 1;
+                MarketDataUpdate {
+                    symbol,
+                    sequence: *sequence,
+                    entries,
+                }
+            })
+            .collect()
+    }
+}
+
+fn level_changes(
+    entry_type: MarketDataEntryType,
+    previous: &[PriceLevel],
+    current: &[PriceLevel],
+) -> Vec<MarketDataEntry> {
+    let entry = |action, level: &PriceLevel| MarketDataEntry {
+        action,
+        entry_type,
+        price: Some(level.price),
+        size: level.quantity,
+        order_count: Some(level.order_count),
+    };
+    let mut changes: Vec<MarketDataEntry> = previous
+        .iter()
+        .filter(|level| !current.iter().any(|other| other.price == level.price))
+        .map(|level| entry(UpdateAction::Delete, level))
+        .collect();
+    for level in current {
+        match previous.iter().find(|other| other.price == level.price) {
+            None => changes.push(entry(UpdateAction::New, level)),
+            Some(other) if other != level => changes.push(entry(UpdateAction::Change, level)),
+            Some(_) => {}
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+fn test_depth(bids: &[(i64, u64)], asks: &[(i64, u64)]) -> MarketDepth {
+    let levels = |levels: &[(i64, u64)]| {
+        levels
+            .iter()
+            .map(|(price, quantity)| PriceLevel {
+                price: Price::from(*price),
+                quantity: *quantity,
+                order_count: 1,
+            })
+            .collect()
+    };
+    MarketDepth {
+        bids: levels(bids),
+        asks: levels(asks),
+    }
+}
+
+#[test]
+fn test_market_data_feed_levels() {
+    let mut feed = MarketDataFeed::new();
+    feed.book_changed("AAPL");
+    assert_eq!(feed.changed_symbols(), vec!["AAPL"]);
+    let updates = feed.publish(vec![(
+        "AAPL".to_string(),
+        test_depth(&[(99, 100)], &[(101, 100)]),
+    )]);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].sequence, 1);
+    assert_eq!(updates[0].entries.len(), 2);
+    assert_eq!(updates[0].entries[0].action, UpdateAction::New);
+    assert!(feed.changed_symbols().is_empty());
+
+    let updates = feed.publish(vec![("AAPL".to_string(), test_depth(&[(99, 200)], &[]))]);
+    assert_eq!(updates[0].sequence, 2);
+    let actions: Vec<(MarketDataEntryType, UpdateAction)> = updates[0]
+        .entries
+        .iter()
+        .map(|entry| (entry.entry_type, entry.action))
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            (MarketDataEntryType::Bid, UpdateAction::Change),
+            (MarketDataEntryType::Offer, UpdateAction::Delete)
+        ]
+    );
+
+    // Nothing is published when the book is back where it was
+    let updates = feed.publish(vec![("AAPL".to_string(), test_depth(&[(99, 200)], &[]))]);
+    assert!(updates.is_empty());
+    assert_eq!(feed.get_sequence("AAPL"), 2);
+}
+
+#[test]
+fn test_market_data_feed_trades() {
+    let mut feed = MarketDataFeed::new();
+    feed.trade("AAPL", Price::from(100), 50);
+    feed.trade("AAPL", Price::from(101), 70);
+    feed.auction_price(
+        "GOOG",
+        MarketDataEntryType::OpeningPrice,
+        Price::from(150),
+        300,
+    );
+    let updates = feed.publish(Vec::new());
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].symbol, "AAPL");
+    assert_eq!(updates[0].entries.len(), 4);
+    assert_eq!(
+        updates[0].entries[3].entry_type,
+        MarketDataEntryType::TradeVolume
+    );
+    assert_eq!(updates[0].entries[3].size, 120);
+    assert_eq!(updates[1].symbol, "GOOG");
+    assert_eq!(updates[1].sequence, 1);
+}
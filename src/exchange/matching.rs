@@ -0,0 +1,212 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::order::Order;
+
+// Decides how an incoming order's quantity is shared among the orders resting
+// at the best price level. `level` is in time priority, and the allocations
+// returned line up with it and add up to at most `quantity`. The same inputs
+// must always give the same allocations.
+pub trait MatchingAlgorithm {
+    fn allocate(&self, quantity: u32, level: &[&Order]) -> Vec<u32>;
+}
+
+// Price-time priority, the oldest order is filled first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fifo;
+
+impl MatchingAlgorithm for Fifo {
+    fn allocate(&self, quantity: u32, level: &[&Order]) -> Vec<u32> {
+        let mut allocations = vec![0; level.len()];
+        allocate_in_time_priority(quantity, level, &mut allocations);
+        allocations
+    }
+}
+
+// Every order gets a share in proportion to its size, rounded down, with the
+// remainder going out in time priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProRata;
+
+impl MatchingAlgorithm for ProRata {
+    fn allocate(&self, quantity: u32, level: &[&Order]) -> Vec<u32> {
+        let mut allocations = vec![0; level.len()];
+        allocate_pro_rata(quantity, level, &mut allocations);
+        allocations
+    }
+}
+
+// The oldest order at the level is filled in full first, and the rest is
+// shared pro-rata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProRataTopOrder;
+
+impl MatchingAlgorithm for ProRataTopOrder {
+    fn allocate(&self, quantity: u32, level: &[&Order]) -> Vec<u32> {
+        let mut allocations = vec![0; level.len()];
+        let top_order = level
+            .first()
+            .map_or(0, |order| order.quantity.min(quantity));
+        if let Some(allocation) = allocations.first_mut() {
+            *allocation = top_order;
+        }
+        allocate_pro_rata(quantity - top_order, level, &mut allocations);
+        allocations
+    }
+}
+
+// The lead market makers' orders are guaranteed `allocation_percent` of the
+// incoming quantity between them, in time priority, and the rest is shared
+// pro-rata among every order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeadMarketMaker {
+    pub participants: Vec<String>,
+    pub allocation_percent: u32,
+}
+
+impl MatchingAlgorithm for LeadMarketMaker {
+    fn allocate(&self, quantity: u32, level: &[&Order]) -> Vec<u32> {
+        let mut allocations = vec![0; level.len()];
+        let mut guaranteed =
+            (quantity as u64 * self.allocation_percent.min(100) as u64 / 100) as u32;
+        for (order, allocation) in level.iter().zip(allocations.iter_mut()) {
+            if self.participants.contains(&order.participant) {
+                *allocation = order.quantity.min(guaranteed);
+                guaranteed -= *allocation;
+            }
+        }
+        let allocated: u32 = allocations.iter().sum();
+        allocate_pro_rata(quantity - allocated, level, &mut allocations);
+        allocations
+    }
+}
+
+// Shares `quantity` among what is left of each order on top of `allocations`
+fn allocate_pro_rata(quantity: u32, level: &[&Order], allocations: &mut [u32]) {
+    let open: Vec<u64> = level
+        .iter()
+        .zip(allocations.iter())
+        .map(|(order, allocation)| (order.quantity - allocation) as u64)
+        .collect();
+    let total: u64 = open.iter().sum();
+    if total == 0 {
+        return;
+    }
+    let quantity = (quantity as u64).min(total);
+    let mut allocated = 0;
+    for (open, allocation) in open.iter().zip(allocations.iter_mut()) {
+        let share = quantity * open / total;
+        *allocation += share as u32;
+        allocated += share;
+    }
+    allocate_in_time_priority((quantity - allocated) as u32, level, allocations);
+}
+
+fn allocate_in_time_priority(mut quantity: u32, level: &[&Order], allocations: &mut [u32]) {
+    for (order, allocation) in level.iter().zip(allocations.iter_mut()) {
+        let fill = (order.quantity - *allocation).min(quantity);
+        *allocation += fill;
+        quantity -= fill;
+    }
+}
+
+// The matching algorithm an instrument trades with, as named in the
+// reference data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchingRule {
+    #[default]
+    Fifo,
+    ProRata,
+    ProRataTopOrder,
+    LeadMarketMaker,
+}
+
+impl FromStr for MatchingRule {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(MatchingRule::Fifo),
+            "pro_rata" => Ok(MatchingRule::ProRata),
+            "pro_rata_top_order" => Ok(MatchingRule::ProRataTopOrder),
+            "lmm" => Ok(MatchingRule::LeadMarketMaker),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for MatchingRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            MatchingRule::Fifo => "fifo",
+            MatchingRule::ProRata => "pro_rata",
+            MatchingRule::ProRataTopOrder => "pro_rata_top_order",
+            MatchingRule::LeadMarketMaker => "lmm",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+fn test_level(orders: &[(u32, &str)]) -> Vec<Order> {
+    use crate::order::Side;
+    use crate::price::Price;
+    orders
+        .iter()
+        .map(|(quantity, participant)| {
+            let mut order = Order::new("ES", *quantity, Price::from(100), Side::Sell);
+            order.participant = participant.to_string();
+            order
+        })
+        .collect()
+}
+
+#[test]
+fn test_fifo_allocation() {
+    let level = test_level(&[(30, "A"), (50, "B"), (20, "C")]);
+    let level: Vec<&Order> = level.iter().collect();
+    assert_eq!(Fifo.allocate(60, &level), vec![30, 30, 0]);
+    assert_eq!(Fifo.allocate(500, &level), vec![30, 50, 20]);
+}
+
+#[test]
+fn test_pro_rata_allocation() {
+    let level = test_level(&[(30, "A"), (50, "B"), (20, "C")]);
+    let level: Vec<&Order> = level.iter().collect();
+    assert_eq!(ProRata.allocate(50, &level), vec![15, 25, 10]);
+    // 6.9, 11.5 and 4.6 round down to 6, 11 and 4, the 2 left go to the
+    // oldest order
+    assert_eq!(ProRata.allocate(23, &level), vec![6 + 2, 11, 4]);
+    assert_eq!(ProRata.allocate(500, &level), vec![30, 50, 20]);
+}
+
+#[test]
+fn test_pro_rata_top_order_allocation() {
+    let level = test_level(&[(20, "A"), (60, "B"), (20, "C")]);
+    let level: Vec<&Order> = level.iter().collect();
+    assert_eq!(ProRataTopOrder.allocate(60, &level), vec![20, 30, 10]);
+    assert_eq!(ProRataTopOrder.allocate(10, &level), vec![10, 0, 0]);
+}
+
+#[test]
+fn test_lead_market_maker_allocation() {
+    let level = test_level(&[(50, "A"), (100, "MM"), (50, "C")]);
+    let level: Vec<&Order> = level.iter().collect();
+    let lmm = LeadMarketMaker {
+        participants: vec!["MM".to_string()],
+        allocation_percent: 40,
+    };
+    // 40 guaranteed to MM, then 60 shared over open quantities of 50, 60, 50
+    assert_eq!(lmm.allocate(100, &level), vec![18 + 2, 40 + 22, 18]);
+    assert_eq!(lmm.allocate(100, &level).iter().sum::<u32>(), 100);
+}
+
+#[test]
+fn test_matching_rule_names() {
+    assert_eq!(
+        "pro_rata".parse::<MatchingRule>(),
+        Ok(MatchingRule::ProRata)
+    );
+    assert_eq!(MatchingRule::LeadMarketMaker.to_string(), "lmm");
+    assert!("random".parse::<MatchingRule>().is_err());
+}
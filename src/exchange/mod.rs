@@ -1,3 +1,23 @@
+pub mod auction;
+pub mod book;
+pub mod cancellation;
+pub mod depth;
+#[allow(clippy::module_inception)]
 pub mod exchange;
 mod executions;
+pub mod fee;
+mod fields;
+pub mod instrument;
+pub mod journal;
+pub mod marketdata;
+pub mod matching;
 mod orderbook;
+pub mod orderrecord;
+pub mod phase;
+pub mod position;
+pub mod quote;
+pub mod reject;
+pub mod risk;
+pub mod shard;
+pub mod snapshot;
+pub mod trade;
@@ -1,12 +1,23 @@
+use super::book::BookSide;
+use super::cancellation::{CancelReason, Cancellation};
 use super::executions::ExecutionList;
-use crate::order::{Order, Side};
-use skiplist::ordered_skiplist::OrderedSkipList;
-use std::collections::HashMap;
+use super::matching::MatchingAlgorithm;
+use super::quote::Quote;
+use crate::order::{Order, SelfTradePrevention, Side};
+use crate::price::Price;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 pub struct OrderBook {
-    pub buy_orders: HashMap<String, OrderedSkipList<Order>>,
-    pub sell_orders: HashMap<String, OrderedSkipList<Order>>,
+    pub buy_orders: HashMap<String, BookSide>,
+    pub sell_orders: HashMap<String, BookSide>,
     pub executions: ExecutionList,
+    pub cancellations: Vec<Cancellation>,
+    // The ids of the pegged orders of each symbol, including some that have
+    // since left the book
+    pegged_orders: HashMap<String, BTreeSet<u32>>,
+    // The quote of each market maker in each symbol, by participant and
+    // symbol. Their legs rest among the orders.
+    quotes: HashMap<(String, String), Quote>,
 }
 
 impl OrderBook {
@@ -15,28 +26,27 @@ impl OrderBook {
             buy_orders: HashMap::new(),
             sell_orders: HashMap::new(),
             executions: ExecutionList::new(),
+            cancellations: Vec::new(),
+            pegged_orders: HashMap::new(),
+            quotes: HashMap::new(),
         }
     }
 
     pub fn add_order(&mut self, order: Order) {
-        let (symbol, side) = (&order.symbol.to_owned(), &order.side);
-
-        match side {
-            Side::Buy => {
-                let buy_orders = self
-                    .buy_orders
-                    .entry(symbol.to_string())
-                    .or_insert(OrderedSkipList::new());
-                buy_orders.insert(order);
-            }
-            Side::Sell => {
-                let sell_orders = self
-                    .sell_orders
-                    .entry(symbol.to_string())
-                    .or_insert(OrderedSkipList::new());
-                sell_orders.insert(order);
-            }
+        if order.peg.is_some() {
+            self.pegged_orders
+                .entry(order.symbol.to_owned())
+                .or_default()
+                .insert(order.id);
         }
+        let orders = match order.side {
+            Side::Buy => &mut self.buy_orders,
+            Side::Sell => &mut self.sell_orders,
+        };
+        orders
+            .entry(order.symbol.to_owned())
+            .or_insert_with(|| BookSide::new(order.side.clone()))
+            .insert(order);
     }
 
     pub fn remove_order(&mut self, order: Order) -> Option<Order> {
@@ -45,7 +55,7 @@ impl OrderBook {
         match side {
             Side::Buy => {
                 let buy_orders = self.buy_orders.get_mut(symbol)?;
-                let removed = buy_orders.remove(&order);
+                let removed = buy_orders.remove(order.id);
                 if buy_orders.is_empty() {
                     self.buy_orders.remove(symbol);
                 }
@@ -53,7 +63,7 @@ impl OrderBook {
             }
             Side::Sell => {
                 let sell_orders = self.sell_orders.get_mut(symbol)?;
-                let removed = sell_orders.remove(&order);
+                let removed = sell_orders.remove(order.id);
                 if sell_orders.is_empty() {
                     self.sell_orders.remove(symbol);
                 }
@@ -62,38 +72,332 @@ impl OrderBook {
         }
     }
 
-    pub fn match_orders(&mut self, symbol: &str) -> Option<ExecutionList> {
+    pub fn get_quote(&self, participant: &str, symbol: &str) -> Option<&Quote> {
+        self.quotes
+            .get(&(participant.to_string(), symbol.to_string()))
+    }
+
+    pub fn add_quote(&mut self, quote: Quote) {
+        self.quotes.insert(
+            (quote.participant.to_owned(), quote.symbol.to_owned()),
+            quote,
+        );
+    }
+
+    pub fn get_quotes(&self) -> impl Iterator<Item = &Quote> {
+        self.quotes.values()
+    }
+
+    pub fn get_order(&self, symbol: &str, side: &Side, order_id: u32) -> Option<&Order> {
+        let orders = match side {
+            Side::Buy => self.buy_orders.get(symbol)?,
+            Side::Sell => self.sell_orders.get(symbol)?,
+        };
+        orders.get(order_id)
+    }
+
+    // The best bid and offer of `symbol` among the displayed orders that are
+    // not pegged, which pegged orders are priced from. All-or-none and
+    // minimum quantity orders are left out too, as they may rest through the
+    // touch.
+    pub fn get_touch(&self, symbol: &str) -> (Option<Price>, Option<Price>) {
+        let unpegged =
+            |order: &Order| order.peg.is_none() && !order.hidden && order.minimum_fill() == 0;
+        (
+            self.buy_orders
+                .get(symbol)
+                .and_then(|orders| orders.best_price_of(unpegged)),
+            self.sell_orders
+                .get(symbol)
+                .and_then(|orders| orders.best_price_of(unpegged)),
+        )
+    }
+
+    // Moves the pegged orders of `symbol` to their peg at the current touch.
+    // An order whose price changes loses its time priority: it joins the back
+    // of the queue at its new price with a sequence from `next_sequence`,
+    // the orders repriced together keeping their order. Orders without a
+    // price, while their reference is missing, stay where they are, and
+    // midpoints are rounded on the tick `tick_size` gives at a price. Returns
+    // each repriced order as it was and as it now is.
+    pub fn reprice_pegged_orders(
+        &mut self,
+        symbol: &str,
+        tick_size: impl Fn(Price) -> Price,
+        mut next_sequence: impl FnMut() -> u32,
+    ) -> Vec<(Order, Order)> {
+        let Some(order_ids) = self.pegged_orders.get_mut(symbol) else {
+            return Vec::new();
+        };
+        let (buy_orders, sell_orders) = (self.buy_orders.get(symbol), self.sell_orders.get(symbol));
+        let find = |order_id: &u32| {
+            buy_orders
+                .and_then(|orders| orders.get(*order_id))
+                .or_else(|| sell_orders.and_then(|orders| orders.get(*order_id)))
+        };
+        order_ids.retain(|order_id| find(order_id).is_some());
+        let mut pegged: Vec<Order> = order_ids.iter().filter_map(find).cloned().collect();
+        pegged.sort_by_key(|order| order.sequence);
+
+        let (bid, ask) = self.get_touch(symbol);
+        let mut repriced = Vec::new();
+        for order in pegged {
+            let Some(price) = order
+                .peg
+                .and_then(|peg| peg.price(&order.side, bid, ask, &tick_size))
+                .filter(|price| *price != order.price)
+            else {
+                continue;
+            };
+            let Some(mut moved) = self.remove_order(order.clone()) else {
+                continue;
+            };
+            moved.price = price;
+            moved.sequence = next_sequence();
+            self.add_order(moved.clone());
+            repriced.push((order, moved));
+        }
+        repriced
+    }
+
+    // Lowers the open quantity of a resting order. Price and sequence are left
+    // untouched, so the order goes back into the same place in the queue.
+    pub fn reduce_order(&mut self, order: &Order, quantity: u32) -> Option<Order> {
+        if quantity == 0 || quantity > order.quantity {
+            return None;
+        }
+
+        let orders = match order.side {
+            Side::Buy => self.buy_orders.get_mut(&order.symbol)?,
+            Side::Sell => self.sell_orders.get_mut(&order.symbol)?,
+        };
+        orders.update_quantity(order.id, quantity)
+    }
+
+    // Continuous matching with `algorithm` sharing each incoming order among
+    // the resting orders at the best price, each trade printing at the price
+    // of the resting orders. Stops before the first trade outside the
+    // (low, high) price band, if there is one, and returns the price that
+    // trade would have had.
+    pub fn match_orders(
+        &mut self,
+        symbol: &str,
+        algorithm: &dyn MatchingAlgorithm,
+        price_band: Option<(Price, Price)>,
+    ) -> Option<Price> {
+        self.match_book(symbol, algorithm, None, price_band)
+    }
+
+    // Executes every order that crosses the auction price at that price
+    pub fn uncross(
+        &mut self,
+        symbol: &str,
+        auction_price: Price,
+        algorithm: &dyn MatchingAlgorithm,
+    ) -> Option<ExecutionList> {
+        self.match_book(symbol, algorithm, Some(auction_price), None);
+        Some(self.executions.to_owned())
+    }
+
+    // Repeatedly takes the newer of the two front orders as the aggressor and
+    // fills it against the opposite price levels, as shared out by
+    // `algorithm`. The front of a side is its first order that the other side
+    // can fill, so an all-or-none or minimum quantity order that cannot be
+    // filled does not hold up the orders behind it.
+    fn match_book(
+        &mut self,
+        symbol: &str,
+        algorithm: &dyn MatchingAlgorithm,
+        auction_price: Option<Price>,
+        price_band: Option<(Price, Price)>,
+    ) -> Option<Price> {
         let buy_orders = self.buy_orders.get_mut(symbol)?;
 
         let sell_orders = self.sell_orders.get_mut(symbol)?;
 
-        while let (Some(mut buy_order), Some(mut sell_order)) =
-            (buy_orders.pop_front(), sell_orders.pop_front())
+        // Aggressors that have started trading, whose minimum quantity has
+        // been met
+        let mut started = HashSet::new();
+
+        // Orders are filled in place at the front of each side, for as long
+        // as either front can trade
+        while let Some(mut aggressor) = front(buy_orders, sell_orders, auction_price, &started)
+            .into_iter()
+            .chain(front(sell_orders, buy_orders, auction_price, &started))
+            .max_by_key(|order| order.sequence)
+            .cloned()
         {
-            if buy_order.price >= sell_order.price {
-                let execution_id: usize = self.executions.matches.len() + 1;
-                self.executions
-                    .insert(execution_id, (buy_order.to_owned(), sell_order.to_owned()));
-                if buy_order.quantity > sell_order.quantity {
-                    buy_order.quantity -= sell_order.quantity;
-                    buy_orders.insert(buy_order);
-                    continue;
-                } else if buy_order.quantity < sell_order.quantity {
-                    sell_order.quantity -= buy_order.quantity;
-                    sell_orders.insert(sell_order);
+            let (aggressors, passives) = match aggressor.side {
+                Side::Buy => (&mut *buy_orders, &mut *sell_orders),
+                Side::Sell => (&mut *sell_orders, &mut *buy_orders),
+            };
+            let Some(level_price) = passives
+                .iter()
+                .take_while(|passive| crosses(&aggressor, passive, auction_price))
+                .find(|passive| accepts(passive, aggressor.quantity))
+                .map(|passive| passive.price)
+            else {
+                break;
+            };
+            let price = auction_price.unwrap_or(level_price);
+            if price_band.is_some_and(|(low, high)| price < low || price > high) {
+                return Some(price);
+            }
+
+            let mut level: Vec<Order> = passives
+                .level_orders(level_price)
+                .filter(|passive| accepts(passive, aggressor.quantity))
+                .cloned()
+                .collect();
+            let allocations = allocate(algorithm, aggressor.quantity, &mut level);
+            started.insert(aggressor.id);
+            let mut filled = false;
+            for (mut passive, quantity) in level.into_iter().zip(allocations) {
+                if quantity == 0 || aggressor.quantity == 0 {
                     continue;
                 }
-            } else {
-                if buy_order.quantity > 0 {
-                    buy_orders.insert(buy_order);
-                }
-                if sell_order.quantity > 0 {
-                    sell_orders.insert(sell_order);
+                filled = true;
+
+                if let Some(mode) = self_trade_prevention(&aggressor, &passive) {
+                    let (newest, oldest) = if aggressor.sequence > passive.sequence {
+                        (&mut aggressor, &mut passive)
+                    } else {
+                        (&mut passive, &mut aggressor)
+                    };
+                    let (newest_quantity, oldest_quantity) = match mode {
+                        SelfTradePrevention::CancelNewest => (newest.quantity, 0),
+                        SelfTradePrevention::CancelOldest => (0, oldest.quantity),
+                        SelfTradePrevention::CancelBoth => (newest.quantity, oldest.quantity),
+                        SelfTradePrevention::DecrementAndCancel => {
+                            let quantity = newest.quantity.min(oldest.quantity);
+                            (quantity, quantity)
+                        }
+                    };
+                    for (order, quantity) in [(newest, newest_quantity), (oldest, oldest_quantity)]
+                    {
+                        if quantity > 0 {
+                            self.cancellations.push(Cancellation {
+                                order: order.to_owned(),
+                                quantity,
+                                reason: CancelReason::SelfTradePrevention(mode),
+                                execution_count: self.executions.len(),
+                            });
+                            order.quantity -= quantity;
+                        }
+                    }
+                    passives.update_quantity(passive.id, passive.quantity);
+                    // The rest of the level is shared out again without the
+                    // cancelled quantity
+                    break;
                 }
+
+                let execution = match aggressor.side {
+                    Side::Buy => (aggressor.to_owned(), passive.to_owned()),
+                    Side::Sell => (passive.to_owned(), aggressor.to_owned()),
+                };
+                let execution_id = self.executions.next_id();
+                // Nobody takes liquidity from anyone in an auction
+                let taker = auction_price.is_none().then(|| aggressor.side.clone());
+                self.executions
+                    .insert(execution_id, execution, price, quantity, taker);
+                aggressor.quantity -= quantity;
+                passive.quantity -= quantity;
+                passives.update_quantity(passive.id, passive.quantity);
+            }
+            // Filled in place, so a partly filled order keeps its priority
+            aggressors.update_quantity(aggressor.id, aggressor.quantity);
+            if !filled {
                 break;
             }
         }
-        Some(self.executions.to_owned())
+        None
+    }
+}
+
+// Whether `order` trades with `other`, at the auction price if there is one
+fn crosses(order: &Order, other: &Order, auction_price: Option<Price>) -> bool {
+    let (buy_price, sell_price) = match order.side {
+        Side::Buy => (order.price, other.price),
+        Side::Sell => (other.price, order.price),
+    };
+    match auction_price {
+        Some(price) => buy_price >= price && sell_price <= price,
+        None => buy_price >= sell_price,
+    }
+}
+
+// Whether `order` takes a fill from an aggressor with `quantity` left
+fn accepts(order: &Order, quantity: u32) -> bool {
+    quantity.min(order.quantity) >= order.minimum_fill()
+}
+
+// The first order of `side`, in priority, that the orders of `contra` can
+// fill at least the minimum quantity of, or any of once it has `started`
+// trading. Only the orders that cross are looked at.
+fn front<'a>(
+    side: &'a BookSide,
+    contra: &BookSide,
+    auction_price: Option<Price>,
+    started: &HashSet<u32>,
+) -> Option<&'a Order> {
+    let contra_front = contra.front()?;
+    side.iter()
+        .take_while(|order| match (auction_price, &order.side) {
+            (Some(price), Side::Buy) => order.price >= price,
+            (Some(price), Side::Sell) => order.price <= price,
+            (None, _) => crosses(order, contra_front, None),
+        })
+        .find(|order| {
+            let needed = match order.all_or_none || !started.contains(&order.id) {
+                true => order.minimum_fill().max(1),
+                false => 1,
+            };
+            let mut available = 0;
+            contra
+                .iter()
+                .take_while(|other| crosses(order, other, auction_price))
+                .filter(|other| accepts(other, order.quantity))
+                .any(|other| {
+                    available += other.quantity;
+                    available >= needed
+                })
+        })
+}
+
+// Shares `quantity` out among `level` with `algorithm`, the displayed orders
+// first and the hidden ones behind them with what is left. An order offered
+// less than its minimum fill is dropped from `level` and the quantity shared
+// out again without it. The allocations line up with what is left of `level`.
+fn allocate(algorithm: &dyn MatchingAlgorithm, quantity: u32, level: &mut Vec<Order>) -> Vec<u32> {
+    loop {
+        let (displayed, hidden): (Vec<&Order>, Vec<&Order>) =
+            level.iter().partition(|order| !order.hidden);
+        let mut allocations = algorithm.allocate(quantity, &displayed);
+        let allocated: u32 = allocations.iter().sum();
+        allocations.extend(algorithm.allocate(quantity - allocated, &hidden));
+        let short = level
+            .iter()
+            .zip(&allocations)
+            .position(|(order, allocation)| *allocation > 0 && *allocation < order.minimum_fill());
+        match short {
+            Some(index) => {
+                level.remove(index);
+            }
+            None => return allocations,
+        }
+    }
+}
+
+// Orders from the same participant or STP group must not trade with each
+// other when the newer of the two asks for self-trade prevention
+fn self_trade_prevention(order: &Order, other: &Order) -> Option<SelfTradePrevention> {
+    if order.stp_key().is_empty() || order.stp_key() != other.stp_key() {
+        return None;
+    }
+    if order.sequence > other.sequence {
+        order.self_trade_prevention
+    } else {
+        other.self_trade_prevention
     }
 }
 
@@ -107,9 +411,9 @@ fn create_order_book() {
 #[test]
 fn test_order_book_add_order() {
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Sell);
-    let order3 = Order::new("AAPL", 100, 200.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    let order3 = Order::new("AAPL", 100, Price::from(200), Side::Sell);
     order_book.add_order(order1);
     order_book.add_order(order2);
     order_book.add_order(order3);
@@ -122,9 +426,9 @@ fn test_order_book_add_order() {
 #[test]
 fn test_order_book_remove_order() {
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Sell);
-    let order3 = Order::new("AAPL", 100, 200.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    let order3 = Order::new("AAPL", 100, Price::from(200), Side::Sell);
     order_book.add_order(order1.clone());
     order_book.add_order(order2.clone());
     order_book.add_order(order3.clone());
@@ -152,10 +456,10 @@ fn test_order_book_remove_order() {
 #[test]
 fn test_order_book_priority() {
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 200.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order3 = Order::new("AAPL", 100, 200.0, Side::Sell);
-    let order4 = Order::new("AAPL", 100, 150.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order3 = Order::new("AAPL", 100, Price::from(200), Side::Sell);
+    let order4 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
     order_book.add_order(order1.clone());
     order_book.add_order(order2.clone());
     order_book.add_order(order3.clone());
@@ -180,16 +484,18 @@ fn test_order_book_priority() {
 
 #[test]
 fn test_match_orders() {
+    use super::matching::Fifo;
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order3 = Order::new("AAPL", 100, 150.0, Side::Sell);
-    let order4 = Order::new("AAPL", 100, 200.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order3 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    let order4 = Order::new("AAPL", 100, Price::from(200), Side::Sell);
     order_book.add_order(order1.clone());
     order_book.add_order(order2.clone());
     order_book.add_order(order3.clone());
     order_book.add_order(order4.clone());
-    let executions = order_book.match_orders("AAPL").unwrap();
+    order_book.match_orders("AAPL", &Fifo, None);
+    let executions = &order_book.executions;
     assert_eq!(
         executions
             .get_matches_for_id(order1.id)
@@ -201,19 +507,21 @@ fn test_match_orders() {
 
 #[test]
 fn test_multiple_match_orders() {
+    use super::matching::Fifo;
     use std::collections::HashSet;
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 200.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Buy);
-    let order3 = Order::new("AAPL", 100, 200.0, Side::Buy);
-    let order4 = Order::new("AAPL", 100, 150.0, Side::Sell);
-    let order5 = Order::new("AAPL", 100, 200.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order3 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
+    let order4 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    let order5 = Order::new("AAPL", 100, Price::from(200), Side::Sell);
     order_book.add_order(order1.clone());
     order_book.add_order(order2.clone());
     order_book.add_order(order3.clone());
     order_book.add_order(order4.clone());
     order_book.add_order(order5.clone());
-    let executions = order_book.match_orders("AAPL").unwrap();
+    order_book.match_orders("AAPL", &Fifo, None);
+    let executions = &order_book.executions;
     let mut expected: HashSet<(Order, Order)> = HashSet::new();
     expected.insert((order1.clone(), order4.clone()));
     assert_eq!(executions.get_matches_for_id(order1.id), expected);
@@ -224,16 +532,18 @@ fn test_multiple_match_orders() {
 
 #[test]
 fn test_partial_match_orders() {
+    use super::matching::Fifo;
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 200.0, Side::Buy);
-    let order2 = Order::new("AAPL", 200, 150.0, Side::Buy);
-    let order3 = Order::new("AAPL", 150, 200.0, Side::Sell);
-    let order4 = Order::new("AAPL", 300, 300.0, Side::Sell);
+    let order1 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
+    let order2 = Order::new("AAPL", 200, Price::from(150), Side::Buy);
+    let order3 = Order::new("AAPL", 150, Price::from(200), Side::Sell);
+    let order4 = Order::new("AAPL", 300, Price::from(300), Side::Sell);
     order_book.add_order(order1.clone());
     order_book.add_order(order2.clone());
     order_book.add_order(order3.clone());
     order_book.add_order(order4.clone());
-    let executions = order_book.match_orders("AAPL").unwrap();
+    order_book.match_orders("AAPL", &Fifo, None);
+    let executions = &order_book.executions;
     assert_eq!(
         executions
             .get_matches_for_id(order1.id)
@@ -245,20 +555,21 @@ fn test_partial_match_orders() {
 
 #[test]
 fn test_multiple_partial_match_orders() {
+    use super::matching::Fifo;
     use std::collections::HashSet;
     let mut order_book = OrderBook::new();
-    let order1 = Order::new("AAPL", 100, 200.0, Side::Buy);
-    let order2 = Order::new("AAPL", 200, 150.0, Side::Buy);
+    let order1 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
+    let order2 = Order::new("AAPL", 200, Price::from(150), Side::Buy);
 
-    let order3 = Order::new("AAPL", 150, 200.0, Side::Sell);
+    let order3 = Order::new("AAPL", 150, Price::from(200), Side::Sell);
 
-    let order4 = Order::new("AAPL", 300, 300.0, Side::Sell);
-    let order5 = Order::new("AAPL", 100, 200.0, Side::Buy);
+    let order4 = Order::new("AAPL", 300, Price::from(300), Side::Sell);
+    let order5 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
 
-    let order6 = Order::new("AAPL", 200, 150.0, Side::Buy);
-    let order7 = Order::new("AAPL", 150, 200.0, Side::Sell);
+    let order6 = Order::new("AAPL", 200, Price::from(150), Side::Buy);
+    let order7 = Order::new("AAPL", 150, Price::from(200), Side::Sell);
 
-    let order8 = Order::new("AAPL", 300, 300.0, Side::Sell);
+    let order8 = Order::new("AAPL", 300, Price::from(300), Side::Sell);
 
     order_book.add_order(order1.clone());
     order_book.add_order(order2.clone());
@@ -268,7 +579,8 @@ fn test_multiple_partial_match_orders() {
     order_book.add_order(order6.clone());
     order_book.add_order(order7.clone());
     order_book.add_order(order8.clone());
-    let executions = order_book.match_orders("AAPL").unwrap();
+    order_book.match_orders("AAPL", &Fifo, None);
+    let executions = &order_book.executions;
     let mut expected_exec: HashSet<(Order, Order)> = HashSet::new();
 
     expected_exec.insert((order1.clone(), order3.clone()));
@@ -279,3 +591,254 @@ fn test_multiple_partial_match_orders() {
 
     assert_eq!(executions.get_matches_for_id(order5.id), expected_exec);
 }
+
+#[test]
+fn test_order_book_get_order() {
+    let mut order_book = OrderBook::new();
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order_book.add_order(order1.clone());
+    assert_eq!(
+        order_book.get_order("AAPL", &Side::Buy, order1.id),
+        Some(&order1)
+    );
+    assert_eq!(order_book.get_order("AAPL", &Side::Sell, order1.id), None);
+    assert_eq!(order_book.get_order("GOOG", &Side::Buy, order1.id), None);
+}
+
+#[test]
+fn test_order_book_reduce_order() {
+    let mut order_book = OrderBook::new();
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order_book.add_order(order1.clone());
+    order_book.add_order(order2.clone());
+    let reduced = order_book.reduce_order(&order1, 40).unwrap();
+    assert_eq!(reduced.quantity, 40);
+    let front = order_book.buy_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(front, &order1);
+    assert_eq!(front.quantity, 40);
+    assert_eq!(order_book.reduce_order(&reduced, 50), None);
+    assert_eq!(order_book.reduce_order(&reduced, 0), None);
+}
+
+#[cfg(test)]
+fn self_trade_order_book(
+    mode: SelfTradePrevention,
+    resting_quantity: u32,
+    incoming_quantity: u32,
+) -> (OrderBook, Order, Order) {
+    let mut order_book = OrderBook::new();
+    let mut resting = Order::new("AAPL", resting_quantity, Price::from(150), Side::Sell);
+    resting.participant = "FIRM1".to_string();
+    let mut incoming = Order::new("AAPL", incoming_quantity, Price::from(150), Side::Buy);
+    incoming.participant = "FIRM1".to_string();
+    incoming.self_trade_prevention = Some(mode);
+    order_book.add_order(resting.clone());
+    order_book.add_order(incoming.clone());
+    order_book.match_orders("AAPL", &super::matching::Fifo, None);
+    (order_book, resting, incoming)
+}
+
+#[test]
+fn test_self_trade_cancel_newest() {
+    let (order_book, resting, incoming) =
+        self_trade_order_book(SelfTradePrevention::CancelNewest, 100, 100);
+    assert!(order_book.executions.matches.is_empty());
+    assert_eq!(order_book.cancellations.len(), 1);
+    assert_eq!(order_book.cancellations[0].order, incoming);
+    assert_eq!(order_book.cancellations[0].quantity, 100);
+    assert_eq!(order_book.buy_orders.get("AAPL").unwrap().len(), 0);
+    assert_eq!(
+        order_book.sell_orders.get("AAPL").unwrap().front(),
+        Some(&resting)
+    );
+}
+
+#[test]
+fn test_self_trade_cancel_oldest() {
+    let (order_book, resting, incoming) =
+        self_trade_order_book(SelfTradePrevention::CancelOldest, 100, 100);
+    assert_eq!(order_book.cancellations.len(), 1);
+    assert_eq!(order_book.cancellations[0].order, resting);
+    assert_eq!(order_book.sell_orders.get("AAPL").unwrap().len(), 0);
+    assert_eq!(
+        order_book.buy_orders.get("AAPL").unwrap().front(),
+        Some(&incoming)
+    );
+}
+
+#[test]
+fn test_self_trade_cancel_both() {
+    let (order_book, _, _) = self_trade_order_book(SelfTradePrevention::CancelBoth, 100, 200);
+    assert_eq!(order_book.cancellations.len(), 2);
+    assert_eq!(order_book.buy_orders.get("AAPL").unwrap().len(), 0);
+    assert_eq!(order_book.sell_orders.get("AAPL").unwrap().len(), 0);
+}
+
+#[test]
+fn test_self_trade_decrement_and_cancel() {
+    let (order_book, resting, incoming) =
+        self_trade_order_book(SelfTradePrevention::DecrementAndCancel, 100, 250);
+    assert!(order_book.executions.matches.is_empty());
+    assert_eq!(order_book.cancellations.len(), 2);
+    assert_eq!(order_book.cancellations[0].order, incoming);
+    assert_eq!(order_book.cancellations[0].leaves_quantity(), 150);
+    assert_eq!(order_book.cancellations[1].order, resting);
+    assert_eq!(order_book.cancellations[1].leaves_quantity(), 0);
+    let front = order_book.buy_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(front.quantity, 150);
+    assert_eq!(order_book.sell_orders.get("AAPL").unwrap().len(), 0);
+}
+
+#[test]
+fn test_self_trade_other_participant_matches() {
+    use super::matching::Fifo;
+    let mut order_book = OrderBook::new();
+    let mut resting = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    resting.participant = "FIRM2".to_string();
+    let mut incoming = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    incoming.participant = "FIRM1".to_string();
+    incoming.self_trade_prevention = Some(SelfTradePrevention::CancelBoth);
+    order_book.add_order(resting);
+    order_book.add_order(incoming);
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert_eq!(order_book.executions.matches.len(), 1);
+    assert!(order_book.cancellations.is_empty());
+}
+
+#[test]
+fn test_match_keeps_remainder_when_other_side_empties() {
+    use super::matching::Fifo;
+    let mut order_book = OrderBook::new();
+    let order1 = Order::new("AAPL", 50, Price::from(150), Side::Sell);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order_book.add_order(order1);
+    order_book.add_order(order2.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    let front = order_book.buy_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(front, &order2);
+    assert_eq!(front.quantity, 50);
+}
+
+#[test]
+fn test_order_book_uncross() {
+    use super::matching::Fifo;
+    let mut order_book = OrderBook::new();
+    let order1 = Order::new("AAPL", 100, Price::from(103), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    let order3 = Order::new("AAPL", 150, Price::from(99), Side::Sell);
+    let order4 = Order::new("AAPL", 100, Price::from(102), Side::Sell);
+    for order in [&order1, &order2, &order3, &order4] {
+        order_book.add_order(order.clone());
+    }
+    order_book.uncross("AAPL", Price::from(101), &Fifo);
+    assert_eq!(order_book.executions.matches.len(), 1);
+    assert_eq!(order_book.executions.get_price(1), Some(Price::from(101)));
+    let buy_front = order_book.buy_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(buy_front, &order2);
+    let sell_front = order_book.sell_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(sell_front, &order3);
+    assert_eq!(sell_front.quantity, 50);
+}
+
+#[test]
+fn test_match_orders_in_band() {
+    use super::matching::Fifo;
+    let mut order_book = OrderBook::new();
+    let order1 = Order::new("AAPL", 100, Price::from(100), Side::Sell);
+    let order2 = Order::new("AAPL", 100, Price::from(120), Side::Sell);
+    let order3 = Order::new("AAPL", 200, Price::from(150), Side::Buy);
+    order_book.add_order(order1);
+    order_book.add_order(order2.clone());
+    order_book.add_order(order3.clone());
+    let band = (Price::from(95), Price::from(105));
+    assert_eq!(
+        order_book.match_orders("AAPL", &Fifo, Some(band)),
+        Some(Price::from(120))
+    );
+    assert_eq!(order_book.executions.matches.len(), 1);
+    let buy_front = order_book.buy_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(buy_front, &order3);
+    assert_eq!(buy_front.quantity, 100);
+    let sell_front = order_book.sell_orders.get("AAPL").unwrap().front().unwrap();
+    assert_eq!(sell_front, &order2);
+}
+
+#[test]
+fn test_order_book_all_or_none() {
+    use super::matching::Fifo;
+    let mut order_book = OrderBook::new();
+    let mut all_or_none = Order::new("AAPL", 500, Price::from(101), Side::Buy);
+    all_or_none.all_or_none = true;
+    let bid = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    order_book.add_order(all_or_none.clone());
+    order_book.add_order(bid.clone());
+
+    // Too small for the all-or-none bid, so the sell trades with the bid behind it
+    let sell = Order::new("AAPL", 100, Price::from(100), Side::Sell);
+    order_book.add_order(sell.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert_eq!(
+        order_book.executions.get_fills(),
+        vec![(bid.id, sell.id, Price::from(100), 100)]
+    );
+
+    // Resting through the all-or-none bid until there is enough to fill it
+    let small = Order::new("AAPL", 300, Price::from(101), Side::Sell);
+    order_book.add_order(small.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert_eq!(order_book.executions.get_fills().len(), 1);
+    let large = Order::new("AAPL", 200, Price::from(101), Side::Sell);
+    order_book.add_order(large.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert_eq!(
+        order_book.executions.get_fills()[1..],
+        [
+            (all_or_none.id, small.id, Price::from(101), 300),
+            (all_or_none.id, large.id, Price::from(101), 200)
+        ]
+    );
+    assert!(order_book.buy_orders.get("AAPL").unwrap().is_empty());
+}
+
+#[test]
+fn test_order_book_min_quantity() {
+    use super::matching::Fifo;
+    let mut order_book = OrderBook::new();
+    let asks = [100, 101].map(|price| Order::new("AAPL", 100, Price::from(price), Side::Sell));
+    for ask in &asks {
+        order_book.add_order(ask.clone());
+    }
+
+    // Only 200 is on offer, so the buy rests without trading
+    let mut unfilled = Order::new("AAPL", 300, Price::from(101), Side::Buy);
+    unfilled.min_quantity = 250;
+    order_book.add_order(unfilled.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert!(order_book.executions.get_fills().is_empty());
+
+    // Enough for this one, which takes both levels and rests with the rest
+    let mut buy = Order::new("AAPL", 300, Price::from(101), Side::Buy);
+    buy.min_quantity = 150;
+    order_book.add_order(buy.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert_eq!(
+        order_book.executions.get_fills(),
+        vec![
+            (buy.id, asks[0].id, Price::from(100), 100),
+            (buy.id, asks[1].id, Price::from(101), 100)
+        ]
+    );
+    let bids = order_book.buy_orders.get("AAPL").unwrap();
+    assert_eq!(bids.get(buy.id).unwrap().quantity, 100);
+
+    // A resting order only takes fills of at least its minimum quantity
+    let sell = Order::new("AAPL", 200, Price::from(101), Side::Sell);
+    order_book.add_order(sell.clone());
+    order_book.match_orders("AAPL", &Fifo, None);
+    assert_eq!(
+        order_book.executions.get_fills()[2..],
+        [(buy.id, sell.id, Price::from(101), 100)]
+    );
+    assert_eq!(order_book.sell_orders.get("AAPL").unwrap().len(), 1);
+}
@@ -0,0 +1,257 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use crate::order::Order;
+use crate::price::Price;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Replaced,
+    Rejected,
+    Expired,
+}
+
+impl OrderStatus {
+    // Whether the order can still trade
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::New | OrderStatus::PartiallyFilled | OrderStatus::Replaced
+        )
+    }
+}
+
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OrderStatus::New => "new",
+            OrderStatus::PartiallyFilled => "partially_filled",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Canceled => "canceled",
+            OrderStatus::Replaced => "replaced",
+            OrderStatus::Rejected => "rejected",
+            OrderStatus::Expired => "expired",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "new" => Ok(OrderStatus::New),
+            "partially_filled" => Ok(OrderStatus::PartiallyFilled),
+            "filled" => Ok(OrderStatus::Filled),
+            "canceled" => Ok(OrderStatus::Canceled),
+            "replaced" => Ok(OrderStatus::Replaced),
+            "rejected" => Ok(OrderStatus::Rejected),
+            "expired" => Ok(OrderStatus::Expired),
+            _ => Err(()),
+        }
+    }
+}
+
+// The life of one order, kept after it has left the book. `order` is the
+// latest version of it, with the quantity last entered by the client, so
+// cumulative and leaves quantity add up to its OrderQty while it is open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRecord {
+    pub order: Order,
+    pub original_quantity: u32,
+    pub cum_quantity: u32,
+    pub leaves_quantity: u32,
+    pub status: OrderStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // Sum of price times quantity over every fill, for the average price
+    pub(super) notional: i128,
+}
+
+impl OrderRecord {
+    pub fn new(order: &Order, now: DateTime<Utc>) -> OrderRecord {
+        OrderRecord {
+            order: order.clone(),
+            original_quantity: order.quantity,
+            cum_quantity: 0,
+            leaves_quantity: order.quantity,
+            status: OrderStatus::New,
+            created_at: now,
+            updated_at: now,
+            notional: 0,
+        }
+    }
+
+    pub fn rejected(order: &Order, now: DateTime<Utc>) -> OrderRecord {
+        let mut record = OrderRecord::new(order, now);
+        record.leaves_quantity = 0;
+        record.status = OrderStatus::Rejected;
+        record
+    }
+
+    pub fn fill(&mut self, quantity: u32, price: Price, now: DateTime<Utc>) {
+        self.cum_quantity += quantity;
+        self.leaves_quantity = self.leaves_quantity.saturating_sub(quantity);
+        self.notional += price.raw() as i128 * quantity as i128;
+        self.status = if self.leaves_quantity == 0 {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        self.updated_at = now;
+    }
+
+    // Takes `quantity` off the order, cancelling it once nothing is left. A
+    // partial cancel reduces the order quantity.
+    pub fn cancel(&mut self, quantity: u32, now: DateTime<Utc>) {
+        self.leaves_quantity = self.leaves_quantity.saturating_sub(quantity);
+        if self.leaves_quantity == 0 {
+            self.status = OrderStatus::Canceled;
+        } else {
+            self.order.quantity = self.cum_quantity + self.leaves_quantity;
+        }
+        self.updated_at = now;
+    }
+
+    // Corrects a fill of `quantity` at `price` to `corrected_quantity` at
+    // `corrected_price`, busting it when corrected to nothing. Busted
+    // quantity is not put back on the book, so an open order is left with
+    // less to fill, and a filled one with nothing filled is cancelled.
+    pub fn correct_fill(
+        &mut self,
+        (quantity, price): (u32, Price),
+        (corrected_quantity, corrected_price): (u32, Price),
+        now: DateTime<Utc>,
+    ) {
+        self.cum_quantity = self.cum_quantity.saturating_sub(quantity) + corrected_quantity;
+        self.notional += corrected_price.raw() as i128 * corrected_quantity as i128
+            - price.raw() as i128 * quantity as i128;
+        if self.status.is_open() {
+            self.order.quantity = self.cum_quantity + self.leaves_quantity;
+            self.status = if self.cum_quantity == 0 {
+                OrderStatus::New
+            } else {
+                OrderStatus::PartiallyFilled
+            };
+        } else if self.status == OrderStatus::Filled && self.cum_quantity == 0 {
+            self.status = OrderStatus::Canceled;
+        }
+        self.updated_at = now;
+    }
+
+    // `replaced` rests with what is left open of `order_quantity`, the new
+    // total quantity as the client sent it
+    pub fn replace(&mut self, replaced: &Order, order_quantity: u32, now: DateTime<Utc>) {
+        self.leaves_quantity = order_quantity.saturating_sub(self.cum_quantity);
+        self.order = replaced.clone();
+        self.order.quantity = order_quantity;
+        self.status = OrderStatus::Replaced;
+        self.updated_at = now;
+    }
+
+    // The volume weighted price of the fills, or zero before the first one
+    pub fn average_price(&self) -> Price {
+        if self.cum_quantity == 0 {
+            return Price::ZERO;
+        }
+        Price::from_raw((self.notional / self.cum_quantity as i128) as i64)
+    }
+}
+
+#[test]
+fn test_order_record_fills() {
+    use crate::order::Side;
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let now = Utc::now();
+    let mut record = OrderRecord::new(&order, now);
+    assert_eq!(record.status, OrderStatus::New);
+    assert_eq!(record.average_price(), Price::ZERO);
+    record.fill(40, Price::from(100), now);
+    assert_eq!(record.status, OrderStatus::PartiallyFilled);
+    record.fill(60, Price::from(150), now);
+    assert_eq!(record.status, OrderStatus::Filled);
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (100, 0));
+    assert_eq!(record.average_price(), Price::from(130));
+    assert!(!record.status.is_open());
+}
+
+#[test]
+fn test_order_record_replace_and_cancel() {
+    use crate::order::Side;
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let now = Utc::now();
+    let mut record = OrderRecord::new(&order, now);
+    record.fill(30, Price::from(150), now);
+    let mut replaced = order.clone();
+    replaced.quantity = 50;
+    record.replace(&replaced, 80, now);
+    assert_eq!(record.status, OrderStatus::Replaced);
+    assert_eq!(record.order.quantity, 80);
+    assert_eq!(record.leaves_quantity, 50);
+    assert_eq!(record.original_quantity, 100);
+    record.cancel(20, now);
+    assert_eq!(record.status, OrderStatus::Replaced);
+    assert_eq!(record.order.quantity, 60);
+    record.cancel(30, now);
+    assert_eq!(record.status, OrderStatus::Canceled);
+    assert_eq!((record.leaves_quantity, record.order.quantity), (0, 60));
+
+    let rejected = OrderRecord::rejected(&order, now);
+    assert_eq!(rejected.leaves_quantity, 0);
+    assert_eq!(rejected.status, OrderStatus::Rejected);
+    assert_eq!(
+        rejected.status.to_string().parse::<OrderStatus>(),
+        Ok(OrderStatus::Rejected)
+    );
+}
+
+#[test]
+fn test_order_record_replace_partially_filled() {
+    use crate::order::Side;
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let now = Utc::now();
+    let mut record = OrderRecord::new(&order, now);
+    record.fill(60, Price::from(150), now);
+    let mut replaced = order.clone();
+    replaced.quantity = 40;
+    replaced.price = Price::from(149);
+    record.replace(&replaced, 100, now);
+    assert_eq!(record.order.quantity, 100);
+    assert_eq!(record.order.price, Price::from(149));
+    assert_eq!((record.cum_quantity, record.leaves_quantity), (60, 40));
+    record.fill(40, Price::from(149), now);
+    assert_eq!(record.status, OrderStatus::Filled);
+    assert_eq!(record.order.quantity, record.cum_quantity);
+}
+
+#[test]
+fn test_order_record_busts() {
+    use crate::order::Side;
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let now = Utc::now();
+    let mut record = OrderRecord::new(&order, now);
+    record.fill(40, Price::from(100), now);
+    record.fill(20, Price::from(103), now);
+    record.correct_fill((40, Price::from(100)), (30, Price::from(101)), now);
+    assert_eq!(record.cum_quantity, 50);
+    assert_eq!(record.leaves_quantity, 40);
+    assert_eq!(record.order.quantity, 90);
+    assert_eq!(record.average_price(), "101.8".parse().unwrap());
+    record.correct_fill((30, Price::from(101)), (0, Price::ZERO), now);
+    record.correct_fill((20, Price::from(103)), (0, Price::ZERO), now);
+    assert_eq!(record.status, OrderStatus::New);
+    assert_eq!(record.order.quantity, 40);
+
+    let mut filled = OrderRecord::new(&order, now);
+    filled.fill(100, Price::from(150), now);
+    filled.correct_fill((100, Price::from(150)), (0, Price::ZERO), now);
+    assert_eq!(filled.status, OrderStatus::Canceled);
+    assert_eq!(filled.average_price(), Price::ZERO);
+}
@@ -0,0 +1,311 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::auction::Equilibrium;
+use crate::config::{self, ConfigSection, ANY};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TradingPhase {
+    PreOpen,
+    OpeningAuction,
+    Continuous,
+    ClosingAuction,
+    VolatilityAuction,
+    Halted,
+    Closed,
+}
+
+impl TradingPhase {
+    // New orders and amendments are only taken before the open, while an
+    // auction is being called or during continuous trading. Cancels are
+    // always accepted.
+    pub fn allows_order_entry(&self) -> bool {
+        matches!(
+            self,
+            TradingPhase::PreOpen
+                | TradingPhase::OpeningAuction
+                | TradingPhase::Continuous
+                | TradingPhase::ClosingAuction
+                | TradingPhase::VolatilityAuction
+        )
+    }
+
+    pub fn allows_matching(&self) -> bool {
+        *self == TradingPhase::Continuous
+    }
+
+    pub fn is_auction(&self) -> bool {
+        matches!(
+            self,
+            TradingPhase::OpeningAuction
+                | TradingPhase::ClosingAuction
+                | TradingPhase::VolatilityAuction
+        )
+    }
+
+    // The trading day runs PreOpen, OpeningAuction, Continuous,
+    // ClosingAuction, Closed. Either auction may be skipped, continuous
+    // trading can be interrupted by a volatility auction, and any open phase
+    // can be halted and closed. A halt is only lifted by resuming the phase
+    // it interrupted, which is not a transition of its own.
+    pub fn can_transition_to(&self, next: TradingPhase) -> bool {
+        match (self, next) {
+            (from, to) if *from == to => false,
+            (TradingPhase::Closed, TradingPhase::PreOpen) => true,
+            (TradingPhase::Closed, _) => false,
+            (_, TradingPhase::Halted) | (_, TradingPhase::Closed) => true,
+            (TradingPhase::PreOpen, TradingPhase::OpeningAuction)
+            | (TradingPhase::PreOpen, TradingPhase::Continuous)
+            | (TradingPhase::OpeningAuction, TradingPhase::Continuous)
+            | (TradingPhase::Continuous, TradingPhase::ClosingAuction)
+            | (TradingPhase::Continuous, TradingPhase::VolatilityAuction)
+            | (TradingPhase::VolatilityAuction, TradingPhase::Continuous)
+            | (TradingPhase::VolatilityAuction, TradingPhase::ClosingAuction) => true,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for TradingPhase {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pre_open" => Ok(TradingPhase::PreOpen),
+            "opening_auction" => Ok(TradingPhase::OpeningAuction),
+            "continuous" => Ok(TradingPhase::Continuous),
+            "closing_auction" => Ok(TradingPhase::ClosingAuction),
+            "volatility_auction" => Ok(TradingPhase::VolatilityAuction),
+            "halted" => Ok(TradingPhase::Halted),
+            "closed" => Ok(TradingPhase::Closed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for TradingPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TradingPhase::PreOpen => "pre_open",
+            TradingPhase::OpeningAuction => "opening_auction",
+            TradingPhase::Continuous => "continuous",
+            TradingPhase::ClosingAuction => "closing_auction",
+            TradingPhase::VolatilityAuction => "volatility_auction",
+            TradingPhase::Halted => "halted",
+            TradingPhase::Closed => "closed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseChange {
+    pub symbol: String,
+    pub from: TradingPhase,
+    pub to: TradingPhase,
+    // Set when leaving an auction uncrossed the book
+    pub equilibrium: Option<Equilibrium>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledPhase {
+    pub at: Duration,
+    pub symbol: String,
+    pub phase: TradingPhase,
+}
+
+// Phase transitions by time since the exchange started
+#[derive(Debug, Clone, Default)]
+pub struct TradingSchedule {
+    entries: Vec<ScheduledPhase>,
+    next_entry: usize,
+}
+
+impl TradingSchedule {
+    pub fn new() -> TradingSchedule {
+        TradingSchedule {
+            entries: Vec::new(),
+            next_entry: 0,
+        }
+    }
+
+    pub fn load(file_path: &str) -> Result<TradingSchedule, String> {
+        TradingSchedule::from_sections(config::read_sections(file_path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<TradingSchedule, String> {
+        TradingSchedule::from_sections(config::parse_sections(contents)?)
+    }
+
+    // Builds the schedule from [SYMBOL] sections, or [*] for every instrument,
+    // mapping seconds since start to the phase entered:
+    //
+    //   [*]
+    //   0 = pre_open
+    //   1 = opening_auction
+    //   2.5 = continuous
+    fn from_sections(sections: Vec<ConfigSection>) -> Result<TradingSchedule, String> {
+        let mut schedule = TradingSchedule::new();
+
+        for section in sections {
+            for entry in &section.entries {
+                let seconds = entry
+                    .key
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                    .ok_or_else(|| entry.unknown())?;
+                schedule.add(
+                    Duration::from_secs_f64(seconds),
+                    &section.name,
+                    entry.parse()?,
+                );
+            }
+        }
+        Ok(schedule)
+    }
+
+    // Entries at the same time apply in the order they were added
+    pub fn add(&mut self, at: Duration, symbol: &str, phase: TradingPhase) {
+        let index = self.entries.partition_point(|entry| entry.at <= at);
+        self.entries.insert(
+            index.max(self.next_entry),
+            ScheduledPhase {
+                at,
+                symbol: symbol.to_string(),
+                phase,
+            },
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // The entries of a schedule not yet started that apply to any of
+    // `symbols`, including those for every instrument
+    pub fn for_symbols(&self, symbols: &[String]) -> TradingSchedule {
+        TradingSchedule {
+            entries: self
+                .entries
+                .iter()
+                .filter(|entry| entry.symbol == ANY || symbols.contains(&entry.symbol))
+                .cloned()
+                .collect(),
+            next_entry: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_entry >= self.entries.len()
+    }
+
+    // True when an entry not returned yet has come due by `elapsed`
+    pub fn is_due(&self, elapsed: Duration) -> bool {
+        self.entries
+            .get(self.next_entry)
+            .is_some_and(|entry| entry.at <= elapsed)
+    }
+
+    // Returns the entries that have come due by `elapsed` and not been
+    // returned before
+    pub fn take_due(&mut self, elapsed: Duration) -> Vec<ScheduledPhase> {
+        let due = self.entries[self.next_entry..]
+            .iter()
+            .take_while(|entry| entry.at <= elapsed)
+            .cloned()
+            .collect::<Vec<ScheduledPhase>>();
+        self.next_entry += due.len();
+        due
+    }
+}
+
+#[test]
+fn test_trading_phase_transitions() {
+    use TradingPhase::*;
+    assert!(PreOpen.can_transition_to(OpeningAuction));
+    assert!(OpeningAuction.can_transition_to(Continuous));
+    assert!(Continuous.can_transition_to(ClosingAuction));
+    assert!(ClosingAuction.can_transition_to(Closed));
+    assert!(Closed.can_transition_to(PreOpen));
+    assert!(Continuous.can_transition_to(Halted));
+    assert!(!Halted.can_transition_to(OpeningAuction));
+    assert!(!Halted.can_transition_to(Continuous));
+    assert!(Halted.can_transition_to(Closed));
+    assert!(Continuous.can_transition_to(VolatilityAuction));
+    assert!(VolatilityAuction.can_transition_to(Continuous));
+    assert!(!OpeningAuction.can_transition_to(VolatilityAuction));
+    assert!(!Continuous.can_transition_to(OpeningAuction));
+    assert!(!ClosingAuction.can_transition_to(Continuous));
+    assert!(!Closed.can_transition_to(Continuous));
+    assert!(!Closed.can_transition_to(Halted));
+    assert!(!Halted.can_transition_to(Halted));
+}
+
+#[test]
+fn test_trading_phase_actions() {
+    assert!(TradingPhase::PreOpen.allows_order_entry());
+    assert!(!TradingPhase::PreOpen.allows_matching());
+    assert!(TradingPhase::OpeningAuction.allows_order_entry());
+    assert!(!TradingPhase::OpeningAuction.allows_matching());
+    assert!(TradingPhase::Continuous.allows_matching());
+    assert!(!TradingPhase::Halted.allows_order_entry());
+    assert!(TradingPhase::ClosingAuction.is_auction());
+    assert_eq!(
+        "closing_auction".parse::<TradingPhase>(),
+        Ok(TradingPhase::ClosingAuction)
+    );
+    assert_eq!(TradingPhase::PreOpen.to_string(), "pre_open");
+}
+
+#[test]
+fn test_trading_schedule() {
+    let mut schedule = TradingSchedule::parse(
+        "[*]
+        0 = pre_open
+        1 = opening_auction
+        2.5 = continuous
+        [AAPL]
+        1 = halted",
+    )
+    .unwrap();
+    assert!(!schedule.is_empty());
+    let due = schedule.take_due(Duration::ZERO);
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].phase, TradingPhase::PreOpen);
+    assert!(schedule.take_due(Duration::from_millis(500)).is_empty());
+    let due = schedule.take_due(Duration::from_secs(2));
+    assert_eq!(due.len(), 2);
+    assert_eq!(due[0].symbol, "*");
+    assert_eq!(due[1].symbol, "AAPL");
+    assert!(!schedule.is_finished());
+    schedule.take_due(Duration::from_secs(3));
+    assert!(schedule.is_finished());
+    assert!(TradingSchedule::parse("[*]\n0 = lunch").is_err());
+    assert!(TradingSchedule::parse("[*]\nnoon = closed").is_err());
+}
+
+#[test]
+fn test_trading_schedule_for_symbols() {
+    let schedule = TradingSchedule::parse(
+        "[*]
+        0 = pre_open
+        [AAPL]
+        1 = halted
+        [GOOG]
+        2 = halted",
+    )
+    .unwrap();
+    let mut shard_schedule = schedule.for_symbols(&["GOOG".to_string()]);
+    let due = shard_schedule.take_due(Duration::from_secs(2));
+    assert_eq!(due.len(), 2);
+    assert_eq!(due[1].symbol, "GOOG");
+    assert_eq!(
+        schedule
+            .for_symbols(&[])
+            .take_due(Duration::from_secs(2))
+            .len(),
+        1
+    );
+}
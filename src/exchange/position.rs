@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use crate::order::{Order, Side};
+use crate::price::Price;
+
+// The position of one participant and account in one symbol, built from its
+// fills. Amounts are kept as raw price units times quantity, so averaging
+// and closing out never round.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    pub participant: String,
+    pub account: String,
+    pub symbol: String,
+    // Long when positive, short when negative
+    pub net_quantity: i64,
+    // What the open position cost, signed with it
+    cost: i128,
+    realized: i128,
+}
+
+impl Position {
+    fn new(order: &Order) -> Position {
+        Position {
+            participant: order.participant.to_owned(),
+            account: order.account.to_owned(),
+            symbol: order.symbol.to_owned(),
+            ..Position::default()
+        }
+    }
+
+    // Adds a fill of `quantity` at `price` on `side`. A fill against the
+    // position first closes it out at its average cost, realizing the
+    // difference, and whatever is left opens a position the other way.
+    pub fn fill(&mut self, side: &Side, price: Price, quantity: u32) {
+        let quantity = match side {
+            Side::Buy => quantity as i64,
+            Side::Sell => -(quantity as i64),
+        };
+        let closed = match self.net_quantity.signum() * quantity.signum() {
+            -1 => quantity.signum() * quantity.abs().min(self.net_quantity.abs()),
+            _ => 0,
+        };
+        if closed != 0 {
+            let closed_cost = self.cost * closed.abs() as i128 / self.net_quantity.abs() as i128;
+            self.realized -= price.raw() as i128 * closed as i128 + closed_cost;
+            self.cost -= closed_cost;
+            self.net_quantity += closed;
+        }
+        let opened = quantity - closed;
+        self.cost += price.raw() as i128 * opened as i128;
+        self.net_quantity += opened;
+    }
+
+    // The average price of the open position, None when flat
+    pub fn average_cost(&self) -> Option<Price> {
+        (self.net_quantity != 0).then(|| to_price(self.cost / self.net_quantity as i128))
+    }
+
+    pub fn realized_pnl(&self) -> Price {
+        to_price(self.realized)
+    }
+
+    // The profit or loss of the open position marked at `mark_price`
+    pub fn unrealized_pnl(&self, mark_price: Price) -> Price {
+        to_price(mark_price.raw() as i128 * self.net_quantity as i128 - self.cost)
+    }
+}
+
+fn to_price(amount: i128) -> Price {
+    Price::from_raw(amount.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+}
+
+// The positions a participant asks for, in one account or symbol or all of
+// them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionRequest {
+    pub participant: String,
+    pub account: Option<String>,
+    pub symbol: Option<String>,
+}
+
+// Every position kept by an exchange, by participant, account and symbol
+#[derive(Debug, Clone, Default)]
+pub struct Positions {
+    positions: BTreeMap<(String, String, String), Position>,
+}
+
+impl Positions {
+    pub fn new() -> Positions {
+        Positions::default()
+    }
+
+    // Adds a fill of `order`, which carries its owner and side
+    pub fn fill(&mut self, order: &Order, price: Price, quantity: u32) {
+        self.positions
+            .entry((
+                order.participant.to_owned(),
+                order.account.to_owned(),
+                order.symbol.to_owned(),
+            ))
+            .or_insert_with(|| Position::new(order))
+            .fill(&order.side, price, quantity);
+    }
+
+    // Drops every position in `symbol`, to build them again from its fills
+    pub fn remove_symbol(&mut self, symbol: &str) {
+        self.positions
+            .retain(|(_, _, position_symbol), _| position_symbol != symbol);
+    }
+
+    pub fn get(&self, participant: &str, account: &str, symbol: &str) -> Option<&Position> {
+        self.positions.get(&(
+            participant.to_string(),
+            account.to_string(),
+            symbol.to_string(),
+        ))
+    }
+
+    // The positions of `participant`, in `account` and `symbol` when given,
+    // by account and then symbol
+    pub fn get_positions(
+        &self,
+        participant: &str,
+        account: Option<&str>,
+        symbol: Option<&str>,
+    ) -> Vec<&Position> {
+        self.positions
+            .values()
+            .filter(|position| {
+                position.participant == participant
+                    && account.is_none_or(|account| position.account == account)
+                    && symbol.is_none_or(|symbol| position.symbol == symbol)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_position_pnl() {
+    let mut position = Position::default();
+    position.fill(&Side::Buy, Price::from(100), 10);
+    position.fill(&Side::Buy, Price::from(103), 20);
+    assert_eq!(position.net_quantity, 30);
+    assert_eq!(position.average_cost(), Some(Price::from(102)));
+    assert_eq!(position.unrealized_pnl(Price::from(101)), Price::from(-30));
+
+    // Closing out realizes against the average cost
+    position.fill(&Side::Sell, Price::from(105), 10);
+    assert_eq!(position.realized_pnl(), Price::from(30));
+    assert_eq!(position.average_cost(), Some(Price::from(102)));
+
+    // and selling through flat opens a short at the fill price
+    position.fill(&Side::Sell, Price::from(104), 30);
+    assert_eq!(position.net_quantity, -10);
+    assert_eq!(position.realized_pnl(), Price::from(70));
+    assert_eq!(position.average_cost(), Some(Price::from(104)));
+    assert_eq!(position.unrealized_pnl(Price::from(100)), Price::from(40));
+
+    position.fill(&Side::Buy, Price::from(99), 10);
+    assert_eq!(position.net_quantity, 0);
+    assert_eq!(position.average_cost(), None);
+    assert_eq!(position.realized_pnl(), Price::from(120));
+    assert_eq!(position.unrealized_pnl(Price::from(100)), Price::ZERO);
+}
+
+#[test]
+fn test_positions_by_account() {
+    let mut buy = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    buy.participant = "FIRM1".to_string();
+    buy.account = "ACC1".to_string();
+    let mut positions = Positions::new();
+    positions.fill(&buy, Price::from(150), 40);
+    buy.account = "ACC2".to_string();
+    positions.fill(&buy, Price::from(150), 60);
+    buy.symbol = "MSFT".to_string();
+    positions.fill(&buy, Price::from(300), 10);
+
+    assert_eq!(
+        positions.get("FIRM1", "ACC1", "AAPL").unwrap().net_quantity,
+        40
+    );
+    assert_eq!(positions.get_positions("FIRM1", None, None).len(), 3);
+    assert_eq!(
+        positions.get_positions("FIRM1", Some("ACC2"), None).len(),
+        2
+    );
+    assert_eq!(
+        positions.get_positions("FIRM1", None, Some("AAPL")).len(),
+        2
+    );
+    assert!(positions.get_positions("FIRM2", None, None).is_empty());
+}
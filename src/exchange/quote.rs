@@ -0,0 +1,134 @@
+use super::reject::RejectReason;
+use crate::order::{Order, Side};
+use crate::price::Price;
+
+// The price and size of one side of a quote
+pub type QuoteSide = (Price, u32);
+
+// What a market maker quotes in one symbol, as sent in a MassQuote. Either
+// side may be left out, which pulls that side of the maker's quote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteEntry {
+    pub participant: String,
+    pub account: String,
+    // The maker's own ids for the set and entry, echoed back on the ack
+    pub quote_set_id: String,
+    pub quote_entry_id: String,
+    pub symbol: String,
+    pub bid: Option<QuoteSide>,
+    pub offer: Option<QuoteSide>,
+}
+
+impl QuoteEntry {
+    // The order `side` of the entry rests as, under order id `order_id`
+    pub fn leg(&self, side: Side, order_id: u32) -> Option<Order> {
+        let (price, quantity) = match side {
+            Side::Buy => self.bid?,
+            Side::Sell => self.offer?,
+        };
+        let mut order = Order::new(&self.symbol, quantity, price, side);
+        order.id = order_id;
+        order.cl_ord_id = self.quote_entry_id.to_owned();
+        order.participant = self.participant.to_owned();
+        order.account = self.account.to_owned();
+        Some(order)
+    }
+
+    // A two-sided quote has to leave a spread between its bid and offer
+    pub fn check_spread(&self) -> Result<(), RejectReason> {
+        match (self.bid, self.offer) {
+            (Some((bid, _)), Some((offer, _))) if bid >= offer => {
+                Err(RejectReason::CrossedQuote { bid, offer })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// A market maker's quote in one symbol, kept on the order book apart from
+// its orders. Its bid and offer rest as orders under ids handed out with the
+// maker's first quote in the symbol, and kept for every quote after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub participant: String,
+    pub symbol: String,
+    pub bid_id: u32,
+    pub offer_id: u32,
+}
+
+impl Quote {
+    pub fn leg_id(&self, side: &Side) -> u32 {
+        match side {
+            Side::Buy => self.bid_id,
+            Side::Sell => self.offer_id,
+        }
+    }
+}
+
+// The quotes a QuoteCancel pulls: every quote of `participant`, or its quote
+// in one symbol when given
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteCancel {
+    pub participant: String,
+    pub symbol: Option<String>,
+}
+
+impl QuoteCancel {
+    pub fn matches(&self, quote: &Quote) -> bool {
+        quote.participant == self.participant
+            && self
+                .symbol
+                .as_ref()
+                .is_none_or(|symbol| *symbol == quote.symbol)
+    }
+}
+
+#[test]
+fn test_quote_entry_legs() {
+    let entry = QuoteEntry {
+        participant: "MM1".to_string(),
+        account: "ACC1".to_string(),
+        quote_set_id: "S1".to_string(),
+        quote_entry_id: "Q1".to_string(),
+        symbol: "AAPL".to_string(),
+        bid: Some((Price::from(149), 100)),
+        offer: None,
+    };
+    let bid = entry.leg(Side::Buy, 7).unwrap();
+    assert_eq!(
+        (bid.id, bid.price, bid.quantity),
+        (7, Price::from(149), 100)
+    );
+    assert_eq!(bid.participant, "MM1");
+    assert_eq!(bid.cl_ord_id, "Q1");
+    assert!(entry.leg(Side::Sell, 8).is_none());
+    assert_eq!(entry.check_spread(), Ok(()));
+
+    let mut crossed = entry.clone();
+    crossed.offer = Some((Price::from(149), 100));
+    assert_eq!(
+        crossed.check_spread(),
+        Err(RejectReason::CrossedQuote {
+            bid: Price::from(149),
+            offer: Price::from(149),
+        })
+    );
+
+    let quote = Quote {
+        participant: "MM1".to_string(),
+        symbol: "AAPL".to_string(),
+        bid_id: 7,
+        offer_id: 8,
+    };
+    assert_eq!(quote.leg_id(&Side::Sell), 8);
+    let cancel = QuoteCancel {
+        participant: "MM1".to_string(),
+        symbol: None,
+    };
+    assert!(cancel.matches(&quote));
+    let cancel = QuoteCancel {
+        symbol: Some("MSFT".to_string()),
+        ..cancel
+    };
+    assert!(!cancel.matches(&quote));
+}
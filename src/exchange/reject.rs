@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use super::phase::TradingPhase;
+use super::risk::RiskBreach;
+use crate::price::Price;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectReason {
+    UnknownSymbol(String),
+    UnknownOrder,
+    InvalidQuantity,
+    OddLot {
+        quantity: u32,
+        lot_size: u32,
+    },
+    QuantityOutOfRange {
+        quantity: u32,
+        min_quantity: u32,
+        max_quantity: u32,
+    },
+    OffTick {
+        price: Price,
+        tick_size: Price,
+    },
+    PriceOutOfBand {
+        price: Price,
+        low_limit_price: Option<Price>,
+        high_limit_price: Option<Price>,
+    },
+    RiskBreach(RiskBreach),
+    TradingPhase(TradingPhase),
+    InvalidPhaseTransition {
+        from: TradingPhase,
+        to: TradingPhase,
+    },
+    NoPegReference,
+    // The peg offset takes the price to zero or below, or out of range
+    InvalidPegPrice {
+        reference: Price,
+        offset: Price,
+    },
+    UnknownTrade(String),
+    // A trade can only be corrected down, to at least 1
+    InvalidCorrection {
+        quantity: u32,
+        traded_quantity: u32,
+    },
+    // A replace has to leave some of the order open
+    QuantityNotAboveFilled {
+        quantity: u32,
+        cum_quantity: u32,
+    },
+    // A two-sided quote whose bid is at or above its offer
+    CrossedQuote {
+        bid: Price,
+        offer: Price,
+    },
+}
+
+impl Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectReason::UnknownSymbol(symbol) => write!(f, "Unknown symbol {}", symbol),
+            RejectReason::UnknownOrder => write!(f, "Unknown order"),
+            RejectReason::InvalidQuantity => write!(f, "Invalid order quantity"),
+            RejectReason::OddLot { quantity, lot_size } => write!(
+                f,
+                "Quantity {} is not a multiple of lot size {}",
+                quantity, lot_size
+            ),
+            RejectReason::QuantityOutOfRange {
+                quantity,
+                min_quantity,
+                max_quantity,
+            } => write!(
+                f,
+                "Quantity {} is outside of range {}-{}",
+                quantity, min_quantity, max_quantity
+            ),
+            RejectReason::OffTick { price, tick_size } => write!(
+                f,
+                "Price {} is not a multiple of tick size {}",
+                price, tick_size
+            ),
+            RejectReason::PriceOutOfBand {
+                price,
+                low_limit_price,
+                high_limit_price,
+            } => write!(
+                f,
+                "Price {} is outside of band {}-{}",
+                price,
+                low_limit_price.map_or("".to_string(), |price| price.to_string()),
+                high_limit_price.map_or("".to_string(), |price| price.to_string())
+            ),
+            RejectReason::RiskBreach(breach) => write!(f, "Risk check failed, {}", breach),
+            RejectReason::TradingPhase(phase) => {
+                write!(f, "Orders are not accepted in trading phase {}", phase)
+            }
+            RejectReason::InvalidPhaseTransition { from, to } => {
+                write!(f, "Cannot move from trading phase {} to {}", from, to)
+            }
+            RejectReason::NoPegReference => write!(f, "No best bid or offer to peg to"),
+            RejectReason::InvalidPegPrice { reference, offset } => write!(
+                f,
+                "Peg offset {} from {} does not give a valid price",
+                offset, reference
+            ),
+            RejectReason::UnknownTrade(trade_id) => write!(f, "Unknown trade {}", trade_id),
+            RejectReason::InvalidCorrection {
+                quantity,
+                traded_quantity,
+            } => write!(
+                f,
+                "Cannot correct a trade of {} to {}",
+                traded_quantity, quantity
+            ),
+            RejectReason::QuantityNotAboveFilled {
+                quantity,
+                cum_quantity,
+            } => write!(
+                f,
+                "Quantity {} is not above the {} already filled",
+                quantity, cum_quantity
+            ),
+            RejectReason::CrossedQuote { bid, offer } => {
+                write!(f, "Bid {} is at or above offer {}", bid, offer)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reject_reason_to_string() {
+    assert_eq!(RejectReason::UnknownOrder.to_string(), "Unknown order");
+    assert_eq!(
+        RejectReason::UnknownSymbol("XYZ".to_string()).to_string(),
+        "Unknown symbol XYZ"
+    );
+    assert_eq!(
+        RejectReason::OffTick {
+            price: "100.03".parse().unwrap(),
+            tick_size: "0.05".parse().unwrap(),
+        }
+        .to_string(),
+        "Price 100.03 is not a multiple of tick size 0.05"
+    );
+    assert_eq!(
+        RejectReason::PriceOutOfBand {
+            price: "1001".parse().unwrap(),
+            low_limit_price: Some("1".parse().unwrap()),
+            high_limit_price: Some("1000".parse().unwrap()),
+        }
+        .to_string(),
+        "Price 1001 is outside of band 1-1000"
+    );
+    assert_eq!(
+        RejectReason::InvalidCorrection {
+            quantity: 0,
+            traded_quantity: 100,
+        }
+        .to_string(),
+        "Cannot correct a trade of 100 to 0"
+    );
+    assert_eq!(
+        RejectReason::CrossedQuote {
+            bid: "150".parse().unwrap(),
+            offer: "149.5".parse().unwrap(),
+        }
+        .to_string(),
+        "Bid 150 is at or above offer 149.5"
+    );
+}
@@ -0,0 +1,501 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::config::{self, ConfigSection, ANY};
+use crate::order::{Order, Side};
+use crate::price::Price;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskLimits {
+    pub max_order_quantity: Option<u32>,
+    pub max_notional: Option<Price>,
+    // Maximum distance from the last trade or reference price in basis points
+    pub max_price_deviation_bps: Option<u32>,
+    pub max_open_orders: Option<usize>,
+    pub max_gross_position: Option<u64>,
+    pub max_net_position: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskBreach {
+    MaxOrderQuantity {
+        quantity: u32,
+        limit: u32,
+    },
+    MaxNotional {
+        notional: Option<Price>,
+        limit: Price,
+    },
+    PriceDeviation {
+        price: Price,
+        reference_price: Price,
+        limit_bps: u32,
+    },
+    MaxOpenOrders {
+        limit: usize,
+    },
+    MaxGrossPosition {
+        position: u64,
+        limit: u64,
+    },
+    MaxNetPosition {
+        position: i64,
+        limit: u64,
+    },
+}
+
+impl Display for RiskBreach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskBreach::MaxOrderQuantity { quantity, limit } => write!(
+                f,
+                "max order quantity, {} exceeds limit {}",
+                quantity, limit
+            ),
+            RiskBreach::MaxNotional { notional, limit } => write!(
+                f,
+                "max notional, {} exceeds limit {}",
+                notional.map_or("overflow".to_string(), |notional| notional.to_string()),
+                limit
+            ),
+            RiskBreach::PriceDeviation {
+                price,
+                reference_price,
+                limit_bps,
+            } => write!(
+                f,
+                "price deviation, {} is more than {}bps away from {}",
+                price, limit_bps, reference_price
+            ),
+            RiskBreach::MaxOpenOrders { limit } => {
+                write!(f, "max open orders, limit of {} reached", limit)
+            }
+            RiskBreach::MaxGrossPosition { position, limit } => write!(
+                f,
+                "max gross position, {} would exceed limit {}",
+                position, limit
+            ),
+            RiskBreach::MaxNetPosition { position, limit } => write!(
+                f,
+                "max net position, {} would exceed limit {}",
+                position, limit
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Exposure {
+    open_orders: HashSet<u32>,
+    open_buy_quantity: u64,
+    open_sell_quantity: u64,
+    net_position: i64,
+}
+
+// Pre-trade limits per participant and symbol, together with the open order
+// and position exposure they are checked against
+#[derive(Debug, Clone, Default)]
+pub struct RiskManager {
+    limits: HashMap<(String, String), RiskLimits>,
+    exposures: HashMap<(String, String), Exposure>,
+}
+
+impl RiskManager {
+    pub fn new() -> RiskManager {
+        RiskManager {
+            limits: HashMap::new(),
+            exposures: HashMap::new(),
+        }
+    }
+
+    pub fn load(file_path: &str) -> Result<RiskManager, String> {
+        RiskManager::from_sections(config::read_sections(file_path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<RiskManager, String> {
+        RiskManager::from_sections(config::parse_sections(contents)?)
+    }
+
+    // Builds the limits from [PARTICIPANT] or [PARTICIPANT:SYMBOL] sections,
+    // where either part may be '*':
+    //
+    //   [*]
+    //   max_order_quantity = 10000
+    //   max_notional = 1000000
+    //   max_price_deviation_bps = 1000
+    //   max_open_orders = 500
+    //   max_gross_position = 100000
+    //   max_net_position = 50000
+    fn from_sections(sections: Vec<ConfigSection>) -> Result<RiskManager, String> {
+        let mut risk_manager = RiskManager::new();
+
+        for section in sections {
+            let (participant, symbol) =
+                section.name.split_once(':').unwrap_or((&section.name, ANY));
+            let mut limits = RiskLimits::default();
+            for entry in &section.entries {
+                match entry.key.as_str() {
+                    "max_order_quantity" => limits.max_order_quantity = Some(entry.parse()?),
+                    "max_notional" => limits.max_notional = Some(entry.parse()?),
+                    "max_price_deviation_bps" => {
+                        limits.max_price_deviation_bps = Some(entry.parse()?)
+                    }
+                    "max_open_orders" => limits.max_open_orders = Some(entry.parse()?),
+                    "max_gross_position" => limits.max_gross_position = Some(entry.parse()?),
+                    "max_net_position" => limits.max_net_position = Some(entry.parse()?),
+                    _ => return Err(entry.unknown()),
+                }
+            }
+            risk_manager.set_limits(participant.trim(), symbol.trim(), limits);
+        }
+        Ok(risk_manager)
+    }
+
+    pub fn set_limits(&mut self, participant: &str, symbol: &str, limits: RiskLimits) {
+        self.limits
+            .insert((participant.to_string(), symbol.to_string()), limits);
+    }
+
+    // Returns the most specific limits configured for the participant and
+    // symbol, falling back to the '*' wildcards
+    pub fn get_limits(&self, participant: &str, symbol: &str) -> Option<&RiskLimits> {
+        [
+            (participant, symbol),
+            (participant, ANY),
+            (ANY, symbol),
+            (ANY, ANY),
+        ]
+        .iter()
+        .find_map(|(participant, symbol)| {
+            self.limits
+                .get(&(participant.to_string(), symbol.to_string()))
+        })
+    }
+
+    // Checks `order` against the participant's limits. When `replacing` is set
+    // the order amends that resting order instead of adding a new one.
+    pub fn check(
+        &self,
+        order: &Order,
+        replacing: Option<&Order>,
+        reference_price: Option<Price>,
+    ) -> Result<(), RiskBreach> {
+        let limits = match self.get_limits(&order.participant, &order.symbol) {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+
+        if let Some(limit) = limits.max_order_quantity {
+            if order.quantity > limit {
+                return Err(RiskBreach::MaxOrderQuantity {
+                    quantity: order.quantity,
+                    limit,
+                });
+            }
+        }
+
+        if let Some(limit) = limits.max_notional {
+            let notional = order.price.checked_mul(order.quantity);
+            if notional.is_none_or(|notional| notional > limit) {
+                return Err(RiskBreach::MaxNotional { notional, limit });
+            }
+        }
+
+        if let (Some(limit_bps), Some(reference_price)) =
+            (limits.max_price_deviation_bps, reference_price)
+        {
+            let deviation = (order.price.raw() as i128 - reference_price.raw() as i128).abs();
+            if deviation * 10_000 > reference_price.raw().abs() as i128 * limit_bps as i128 {
+                return Err(RiskBreach::PriceDeviation {
+                    price: order.price,
+                    reference_price,
+                    limit_bps,
+                });
+            }
+        }
+
+        let exposure = self
+            .exposures
+            .get(&(order.participant.to_owned(), order.symbol.to_owned()))
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(limit) = limits.max_open_orders {
+            if replacing.is_none() && exposure.open_orders.len() >= limit {
+                return Err(RiskBreach::MaxOpenOrders { limit });
+            }
+        }
+
+        let (mut open_buy_quantity, mut open_sell_quantity) =
+            (exposure.open_buy_quantity, exposure.open_sell_quantity);
+        if let Some(replaced) = replacing {
+            match replaced.side {
+                Side::Buy => open_buy_quantity -= replaced.quantity as u64,
+                Side::Sell => open_sell_quantity -= replaced.quantity as u64,
+            }
+        }
+        match order.side {
+            Side::Buy => open_buy_quantity += order.quantity as u64,
+            Side::Sell => open_sell_quantity += order.quantity as u64,
+        }
+
+        // Both limits assume everything still open trades
+        if let Some(limit) = limits.max_gross_position {
+            let position =
+                exposure.net_position.unsigned_abs() + open_buy_quantity + open_sell_quantity;
+            if position > limit {
+                return Err(RiskBreach::MaxGrossPosition { position, limit });
+            }
+        }
+
+        if let Some(limit) = limits.max_net_position {
+            let long = exposure.net_position + open_buy_quantity as i64;
+            let short = exposure.net_position - open_sell_quantity as i64;
+            let position = if long.abs() >= short.abs() {
+                long
+            } else {
+                short
+            };
+            if position.unsigned_abs() > limit {
+                return Err(RiskBreach::MaxNetPosition { position, limit });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn order_added(&mut self, order: &Order) {
+        let exposure = self.exposure_mut(order);
+        exposure.open_orders.insert(order.id);
+        match order.side {
+            Side::Buy => exposure.open_buy_quantity += order.quantity as u64,
+            Side::Sell => exposure.open_sell_quantity += order.quantity as u64,
+        }
+    }
+
+    pub fn order_removed(&mut self, order: &Order) {
+        let exposure = self.exposure_mut(order);
+        exposure.open_orders.remove(&order.id);
+        match order.side {
+            Side::Buy => exposure.open_buy_quantity -= order.quantity as u64,
+            Side::Sell => exposure.open_sell_quantity -= order.quantity as u64,
+        }
+    }
+
+    // `order` is the order as it rested before this fill
+    pub fn order_filled(&mut self, order: &Order, quantity: u32) {
+        let exposure = self.exposure_mut(order);
+        if quantity >= order.quantity {
+            exposure.open_orders.remove(&order.id);
+        }
+        match order.side {
+            Side::Buy => {
+                exposure.open_buy_quantity -= quantity as u64;
+                exposure.net_position += quantity as i64;
+            }
+            Side::Sell => {
+                exposure.open_sell_quantity -= quantity as u64;
+                exposure.net_position -= quantity as i64;
+            }
+        }
+    }
+
+    // A fill made before the exchange was restored, which only counts
+    // towards the net position
+    pub fn fill_restored(&mut self, order: &Order, quantity: u32) {
+        let exposure = self.exposure_mut(order);
+        match order.side {
+            Side::Buy => exposure.net_position += quantity as i64,
+            Side::Sell => exposure.net_position -= quantity as i64,
+        }
+    }
+
+    // A busted fill, which no longer counts towards the net position. The
+    // order's open quantity stays as the fill left it.
+    pub fn fill_busted(&mut self, order: &Order, quantity: u32) {
+        let exposure = self.exposure_mut(order);
+        match order.side {
+            Side::Buy => exposure.net_position -= quantity as i64,
+            Side::Sell => exposure.net_position += quantity as i64,
+        }
+    }
+
+    pub fn get_open_orders(&self, participant: &str, symbol: &str) -> usize {
+        self.exposures
+            .get(&(participant.to_string(), symbol.to_string()))
+            .map_or(0, |exposure| exposure.open_orders.len())
+    }
+
+    pub fn get_net_position(&self, participant: &str, symbol: &str) -> i64 {
+        self.exposures
+            .get(&(participant.to_string(), symbol.to_string()))
+            .map_or(0, |exposure| exposure.net_position)
+    }
+
+    fn exposure_mut(&mut self, order: &Order) -> &mut Exposure {
+        self.exposures
+            .entry((order.participant.to_owned(), order.symbol.to_owned()))
+            .or_default()
+    }
+}
+
+#[cfg(test)]
+fn test_order(quantity: u32, price: i64, side: Side) -> Order {
+    let mut order = Order::new("AAPL", quantity, Price::from(price), side);
+    order.participant = "FIRM1".to_string();
+    order
+}
+
+#[test]
+fn test_risk_manager_parse() {
+    let risk_manager = RiskManager::parse(
+        "[*]
+        max_order_quantity = 10000
+        [FIRM1]
+        max_order_quantity = 500
+        max_notional = 100000.5
+        [FIRM1:AAPL]
+        max_open_orders = 2",
+    )
+    .unwrap();
+    assert_eq!(
+        risk_manager
+            .get_limits("FIRM2", "AAPL")
+            .unwrap()
+            .max_order_quantity,
+        Some(10000)
+    );
+    let limits = risk_manager.get_limits("FIRM1", "MSFT").unwrap();
+    assert_eq!(limits.max_order_quantity, Some(500));
+    assert_eq!(limits.max_notional, Some("100000.5".parse().unwrap()));
+    let limits = risk_manager.get_limits("FIRM1", "AAPL").unwrap();
+    assert_eq!(limits.max_open_orders, Some(2));
+    assert_eq!(limits.max_order_quantity, None);
+    assert!(RiskManager::parse("[*]\nmax_orders = 1").is_err());
+}
+
+#[test]
+fn test_order_limits() {
+    let mut risk_manager = RiskManager::new();
+    risk_manager.set_limits(
+        "FIRM1",
+        ANY,
+        RiskLimits {
+            max_order_quantity: Some(1000),
+            max_notional: Some(Price::from(50000)),
+            max_price_deviation_bps: Some(1000),
+            ..Default::default()
+        },
+    );
+    let reference_price = Some(Price::from(100));
+    assert!(risk_manager
+        .check(&test_order(100, 105, Side::Buy), None, reference_price)
+        .is_ok());
+    assert_eq!(
+        risk_manager.check(&test_order(2000, 10, Side::Buy), None, reference_price),
+        Err(RiskBreach::MaxOrderQuantity {
+            quantity: 2000,
+            limit: 1000
+        })
+    );
+    assert!(matches!(
+        risk_manager.check(&test_order(1000, 100, Side::Buy), None, reference_price),
+        Err(RiskBreach::MaxNotional { .. })
+    ));
+    assert!(matches!(
+        risk_manager.check(&test_order(100, 111, Side::Sell), None, reference_price),
+        Err(RiskBreach::PriceDeviation { .. })
+    ));
+    assert!(risk_manager
+        .check(&test_order(100, 111, Side::Sell), None, None)
+        .is_ok());
+    let mut other = test_order(2000, 10, Side::Buy);
+    other.participant = "FIRM2".to_string();
+    assert!(risk_manager.check(&other, None, None).is_ok());
+}
+
+#[test]
+fn test_open_order_limit() {
+    let mut risk_manager = RiskManager::new();
+    risk_manager.set_limits(
+        ANY,
+        ANY,
+        RiskLimits {
+            max_open_orders: Some(2),
+            ..Default::default()
+        },
+    );
+    let order1 = test_order(100, 100, Side::Buy);
+    let order2 = test_order(100, 100, Side::Buy);
+    risk_manager.order_added(&order1);
+    risk_manager.order_added(&order2);
+    let order3 = test_order(100, 100, Side::Buy);
+    assert_eq!(
+        risk_manager.check(&order3, None, None),
+        Err(RiskBreach::MaxOpenOrders { limit: 2 })
+    );
+    assert!(risk_manager.check(&order3, Some(&order1), None).is_ok());
+    risk_manager.order_filled(&order1, 100);
+    assert!(risk_manager.check(&order3, None, None).is_ok());
+    assert_eq!(risk_manager.get_open_orders("FIRM1", "AAPL"), 1);
+}
+
+#[test]
+fn test_position_limits() {
+    let mut risk_manager = RiskManager::new();
+    risk_manager.set_limits(
+        "FIRM1",
+        "AAPL",
+        RiskLimits {
+            max_gross_position: Some(1000),
+            max_net_position: Some(500),
+            ..Default::default()
+        },
+    );
+    let buy = test_order(400, 100, Side::Buy);
+    risk_manager.order_added(&buy);
+    risk_manager.order_filled(&buy, 400);
+    assert_eq!(risk_manager.get_net_position("FIRM1", "AAPL"), 400);
+    assert_eq!(
+        risk_manager.check(&test_order(200, 100, Side::Buy), None, None),
+        Err(RiskBreach::MaxNetPosition {
+            position: 600,
+            limit: 500
+        })
+    );
+    let sell = test_order(500, 100, Side::Sell);
+    assert!(risk_manager.check(&sell, None, None).is_ok());
+    risk_manager.order_added(&sell);
+    assert_eq!(
+        risk_manager.check(&test_order(200, 100, Side::Sell), None, None),
+        Err(RiskBreach::MaxGrossPosition {
+            position: 1100,
+            limit: 1000
+        })
+    );
+    risk_manager.order_removed(&sell);
+    assert!(risk_manager
+        .check(&test_order(200, 100, Side::Sell), None, None)
+        .is_ok());
+}
+
+#[test]
+fn test_risk_breach_to_string() {
+    assert_eq!(
+        RiskBreach::MaxOrderQuantity {
+            quantity: 2000,
+            limit: 1000
+        }
+        .to_string(),
+        "max order quantity, 2000 exceeds limit 1000"
+    );
+    assert_eq!(
+        RiskBreach::PriceDeviation {
+            price: Price::from(120),
+            reference_price: Price::from(100),
+            limit_bps: 1000
+        }
+        .to_string(),
+        "price deviation, 120 is more than 1000bps away from 100"
+    );
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use super::exchange::ExchangeConfig;
+use super::instrument::ReferenceData;
+use crate::order::OrderIds;
+
+// Which of `shard_count` shards trades `symbol`. FNV-1a rather than the
+// standard hasher, so a symbol lands on the same shard in every build.
+pub fn shard_for(symbol: &str, shard_count: usize) -> usize {
+    let hash = symbol.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    (hash % shard_count.max(1) as u64) as usize
+}
+
+// Instruments split by symbol hash across shards, each with an exchange
+// config of its own instruments and schedule. Shards no instrument hashes to
+// are left out.
+#[derive(Debug, Clone)]
+pub struct ShardPlan {
+    configs: Vec<ExchangeConfig>,
+    shards: HashMap<String, usize>,
+}
+
+impl ShardPlan {
+    pub fn new(config: &ExchangeConfig, shard_count: usize) -> ShardPlan {
+        let mut symbols: Vec<Vec<String>> = vec![Vec::new(); shard_count.max(1)];
+        for instrument in config.reference_data.get_instruments() {
+            symbols[shard_for(&instrument.symbol, shard_count)].push(instrument.symbol.to_owned());
+        }
+        symbols.retain(|symbols| !symbols.is_empty());
+        if symbols.is_empty() {
+            // Still one shard, to reject orders for unknown symbols
+            symbols.push(Vec::new());
+        }
+
+        let mut plan = ShardPlan {
+            configs: Vec::new(),
+            shards: HashMap::new(),
+        };
+        let shard_count = symbols.len() as u32;
+        for (index, symbols) in symbols.into_iter().enumerate() {
+            let mut reference_data = ReferenceData::new();
+            for symbol in &symbols {
+                if let Some(instrument) = config.reference_data.get_instrument(symbol) {
+                    reference_data.add_instrument(instrument.clone());
+                }
+                plan.shards.insert(symbol.to_owned(), index);
+            }
+            plan.configs.push(ExchangeConfig {
+                reference_data,
+                risk_manager: config.risk_manager.clone(),
+                schedule: config.schedule.for_symbols(&symbols),
+                // Every shard numbers its own orders, so none share an id
+                order_ids: OrderIds::new(index as u32 + 1, shard_count),
+                fee_schedule: config.fee_schedule.clone(),
+            });
+        }
+        plan
+    }
+
+    pub fn len(&self) -> usize {
+        self.configs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.configs.is_empty()
+    }
+
+    // The shard trading `symbol`. Symbols that are not defined go to the
+    // first shard, which rejects them.
+    pub fn get_shard(&self, symbol: &str) -> usize {
+        self.shards.get(symbol).copied().unwrap_or(0)
+    }
+
+    pub fn get_configs(&self) -> &[ExchangeConfig] {
+        &self.configs
+    }
+}
+
+#[test]
+fn test_shard_for() {
+    assert_eq!(shard_for("AAPL", 1), 0);
+    assert_eq!(shard_for("AAPL", 4), shard_for("AAPL", 4));
+    assert!(shard_for("GOOGL", 3) < 3);
+    assert_eq!(shard_for("AAPL", 0), 0);
+}
+
+#[test]
+fn test_shard_plan() {
+    use super::instrument::Instrument;
+    use super::phase::TradingSchedule;
+    let mut reference_data = ReferenceData::new();
+    let symbols = ["AAPL", "GOOGL", "MSFT", "AMZN", "FB"];
+    for symbol in symbols {
+        reference_data.add_instrument(Instrument::new(symbol));
+    }
+    let config = ExchangeConfig {
+        reference_data,
+        schedule: TradingSchedule::parse("[*]\n0 = pre_open\n[FB]\n1 = halted").unwrap(),
+        ..Default::default()
+    };
+
+    let plan = ShardPlan::new(&config, 4);
+    assert!(!plan.is_empty() && plan.len() <= 4);
+    let instruments: usize = plan
+        .get_configs()
+        .iter()
+        .map(|config| config.reference_data.get_instruments().len())
+        .sum();
+    assert_eq!(instruments, symbols.len());
+    for symbol in symbols {
+        let shard = &plan.get_configs()[plan.get_shard(symbol)];
+        assert!(shard.reference_data.get_instrument(symbol).is_some());
+        assert!(!shard.schedule.is_empty());
+    }
+    assert_eq!(plan.get_shard("TSLA"), 0);
+
+    let plan = ShardPlan::new(&config, 1);
+    assert_eq!(plan.len(), 1);
+    assert_eq!(
+        plan.get_configs()[0].reference_data.get_instruments().len(),
+        5
+    );
+    assert_eq!(ShardPlan::new(&ExchangeConfig::default(), 4).len(), 1);
+}
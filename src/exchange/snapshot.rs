@@ -0,0 +1,494 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+
+use chrono::{DateTime, Utc};
+
+use super::exchange::ExchangeConfig;
+use super::fields::{order_fields, write_fields, Fields};
+use super::journal::{self, JournalEntry};
+use super::orderrecord::OrderRecord;
+use super::quote::Quote;
+use super::shard::ShardPlan;
+use crate::order::{Order, Side};
+use crate::price::Price;
+
+// A fill, with both orders as they rested before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    pub execution_id: usize,
+    pub buy_order: Order,
+    pub sell_order: Order,
+    pub price: Price,
+    pub quantity: u32,
+    // The side that took liquidity, None for auction executions
+    pub aggressor: Option<Side>,
+}
+
+// The state of an exchange between runs, independent of how it is sharded:
+// the resting orders in priority order, the record of every order, the
+// quotes of the market makers, the executions in the order they happened for
+// each symbol and the last trade prices. Trading phases are left out, since
+// every run follows the schedule from its start.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    // The last journal entry the snapshot includes
+    pub sequence: u64,
+    // The highest order id or time priority handed out, which the id
+    // counters of a restored exchange carry on from
+    pub last_order_id: u32,
+    // The highest execution id handed out, busted or not
+    pub last_execution_id: usize,
+    pub orders: Vec<Order>,
+    pub records: Vec<OrderRecord>,
+    pub quotes: Vec<Quote>,
+    pub executions: Vec<Execution>,
+    pub last_trade_prices: BTreeMap<String, Price>,
+}
+
+impl Snapshot {
+    pub fn load(file_path: &str) -> Result<Snapshot, String> {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+        Snapshot::parse(&contents)
+    }
+
+    // Written next to `file_path` first, so a crash while saving leaves the
+    // last snapshot in place
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        let partial_path = format!("{}.partial", file_path);
+        fs::write(&partial_path, self.to_string())
+            .and_then(|()| fs::rename(&partial_path, file_path))
+            .map_err(|e| format!("Failed to write {}: {}", file_path, e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Snapshot, String> {
+        let mut snapshot = Snapshot::default();
+        for (index, line) in contents.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            snapshot
+                .parse_line(line)
+                .map_err(|_| format!("Line {}: invalid snapshot entry", index + 1))?;
+        }
+        let ids = snapshot
+            .orders
+            .iter()
+            .chain(snapshot.records.iter().map(|record| &record.order))
+            .map(|order| order.id.max(order.sequence))
+            .chain(
+                snapshot
+                    .quotes
+                    .iter()
+                    .map(|quote| quote.bid_id.max(quote.offer_id)),
+            );
+        snapshot.last_order_id = ids.fold(snapshot.last_order_id, u32::max);
+        snapshot.last_execution_id = snapshot
+            .executions
+            .iter()
+            .map(|execution| execution.execution_id)
+            .fold(snapshot.last_execution_id, usize::max);
+        Ok(snapshot)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ()> {
+        let mut parts = line.split('\t');
+        let name = parts.next().ok_or(())?;
+        let fields = Fields::parse(parts)?;
+        match name {
+            "snapshot" => {
+                self.sequence = fields.value("sequence")?;
+                self.last_order_id = fields.value("last_order_id")?;
+                self.last_execution_id = fields.optional("last_execution_id")?.unwrap_or(0);
+            }
+            "order" => self.orders.push(fields.order("")?),
+            "record" => self.records.push(OrderRecord {
+                order: fields.order("")?,
+                original_quantity: fields.value("original_quantity")?,
+                cum_quantity: fields.value("cum_quantity")?,
+                leaves_quantity: fields.value("leaves_quantity")?,
+                status: fields.value("status")?,
+                created_at: fields.value::<DateTime<Utc>>("created_at")?,
+                updated_at: fields.value::<DateTime<Utc>>("updated_at")?,
+                notional: fields.value("notional")?,
+            }),
+            "quote" => self.quotes.push(Quote {
+                participant: fields.get("participant")?,
+                symbol: fields.get("symbol")?,
+                bid_id: fields.value("bid_id")?,
+                offer_id: fields.value("offer_id")?,
+            }),
+            "execution" => self.executions.push(Execution {
+                // Executions used to be numbered in the order they happened
+                execution_id: fields
+                    .optional("execution_id")?
+                    .unwrap_or(self.executions.len() + 1),
+                buy_order: fields.order("buy_")?,
+                sell_order: fields.order("sell_")?,
+                price: fields.value("price")?,
+                quantity: fields.value("quantity")?,
+                aggressor: fields.optional("aggressor")?,
+            }),
+            "last_trade" => {
+                self.last_trade_prices
+                    .insert(fields.get("symbol")?, fields.value("price")?);
+            }
+            _ => return Err(()),
+        }
+        Ok(())
+    }
+
+    // Combines the snapshots of the shards of one exchange
+    pub fn merge(snapshots: impl IntoIterator<Item = Snapshot>) -> Snapshot {
+        let mut merged = Snapshot::default();
+        for snapshot in snapshots {
+            merged.sequence = merged.sequence.max(snapshot.sequence);
+            merged.last_order_id = merged.last_order_id.max(snapshot.last_order_id);
+            merged.last_execution_id = merged.last_execution_id.max(snapshot.last_execution_id);
+            merged.orders.extend(snapshot.orders);
+            merged.records.extend(snapshot.records);
+            merged.quotes.extend(snapshot.quotes);
+            merged.executions.extend(snapshot.executions);
+            merged.last_trade_prices.extend(snapshot.last_trade_prices);
+        }
+        merged.records.sort_by_key(|record| record.order.id);
+        merged
+    }
+
+    // The part of the snapshot each shard of `plan` trades, in the same order
+    pub fn split(&self, plan: &ShardPlan) -> Vec<Snapshot> {
+        let mut shards: Vec<Snapshot> = (0..plan.len())
+            .map(|_| Snapshot {
+                sequence: self.sequence,
+                last_order_id: self.last_order_id,
+                last_execution_id: self.last_execution_id,
+                ..Default::default()
+            })
+            .collect();
+        for order in &self.orders {
+            shards[plan.get_shard(&order.symbol)]
+                .orders
+                .push(order.clone());
+        }
+        for record in &self.records {
+            shards[plan.get_shard(&record.order.symbol)]
+                .records
+                .push(record.clone());
+        }
+        for quote in &self.quotes {
+            shards[plan.get_shard(&quote.symbol)]
+                .quotes
+                .push(quote.clone());
+        }
+        for execution in &self.executions {
+            shards[plan.get_shard(&execution.buy_order.symbol)]
+                .executions
+                .push(execution.clone());
+        }
+        for (symbol, price) in &self.last_trade_prices {
+            shards[plan.get_shard(symbol)]
+                .last_trade_prices
+                .insert(symbol.to_owned(), *price);
+        }
+        shards
+    }
+
+    // Brings the state of the exchange up to date for a new run: the last
+    // snapshot at `snapshot_file`, if there is one, with every entry of the
+    // journal at `journal_file` after it replayed on top
+    pub fn recover(
+        snapshot_file: &str,
+        journal_file: &str,
+        config: &ExchangeConfig,
+    ) -> Result<Snapshot, String> {
+        let snapshot = match fs::metadata(snapshot_file) {
+            Ok(_) => Some(Snapshot::load(snapshot_file)?),
+            Err(_) => None,
+        };
+        let entries: Vec<JournalEntry> = match fs::metadata(journal_file) {
+            Ok(_) => journal::read_entries(journal_file)?,
+            Err(_) => Vec::new(),
+        };
+        let sequence = snapshot.as_ref().map_or(0, |snapshot| snapshot.sequence);
+        let Some(last_entry) = entries.last().filter(|entry| entry.sequence > sequence) else {
+            return Ok(snapshot.unwrap_or_default());
+        };
+        let exchanges = journal::replay(snapshot.as_ref(), &entries, config)?;
+        let mut recovered = Snapshot::merge(exchanges.iter().map(|exchange| exchange.snapshot()));
+        recovered.sequence = last_entry.sequence;
+        Ok(recovered)
+    }
+}
+
+// One entry per line, as tab separated fields:
+//
+//   <entry> <key>=<value> ...
+//
+// starting with a `snapshot` line and followed by `order`, `record`,
+// `quote`, `execution` and `last_trade` lines, the fields written as in the journal
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = |f: &mut std::fmt::Formatter<'_>, name: &str, fields: &[(String, String)]| {
+            write!(f, "{}", name)?;
+            write_fields(f, fields)?;
+            writeln!(f)
+        };
+        line(
+            f,
+            "snapshot",
+            &[
+                ("sequence".to_string(), self.sequence.to_string()),
+                ("last_order_id".to_string(), self.last_order_id.to_string()),
+                (
+                    "last_execution_id".to_string(),
+                    self.last_execution_id.to_string(),
+                ),
+            ],
+        )?;
+        for order in &self.orders {
+            let mut fields = Vec::new();
+            order_fields("", order, &mut fields);
+            line(f, "order", &fields)?;
+        }
+        for record in &self.records {
+            let mut fields = Vec::new();
+            order_fields("", &record.order, &mut fields);
+            for (key, value) in [
+                ("original_quantity", record.original_quantity.to_string()),
+                ("cum_quantity", record.cum_quantity.to_string()),
+                ("leaves_quantity", record.leaves_quantity.to_string()),
+                ("status", record.status.to_string()),
+                ("created_at", record.created_at.to_rfc3339()),
+                ("updated_at", record.updated_at.to_rfc3339()),
+                ("notional", record.notional.to_string()),
+            ] {
+                fields.push((key.to_string(), value));
+            }
+            line(f, "record", &fields)?;
+        }
+        for quote in &self.quotes {
+            line(
+                f,
+                "quote",
+                &[
+                    ("participant".to_string(), quote.participant.to_owned()),
+                    ("symbol".to_string(), quote.symbol.to_owned()),
+                    ("bid_id".to_string(), quote.bid_id.to_string()),
+                    ("offer_id".to_string(), quote.offer_id.to_string()),
+                ],
+            )?;
+        }
+        for execution in &self.executions {
+            let mut fields = vec![
+                (
+                    "execution_id".to_string(),
+                    execution.execution_id.to_string(),
+                ),
+                ("price".to_string(), execution.price.to_string()),
+                ("quantity".to_string(), execution.quantity.to_string()),
+            ];
+            if let Some(aggressor) = &execution.aggressor {
+                fields.push(("aggressor".to_string(), aggressor.to_string()));
+            }
+            order_fields("buy_", &execution.buy_order, &mut fields);
+            order_fields("sell_", &execution.sell_order, &mut fields);
+            line(f, "execution", &fields)?;
+        }
+        for (symbol, price) in &self.last_trade_prices {
+            line(
+                f,
+                "last_trade",
+                &[
+                    ("symbol".to_string(), symbol.to_owned()),
+                    ("price".to_string(), price.to_string()),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn test_config() -> ExchangeConfig {
+    use super::instrument::{Instrument, ReferenceData};
+    let mut reference_data = ReferenceData::new();
+    for symbol in ["AAPL", "GOOGL", "MSFT"] {
+        reference_data.add_instrument(Instrument::new(symbol));
+    }
+    ExchangeConfig {
+        reference_data,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_snapshot_restore() {
+    use super::exchange::Exchange;
+    use crate::order::Side;
+    let mut exchange = Exchange::with_config(test_config());
+    let orders = [
+        ("AAPL", 100, 150, Side::Sell, "FIRM1"),
+        ("AAPL", 40, 151, Side::Buy, "FIRM2"),
+        ("AAPL", 50, 149, Side::Buy, "FIRM2"),
+        ("GOOGL", 10, 90, Side::Buy, "FIRM1"),
+        ("MSFT", 0, 300, Side::Buy, "FIRM1"),
+    ];
+    for (index, (symbol, quantity, price, side, participant)) in orders.into_iter().enumerate() {
+        let mut order = Order::new(symbol, quantity, Price::from(price), side);
+        exchange.assign_order_id(&mut order);
+        order.cl_ord_id = format!("ORD\t{}", index);
+        order.participant = participant.to_string();
+        let _ = exchange.execute_order(order);
+    }
+
+    let snapshot = exchange.snapshot();
+    assert_eq!(snapshot.orders.len(), 3);
+    assert_eq!(snapshot.records.len(), 5);
+    assert_eq!(snapshot.executions.len(), 1);
+    assert_eq!(snapshot.last_order_id, 5);
+    assert_eq!(Snapshot::parse(&snapshot.to_string()), Ok(snapshot.clone()));
+
+    let mut restored = Exchange::with_config(test_config());
+    restored.restore(&snapshot);
+    assert_eq!(restored.snapshot(), snapshot);
+    for symbol in ["AAPL", "GOOGL", "MSFT"] {
+        assert_eq!(
+            restored.get_order_depth(symbol),
+            exchange.get_order_depth(symbol)
+        );
+    }
+    assert_eq!(restored.get_fills(), exchange.get_fills());
+    assert_eq!(restored.get_order_id("FIRM2", "ORD\t2"), Some(3));
+    assert_eq!(restored.get_reference_price("AAPL"), Some(Price::from(150)));
+    assert_eq!(
+        restored
+            .get_risk_manager()
+            .get_net_position("FIRM2", "AAPL"),
+        40
+    );
+    assert_eq!(
+        restored.get_risk_manager().get_open_orders("FIRM2", "AAPL"),
+        1
+    );
+
+    // New orders are numbered after the restored ones and trade against them
+    let mut order = Order::new("AAPL", 60, Price::from(149), Side::Sell);
+    restored.assign_order_id(&mut order);
+    assert_eq!(order.id, 6);
+    restored.execute_order(order).unwrap();
+    assert_eq!(restored.get_fills()[1], (3, 6, Price::from(149), 50));
+    assert_eq!(restored.get_order_record(3).unwrap().cum_quantity, 50);
+}
+
+#[test]
+fn test_snapshot_written_by_hand() {
+    use super::exchange::Exchange;
+    let snapshot = Snapshot::parse(
+        "order\tid=7\tsymbol=AAPL\tquantity=100\tprice=150\tside=2\tsequence=7\n\
+         order\tid=3\tsymbol=AAPL\tquantity=200\tprice=149\tside=1\tsequence=3\n",
+    )
+    .unwrap();
+    assert_eq!(snapshot.last_order_id, 7);
+    let mut exchange = Exchange::with_config(test_config());
+    exchange.restore(&snapshot);
+    let top = exchange.get_top_of_book("AAPL");
+    let (bid, ask) = (top.bid.unwrap(), top.ask.unwrap());
+    assert_eq!((bid.price, bid.quantity), (Price::from(149), 200));
+    assert_eq!((ask.price, ask.quantity), (Price::from(150), 100));
+    assert!(exchange.get_order_record(7).unwrap().status.is_open());
+    assert!(Snapshot::parse("order\tid=1").is_err());
+
+    let snapshot =
+        Snapshot::parse("quote\tparticipant=MM1\tsymbol=AAPL\tbid_id=9\toffer_id=10\n").unwrap();
+    assert_eq!(snapshot.last_order_id, 10);
+    assert_eq!(Snapshot::parse(&snapshot.to_string()).unwrap(), snapshot);
+}
+
+#[test]
+fn test_snapshot_recover() {
+    use super::exchange::Exchange;
+    use super::journal::{Command, Journal};
+    use crate::order::Side;
+    let directory = std::env::temp_dir();
+    let journal_path = directory.join(format!("snapshot_recover_{}.log", std::process::id()));
+    let snapshot_path = directory.join(format!("snapshot_recover_{}.dat", std::process::id()));
+    let (journal_file, snapshot_file) = (
+        journal_path.to_str().unwrap(),
+        snapshot_path.to_str().unwrap(),
+    );
+    let _ = fs::remove_file(journal_file);
+    let _ = fs::remove_file(snapshot_file);
+    let config = test_config();
+    assert_eq!(
+        Snapshot::recover(snapshot_file, journal_file, &config),
+        Ok(Snapshot::default())
+    );
+
+    // Each run starts from where the last one left off, with as many shards
+    // as it is given
+    let journal = Journal::open(journal_file).unwrap();
+    let mut state = Snapshot::default();
+    let runs = [
+        (
+            1,
+            vec![("AAPL", 100, 150, Side::Sell), ("GOOGL", 50, 90, Side::Buy)],
+        ),
+        (
+            3,
+            vec![("AAPL", 30, 150, Side::Buy), ("MSFT", 10, 300, Side::Sell)],
+        ),
+        (
+            2,
+            vec![("GOOGL", 50, 90, Side::Sell), ("AAPL", 80, 151, Side::Buy)],
+        ),
+    ];
+    for (run, (shard_count, orders)) in runs.into_iter().enumerate() {
+        if run == 2 {
+            state = Snapshot::recover(snapshot_file, journal_file, &config).unwrap();
+            state.save(snapshot_file).unwrap();
+        }
+        journal.append(Command::Start { shard_count }).unwrap();
+        let plan = ShardPlan::new(&config, shard_count);
+        let mut exchanges = journal::restore(&state, &config, shard_count);
+        for (index, exchange) in exchanges.iter_mut().enumerate() {
+            exchange.set_journal(journal.for_shard(index));
+        }
+        for (symbol, quantity, price, side) in orders {
+            let exchange = &mut exchanges[plan.get_shard(symbol)];
+            let mut order = Order::new(symbol, quantity, Price::from(price), side);
+            exchange.assign_order_id(&mut order);
+            exchange.execute_order(order).unwrap();
+        }
+        state = Snapshot::merge(exchanges.iter().map(Exchange::snapshot));
+    }
+
+    let recovered = Snapshot::recover(snapshot_file, journal_file, &config).unwrap();
+    fs::remove_file(journal_file).unwrap();
+    fs::remove_file(snapshot_file).unwrap();
+    assert_eq!(recovered.sequence, 9);
+    // Replayed records are timestamped when they are replayed
+    let fills = |snapshot: &Snapshot| -> Vec<(u32, u32, u32, u32)> {
+        snapshot
+            .records
+            .iter()
+            .map(|record| {
+                let order = &record.order;
+                (
+                    order.id,
+                    order.quantity,
+                    record.cum_quantity,
+                    record.leaves_quantity,
+                )
+            })
+            .collect()
+    };
+    assert_eq!(fills(&recovered), fills(&state));
+    assert_eq!(recovered.last_order_id, state.last_order_id);
+    assert_eq!(recovered.executions.len(), 3);
+    let mut orders = recovered.orders.clone();
+    orders.sort();
+    let mut expected = state.orders.clone();
+    expected.sort();
+    assert_eq!(orders, expected);
+}
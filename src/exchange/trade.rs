@@ -0,0 +1,149 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+use super::fee::Liquidity;
+use super::orderrecord::OrderRecord;
+use crate::order::{Order, Side};
+use crate::price::Price;
+
+// Who traded on one side of a trade, with which order, and what the fill
+// cost them
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeSide {
+    pub side: Side,
+    pub participant: String,
+    pub account: String,
+    pub order_id: u32,
+    pub cl_ord_id: String,
+    // What is left of the order after the fill
+    pub leaves_quantity: u32,
+    pub liquidity: Liquidity,
+    // Negative for a rebate
+    pub fee: Price,
+}
+
+impl TradeSide {
+    // The side of `order`, as it rested before filling `quantity`
+    pub fn new(order: &Order, quantity: u32, liquidity: Liquidity, fee: Price) -> TradeSide {
+        TradeSide {
+            side: order.side.clone(),
+            participant: order.participant.to_owned(),
+            account: order.account.to_owned(),
+            order_id: order.id,
+            cl_ord_id: order.cl_ord_id.to_owned(),
+            leaves_quantity: order.quantity.saturating_sub(quantity),
+            liquidity,
+            fee,
+        }
+    }
+}
+
+// A trade as handed over for clearing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    // Unique across the shards, since a symbol only trades on one of them
+    pub trade_id: String,
+    pub symbol: String,
+    pub currency: String,
+    pub price: Price,
+    pub quantity: u32,
+    pub trade_date: NaiveDate,
+    pub settlement_date: NaiveDate,
+    pub buyer: TradeSide,
+    pub seller: TradeSide,
+}
+
+impl Trade {
+    // Each side on its own, as it is reported to clearing
+    pub fn reports(&self) -> [TradeReport; 2] {
+        [&self.buyer, &self.seller].map(|side| TradeReport {
+            trade_id: self.trade_id.to_owned(),
+            symbol: self.symbol.to_owned(),
+            currency: self.currency.to_owned(),
+            price: self.price,
+            quantity: self.quantity,
+            trade_date: self.trade_date,
+            settlement_date: self.settlement_date,
+            side: side.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmendmentType {
+    Bust,
+    Correction,
+}
+
+// A trade busted or corrected after the fact, as reported to both sides
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeAmendment {
+    // Numbered like the trades, so it is never reused
+    pub amendment_id: String,
+    pub amendment_type: AmendmentType,
+    // The trade as it was for a bust, and as corrected for a correction
+    pub trade: Trade,
+    // The records of both orders once the trade was amended
+    pub buy_record: OrderRecord,
+    pub sell_record: OrderRecord,
+}
+
+// The symbol and execution id of trade `trade_id`, which is
+// `<symbol>-<execution id>`
+pub fn parse_trade_id(trade_id: &str) -> Option<(&str, usize)> {
+    let (symbol, execution_id) = trade_id.rsplit_once('-')?;
+    Some((symbol, execution_id.parse().ok()?))
+}
+
+// One side of a trade, which clearing pairs with the other by trade id
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeReport {
+    pub trade_id: String,
+    pub symbol: String,
+    pub currency: String,
+    pub price: Price,
+    pub quantity: u32,
+    pub trade_date: NaiveDate,
+    pub settlement_date: NaiveDate,
+    pub side: TradeSide,
+}
+
+// The date `settlement_days` business days after `trade_date`, skipping
+// weekends but not holidays
+pub fn settlement_date(trade_date: NaiveDate, settlement_days: u32) -> NaiveDate {
+    let mut date = trade_date;
+    let mut days = settlement_days;
+    while days > 0 {
+        date = date + Days::new(1);
+        if !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            days -= 1;
+        }
+    }
+    date
+}
+
+#[test]
+fn test_settlement_date() {
+    let thursday = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+    assert_eq!(settlement_date(thursday, 0), thursday);
+    assert_eq!(
+        settlement_date(thursday, 1),
+        NaiveDate::from_ymd_opt(2024, 3, 8).unwrap()
+    );
+    assert_eq!(
+        settlement_date(thursday, 2),
+        NaiveDate::from_ymd_opt(2024, 3, 11).unwrap()
+    );
+    let saturday = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+    assert_eq!(
+        settlement_date(saturday, 1),
+        NaiveDate::from_ymd_opt(2024, 3, 11).unwrap()
+    );
+}
+
+#[test]
+fn test_parse_trade_id() {
+    assert_eq!(parse_trade_id("AAPL-7"), Some(("AAPL", 7)));
+    assert_eq!(parse_trade_id("BRK-B-12"), Some(("BRK-B", 12)));
+    assert_eq!(parse_trade_id("AAPL"), None);
+    assert_eq!(parse_trade_id("AAPL-x"), None);
+}
@@ -0,0 +1,181 @@
+use super::fixmessage::FixMessage;
+use super::fixtag::FixTag;
+use super::fixvalue::{ExecType, MsgType, OrdStatus};
+use crate::exchange::fee::Liquidity;
+use crate::exchange::orderrecord::OrderRecord;
+use crate::exchange::trade::{AmendmentType, Trade, TradeAmendment, TradeSide};
+
+// CommType (13) of a commission charged as an absolute amount
+const ABSOLUTE: &str = "3";
+
+// LastLiquidityInd (851) of how a side traded
+pub(super) fn liquidity_indicator(liquidity: Liquidity) -> &'static str {
+    match liquidity {
+        Liquidity::Added => "1",
+        Liquidity::Removed => "2",
+        Liquidity::Auction => "4",
+    }
+}
+
+pub(super) fn parse_liquidity_indicator(value: &str) -> Option<Liquidity> {
+    match value {
+        "1" => Some(Liquidity::Added),
+        "2" => Some(Liquidity::Removed),
+        "4" => Some(Liquidity::Auction),
+        _ => None,
+    }
+}
+
+impl FixMessage {
+    // ExecutionReport (35=8, ExecType=F) of one side's fill in `trade`, with
+    // the trade id as ExecID. The side's fee goes in Commission (12), negative
+    // for a rebate.
+    pub fn fill_report(trade: &Trade, side: &TradeSide) -> FixMessage {
+        let mut report = FixMessage::new();
+        report.add_field(FixTag::MsgType, &MsgType::ExecutionReport.to_string());
+        report.add_field(FixTag::OrderID, &side.order_id.to_string());
+        if !side.cl_ord_id.is_empty() {
+            report.add_field(FixTag::ClOrdID, &side.cl_ord_id);
+        }
+        if !side.account.is_empty() {
+            report.add_field(FixTag::Account, &side.account);
+        }
+        report.add_field(FixTag::ExecID, &trade.trade_id);
+        report.add_field(FixTag::ExecType, &ExecType::Trade.to_string());
+        let ord_status = match side.leaves_quantity {
+            0 => OrdStatus::Filled,
+            _ => OrdStatus::PartiallyFilled,
+        };
+        report.add_field(FixTag::OrdStatus, &ord_status.to_string());
+        report.add_field(FixTag::Symbol, &trade.symbol);
+        report.add_field(FixTag::Side, &side.side.to_string());
+        report.add_field(FixTag::LastQty, &trade.quantity.to_string());
+        report.add_field(FixTag::LastPx, &trade.price.to_string());
+        report.add_field(FixTag::LeavesQty, &side.leaves_quantity.to_string());
+        report.add_field(FixTag::Commission, &side.fee.to_string());
+        report.add_field(FixTag::CommType, ABSOLUTE);
+        report.add_field(
+            FixTag::LastLiquidityInd,
+            liquidity_indicator(side.liquidity),
+        );
+        report
+    }
+
+    // ExecutionReport (35=8) of a busted (ExecType=H) or corrected
+    // (ExecType=G) trade to one side, referring to the fill by its ExecID in
+    // ExecRefID (19). A correction carries the corrected price, quantity and
+    // fee, a bust the ones taken back. The order's status is as the
+    // amendment left it.
+    pub fn trade_amendment_report(
+        amendment: &TradeAmendment,
+        side: &TradeSide,
+        record: &OrderRecord,
+    ) -> FixMessage {
+        let exec_type = match amendment.amendment_type {
+            AmendmentType::Bust => ExecType::TradeCancel,
+            AmendmentType::Correction => ExecType::TradeCorrect,
+        };
+        let trade = &amendment.trade;
+        let mut report =
+            FixMessage::execution_report(&record.order, exec_type, OrdStatus::from(record.status));
+        report.modify_field(FixTag::LeavesQty, &record.leaves_quantity.to_string());
+        report.add_field(FixTag::CumQty, &record.cum_quantity.to_string());
+        report.add_field(FixTag::AvgPx, &record.average_price().to_string());
+        report.add_field(FixTag::ExecID, &amendment.amendment_id);
+        report.add_field(FixTag::ExecRefID, &trade.trade_id);
+        report.add_field(FixTag::LastQty, &trade.quantity.to_string());
+        report.add_field(FixTag::LastPx, &trade.price.to_string());
+        report.add_field(FixTag::Commission, &side.fee.to_string());
+        report.add_field(FixTag::CommType, ABSOLUTE);
+        report.add_field(
+            FixTag::LastLiquidityInd,
+            liquidity_indicator(side.liquidity),
+        );
+        report
+    }
+}
+
+#[test]
+fn test_fill_report() {
+    use crate::order::Side;
+    use chrono::NaiveDate;
+    let side = |side, order_id, leaves_quantity, liquidity, fee: &str| TradeSide {
+        side,
+        participant: "FIRM1".to_string(),
+        account: String::new(),
+        order_id,
+        cl_ord_id: String::new(),
+        leaves_quantity,
+        liquidity,
+        fee: fee.parse().unwrap(),
+    };
+    let trade = Trade {
+        trade_id: "AAPL-7".to_string(),
+        symbol: "AAPL".to_string(),
+        currency: "USD".to_string(),
+        price: "150.25".parse().unwrap(),
+        quantity: 100,
+        trade_date: NaiveDate::from_ymd_opt(2024, 3, 7).unwrap(),
+        settlement_date: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+        buyer: side(Side::Buy, 3, 0, Liquidity::Removed, "0.3"),
+        seller: side(Side::Sell, 1, 50, Liquidity::Added, "-0.2"),
+    };
+
+    let buy = FixMessage::fill_report(&trade, &trade.buyer).encode();
+    assert!(buy.contains("|12=0.3|13=3|"));
+    assert!(buy.contains("|17=AAPL-7|"));
+    assert!(buy.contains("|39=2|"));
+    assert!(buy.contains("|851=2|"));
+    let sell = FixMessage::fill_report(&trade, &trade.seller);
+    assert_eq!(sell.fields.get(&FixTag::ExecType).unwrap(), "F");
+    assert_eq!(sell.fields.get(&FixTag::OrdStatus).unwrap(), "1");
+    assert_eq!(sell.fields.get(&FixTag::LeavesQty).unwrap(), "50");
+    assert_eq!(sell.fields.get(&FixTag::Commission).unwrap(), "-0.2");
+    assert_eq!(sell.fields.get(&FixTag::LastLiquidityInd).unwrap(), "1");
+    assert_eq!(parse_liquidity_indicator("4"), Some(Liquidity::Auction));
+    assert_eq!(parse_liquidity_indicator("9"), None);
+}
+
+#[test]
+fn test_trade_amendment_report() {
+    use crate::order::{Order, Side};
+    use chrono::{NaiveDate, Utc};
+    let mut order = Order::new("AAPL", 100, "150".parse().unwrap(), Side::Buy);
+    order.participant = "FIRM1".to_string();
+    let mut record = OrderRecord::new(&order, Utc::now());
+    record.fill(100, "150".parse().unwrap(), Utc::now());
+    record.correct_fill(
+        (100, "150".parse().unwrap()),
+        (0, "0".parse().unwrap()),
+        Utc::now(),
+    );
+    let side = TradeSide::new(&order, 100, Liquidity::Removed, "0.3".parse().unwrap());
+    let amendment = TradeAmendment {
+        amendment_id: "AAPL-9".to_string(),
+        amendment_type: AmendmentType::Bust,
+        trade: Trade {
+            trade_id: "AAPL-7".to_string(),
+            symbol: "AAPL".to_string(),
+            currency: "USD".to_string(),
+            price: "150".parse().unwrap(),
+            quantity: 100,
+            trade_date: NaiveDate::from_ymd_opt(2024, 3, 7).unwrap(),
+            settlement_date: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+            buyer: side.clone(),
+            seller: side.clone(),
+        },
+        buy_record: record.clone(),
+        sell_record: record.clone(),
+    };
+
+    let report = FixMessage::trade_amendment_report(&amendment, &side, &record).encode();
+    assert!(report.contains("|17=AAPL-9|"));
+    assert!(report.contains("|19=AAPL-7|"));
+    assert!(report.contains("|150=H|39=4|"));
+    assert!(report.contains("|14=0|"));
+    assert!(report.contains("|31=150|32=100|"));
+    let mut corrected = amendment.clone();
+    corrected.amendment_type = AmendmentType::Correction;
+    let report = FixMessage::trade_amendment_report(&corrected, &side, &record);
+    assert_eq!(report.fields.get(&FixTag::ExecType).unwrap(), "G");
+}
@@ -1,12 +1,41 @@
 use super::fixtag::FixTag;
-use crate::order::{Order, Side};
+use super::fixvalue::{ExecType, MsgType, OrdStatus};
+use crate::order::{Order, Peg, Side};
+use crate::price::Price;
 use chrono::Utc;
 use std::collections::HashMap;
 
+// Repeating group entries keep their fields in insertion order, since FIX
+// requires the first field of each entry to be the group delimiter
+pub type FixGroupEntry = Vec<(FixTag, String)>;
+
+// The fields of each inbound repeating group, by the tag that counts its
+// entries, starting with the field that begins an entry. A group nested in
+// an entry is kept in it as it came, count and all.
+fn group_fields(count_tag: &FixTag) -> Option<&'static [FixTag]> {
+    match count_tag {
+        FixTag::NoQuoteSets => Some(&[
+            FixTag::QuoteSetID,
+            FixTag::TotNoQuoteEntries,
+            FixTag::NoQuoteEntries,
+            FixTag::QuoteEntryID,
+            FixTag::Symbol,
+            FixTag::BidPx,
+            FixTag::OfferPx,
+            FixTag::BidSize,
+            FixTag::OfferSize,
+        ]),
+        // Only found at the top level in a QuoteCancel
+        FixTag::NoQuoteEntries => Some(&[FixTag::Symbol]),
+        _ => None,
+    }
+}
+
 // Struct representing a FIX message
 #[derive(Debug, Clone, PartialEq)]
 pub struct FixMessage {
     pub fields: HashMap<FixTag, String>,
+    pub groups: HashMap<FixTag, Vec<FixGroupEntry>>,
 }
 
 impl FixMessage {
@@ -14,6 +43,7 @@ impl FixMessage {
     pub fn new() -> FixMessage {
         FixMessage {
             fields: HashMap::new(),
+            groups: HashMap::new(),
         }
     }
 
@@ -44,20 +74,44 @@ impl FixMessage {
         self.fields.insert(tag, value.to_string());
     }
 
+    // Appends an entry to the repeating group counted by `count_tag`
+    pub fn add_group_entry(&mut self, count_tag: FixTag, entry: FixGroupEntry) {
+        self.groups.entry(count_tag).or_default().push(entry);
+    }
+
     pub fn encode(&mut self) -> String {
         self.add_field(FixTag::SendingTime, &Self::get_time());
-        let mut sorted_fields: Vec<(&FixTag, &String)> = self.fields.iter().collect();
+        let mut sorted_fields: Vec<(&FixTag, String)> = self
+            .fields
+            .iter()
+            .map(|(tag, value)| (tag, format!("{}={}|", tag, value)))
+            .collect();
+        sorted_fields.extend(self.groups.iter().map(|(tag, entries)| {
+            let encoded_entries: String = entries
+                .iter()
+                .flatten()
+                .map(|(tag, value)| format!("{}={}|", tag, value))
+                .collect();
+            (
+                tag,
+                format!("{}={}|{}", tag, entries.len(), encoded_entries),
+            )
+        }));
         sorted_fields.sort_by_key(|(tag, _)| *tag);
 
         let encoded_message: String = sorted_fields
             .iter()
-            .map(|(tag, value)| format!("{}={}|", tag, value))
+            .map(|(_, encoded)| encoded.as_str())
             .collect();
         format!("{}{}", encoded_message, "\x01")
     }
 
+    // Fields of the repeating groups the exchange reads go into `groups`,
+    // by their count tag, until a field outside the group comes up
     pub fn decode(message: &str, delimiters: &str) -> FixMessage {
         let mut fields: HashMap<FixTag, String> = HashMap::new();
+        let mut groups: HashMap<FixTag, Vec<FixGroupEntry>> = HashMap::new();
+        let mut group: Option<(FixTag, &[FixTag])> = None;
 
         let tags_values: Vec<&str> = message
             .trim_end_matches(delimiters)
@@ -67,22 +121,93 @@ impl FixMessage {
         for tag_value in tags_values {
             let tag_value_split: Vec<&str> = tag_value.split('=').collect();
             if tag_value_split.len() == 2 {
-                fields.insert(
-                    match tag_value_split[0].parse::<FixTag>().ok() {
-                        Some(tag) => tag,
-                        None => {
-                            log_debug!(
-                                "Tag {} is not a valid FIX tag, skipping",
-                                tag_value_split[0],
-                            );
-                            continue;
+                let tag = match tag_value_split[0].parse::<FixTag>().ok() {
+                    Some(tag) => tag,
+                    None => {
+                        log_debug!(
+                            "Tag {} is not a valid FIX tag, skipping",
+                            tag_value_split[0],
+                        );
+                        continue;
+                    }
+                };
+                let value = tag_value_split[1].to_string();
+                if let Some((count_tag, members)) = &group {
+                    if members.contains(&tag) {
+                        let entries = groups.entry(count_tag.clone()).or_default();
+                        if tag == members[0] || entries.is_empty() {
+                            entries.push(Vec::new());
                         }
-                    },
-                    tag_value_split[1].to_string(),
-                );
+                        if let Some(entry) = entries.last_mut() {
+                            entry.push((tag, value));
+                        }
+                        continue;
+                    }
+                }
+                group = None;
+                if let Some(members) = group_fields(&tag) {
+                    group = Some((tag, members));
+                    continue;
+                }
+                fields.insert(tag, value);
             }
         }
-        FixMessage { fields }
+        FixMessage { fields, groups }
+    }
+
+    pub fn msg_type(&self) -> Option<MsgType> {
+        self.fields.get(&FixTag::MsgType)?.parse::<MsgType>().ok()
+    }
+
+    pub fn execution_report(
+        order: &Order,
+        exec_type: ExecType,
+        ord_status: OrdStatus,
+    ) -> FixMessage {
+        let mut report = FixMessage::new();
+        report.add_field(FixTag::MsgType, &MsgType::ExecutionReport.to_string());
+        report.add_field(FixTag::OrderID, &order.id.to_string());
+        if !order.cl_ord_id.is_empty() {
+            report.add_field(FixTag::ClOrdID, &order.cl_ord_id);
+        }
+        if !order.account.is_empty() {
+            report.add_field(FixTag::Account, &order.account);
+        }
+        report.add_field(FixTag::ExecType, &exec_type.to_string());
+        report.add_field(FixTag::OrdStatus, &ord_status.to_string());
+        report.add_field(FixTag::Symbol, &order.symbol);
+        report.add_field(FixTag::Side, &(order.side.clone() as u8).to_string());
+        report.add_field(FixTag::OrderQty, &order.quantity.to_string());
+        report.add_field(FixTag::Price, &order.price.to_string());
+        let leaves_qty = match ord_status {
+            OrdStatus::Rejected | OrdStatus::Canceled => 0,
+            _ => order.quantity,
+        };
+        report.add_field(FixTag::LeavesQty, &leaves_qty.to_string());
+        report
+    }
+
+    // Builds an OrderCancelReject for a cancel/replace request that could not
+    // be applied, echoing the identifiers the client sent
+    pub fn order_cancel_reject(request: &FixMessage, text: &str) -> FixMessage {
+        let mut reject = FixMessage::new();
+        reject.add_field(FixTag::MsgType, &MsgType::OrderCancelReject.to_string());
+        reject.add_field(
+            FixTag::OrderID,
+            request
+                .fields
+                .get(&FixTag::OrderID)
+                .map_or("NONE", String::as_str),
+        );
+        for tag in [FixTag::ClOrdID, FixTag::OrigClOrdID] {
+            if let Some(value) = request.fields.get(&tag) {
+                reject.add_field(tag, value);
+            }
+        }
+        reject.add_field(FixTag::OrdStatus, &OrdStatus::Rejected.to_string());
+        reject.add_field(FixTag::CxlRejResponseTo, "2");
+        reject.add_field(FixTag::Text, text);
+        reject
     }
 
     pub fn get_time() -> String {
@@ -90,17 +215,72 @@ impl FixMessage {
         now.format("%Y%m%d-%H:%M:%S%.3f").to_string()
     }
 
+    // A limit order, or a pegged order for OrdType=P, whose Price is optional
+    // and caps the peg. DisplayQty=0 hides the order; showing only part of it
+    // is not supported. ExecInst=G makes the order all-or-none.
     pub fn to_order(&self) -> Option<Order> {
         let symbol = self.fields.get(&FixTag::Symbol)?;
         let quantity = self.fields.get(&FixTag::OrderQty)?.parse::<u32>().ok()?;
-        let price = self.fields.get(&FixTag::Price)?.parse::<f64>().ok()?;
-        let side = match self.fields.get(&FixTag::Side)?.parse::<isize>().ok()? {
-            1 => Side::Buy,
-            2 => Side::Sell,
-            _ => return None,
+        let price = match self.fields.get(&FixTag::Price) {
+            Some(price) => Some(price.parse::<Price>().ok()?),
+            None => None,
         };
+        let side = self.fields.get(&FixTag::Side)?.parse::<Side>().ok()?;
 
-        Some(Order::new(symbol, quantity, price, side))
+        let peg = match self.fields.get(&FixTag::OrdType).map(String::as_str) {
+            Some("P") => Some(Peg {
+                peg_type: self.fields.get(&FixTag::PegPriceType)?.parse().ok()?,
+                offset: match self.fields.get(&FixTag::PegOffsetValue) {
+                    Some(offset) => offset.parse().ok()?,
+                    None => Price::ZERO,
+                },
+                limit: price,
+            }),
+            _ => None,
+        };
+        let mut order = match peg {
+            // Priced by the exchange
+            Some(_) => Order::new(symbol, quantity, Price::ZERO, side),
+            None => Order::new(symbol, quantity, price?, side),
+        };
+        order.peg = peg;
+        if let Some(display_quantity) = self.fields.get(&FixTag::DisplayQty) {
+            match display_quantity.parse::<u32>().ok()? {
+                0 => order.hidden = true,
+                display_quantity if display_quantity >= quantity => {}
+                _ => return None,
+            }
+        }
+        if let Some(min_quantity) = self.fields.get(&FixTag::MinQty) {
+            order.min_quantity = min_quantity.parse().ok().filter(|min| *min <= quantity)?;
+        }
+        if let Some(instructions) = self.fields.get(&FixTag::ExecInst) {
+            order.all_or_none = instructions
+                .split(' ')
+                .any(|instruction| instruction == "G");
+        }
+        if let Some(cl_ord_id) = self.fields.get(&FixTag::ClOrdID) {
+            order.cl_ord_id = cl_ord_id.to_owned();
+        }
+        if let Some(participant) = self.fields.get(&FixTag::SenderCompID) {
+            order.participant = participant.to_owned();
+        }
+        if let Some(account) = self.fields.get(&FixTag::Account) {
+            order.account = account.to_owned();
+        }
+        if let Some(stp_group) = self.fields.get(&FixTag::SelfMatchPreventionID) {
+            order.stp_group = stp_group.to_owned();
+        }
+        if let Some(instruction) = self.fields.get(&FixTag::SelfMatchPreventionInstruction) {
+            order.self_trade_prevention = Some(instruction.parse().ok()?);
+        }
+        Some(order)
+    }
+}
+
+impl Default for FixMessage {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -138,6 +318,7 @@ fn test_encode_fix_message() {
 }
 
 #[test]
+#[allow(clippy::useless_format)]
 fn test_decode_fix_message() {
     let fix_message = FixMessage::decode(&format!("8=FIX.4.2|35=A|49=SENDER|56=TARGET|\x01"), "|");
     assert_eq!(fix_message.fields.len(), 4);
@@ -175,7 +356,7 @@ fn test_to_order() {
     let order = fix_message.to_order().unwrap();
     assert_eq!(order.symbol, "AAPL");
     assert_eq!(order.quantity, 100);
-    assert_eq!(order.price, 100.00);
+    assert_eq!(order.price, Price::from(100));
     assert_eq!(order.side, Side::Buy);
 }
 
@@ -186,3 +367,202 @@ fn test_modify_field() {
     fix_message.modify_field(FixTag::Symbol, "GOOG");
     assert_eq!(fix_message.fields.get(&FixTag::Symbol).unwrap(), "GOOG");
 }
+
+#[test]
+fn test_to_order_with_cl_ord_id() {
+    let mut fix_message = FixMessage::new();
+    fix_message.add_field(FixTag::Symbol, "AAPL");
+    fix_message.add_field(FixTag::OrderQty, "100");
+    fix_message.add_field(FixTag::Price, "100.00");
+    fix_message.add_field(FixTag::Side, "2");
+    fix_message.add_field(FixTag::ClOrdID, "ORD1");
+    fix_message.add_field(FixTag::SenderCompID, "FIRM1");
+    let order = fix_message.to_order().unwrap();
+    assert_eq!(order.cl_ord_id, "ORD1");
+    assert_eq!(order.participant, "FIRM1");
+    assert_eq!(order.side, Side::Sell);
+    assert_eq!(order.self_trade_prevention, None);
+}
+
+#[test]
+fn test_to_order_with_self_trade_prevention() {
+    use crate::order::SelfTradePrevention;
+    let mut fix_message = FixMessage::new();
+    fix_message.add_field(FixTag::Symbol, "AAPL");
+    fix_message.add_field(FixTag::OrderQty, "100");
+    fix_message.add_field(FixTag::Price, "100.00");
+    fix_message.add_field(FixTag::Side, "1");
+    fix_message.add_field(FixTag::SelfMatchPreventionID, "DESK1");
+    fix_message.add_field(FixTag::SelfMatchPreventionInstruction, "2");
+    let order = fix_message.to_order().unwrap();
+    assert_eq!(order.stp_group, "DESK1");
+    assert_eq!(
+        order.self_trade_prevention,
+        Some(SelfTradePrevention::CancelOldest)
+    );
+    fix_message.modify_field(FixTag::SelfMatchPreventionInstruction, "9");
+    assert!(fix_message.to_order().is_none());
+}
+
+#[test]
+fn test_msg_type() {
+    let mut fix_message = FixMessage::new();
+    assert_eq!(fix_message.msg_type(), None);
+    fix_message.add_field(FixTag::MsgType, "G");
+    assert_eq!(
+        fix_message.msg_type(),
+        Some(MsgType::OrderCancelReplaceRequest)
+    );
+}
+
+#[test]
+fn test_execution_report() {
+    let mut order = Order::new("AAPL", 100, "150.5".parse().unwrap(), Side::Sell);
+    order.cl_ord_id = "ORD2".to_string();
+    let report = FixMessage::execution_report(&order, ExecType::Replaced, OrdStatus::Replaced);
+    assert_eq!(report.fields.get(&FixTag::MsgType).unwrap(), "8");
+    assert_eq!(
+        report.fields.get(&FixTag::OrderID).unwrap(),
+        &order.id.to_string()
+    );
+    assert_eq!(report.fields.get(&FixTag::ClOrdID).unwrap(), "ORD2");
+    assert_eq!(report.fields.get(&FixTag::ExecType).unwrap(), "5");
+    assert_eq!(report.fields.get(&FixTag::OrdStatus).unwrap(), "5");
+    assert_eq!(report.fields.get(&FixTag::Side).unwrap(), "2");
+    assert_eq!(report.fields.get(&FixTag::OrderQty).unwrap(), "100");
+    assert_eq!(report.fields.get(&FixTag::Price).unwrap(), "150.5");
+}
+
+#[test]
+fn test_rejected_execution_report() {
+    let order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let report = FixMessage::execution_report(&order, ExecType::Rejected, OrdStatus::Rejected);
+    assert_eq!(report.fields.get(&FixTag::ExecType).unwrap(), "8");
+    assert_eq!(report.fields.get(&FixTag::OrdStatus).unwrap(), "8");
+    assert_eq!(report.fields.get(&FixTag::LeavesQty).unwrap(), "0");
+}
+
+#[test]
+fn test_order_cancel_reject() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::MsgType, "G");
+    request.add_field(FixTag::ClOrdID, "ORD2");
+    request.add_field(FixTag::OrigClOrdID, "ORD1");
+    let reject = FixMessage::order_cancel_reject(&request, "Unknown order");
+    assert_eq!(reject.msg_type(), Some(MsgType::OrderCancelReject));
+    assert_eq!(reject.fields.get(&FixTag::OrderID).unwrap(), "NONE");
+    assert_eq!(reject.fields.get(&FixTag::ClOrdID).unwrap(), "ORD2");
+    assert_eq!(reject.fields.get(&FixTag::OrigClOrdID).unwrap(), "ORD1");
+    assert_eq!(reject.fields.get(&FixTag::CxlRejResponseTo).unwrap(), "2");
+    assert_eq!(reject.fields.get(&FixTag::Text).unwrap(), "Unknown order");
+}
+
+#[test]
+fn test_encode_repeating_group() {
+    let mut fix_message = FixMessage::new();
+    fix_message.add_field(FixTag::MsgType, "y");
+    fix_message.add_group_entry(
+        FixTag::NoRelatedSym,
+        vec![
+            (FixTag::Symbol, "AAPL".to_string()),
+            (FixTag::Currency, "USD".to_string()),
+        ],
+    );
+    fix_message.add_group_entry(
+        FixTag::NoRelatedSym,
+        vec![(FixTag::Symbol, "MSFT".to_string())],
+    );
+    assert_eq!(
+        fix_message.encode(),
+        format!(
+            "35=y|52={}|146=2|55=AAPL|15=USD|55=MSFT|\x01",
+            fix_message.fields.get(&FixTag::SendingTime).unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_decode_repeating_group() {
+    let fix_message = FixMessage::decode(
+        "35=i|49=MM1|117=Q1|296=2|302=S1|295=2|299=E1|55=AAPL|132=149|299=E2|55=MSFT|133=301|302=S2|295=1|299=E3|55=GOOGL|10=000|\x01",
+        "|",
+    );
+    assert_eq!(fix_message.fields.get(&FixTag::QuoteID).unwrap(), "Q1");
+    assert_eq!(fix_message.fields.get(&FixTag::CheckSum).unwrap(), "000");
+    assert!(!fix_message.fields.contains_key(&FixTag::Symbol));
+    let quote_sets = fix_message.groups.get(&FixTag::NoQuoteSets).unwrap();
+    assert_eq!(quote_sets.len(), 2);
+    assert_eq!(quote_sets[0].len(), 8);
+    assert_eq!(quote_sets[0][0], (FixTag::QuoteSetID, "S1".to_string()));
+    assert_eq!(quote_sets[0][7], (FixTag::OfferPx, "301".to_string()));
+    assert_eq!(quote_sets[1][3], (FixTag::Symbol, "GOOGL".to_string()));
+
+    let fix_message = FixMessage::decode("35=Z|298=1|295=2|55=AAPL|55=MSFT|\x01", "|");
+    let entries = fix_message.groups.get(&FixTag::NoQuoteEntries).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1], vec![(FixTag::Symbol, "MSFT".to_string())]);
+}
+
+#[test]
+fn test_to_pegged_order() {
+    use crate::order::PegType;
+    let mut fix_message = FixMessage::new();
+    fix_message.add_field(FixTag::Symbol, "AAPL");
+    fix_message.add_field(FixTag::OrderQty, "100");
+    fix_message.add_field(FixTag::Side, "1");
+    fix_message.add_field(FixTag::OrdType, "P");
+    fix_message.add_field(FixTag::PegPriceType, "5");
+    fix_message.add_field(FixTag::PegOffsetValue, "-0.01");
+    let order = fix_message.to_order().unwrap();
+    assert_eq!(
+        order.peg,
+        Some(Peg {
+            peg_type: PegType::Primary,
+            offset: "-0.01".parse().unwrap(),
+            limit: None,
+        })
+    );
+    fix_message.add_field(FixTag::Price, "150");
+    assert_eq!(
+        fix_message.to_order().unwrap().peg.unwrap().limit,
+        Some(Price::from(150))
+    );
+    fix_message.modify_field(FixTag::PegPriceType, "9");
+    assert!(fix_message.to_order().is_none());
+    fix_message.modify_field(FixTag::OrdType, "2");
+    assert_eq!(fix_message.to_order().unwrap().peg, None);
+}
+
+#[test]
+fn test_to_hidden_order() {
+    let mut fix_message = FixMessage::new();
+    fix_message.add_field(FixTag::Symbol, "AAPL");
+    fix_message.add_field(FixTag::OrderQty, "100");
+    fix_message.add_field(FixTag::Side, "2");
+    fix_message.add_field(FixTag::Price, "150");
+    assert!(!fix_message.to_order().unwrap().hidden);
+    fix_message.add_field(FixTag::DisplayQty, "0");
+    assert!(fix_message.to_order().unwrap().hidden);
+    fix_message.modify_field(FixTag::DisplayQty, "100");
+    assert!(!fix_message.to_order().unwrap().hidden);
+    fix_message.modify_field(FixTag::DisplayQty, "10");
+    assert!(fix_message.to_order().is_none());
+}
+
+#[test]
+fn test_to_order_conditions() {
+    let mut fix_message = FixMessage::new();
+    fix_message.add_field(FixTag::Symbol, "AAPL");
+    fix_message.add_field(FixTag::OrderQty, "100");
+    fix_message.add_field(FixTag::Side, "1");
+    fix_message.add_field(FixTag::Price, "150");
+    fix_message.add_field(FixTag::MinQty, "40");
+    fix_message.add_field(FixTag::ExecInst, "M G");
+    let order = fix_message.to_order().unwrap();
+    assert_eq!(order.min_quantity, 40);
+    assert!(order.all_or_none);
+    fix_message.modify_field(FixTag::ExecInst, "M");
+    assert!(!fix_message.to_order().unwrap().all_or_none);
+    fix_message.modify_field(FixTag::MinQty, "101");
+    assert!(fix_message.to_order().is_none());
+}
@@ -20,6 +20,106 @@ pub enum FixTag {
     CumQty,
     AvgPx,
     Text,
+    ClOrdID,
+    OrigClOrdID,
+    ExecType,
+    OrdStatus,
+    CxlRejResponseTo,
+    SecurityID,
+    Currency,
+    SecurityReqID,
+    SecurityResponseID,
+    SecurityResponseType,
+    SecurityListRequestType,
+    SecurityRequestResult,
+    RoundLot,
+    MinTradeVol,
+    MaxTradeVol,
+    MinPriceIncrement,
+    LowLimitPrice,
+    HighLimitPrice,
+    TotNoRelatedSym,
+    NoRelatedSym,
+    NoTickRules,
+    StartTickPriceRange,
+    EndTickPriceRange,
+    TickIncrement,
+    SelfMatchPreventionID,
+    SelfMatchPreventionInstruction,
+    SecurityTradingStatus,
+    TradingSessionID,
+    TradSesStatus,
+    TradingSessionSubID,
+    MDReqID,
+    SubscriptionRequestType,
+    MarketDepth,
+    NoMDEntries,
+    MDEntryType,
+    MDEntryPx,
+    MDEntrySize,
+    MDReqRejReason,
+    MDEntryPositionNo,
+    NumberOfOrders,
+    MDBookType,
+    RptSeq,
+    MDUpdateAction,
+    TransactTime,
+    OrdStatusReqID,
+    MassCancelRequestType,
+    MassCancelResponse,
+    MassCancelRejectReason,
+    TotalAffectedOrders,
+    PegOffsetValue,
+    PegPriceType,
+    DisplayQty,
+    ExecInst,
+    MinQty,
+    Account,
+    PosReqID,
+    PosReqType,
+    PosMaintRptID,
+    TotalNumPosReports,
+    PosReqResult,
+    PosReqStatus,
+    ClearingBusinessDate,
+    SettlPrice,
+    SettlPriceType,
+    PriorSettlPrice,
+    NoPositions,
+    PosType,
+    LongQty,
+    ShortQty,
+    NoPosAmt,
+    PosAmtType,
+    PosAmt,
+    LastPx,
+    LastQty,
+    TradeDate,
+    SettlDate,
+    TradeReportID,
+    TrdMatchID,
+    NoSides,
+    NoPartyIDs,
+    PartyID,
+    PartyRole,
+    Commission,
+    CommType,
+    LastLiquidityInd,
+    ExecRefID,
+    QuoteID,
+    BidPx,
+    OfferPx,
+    BidSize,
+    OfferSize,
+    NoQuoteEntries,
+    NoQuoteSets,
+    QuoteStatus,
+    QuoteCancelType,
+    QuoteEntryID,
+    QuoteRejectReason,
+    QuoteSetID,
+    TotNoQuoteEntries,
+    QuoteEntryRejectReason,
     CheckSum,
 }
 
@@ -45,6 +145,106 @@ impl FixTag {
             FixTag::CumQty => 14,
             FixTag::AvgPx => 6,
             FixTag::Text => 58,
+            FixTag::ClOrdID => 11,
+            FixTag::OrigClOrdID => 41,
+            FixTag::ExecType => 150,
+            FixTag::OrdStatus => 39,
+            FixTag::CxlRejResponseTo => 434,
+            FixTag::SecurityID => 48,
+            FixTag::Currency => 15,
+            FixTag::SecurityReqID => 320,
+            FixTag::SecurityResponseID => 322,
+            FixTag::SecurityResponseType => 323,
+            FixTag::SecurityListRequestType => 559,
+            FixTag::SecurityRequestResult => 560,
+            FixTag::RoundLot => 561,
+            FixTag::MinTradeVol => 562,
+            FixTag::MaxTradeVol => 1140,
+            FixTag::MinPriceIncrement => 969,
+            FixTag::LowLimitPrice => 1148,
+            FixTag::HighLimitPrice => 1149,
+            FixTag::TotNoRelatedSym => 393,
+            FixTag::NoRelatedSym => 146,
+            FixTag::NoTickRules => 1205,
+            FixTag::StartTickPriceRange => 1206,
+            FixTag::EndTickPriceRange => 1207,
+            FixTag::TickIncrement => 1208,
+            FixTag::SelfMatchPreventionID => 2362,
+            FixTag::SelfMatchPreventionInstruction => 2964,
+            FixTag::SecurityTradingStatus => 326,
+            FixTag::TradingSessionID => 336,
+            FixTag::TradSesStatus => 340,
+            FixTag::TradingSessionSubID => 625,
+            FixTag::MDReqID => 262,
+            FixTag::SubscriptionRequestType => 263,
+            FixTag::MarketDepth => 264,
+            FixTag::NoMDEntries => 268,
+            FixTag::MDEntryType => 269,
+            FixTag::MDEntryPx => 270,
+            FixTag::MDEntrySize => 271,
+            FixTag::MDReqRejReason => 281,
+            FixTag::MDEntryPositionNo => 290,
+            FixTag::NumberOfOrders => 346,
+            FixTag::MDBookType => 1021,
+            FixTag::RptSeq => 83,
+            FixTag::MDUpdateAction => 279,
+            FixTag::TransactTime => 60,
+            FixTag::OrdStatusReqID => 790,
+            FixTag::MassCancelRequestType => 530,
+            FixTag::MassCancelResponse => 531,
+            FixTag::MassCancelRejectReason => 532,
+            FixTag::TotalAffectedOrders => 533,
+            FixTag::PegOffsetValue => 211,
+            FixTag::PegPriceType => 1094,
+            FixTag::DisplayQty => 1138,
+            FixTag::ExecInst => 18,
+            FixTag::MinQty => 110,
+            FixTag::Account => 1,
+            FixTag::PosReqID => 710,
+            FixTag::PosReqType => 724,
+            FixTag::PosMaintRptID => 721,
+            FixTag::TotalNumPosReports => 727,
+            FixTag::PosReqResult => 728,
+            FixTag::PosReqStatus => 729,
+            FixTag::ClearingBusinessDate => 715,
+            FixTag::SettlPrice => 730,
+            FixTag::SettlPriceType => 731,
+            FixTag::PriorSettlPrice => 734,
+            FixTag::NoPositions => 702,
+            FixTag::PosType => 703,
+            FixTag::LongQty => 704,
+            FixTag::ShortQty => 705,
+            FixTag::NoPosAmt => 753,
+            FixTag::PosAmtType => 707,
+            FixTag::PosAmt => 708,
+            FixTag::LastPx => 31,
+            FixTag::LastQty => 32,
+            FixTag::TradeDate => 75,
+            FixTag::SettlDate => 64,
+            FixTag::TradeReportID => 571,
+            FixTag::TrdMatchID => 880,
+            FixTag::NoSides => 552,
+            FixTag::NoPartyIDs => 453,
+            FixTag::PartyID => 448,
+            FixTag::PartyRole => 452,
+            FixTag::Commission => 12,
+            FixTag::CommType => 13,
+            FixTag::LastLiquidityInd => 851,
+            FixTag::ExecRefID => 19,
+            FixTag::QuoteID => 117,
+            FixTag::BidPx => 132,
+            FixTag::OfferPx => 133,
+            FixTag::BidSize => 134,
+            FixTag::OfferSize => 135,
+            FixTag::NoQuoteEntries => 295,
+            FixTag::NoQuoteSets => 296,
+            FixTag::QuoteStatus => 297,
+            FixTag::QuoteCancelType => 298,
+            FixTag::QuoteEntryID => 299,
+            FixTag::QuoteRejectReason => 300,
+            FixTag::QuoteSetID => 302,
+            FixTag::TotNoQuoteEntries => 304,
+            FixTag::QuoteEntryRejectReason => 368,
         }
     }
 }
@@ -72,6 +272,106 @@ impl FromStr for FixTag {
             "14" => Ok(FixTag::CumQty),
             "6" => Ok(FixTag::AvgPx),
             "58" => Ok(FixTag::Text),
+            "11" => Ok(FixTag::ClOrdID),
+            "41" => Ok(FixTag::OrigClOrdID),
+            "150" => Ok(FixTag::ExecType),
+            "39" => Ok(FixTag::OrdStatus),
+            "434" => Ok(FixTag::CxlRejResponseTo),
+            "48" => Ok(FixTag::SecurityID),
+            "15" => Ok(FixTag::Currency),
+            "320" => Ok(FixTag::SecurityReqID),
+            "322" => Ok(FixTag::SecurityResponseID),
+            "323" => Ok(FixTag::SecurityResponseType),
+            "559" => Ok(FixTag::SecurityListRequestType),
+            "560" => Ok(FixTag::SecurityRequestResult),
+            "561" => Ok(FixTag::RoundLot),
+            "562" => Ok(FixTag::MinTradeVol),
+            "1140" => Ok(FixTag::MaxTradeVol),
+            "969" => Ok(FixTag::MinPriceIncrement),
+            "1148" => Ok(FixTag::LowLimitPrice),
+            "1149" => Ok(FixTag::HighLimitPrice),
+            "393" => Ok(FixTag::TotNoRelatedSym),
+            "146" => Ok(FixTag::NoRelatedSym),
+            "1205" => Ok(FixTag::NoTickRules),
+            "1206" => Ok(FixTag::StartTickPriceRange),
+            "1207" => Ok(FixTag::EndTickPriceRange),
+            "1208" => Ok(FixTag::TickIncrement),
+            "2362" => Ok(FixTag::SelfMatchPreventionID),
+            "2964" => Ok(FixTag::SelfMatchPreventionInstruction),
+            "326" => Ok(FixTag::SecurityTradingStatus),
+            "336" => Ok(FixTag::TradingSessionID),
+            "340" => Ok(FixTag::TradSesStatus),
+            "625" => Ok(FixTag::TradingSessionSubID),
+            "262" => Ok(FixTag::MDReqID),
+            "263" => Ok(FixTag::SubscriptionRequestType),
+            "264" => Ok(FixTag::MarketDepth),
+            "268" => Ok(FixTag::NoMDEntries),
+            "269" => Ok(FixTag::MDEntryType),
+            "270" => Ok(FixTag::MDEntryPx),
+            "271" => Ok(FixTag::MDEntrySize),
+            "281" => Ok(FixTag::MDReqRejReason),
+            "290" => Ok(FixTag::MDEntryPositionNo),
+            "346" => Ok(FixTag::NumberOfOrders),
+            "1021" => Ok(FixTag::MDBookType),
+            "83" => Ok(FixTag::RptSeq),
+            "279" => Ok(FixTag::MDUpdateAction),
+            "60" => Ok(FixTag::TransactTime),
+            "790" => Ok(FixTag::OrdStatusReqID),
+            "530" => Ok(FixTag::MassCancelRequestType),
+            "531" => Ok(FixTag::MassCancelResponse),
+            "532" => Ok(FixTag::MassCancelRejectReason),
+            "533" => Ok(FixTag::TotalAffectedOrders),
+            "211" => Ok(FixTag::PegOffsetValue),
+            "1094" => Ok(FixTag::PegPriceType),
+            "1138" => Ok(FixTag::DisplayQty),
+            "18" => Ok(FixTag::ExecInst),
+            "110" => Ok(FixTag::MinQty),
+            "1" => Ok(FixTag::Account),
+            "710" => Ok(FixTag::PosReqID),
+            "724" => Ok(FixTag::PosReqType),
+            "721" => Ok(FixTag::PosMaintRptID),
+            "727" => Ok(FixTag::TotalNumPosReports),
+            "728" => Ok(FixTag::PosReqResult),
+            "729" => Ok(FixTag::PosReqStatus),
+            "715" => Ok(FixTag::ClearingBusinessDate),
+            "730" => Ok(FixTag::SettlPrice),
+            "731" => Ok(FixTag::SettlPriceType),
+            "734" => Ok(FixTag::PriorSettlPrice),
+            "702" => Ok(FixTag::NoPositions),
+            "703" => Ok(FixTag::PosType),
+            "704" => Ok(FixTag::LongQty),
+            "705" => Ok(FixTag::ShortQty),
+            "753" => Ok(FixTag::NoPosAmt),
+            "707" => Ok(FixTag::PosAmtType),
+            "708" => Ok(FixTag::PosAmt),
+            "31" => Ok(FixTag::LastPx),
+            "32" => Ok(FixTag::LastQty),
+            "75" => Ok(FixTag::TradeDate),
+            "64" => Ok(FixTag::SettlDate),
+            "571" => Ok(FixTag::TradeReportID),
+            "880" => Ok(FixTag::TrdMatchID),
+            "552" => Ok(FixTag::NoSides),
+            "453" => Ok(FixTag::NoPartyIDs),
+            "448" => Ok(FixTag::PartyID),
+            "452" => Ok(FixTag::PartyRole),
+            "12" => Ok(FixTag::Commission),
+            "13" => Ok(FixTag::CommType),
+            "851" => Ok(FixTag::LastLiquidityInd),
+            "19" => Ok(FixTag::ExecRefID),
+            "117" => Ok(FixTag::QuoteID),
+            "132" => Ok(FixTag::BidPx),
+            "133" => Ok(FixTag::OfferPx),
+            "134" => Ok(FixTag::BidSize),
+            "135" => Ok(FixTag::OfferSize),
+            "295" => Ok(FixTag::NoQuoteEntries),
+            "296" => Ok(FixTag::NoQuoteSets),
+            "297" => Ok(FixTag::QuoteStatus),
+            "298" => Ok(FixTag::QuoteCancelType),
+            "299" => Ok(FixTag::QuoteEntryID),
+            "300" => Ok(FixTag::QuoteRejectReason),
+            "302" => Ok(FixTag::QuoteSetID),
+            "304" => Ok(FixTag::TotNoQuoteEntries),
+            "368" => Ok(FixTag::QuoteEntryRejectReason),
             _ => Err(()),
         }
     }
@@ -85,6 +385,7 @@ impl Display for FixTag {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_fix_tag_from_str() {
     assert_eq!("8".parse::<FixTag>().unwrap(), FixTag::BeginString);
     assert_eq!("9".parse::<FixTag>().unwrap(), FixTag::BodyLength);
@@ -105,6 +406,148 @@ fn test_fix_tag_from_str() {
     assert_eq!("14".parse::<FixTag>().unwrap(), FixTag::CumQty);
     assert_eq!("6".parse::<FixTag>().unwrap(), FixTag::AvgPx);
     assert_eq!("58".parse::<FixTag>().unwrap(), FixTag::Text);
+    assert_eq!("11".parse::<FixTag>().unwrap(), FixTag::ClOrdID);
+    assert_eq!("41".parse::<FixTag>().unwrap(), FixTag::OrigClOrdID);
+    assert_eq!("150".parse::<FixTag>().unwrap(), FixTag::ExecType);
+    assert_eq!("39".parse::<FixTag>().unwrap(), FixTag::OrdStatus);
+    assert_eq!("434".parse::<FixTag>().unwrap(), FixTag::CxlRejResponseTo);
+    assert_eq!("48".parse::<FixTag>().unwrap(), FixTag::SecurityID);
+    assert_eq!("15".parse::<FixTag>().unwrap(), FixTag::Currency);
+    assert_eq!("320".parse::<FixTag>().unwrap(), FixTag::SecurityReqID);
+    assert_eq!("322".parse::<FixTag>().unwrap(), FixTag::SecurityResponseID);
+    assert_eq!(
+        "323".parse::<FixTag>().unwrap(),
+        FixTag::SecurityResponseType
+    );
+    assert_eq!(
+        "559".parse::<FixTag>().unwrap(),
+        FixTag::SecurityListRequestType
+    );
+    assert_eq!(
+        "560".parse::<FixTag>().unwrap(),
+        FixTag::SecurityRequestResult
+    );
+    assert_eq!("561".parse::<FixTag>().unwrap(), FixTag::RoundLot);
+    assert_eq!("562".parse::<FixTag>().unwrap(), FixTag::MinTradeVol);
+    assert_eq!("1140".parse::<FixTag>().unwrap(), FixTag::MaxTradeVol);
+    assert_eq!("969".parse::<FixTag>().unwrap(), FixTag::MinPriceIncrement);
+    assert_eq!("1148".parse::<FixTag>().unwrap(), FixTag::LowLimitPrice);
+    assert_eq!("1149".parse::<FixTag>().unwrap(), FixTag::HighLimitPrice);
+    assert_eq!("393".parse::<FixTag>().unwrap(), FixTag::TotNoRelatedSym);
+    assert_eq!("146".parse::<FixTag>().unwrap(), FixTag::NoRelatedSym);
+    assert_eq!("1205".parse::<FixTag>().unwrap(), FixTag::NoTickRules);
+    assert_eq!(
+        "1206".parse::<FixTag>().unwrap(),
+        FixTag::StartTickPriceRange
+    );
+    assert_eq!("1207".parse::<FixTag>().unwrap(), FixTag::EndTickPriceRange);
+    assert_eq!("1208".parse::<FixTag>().unwrap(), FixTag::TickIncrement);
+    assert_eq!(
+        "2362".parse::<FixTag>().unwrap(),
+        FixTag::SelfMatchPreventionID
+    );
+    assert_eq!(
+        "2964".parse::<FixTag>().unwrap(),
+        FixTag::SelfMatchPreventionInstruction
+    );
+    assert_eq!(
+        "326".parse::<FixTag>().unwrap(),
+        FixTag::SecurityTradingStatus
+    );
+    assert_eq!("336".parse::<FixTag>().unwrap(), FixTag::TradingSessionID);
+    assert_eq!("340".parse::<FixTag>().unwrap(), FixTag::TradSesStatus);
+    assert_eq!(
+        "625".parse::<FixTag>().unwrap(),
+        FixTag::TradingSessionSubID
+    );
+    assert_eq!("262".parse::<FixTag>().unwrap(), FixTag::MDReqID);
+    assert_eq!(
+        "263".parse::<FixTag>().unwrap(),
+        FixTag::SubscriptionRequestType
+    );
+    assert_eq!("264".parse::<FixTag>().unwrap(), FixTag::MarketDepth);
+    assert_eq!("268".parse::<FixTag>().unwrap(), FixTag::NoMDEntries);
+    assert_eq!("269".parse::<FixTag>().unwrap(), FixTag::MDEntryType);
+    assert_eq!("270".parse::<FixTag>().unwrap(), FixTag::MDEntryPx);
+    assert_eq!("271".parse::<FixTag>().unwrap(), FixTag::MDEntrySize);
+    assert_eq!("281".parse::<FixTag>().unwrap(), FixTag::MDReqRejReason);
+    assert_eq!("290".parse::<FixTag>().unwrap(), FixTag::MDEntryPositionNo);
+    assert_eq!("346".parse::<FixTag>().unwrap(), FixTag::NumberOfOrders);
+    assert_eq!("1021".parse::<FixTag>().unwrap(), FixTag::MDBookType);
+    assert_eq!("83".parse::<FixTag>().unwrap(), FixTag::RptSeq);
+    assert_eq!("279".parse::<FixTag>().unwrap(), FixTag::MDUpdateAction);
+    assert_eq!("60".parse::<FixTag>().unwrap(), FixTag::TransactTime);
+    assert_eq!("790".parse::<FixTag>().unwrap(), FixTag::OrdStatusReqID);
+    assert_eq!(
+        "530".parse::<FixTag>().unwrap(),
+        FixTag::MassCancelRequestType
+    );
+    assert_eq!("531".parse::<FixTag>().unwrap(), FixTag::MassCancelResponse);
+    assert_eq!(
+        "532".parse::<FixTag>().unwrap(),
+        FixTag::MassCancelRejectReason
+    );
+    assert_eq!(
+        "533".parse::<FixTag>().unwrap(),
+        FixTag::TotalAffectedOrders
+    );
+    assert_eq!("211".parse::<FixTag>().unwrap(), FixTag::PegOffsetValue);
+    assert_eq!("1094".parse::<FixTag>().unwrap(), FixTag::PegPriceType);
+    assert_eq!("1138".parse::<FixTag>().unwrap(), FixTag::DisplayQty);
+    assert_eq!("18".parse::<FixTag>().unwrap(), FixTag::ExecInst);
+    assert_eq!("110".parse::<FixTag>().unwrap(), FixTag::MinQty);
+    assert_eq!("1".parse::<FixTag>().unwrap(), FixTag::Account);
+    assert_eq!("710".parse::<FixTag>().unwrap(), FixTag::PosReqID);
+    assert_eq!("724".parse::<FixTag>().unwrap(), FixTag::PosReqType);
+    assert_eq!("721".parse::<FixTag>().unwrap(), FixTag::PosMaintRptID);
+    assert_eq!("727".parse::<FixTag>().unwrap(), FixTag::TotalNumPosReports);
+    assert_eq!("728".parse::<FixTag>().unwrap(), FixTag::PosReqResult);
+    assert_eq!("729".parse::<FixTag>().unwrap(), FixTag::PosReqStatus);
+    assert_eq!(
+        "715".parse::<FixTag>().unwrap(),
+        FixTag::ClearingBusinessDate
+    );
+    assert_eq!("730".parse::<FixTag>().unwrap(), FixTag::SettlPrice);
+    assert_eq!("731".parse::<FixTag>().unwrap(), FixTag::SettlPriceType);
+    assert_eq!("734".parse::<FixTag>().unwrap(), FixTag::PriorSettlPrice);
+    assert_eq!("702".parse::<FixTag>().unwrap(), FixTag::NoPositions);
+    assert_eq!("703".parse::<FixTag>().unwrap(), FixTag::PosType);
+    assert_eq!("704".parse::<FixTag>().unwrap(), FixTag::LongQty);
+    assert_eq!("705".parse::<FixTag>().unwrap(), FixTag::ShortQty);
+    assert_eq!("753".parse::<FixTag>().unwrap(), FixTag::NoPosAmt);
+    assert_eq!("707".parse::<FixTag>().unwrap(), FixTag::PosAmtType);
+    assert_eq!("708".parse::<FixTag>().unwrap(), FixTag::PosAmt);
+    assert_eq!("31".parse::<FixTag>().unwrap(), FixTag::LastPx);
+    assert_eq!("32".parse::<FixTag>().unwrap(), FixTag::LastQty);
+    assert_eq!("75".parse::<FixTag>().unwrap(), FixTag::TradeDate);
+    assert_eq!("64".parse::<FixTag>().unwrap(), FixTag::SettlDate);
+    assert_eq!("571".parse::<FixTag>().unwrap(), FixTag::TradeReportID);
+    assert_eq!("880".parse::<FixTag>().unwrap(), FixTag::TrdMatchID);
+    assert_eq!("552".parse::<FixTag>().unwrap(), FixTag::NoSides);
+    assert_eq!("453".parse::<FixTag>().unwrap(), FixTag::NoPartyIDs);
+    assert_eq!("448".parse::<FixTag>().unwrap(), FixTag::PartyID);
+    assert_eq!("452".parse::<FixTag>().unwrap(), FixTag::PartyRole);
+    assert_eq!("12".parse::<FixTag>().unwrap(), FixTag::Commission);
+    assert_eq!("13".parse::<FixTag>().unwrap(), FixTag::CommType);
+    assert_eq!("851".parse::<FixTag>().unwrap(), FixTag::LastLiquidityInd);
+    assert_eq!("19".parse::<FixTag>().unwrap(), FixTag::ExecRefID);
+    assert_eq!("117".parse::<FixTag>().unwrap(), FixTag::QuoteID);
+    assert_eq!("132".parse::<FixTag>().unwrap(), FixTag::BidPx);
+    assert_eq!("133".parse::<FixTag>().unwrap(), FixTag::OfferPx);
+    assert_eq!("134".parse::<FixTag>().unwrap(), FixTag::BidSize);
+    assert_eq!("135".parse::<FixTag>().unwrap(), FixTag::OfferSize);
+    assert_eq!("295".parse::<FixTag>().unwrap(), FixTag::NoQuoteEntries);
+    assert_eq!("296".parse::<FixTag>().unwrap(), FixTag::NoQuoteSets);
+    assert_eq!("297".parse::<FixTag>().unwrap(), FixTag::QuoteStatus);
+    assert_eq!("298".parse::<FixTag>().unwrap(), FixTag::QuoteCancelType);
+    assert_eq!("299".parse::<FixTag>().unwrap(), FixTag::QuoteEntryID);
+    assert_eq!("300".parse::<FixTag>().unwrap(), FixTag::QuoteRejectReason);
+    assert_eq!("302".parse::<FixTag>().unwrap(), FixTag::QuoteSetID);
+    assert_eq!("304".parse::<FixTag>().unwrap(), FixTag::TotNoQuoteEntries);
+    assert_eq!(
+        "368".parse::<FixTag>().unwrap(),
+        FixTag::QuoteEntryRejectReason
+    );
     assert_eq!("".parse::<FixTag>().is_err(), true);
 }
 
@@ -129,9 +572,110 @@ fn test_fix_tag_to_string() {
     assert_eq!(FixTag::CumQty.to_string(), "14");
     assert_eq!(FixTag::AvgPx.to_string(), "6");
     assert_eq!(FixTag::Text.to_string(), "58");
+    assert_eq!(FixTag::ClOrdID.to_string(), "11");
+    assert_eq!(FixTag::OrigClOrdID.to_string(), "41");
+    assert_eq!(FixTag::ExecType.to_string(), "150");
+    assert_eq!(FixTag::OrdStatus.to_string(), "39");
+    assert_eq!(FixTag::CxlRejResponseTo.to_string(), "434");
+    assert_eq!(FixTag::SecurityID.to_string(), "48");
+    assert_eq!(FixTag::Currency.to_string(), "15");
+    assert_eq!(FixTag::SecurityReqID.to_string(), "320");
+    assert_eq!(FixTag::SecurityResponseID.to_string(), "322");
+    assert_eq!(FixTag::SecurityResponseType.to_string(), "323");
+    assert_eq!(FixTag::SecurityListRequestType.to_string(), "559");
+    assert_eq!(FixTag::SecurityRequestResult.to_string(), "560");
+    assert_eq!(FixTag::RoundLot.to_string(), "561");
+    assert_eq!(FixTag::MinTradeVol.to_string(), "562");
+    assert_eq!(FixTag::MaxTradeVol.to_string(), "1140");
+    assert_eq!(FixTag::MinPriceIncrement.to_string(), "969");
+    assert_eq!(FixTag::LowLimitPrice.to_string(), "1148");
+    assert_eq!(FixTag::HighLimitPrice.to_string(), "1149");
+    assert_eq!(FixTag::TotNoRelatedSym.to_string(), "393");
+    assert_eq!(FixTag::NoRelatedSym.to_string(), "146");
+    assert_eq!(FixTag::NoTickRules.to_string(), "1205");
+    assert_eq!(FixTag::StartTickPriceRange.to_string(), "1206");
+    assert_eq!(FixTag::EndTickPriceRange.to_string(), "1207");
+    assert_eq!(FixTag::TickIncrement.to_string(), "1208");
+    assert_eq!(FixTag::SelfMatchPreventionID.to_string(), "2362");
+    assert_eq!(FixTag::SelfMatchPreventionInstruction.to_string(), "2964");
+    assert_eq!(FixTag::SecurityTradingStatus.to_string(), "326");
+    assert_eq!(FixTag::TradingSessionID.to_string(), "336");
+    assert_eq!(FixTag::TradSesStatus.to_string(), "340");
+    assert_eq!(FixTag::TradingSessionSubID.to_string(), "625");
+    assert_eq!(FixTag::MDReqID.to_string(), "262");
+    assert_eq!(FixTag::SubscriptionRequestType.to_string(), "263");
+    assert_eq!(FixTag::MarketDepth.to_string(), "264");
+    assert_eq!(FixTag::NoMDEntries.to_string(), "268");
+    assert_eq!(FixTag::MDEntryType.to_string(), "269");
+    assert_eq!(FixTag::MDEntryPx.to_string(), "270");
+    assert_eq!(FixTag::MDEntrySize.to_string(), "271");
+    assert_eq!(FixTag::MDReqRejReason.to_string(), "281");
+    assert_eq!(FixTag::MDEntryPositionNo.to_string(), "290");
+    assert_eq!(FixTag::NumberOfOrders.to_string(), "346");
+    assert_eq!(FixTag::MDBookType.to_string(), "1021");
+    assert_eq!(FixTag::RptSeq.to_string(), "83");
+    assert_eq!(FixTag::MDUpdateAction.to_string(), "279");
+    assert_eq!(FixTag::TransactTime.to_string(), "60");
+    assert_eq!(FixTag::OrdStatusReqID.to_string(), "790");
+    assert_eq!(FixTag::MassCancelRequestType.to_string(), "530");
+    assert_eq!(FixTag::MassCancelResponse.to_string(), "531");
+    assert_eq!(FixTag::MassCancelRejectReason.to_string(), "532");
+    assert_eq!(FixTag::TotalAffectedOrders.to_string(), "533");
+    assert_eq!(FixTag::PegOffsetValue.to_string(), "211");
+    assert_eq!(FixTag::PegPriceType.to_string(), "1094");
+    assert_eq!(FixTag::DisplayQty.to_string(), "1138");
+    assert_eq!(FixTag::ExecInst.to_string(), "18");
+    assert_eq!(FixTag::MinQty.to_string(), "110");
+    assert_eq!(FixTag::Account.to_string(), "1");
+    assert_eq!(FixTag::PosReqID.to_string(), "710");
+    assert_eq!(FixTag::PosReqType.to_string(), "724");
+    assert_eq!(FixTag::PosMaintRptID.to_string(), "721");
+    assert_eq!(FixTag::TotalNumPosReports.to_string(), "727");
+    assert_eq!(FixTag::PosReqResult.to_string(), "728");
+    assert_eq!(FixTag::PosReqStatus.to_string(), "729");
+    assert_eq!(FixTag::ClearingBusinessDate.to_string(), "715");
+    assert_eq!(FixTag::SettlPrice.to_string(), "730");
+    assert_eq!(FixTag::SettlPriceType.to_string(), "731");
+    assert_eq!(FixTag::PriorSettlPrice.to_string(), "734");
+    assert_eq!(FixTag::NoPositions.to_string(), "702");
+    assert_eq!(FixTag::PosType.to_string(), "703");
+    assert_eq!(FixTag::LongQty.to_string(), "704");
+    assert_eq!(FixTag::ShortQty.to_string(), "705");
+    assert_eq!(FixTag::NoPosAmt.to_string(), "753");
+    assert_eq!(FixTag::PosAmtType.to_string(), "707");
+    assert_eq!(FixTag::PosAmt.to_string(), "708");
+    assert_eq!(FixTag::LastPx.to_string(), "31");
+    assert_eq!(FixTag::LastQty.to_string(), "32");
+    assert_eq!(FixTag::TradeDate.to_string(), "75");
+    assert_eq!(FixTag::SettlDate.to_string(), "64");
+    assert_eq!(FixTag::TradeReportID.to_string(), "571");
+    assert_eq!(FixTag::TrdMatchID.to_string(), "880");
+    assert_eq!(FixTag::NoSides.to_string(), "552");
+    assert_eq!(FixTag::NoPartyIDs.to_string(), "453");
+    assert_eq!(FixTag::PartyID.to_string(), "448");
+    assert_eq!(FixTag::PartyRole.to_string(), "452");
+    assert_eq!(FixTag::Commission.to_string(), "12");
+    assert_eq!(FixTag::CommType.to_string(), "13");
+    assert_eq!(FixTag::LastLiquidityInd.to_string(), "851");
+    assert_eq!(FixTag::ExecRefID.to_string(), "19");
+    assert_eq!(FixTag::QuoteID.to_string(), "117");
+    assert_eq!(FixTag::BidPx.to_string(), "132");
+    assert_eq!(FixTag::OfferPx.to_string(), "133");
+    assert_eq!(FixTag::BidSize.to_string(), "134");
+    assert_eq!(FixTag::OfferSize.to_string(), "135");
+    assert_eq!(FixTag::NoQuoteEntries.to_string(), "295");
+    assert_eq!(FixTag::NoQuoteSets.to_string(), "296");
+    assert_eq!(FixTag::QuoteStatus.to_string(), "297");
+    assert_eq!(FixTag::QuoteCancelType.to_string(), "298");
+    assert_eq!(FixTag::QuoteEntryID.to_string(), "299");
+    assert_eq!(FixTag::QuoteRejectReason.to_string(), "300");
+    assert_eq!(FixTag::QuoteSetID.to_string(), "302");
+    assert_eq!(FixTag::TotNoQuoteEntries.to_string(), "304");
+    assert_eq!(FixTag::QuoteEntryRejectReason.to_string(), "368");
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_fix_tag_cmp() {
     assert_eq!(FixTag::BeginString < FixTag::BodyLength, true);
     assert_eq!(FixTag::BodyLength < FixTag::MsgType, true);
@@ -150,5 +694,138 @@ fn test_fix_tag_cmp() {
     assert_eq!(FixTag::LeavesQty < FixTag::CumQty, true);
     assert_eq!(FixTag::CumQty < FixTag::AvgPx, true);
     assert_eq!(FixTag::AvgPx < FixTag::Text, true);
-    assert_eq!(FixTag::Text < FixTag::CheckSum, true);
+    assert_eq!(FixTag::Text < FixTag::ClOrdID, true);
+    assert_eq!(FixTag::ClOrdID < FixTag::OrigClOrdID, true);
+    assert_eq!(FixTag::OrigClOrdID < FixTag::ExecType, true);
+    assert_eq!(FixTag::ExecType < FixTag::OrdStatus, true);
+    assert_eq!(FixTag::OrdStatus < FixTag::CxlRejResponseTo, true);
+    assert_eq!(FixTag::CxlRejResponseTo < FixTag::SecurityID, true);
+    assert_eq!(FixTag::SecurityID < FixTag::Currency, true);
+    assert_eq!(FixTag::Currency < FixTag::SecurityReqID, true);
+    assert_eq!(FixTag::SecurityReqID < FixTag::SecurityResponseID, true);
+    assert_eq!(
+        FixTag::SecurityResponseID < FixTag::SecurityResponseType,
+        true
+    );
+    assert_eq!(
+        FixTag::SecurityResponseType < FixTag::SecurityListRequestType,
+        true
+    );
+    assert_eq!(
+        FixTag::SecurityListRequestType < FixTag::SecurityRequestResult,
+        true
+    );
+    assert_eq!(FixTag::SecurityRequestResult < FixTag::RoundLot, true);
+    assert_eq!(FixTag::RoundLot < FixTag::MinTradeVol, true);
+    assert_eq!(FixTag::MinTradeVol < FixTag::MaxTradeVol, true);
+    assert_eq!(FixTag::MaxTradeVol < FixTag::MinPriceIncrement, true);
+    assert_eq!(FixTag::MinPriceIncrement < FixTag::LowLimitPrice, true);
+    assert_eq!(FixTag::LowLimitPrice < FixTag::HighLimitPrice, true);
+    assert_eq!(FixTag::HighLimitPrice < FixTag::TotNoRelatedSym, true);
+    assert_eq!(FixTag::TotNoRelatedSym < FixTag::NoRelatedSym, true);
+    assert_eq!(FixTag::NoRelatedSym < FixTag::NoTickRules, true);
+    assert_eq!(FixTag::NoTickRules < FixTag::StartTickPriceRange, true);
+    assert_eq!(
+        FixTag::StartTickPriceRange < FixTag::EndTickPriceRange,
+        true
+    );
+    assert_eq!(FixTag::EndTickPriceRange < FixTag::TickIncrement, true);
+    assert_eq!(FixTag::TickIncrement < FixTag::SelfMatchPreventionID, true);
+    assert_eq!(
+        FixTag::SelfMatchPreventionID < FixTag::SelfMatchPreventionInstruction,
+        true
+    );
+    assert_eq!(
+        FixTag::SelfMatchPreventionInstruction < FixTag::SecurityTradingStatus,
+        true
+    );
+    assert_eq!(
+        FixTag::SecurityTradingStatus < FixTag::TradingSessionID,
+        true
+    );
+    assert_eq!(FixTag::TradingSessionID < FixTag::TradSesStatus, true);
+    assert_eq!(FixTag::TradSesStatus < FixTag::TradingSessionSubID, true);
+    assert_eq!(FixTag::TradingSessionSubID < FixTag::MDReqID, true);
+    assert_eq!(FixTag::MDReqID < FixTag::SubscriptionRequestType, true);
+    assert_eq!(FixTag::SubscriptionRequestType < FixTag::MarketDepth, true);
+    assert_eq!(FixTag::MarketDepth < FixTag::NoMDEntries, true);
+    assert_eq!(FixTag::NoMDEntries < FixTag::MDEntryType, true);
+    assert_eq!(FixTag::MDEntryType < FixTag::MDEntryPx, true);
+    assert_eq!(FixTag::MDEntryPx < FixTag::MDEntrySize, true);
+    assert_eq!(FixTag::MDEntrySize < FixTag::MDReqRejReason, true);
+    assert_eq!(FixTag::MDReqRejReason < FixTag::MDEntryPositionNo, true);
+    assert_eq!(FixTag::MDEntryPositionNo < FixTag::NumberOfOrders, true);
+    assert_eq!(FixTag::NumberOfOrders < FixTag::MDBookType, true);
+    assert_eq!(FixTag::MDBookType < FixTag::RptSeq, true);
+    assert_eq!(FixTag::RptSeq < FixTag::MDUpdateAction, true);
+    assert_eq!(FixTag::MDUpdateAction < FixTag::TransactTime, true);
+    assert_eq!(FixTag::TransactTime < FixTag::OrdStatusReqID, true);
+    assert_eq!(FixTag::OrdStatusReqID < FixTag::MassCancelRequestType, true);
+    assert_eq!(
+        FixTag::MassCancelRequestType < FixTag::MassCancelResponse,
+        true
+    );
+    assert_eq!(
+        FixTag::MassCancelResponse < FixTag::MassCancelRejectReason,
+        true
+    );
+    assert_eq!(
+        FixTag::MassCancelRejectReason < FixTag::TotalAffectedOrders,
+        true
+    );
+    assert_eq!(FixTag::TotalAffectedOrders < FixTag::PegOffsetValue, true);
+    assert_eq!(FixTag::PegOffsetValue < FixTag::PegPriceType, true);
+    assert_eq!(FixTag::PegPriceType < FixTag::DisplayQty, true);
+    assert_eq!(FixTag::DisplayQty < FixTag::ExecInst, true);
+    assert_eq!(FixTag::ExecInst < FixTag::MinQty, true);
+    assert_eq!(FixTag::MinQty < FixTag::Account, true);
+    assert_eq!(FixTag::Account < FixTag::PosReqID, true);
+    assert_eq!(FixTag::PosReqID < FixTag::PosReqType, true);
+    assert_eq!(FixTag::PosReqType < FixTag::PosMaintRptID, true);
+    assert_eq!(FixTag::PosMaintRptID < FixTag::TotalNumPosReports, true);
+    assert_eq!(FixTag::TotalNumPosReports < FixTag::PosReqResult, true);
+    assert_eq!(FixTag::PosReqResult < FixTag::PosReqStatus, true);
+    assert_eq!(FixTag::PosReqStatus < FixTag::ClearingBusinessDate, true);
+    assert_eq!(FixTag::ClearingBusinessDate < FixTag::SettlPrice, true);
+    assert_eq!(FixTag::SettlPrice < FixTag::SettlPriceType, true);
+    assert_eq!(FixTag::SettlPriceType < FixTag::PriorSettlPrice, true);
+    assert_eq!(FixTag::PriorSettlPrice < FixTag::NoPositions, true);
+    assert_eq!(FixTag::NoPositions < FixTag::PosType, true);
+    assert_eq!(FixTag::PosType < FixTag::LongQty, true);
+    assert_eq!(FixTag::LongQty < FixTag::ShortQty, true);
+    assert_eq!(FixTag::ShortQty < FixTag::NoPosAmt, true);
+    assert_eq!(FixTag::NoPosAmt < FixTag::PosAmtType, true);
+    assert_eq!(FixTag::PosAmtType < FixTag::PosAmt, true);
+    assert_eq!(FixTag::PosAmt < FixTag::LastPx, true);
+    assert_eq!(FixTag::LastPx < FixTag::LastQty, true);
+    assert_eq!(FixTag::LastQty < FixTag::TradeDate, true);
+    assert_eq!(FixTag::TradeDate < FixTag::SettlDate, true);
+    assert_eq!(FixTag::SettlDate < FixTag::TradeReportID, true);
+    assert_eq!(FixTag::TradeReportID < FixTag::TrdMatchID, true);
+    assert_eq!(FixTag::TrdMatchID < FixTag::NoSides, true);
+    assert_eq!(FixTag::NoSides < FixTag::NoPartyIDs, true);
+    assert_eq!(FixTag::NoPartyIDs < FixTag::PartyID, true);
+    assert_eq!(FixTag::PartyID < FixTag::PartyRole, true);
+    assert_eq!(FixTag::PartyRole < FixTag::Commission, true);
+    assert_eq!(FixTag::Commission < FixTag::CommType, true);
+    assert_eq!(FixTag::CommType < FixTag::LastLiquidityInd, true);
+    assert_eq!(FixTag::LastLiquidityInd < FixTag::ExecRefID, true);
+    assert_eq!(FixTag::ExecRefID < FixTag::QuoteID, true);
+    assert_eq!(FixTag::QuoteID < FixTag::BidPx, true);
+    assert_eq!(FixTag::BidPx < FixTag::OfferPx, true);
+    assert_eq!(FixTag::OfferPx < FixTag::BidSize, true);
+    assert_eq!(FixTag::BidSize < FixTag::OfferSize, true);
+    assert_eq!(FixTag::OfferSize < FixTag::NoQuoteEntries, true);
+    assert_eq!(FixTag::NoQuoteEntries < FixTag::NoQuoteSets, true);
+    assert_eq!(FixTag::NoQuoteSets < FixTag::QuoteStatus, true);
+    assert_eq!(FixTag::QuoteStatus < FixTag::QuoteCancelType, true);
+    assert_eq!(FixTag::QuoteCancelType < FixTag::QuoteEntryID, true);
+    assert_eq!(FixTag::QuoteEntryID < FixTag::QuoteRejectReason, true);
+    assert_eq!(FixTag::QuoteRejectReason < FixTag::QuoteSetID, true);
+    assert_eq!(FixTag::QuoteSetID < FixTag::TotNoQuoteEntries, true);
+    assert_eq!(
+        FixTag::TotNoQuoteEntries < FixTag::QuoteEntryRejectReason,
+        true
+    );
+    assert_eq!(FixTag::QuoteEntryRejectReason < FixTag::CheckSum, true);
 }
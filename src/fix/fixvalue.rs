@@ -0,0 +1,256 @@
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MsgType {
+    ExecutionReport,
+    Logout,
+    MarketDataIncrementalRefresh,
+    MarketDataRequest,
+    MarketDataRequestReject,
+    MarketDataSnapshotFullRefresh,
+    MassQuote,
+    MassQuoteAcknowledgement,
+    NewOrderSingle,
+    OrderCancelReject,
+    OrderCancelReplaceRequest,
+    OrderMassCancelReport,
+    OrderMassCancelRequest,
+    OrderStatusRequest,
+    PositionReport,
+    QuoteCancel,
+    RequestForPositions,
+    RequestForPositionsAck,
+    SecurityDefinition,
+    SecurityDefinitionRequest,
+    SecurityList,
+    SecurityListRequest,
+    SecurityStatus,
+    TradeCaptureReport,
+    TradingSessionStatus,
+}
+
+impl MsgType {
+    fn msg_type_value(&self) -> &'static str {
+        match self {
+            MsgType::ExecutionReport => "8",
+            MsgType::Logout => "5",
+            MsgType::MarketDataIncrementalRefresh => "X",
+            MsgType::MarketDataRequest => "V",
+            MsgType::MarketDataRequestReject => "Y",
+            MsgType::MarketDataSnapshotFullRefresh => "W",
+            MsgType::MassQuote => "i",
+            MsgType::MassQuoteAcknowledgement => "b",
+            MsgType::NewOrderSingle => "D",
+            MsgType::OrderCancelReject => "9",
+            MsgType::OrderCancelReplaceRequest => "G",
+            MsgType::OrderMassCancelReport => "r",
+            MsgType::OrderMassCancelRequest => "q",
+            MsgType::OrderStatusRequest => "H",
+            MsgType::PositionReport => "AP",
+            MsgType::QuoteCancel => "Z",
+            MsgType::RequestForPositions => "AN",
+            MsgType::RequestForPositionsAck => "AO",
+            MsgType::SecurityDefinition => "d",
+            MsgType::SecurityDefinitionRequest => "c",
+            MsgType::SecurityList => "y",
+            MsgType::SecurityListRequest => "x",
+            MsgType::SecurityStatus => "f",
+            MsgType::TradeCaptureReport => "AE",
+            MsgType::TradingSessionStatus => "h",
+        }
+    }
+}
+
+impl FromStr for MsgType {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "8" => Ok(MsgType::ExecutionReport),
+            "5" => Ok(MsgType::Logout),
+            "X" => Ok(MsgType::MarketDataIncrementalRefresh),
+            "V" => Ok(MsgType::MarketDataRequest),
+            "Y" => Ok(MsgType::MarketDataRequestReject),
+            "W" => Ok(MsgType::MarketDataSnapshotFullRefresh),
+            "i" => Ok(MsgType::MassQuote),
+            "b" => Ok(MsgType::MassQuoteAcknowledgement),
+            "D" => Ok(MsgType::NewOrderSingle),
+            "9" => Ok(MsgType::OrderCancelReject),
+            "G" => Ok(MsgType::OrderCancelReplaceRequest),
+            "r" => Ok(MsgType::OrderMassCancelReport),
+            "q" => Ok(MsgType::OrderMassCancelRequest),
+            "H" => Ok(MsgType::OrderStatusRequest),
+            "AP" => Ok(MsgType::PositionReport),
+            "Z" => Ok(MsgType::QuoteCancel),
+            "AN" => Ok(MsgType::RequestForPositions),
+            "AO" => Ok(MsgType::RequestForPositionsAck),
+            "d" => Ok(MsgType::SecurityDefinition),
+            "c" => Ok(MsgType::SecurityDefinitionRequest),
+            "y" => Ok(MsgType::SecurityList),
+            "x" => Ok(MsgType::SecurityListRequest),
+            "f" => Ok(MsgType::SecurityStatus),
+            "AE" => Ok(MsgType::TradeCaptureReport),
+            "h" => Ok(MsgType::TradingSessionStatus),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for MsgType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg_type_value())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ExecType {
+    New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Restated,
+    Trade,
+    TradeCorrect,
+    TradeCancel,
+    OrderStatus,
+}
+
+impl Display for ExecType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ExecType::New => "0",
+            ExecType::Canceled => "4",
+            ExecType::Replaced => "5",
+            ExecType::Rejected => "8",
+            ExecType::Restated => "D",
+            ExecType::Trade => "F",
+            ExecType::TradeCorrect => "G",
+            ExecType::TradeCancel => "H",
+            ExecType::OrderStatus => "I",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum OrdStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Replaced,
+    Rejected,
+    Expired,
+}
+
+impl Display for OrdStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OrdStatus::New => "0",
+            OrdStatus::PartiallyFilled => "1",
+            OrdStatus::Filled => "2",
+            OrdStatus::Canceled => "4",
+            OrdStatus::Replaced => "5",
+            OrdStatus::Rejected => "8",
+            OrdStatus::Expired => "C",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[test]
+fn test_msg_type_from_str() {
+    assert_eq!("8".parse::<MsgType>().unwrap(), MsgType::ExecutionReport);
+    assert_eq!("5".parse::<MsgType>().unwrap(), MsgType::Logout);
+    assert_eq!(
+        "X".parse::<MsgType>().unwrap(),
+        MsgType::MarketDataIncrementalRefresh
+    );
+    assert_eq!("V".parse::<MsgType>().unwrap(), MsgType::MarketDataRequest);
+    assert_eq!(
+        "Y".parse::<MsgType>().unwrap(),
+        MsgType::MarketDataRequestReject
+    );
+    assert_eq!(
+        "W".parse::<MsgType>().unwrap(),
+        MsgType::MarketDataSnapshotFullRefresh
+    );
+    assert_eq!("i".parse::<MsgType>().unwrap(), MsgType::MassQuote);
+    assert_eq!(
+        "b".parse::<MsgType>().unwrap(),
+        MsgType::MassQuoteAcknowledgement
+    );
+    assert_eq!("D".parse::<MsgType>().unwrap(), MsgType::NewOrderSingle);
+    assert_eq!("9".parse::<MsgType>().unwrap(), MsgType::OrderCancelReject);
+    assert_eq!(
+        "G".parse::<MsgType>().unwrap(),
+        MsgType::OrderCancelReplaceRequest
+    );
+    assert_eq!(
+        "r".parse::<MsgType>().unwrap(),
+        MsgType::OrderMassCancelReport
+    );
+    assert_eq!(
+        "q".parse::<MsgType>().unwrap(),
+        MsgType::OrderMassCancelRequest
+    );
+    assert_eq!("H".parse::<MsgType>().unwrap(), MsgType::OrderStatusRequest);
+    assert_eq!("AP".parse::<MsgType>().unwrap(), MsgType::PositionReport);
+    assert_eq!("Z".parse::<MsgType>().unwrap(), MsgType::QuoteCancel);
+    assert_eq!(
+        "AN".parse::<MsgType>().unwrap(),
+        MsgType::RequestForPositions
+    );
+    assert_eq!(
+        "AO".parse::<MsgType>().unwrap(),
+        MsgType::RequestForPositionsAck
+    );
+    assert_eq!("d".parse::<MsgType>().unwrap(), MsgType::SecurityDefinition);
+    assert_eq!(
+        "c".parse::<MsgType>().unwrap(),
+        MsgType::SecurityDefinitionRequest
+    );
+    assert_eq!("y".parse::<MsgType>().unwrap(), MsgType::SecurityList);
+    assert_eq!(
+        "x".parse::<MsgType>().unwrap(),
+        MsgType::SecurityListRequest
+    );
+    assert_eq!("f".parse::<MsgType>().unwrap(), MsgType::SecurityStatus);
+    assert_eq!(
+        "AE".parse::<MsgType>().unwrap(),
+        MsgType::TradeCaptureReport
+    );
+    assert_eq!(
+        "h".parse::<MsgType>().unwrap(),
+        MsgType::TradingSessionStatus
+    );
+    assert!("".parse::<MsgType>().is_err());
+}
+
+#[test]
+fn test_msg_type_to_string() {
+    assert_eq!(MsgType::ExecutionReport.to_string(), "8");
+    assert_eq!(MsgType::Logout.to_string(), "5");
+    assert_eq!(MsgType::MarketDataIncrementalRefresh.to_string(), "X");
+    assert_eq!(MsgType::MarketDataRequest.to_string(), "V");
+    assert_eq!(MsgType::MarketDataRequestReject.to_string(), "Y");
+    assert_eq!(MsgType::MarketDataSnapshotFullRefresh.to_string(), "W");
+    assert_eq!(MsgType::MassQuote.to_string(), "i");
+    assert_eq!(MsgType::MassQuoteAcknowledgement.to_string(), "b");
+    assert_eq!(MsgType::NewOrderSingle.to_string(), "D");
+    assert_eq!(MsgType::OrderCancelReject.to_string(), "9");
+    assert_eq!(MsgType::OrderCancelReplaceRequest.to_string(), "G");
+    assert_eq!(MsgType::OrderMassCancelReport.to_string(), "r");
+    assert_eq!(MsgType::OrderMassCancelRequest.to_string(), "q");
+    assert_eq!(MsgType::OrderStatusRequest.to_string(), "H");
+    assert_eq!(MsgType::PositionReport.to_string(), "AP");
+    assert_eq!(MsgType::QuoteCancel.to_string(), "Z");
+    assert_eq!(MsgType::RequestForPositions.to_string(), "AN");
+    assert_eq!(MsgType::RequestForPositionsAck.to_string(), "AO");
+    assert_eq!(MsgType::SecurityDefinition.to_string(), "d");
+    assert_eq!(MsgType::SecurityDefinitionRequest.to_string(), "c");
+    assert_eq!(MsgType::SecurityList.to_string(), "y");
+    assert_eq!(MsgType::SecurityListRequest.to_string(), "x");
+    assert_eq!(MsgType::SecurityStatus.to_string(), "f");
+    assert_eq!(MsgType::TradeCaptureReport.to_string(), "AE");
+    assert_eq!(MsgType::TradingSessionStatus.to_string(), "h");
+}
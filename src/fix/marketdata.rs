@@ -0,0 +1,240 @@
+use super::fixmessage::{FixGroupEntry, FixMessage};
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::depth::{MarketDepth, OrderDepth, PriceLevel};
+use crate::exchange::marketdata::{MarketDataEntryType, MarketDataUpdate, UpdateAction};
+use crate::order::Order;
+
+const BID: MarketDataEntryType = MarketDataEntryType::Bid;
+const OFFER: MarketDataEntryType = MarketDataEntryType::Offer;
+
+impl FixMessage {
+    // MarketDataSnapshotFullRefresh (35=W) with the price levels of one
+    // instrument, best price first on each side
+    pub fn market_data_snapshot(
+        request: &FixMessage,
+        symbol: &str,
+        depth: &MarketDepth,
+    ) -> FixMessage {
+        let mut snapshot = FixMessage::snapshot_header(request, symbol);
+        for (side, levels) in [(BID, &depth.bids), (OFFER, &depth.asks)] {
+            for (position, level) in levels.iter().enumerate() {
+                snapshot.add_group_entry(FixTag::NoMDEntries, level_entry(side, position, level));
+            }
+        }
+        snapshot
+    }
+
+    // MarketDataSnapshotFullRefresh (35=W) with every resting order of one
+    // instrument in priority order
+    pub fn market_data_order_snapshot(
+        request: &FixMessage,
+        symbol: &str,
+        depth: &OrderDepth,
+    ) -> FixMessage {
+        let mut snapshot = FixMessage::snapshot_header(request, symbol);
+        for (side, orders) in [(BID, &depth.bids), (OFFER, &depth.asks)] {
+            for (position, order) in orders.iter().enumerate() {
+                snapshot.add_group_entry(FixTag::NoMDEntries, order_entry(side, position, order));
+            }
+        }
+        snapshot
+    }
+
+    // MarketDataIncrementalRefresh (35=X) carrying one update for the
+    // subscription `request_id`, each entry tagged with the symbol's sequence
+    // number in RptSeq (83)
+    pub fn market_data_incremental(update: &MarketDataUpdate, request_id: &str) -> FixMessage {
+        let mut refresh = FixMessage::new();
+        refresh.add_field(
+            FixTag::MsgType,
+            &MsgType::MarketDataIncrementalRefresh.to_string(),
+        );
+        refresh.add_field(FixTag::MDReqID, request_id);
+        for entry in &update.entries {
+            let mut fields = vec![
+                (
+                    FixTag::MDUpdateAction,
+                    update_action(entry.action).to_string(),
+                ),
+                (
+                    FixTag::MDEntryType,
+                    entry_type(entry.entry_type).to_string(),
+                ),
+                (FixTag::Symbol, update.symbol.to_owned()),
+            ];
+            if let Some(price) = entry.price {
+                fields.push((FixTag::MDEntryPx, price.to_string()));
+            }
+            fields.push((FixTag::MDEntrySize, entry.size.to_string()));
+            if let Some(order_count) = entry.order_count {
+                fields.push((FixTag::NumberOfOrders, order_count.to_string()));
+            }
+            fields.push((FixTag::RptSeq, update.sequence.to_string()));
+            refresh.add_group_entry(FixTag::NoMDEntries, fields);
+        }
+        refresh
+    }
+
+    // MarketDataRequestReject (35=Y) with MDReqRejReason (281) `reason`
+    pub fn market_data_request_reject(
+        request: &FixMessage,
+        reason: &str,
+        text: &str,
+    ) -> FixMessage {
+        let mut reject = FixMessage::new();
+        reject.add_field(
+            FixTag::MsgType,
+            &MsgType::MarketDataRequestReject.to_string(),
+        );
+        if let Some(request_id) = request.fields.get(&FixTag::MDReqID) {
+            reject.add_field(FixTag::MDReqID, request_id);
+        }
+        reject.add_field(FixTag::MDReqRejReason, reason);
+        reject.add_field(FixTag::Text, text);
+        reject
+    }
+
+    fn snapshot_header(request: &FixMessage, symbol: &str) -> FixMessage {
+        let mut snapshot = FixMessage::new();
+        snapshot.add_field(
+            FixTag::MsgType,
+            &MsgType::MarketDataSnapshotFullRefresh.to_string(),
+        );
+        if let Some(request_id) = request.fields.get(&FixTag::MDReqID) {
+            snapshot.add_field(FixTag::MDReqID, request_id);
+        }
+        snapshot.add_field(FixTag::Symbol, symbol);
+        // An empty book is sent as an empty group rather than leaving it out
+        snapshot.groups.entry(FixTag::NoMDEntries).or_default();
+        snapshot
+    }
+}
+
+fn entry_type(entry_type: MarketDataEntryType) -> &'static str {
+    match entry_type {
+        MarketDataEntryType::Bid => "0",
+        MarketDataEntryType::Offer => "1",
+        MarketDataEntryType::Trade => "2",
+        MarketDataEntryType::OpeningPrice => "4",
+        MarketDataEntryType::ClosingPrice => "5",
+        MarketDataEntryType::TradeVolume => "B",
+    }
+}
+
+fn update_action(action: UpdateAction) -> &'static str {
+    match action {
+        UpdateAction::New => "0",
+        UpdateAction::Change => "1",
+        UpdateAction::Delete => "2",
+    }
+}
+
+fn level_entry(side: MarketDataEntryType, position: usize, level: &PriceLevel) -> FixGroupEntry {
+    vec![
+        (FixTag::MDEntryType, entry_type(side).to_string()),
+        (FixTag::MDEntryPx, level.price.to_string()),
+        (FixTag::MDEntrySize, level.quantity.to_string()),
+        (FixTag::NumberOfOrders, level.order_count.to_string()),
+        (FixTag::MDEntryPositionNo, (position + 1).to_string()),
+    ]
+}
+
+fn order_entry(side: MarketDataEntryType, position: usize, order: &Order) -> FixGroupEntry {
+    vec![
+        (FixTag::MDEntryType, entry_type(side).to_string()),
+        (FixTag::MDEntryPx, order.price.to_string()),
+        (FixTag::MDEntrySize, order.quantity.to_string()),
+        (FixTag::OrderID, order.id.to_string()),
+        (FixTag::MDEntryPositionNo, (position + 1).to_string()),
+    ]
+}
+
+#[test]
+fn test_market_data_snapshot() {
+    use crate::price::Price;
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::MDReqID, "REQ1");
+    let depth = MarketDepth {
+        bids: vec![PriceLevel {
+            price: Price::from(99),
+            quantity: 300,
+            order_count: 2,
+        }],
+        asks: Vec::new(),
+    };
+    let snapshot = FixMessage::market_data_snapshot(&request, "AAPL", &depth);
+    assert_eq!(
+        snapshot.msg_type(),
+        Some(MsgType::MarketDataSnapshotFullRefresh)
+    );
+    assert_eq!(snapshot.fields.get(&FixTag::MDReqID).unwrap(), "REQ1");
+    let entries = snapshot.groups.get(&FixTag::NoMDEntries).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0][0], (FixTag::MDEntryType, "0".to_string()));
+    assert_eq!(entries[0][3], (FixTag::NumberOfOrders, "2".to_string()));
+
+    let empty = FixMessage::market_data_snapshot(&request, "AAPL", &MarketDepth::default());
+    assert!(empty.clone().encode().contains("268=0|"));
+}
+
+#[test]
+fn test_market_data_order_snapshot() {
+    use crate::order::Side;
+    use crate::price::Price;
+    let order = Order::new("AAPL", 100, Price::from(101), Side::Sell);
+    let depth = OrderDepth {
+        bids: Vec::new(),
+        asks: vec![order.clone()],
+    };
+    let snapshot = FixMessage::market_data_order_snapshot(&FixMessage::new(), "AAPL", &depth);
+    let entries = snapshot.groups.get(&FixTag::NoMDEntries).unwrap();
+    assert_eq!(entries[0][0], (FixTag::MDEntryType, "1".to_string()));
+    assert_eq!(entries[0][3], (FixTag::OrderID, order.id.to_string()));
+}
+
+#[test]
+fn test_market_data_request_reject() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::MDReqID, "REQ1");
+    let reject = FixMessage::market_data_request_reject(&request, "0", "Unknown symbol TSLA");
+    assert_eq!(reject.msg_type(), Some(MsgType::MarketDataRequestReject));
+    assert_eq!(reject.fields.get(&FixTag::MDReqRejReason).unwrap(), "0");
+}
+
+#[test]
+fn test_market_data_incremental() {
+    use crate::exchange::marketdata::MarketDataEntry;
+    use crate::price::Price;
+    let update = MarketDataUpdate {
+        symbol: "AAPL".to_string(),
+        sequence: 7,
+        entries: vec![
+            MarketDataEntry {
+                action: UpdateAction::New,
+                entry_type: MarketDataEntryType::Trade,
+                price: Some(Price::from(100)),
+                size: 50,
+                order_count: None,
+            },
+            MarketDataEntry {
+                action: UpdateAction::Change,
+                entry_type: MarketDataEntryType::TradeVolume,
+                price: None,
+                size: 150,
+                order_count: None,
+            },
+        ],
+    };
+    let refresh = FixMessage::market_data_incremental(&update, "REQ1");
+    assert_eq!(
+        refresh.msg_type(),
+        Some(MsgType::MarketDataIncrementalRefresh)
+    );
+    let entries = refresh.groups.get(&FixTag::NoMDEntries).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0][0], (FixTag::MDUpdateAction, "0".to_string()));
+    assert_eq!(entries[1][1], (FixTag::MDEntryType, "B".to_string()));
+    assert_eq!(entries[1][3], (FixTag::MDEntrySize, "150".to_string()));
+    assert_eq!(entries[1][4], (FixTag::RptSeq, "7".to_string()));
+}
@@ -0,0 +1,126 @@
+use super::fixmessage::FixMessage;
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::cancellation::MassCancel;
+use crate::order::Side;
+
+// MassCancelRequestType (530) values the exchange supports
+const CANCEL_FOR_SYMBOL: &str = "1";
+const CANCEL_ALL_ORDERS: &str = "7";
+
+// MassCancelResponse (531) of a rejected request
+const CANCEL_REQUEST_REJECTED: &str = "0";
+
+impl FixMessage {
+    // The orders an OrderMassCancelRequest (35=q) applies to: every order of
+    // the sending session for MassCancelRequestType=7, or its orders in one
+    // Symbol (55) for 1, on one Side (54) when given. None for any other
+    // request type, or a request missing its symbol.
+    pub fn to_mass_cancel(&self) -> Option<MassCancel> {
+        let symbol = match self.fields.get(&FixTag::MassCancelRequestType)?.as_str() {
+            CANCEL_FOR_SYMBOL => Some(self.fields.get(&FixTag::Symbol)?.to_owned()),
+            CANCEL_ALL_ORDERS => None,
+            _ => return None,
+        };
+        let side = match self.fields.get(&FixTag::Side) {
+            Some(side) => Some(side.parse::<Side>().ok()?),
+            None => None,
+        };
+        Some(MassCancel {
+            participant: self
+                .fields
+                .get(&FixTag::SenderCompID)
+                .cloned()
+                .unwrap_or_default(),
+            symbol,
+            side,
+        })
+    }
+
+    // OrderMassCancelReport (35=r) accepting a mass cancel, with the number
+    // of orders it cancelled
+    pub fn mass_cancel_report(request: &FixMessage, affected_orders: usize) -> FixMessage {
+        let mut report = FixMessage::mass_cancel_report_header(request);
+        let request_type = request
+            .fields
+            .get(&FixTag::MassCancelRequestType)
+            .map_or("", String::as_str);
+        report.add_field(FixTag::MassCancelResponse, request_type);
+        report.add_field(FixTag::TotalAffectedOrders, &affected_orders.to_string());
+        report
+    }
+
+    // OrderMassCancelReport (35=r) rejecting a mass cancel with
+    // MassCancelRejectReason (532) `reason`
+    pub fn mass_cancel_reject(request: &FixMessage, reason: &str, text: &str) -> FixMessage {
+        let mut report = FixMessage::mass_cancel_report_header(request);
+        report.add_field(FixTag::MassCancelResponse, CANCEL_REQUEST_REJECTED);
+        report.add_field(FixTag::MassCancelRejectReason, reason);
+        report.add_field(FixTag::TotalAffectedOrders, "0");
+        report.add_field(FixTag::Text, text);
+        report
+    }
+
+    fn mass_cancel_report_header(request: &FixMessage) -> FixMessage {
+        let mut report = FixMessage::new();
+        report.add_field(FixTag::MsgType, &MsgType::OrderMassCancelReport.to_string());
+        report.add_field(FixTag::OrderID, "NONE");
+        for tag in [
+            FixTag::ClOrdID,
+            FixTag::MassCancelRequestType,
+            FixTag::Symbol,
+            FixTag::Side,
+        ] {
+            if let Some(value) = request.fields.get(&tag) {
+                report.add_field(tag, value);
+            }
+        }
+        report
+    }
+}
+
+#[test]
+fn test_to_mass_cancel() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::SenderCompID, "FIRM1");
+    request.add_field(FixTag::MassCancelRequestType, "7");
+    assert_eq!(
+        request.to_mass_cancel(),
+        Some(MassCancel {
+            participant: "FIRM1".to_string(),
+            symbol: None,
+            side: None,
+        })
+    );
+    request.modify_field(FixTag::MassCancelRequestType, "1");
+    assert_eq!(request.to_mass_cancel(), None);
+    request.add_field(FixTag::Symbol, "AAPL");
+    request.add_field(FixTag::Side, "2");
+    let mass_cancel = request.to_mass_cancel().unwrap();
+    assert_eq!(mass_cancel.symbol, Some("AAPL".to_string()));
+    assert_eq!(mass_cancel.side, Some(Side::Sell));
+    request.modify_field(FixTag::MassCancelRequestType, "3");
+    assert_eq!(request.to_mass_cancel(), None);
+}
+
+#[test]
+fn test_mass_cancel_report() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::ClOrdID, "MC1");
+    request.add_field(FixTag::MassCancelRequestType, "7");
+    let report = FixMessage::mass_cancel_report(&request, 3);
+    assert_eq!(report.msg_type(), Some(MsgType::OrderMassCancelReport));
+    assert_eq!(report.fields.get(&FixTag::ClOrdID).unwrap(), "MC1");
+    assert_eq!(report.fields.get(&FixTag::MassCancelResponse).unwrap(), "7");
+    assert_eq!(
+        report.fields.get(&FixTag::TotalAffectedOrders).unwrap(),
+        "3"
+    );
+
+    let reject = FixMessage::mass_cancel_reject(&request, "1", "Unknown symbol TSLA");
+    assert_eq!(reject.fields.get(&FixTag::MassCancelResponse).unwrap(), "0");
+    assert_eq!(
+        reject.fields.get(&FixTag::MassCancelRejectReason).unwrap(),
+        "1"
+    );
+}
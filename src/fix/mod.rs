@@ -1,2 +1,12 @@
+mod fill;
 pub mod fixmessage;
 pub mod fixtag;
+pub mod fixvalue;
+mod marketdata;
+mod masscancel;
+mod orderstatus;
+mod position;
+mod quote;
+mod securitydefinition;
+mod tradecapture;
+mod tradingstatus;
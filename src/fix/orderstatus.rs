@@ -0,0 +1,101 @@
+use super::fixmessage::FixMessage;
+use super::fixtag::FixTag;
+use super::fixvalue::{ExecType, MsgType, OrdStatus};
+use crate::exchange::orderrecord::{OrderRecord, OrderStatus};
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H:%M:%S%.3f";
+
+impl From<OrderStatus> for OrdStatus {
+    fn from(status: OrderStatus) -> OrdStatus {
+        match status {
+            OrderStatus::New => OrdStatus::New,
+            OrderStatus::PartiallyFilled => OrdStatus::PartiallyFilled,
+            OrderStatus::Filled => OrdStatus::Filled,
+            OrderStatus::Canceled => OrdStatus::Canceled,
+            OrderStatus::Replaced => OrdStatus::Replaced,
+            OrderStatus::Rejected => OrdStatus::Rejected,
+            OrderStatus::Expired => OrdStatus::Expired,
+        }
+    }
+}
+
+impl FixMessage {
+    // ExecutionReport (35=8, ExecType=I) answering an OrderStatusRequest with
+    // the order's record, timed at its last update
+    pub fn order_status_report(request: &FixMessage, record: &OrderRecord) -> FixMessage {
+        let mut report = FixMessage::execution_report(
+            &record.order,
+            ExecType::OrderStatus,
+            OrdStatus::from(record.status),
+        );
+        report.modify_field(FixTag::LeavesQty, &record.leaves_quantity.to_string());
+        report.add_field(FixTag::CumQty, &record.cum_quantity.to_string());
+        report.add_field(FixTag::AvgPx, &record.average_price().to_string());
+        report.add_field(
+            FixTag::TransactTime,
+            &record.updated_at.format(TIMESTAMP_FORMAT).to_string(),
+        );
+        echo_status_request_id(request, &mut report);
+        report
+    }
+
+    // The answer to an OrderStatusRequest for an order the exchange does not
+    // know, rejected with the identifiers the client sent
+    pub fn order_status_unknown(request: &FixMessage) -> FixMessage {
+        let mut report = FixMessage::new();
+        report.add_field(FixTag::MsgType, &MsgType::ExecutionReport.to_string());
+        report.add_field(FixTag::OrderID, "NONE");
+        for tag in [FixTag::ClOrdID, FixTag::Symbol, FixTag::Side] {
+            if let Some(value) = request.fields.get(&tag) {
+                report.add_field(tag, value);
+            }
+        }
+        report.add_field(FixTag::ExecType, &ExecType::OrderStatus.to_string());
+        report.add_field(FixTag::OrdStatus, &OrdStatus::Rejected.to_string());
+        report.add_field(FixTag::LeavesQty, "0");
+        report.add_field(FixTag::CumQty, "0");
+        report.add_field(FixTag::AvgPx, "0");
+        report.add_field(FixTag::Text, "Unknown order");
+        echo_status_request_id(request, &mut report);
+        report
+    }
+}
+
+fn echo_status_request_id(request: &FixMessage, report: &mut FixMessage) {
+    if let Some(request_id) = request.fields.get(&FixTag::OrdStatusReqID) {
+        report.add_field(FixTag::OrdStatusReqID, request_id);
+    }
+}
+
+#[test]
+fn test_order_status_report() {
+    use crate::order::{Order, Side};
+    use crate::price::Price;
+    use chrono::Utc;
+    let mut order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order.cl_ord_id = "ORD1".to_string();
+    let mut record = OrderRecord::new(&order, Utc::now());
+    record.fill(40, Price::from(149), Utc::now());
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::OrdStatusReqID, "REQ1");
+    let report = FixMessage::order_status_report(&request, &record);
+    assert_eq!(report.msg_type(), Some(MsgType::ExecutionReport));
+    assert_eq!(report.fields.get(&FixTag::ExecType).unwrap(), "I");
+    assert_eq!(report.fields.get(&FixTag::OrdStatus).unwrap(), "1");
+    assert_eq!(report.fields.get(&FixTag::OrderQty).unwrap(), "100");
+    assert_eq!(report.fields.get(&FixTag::CumQty).unwrap(), "40");
+    assert_eq!(report.fields.get(&FixTag::LeavesQty).unwrap(), "60");
+    assert_eq!(report.fields.get(&FixTag::AvgPx).unwrap(), "149");
+    assert_eq!(report.fields.get(&FixTag::OrdStatusReqID).unwrap(), "REQ1");
+    assert!(report.fields.contains_key(&FixTag::TransactTime));
+}
+
+#[test]
+fn test_order_status_unknown() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::ClOrdID, "ORD9");
+    let report = FixMessage::order_status_unknown(&request);
+    assert_eq!(report.fields.get(&FixTag::OrdStatus).unwrap(), "8");
+    assert_eq!(report.fields.get(&FixTag::ClOrdID).unwrap(), "ORD9");
+    assert_eq!(report.fields.get(&FixTag::Text).unwrap(), "Unknown order");
+}
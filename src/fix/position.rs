@@ -0,0 +1,202 @@
+use super::fixmessage::FixMessage;
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::position::{Position, PositionRequest};
+use crate::price::Price;
+use chrono::Utc;
+
+// PosReqType (724) for positions, the only type supported
+const POSITIONS: &str = "0";
+
+// PosReqResult (728)
+const VALID_REQUEST: &str = "0";
+const NO_POSITIONS_FOUND: &str = "2";
+const REQUEST_NOT_SUPPORTED: &str = "4";
+
+// PosReqStatus (729)
+const COMPLETED: &str = "0";
+const REJECTED: &str = "2";
+
+// PosType (703) for the net position from the day's trades
+const TOTAL_TRANSACTION_QUANTITY: &str = "TOT";
+
+// PosAmtType (707) of the realized and unrealized P&L
+const TRADE_VARIATION: &str = "TVAR";
+const MARK_TO_MARKET: &str = "FMTM";
+
+// SettlPriceType (731) for a mark that is not a final settlement price
+const THEORETICAL: &str = "2";
+
+impl FixMessage {
+    // The positions a RequestForPositions (35=AN) asks for: those of the
+    // sending session, in Account (1) and Symbol (55) when given. None for
+    // any PosReqType but 0.
+    pub fn to_position_request(&self) -> Option<PositionRequest> {
+        if self.fields.get(&FixTag::PosReqType)? != POSITIONS {
+            return None;
+        }
+        Some(PositionRequest {
+            participant: self
+                .fields
+                .get(&FixTag::SenderCompID)
+                .cloned()
+                .unwrap_or_default(),
+            account: self.fields.get(&FixTag::Account).cloned(),
+            symbol: self.fields.get(&FixTag::Symbol).cloned(),
+        })
+    }
+
+    // RequestForPositionsAck (35=AO) accepting a request with the number of
+    // PositionReports that follow, or saying there are none
+    pub fn positions_ack(request: &FixMessage, total_reports: usize) -> FixMessage {
+        let mut ack = FixMessage::positions_header(request, MsgType::RequestForPositionsAck);
+        ack.add_field(FixTag::PosMaintRptID, "NONE");
+        ack.add_field(FixTag::TotalNumPosReports, &total_reports.to_string());
+        let result = match total_reports {
+            0 => NO_POSITIONS_FOUND,
+            _ => VALID_REQUEST,
+        };
+        ack.add_field(FixTag::PosReqResult, result);
+        ack.add_field(FixTag::PosReqStatus, COMPLETED);
+        ack
+    }
+
+    // RequestForPositionsAck (35=AO) rejecting a request
+    pub fn positions_reject(request: &FixMessage, text: &str) -> FixMessage {
+        let mut ack = FixMessage::positions_header(request, MsgType::RequestForPositionsAck);
+        ack.add_field(FixTag::PosMaintRptID, "NONE");
+        ack.add_field(FixTag::TotalNumPosReports, "0");
+        ack.add_field(FixTag::PosReqResult, REQUEST_NOT_SUPPORTED);
+        ack.add_field(FixTag::PosReqStatus, REJECTED);
+        ack.add_field(FixTag::Text, text);
+        ack
+    }
+
+    // PositionReport (35=AP) number `index` of `total_reports`. The net
+    // position goes out as LongQty (704) or ShortQty (705), the realized P&L
+    // as a TVAR amount, and once there is a last trade price to mark at, the
+    // mark as SettlPrice (730), the average cost as PriorSettlPrice (734) and
+    // the unrealized P&L as an FMTM amount.
+    pub fn position_report(
+        request: &FixMessage,
+        position: &Position,
+        mark_price: Option<Price>,
+        index: usize,
+        total_reports: usize,
+    ) -> FixMessage {
+        let mut report = FixMessage::positions_header(request, MsgType::PositionReport);
+        let request_id = request
+            .fields
+            .get(&FixTag::PosReqID)
+            .map_or("POS", String::as_str);
+        report.add_field(
+            FixTag::PosMaintRptID,
+            &format!("{}-{}", request_id, index + 1),
+        );
+        report.add_field(FixTag::TotalNumPosReports, &total_reports.to_string());
+        report.add_field(FixTag::PosReqResult, VALID_REQUEST);
+        report.add_field(
+            FixTag::ClearingBusinessDate,
+            &Utc::now().format("%Y%m%d").to_string(),
+        );
+        report.modify_field(FixTag::Account, &position.account);
+        report.modify_field(FixTag::Symbol, &position.symbol);
+        report.add_group_entry(
+            FixTag::NoPositions,
+            vec![
+                (FixTag::PosType, TOTAL_TRANSACTION_QUANTITY.to_string()),
+                (FixTag::LongQty, position.net_quantity.max(0).to_string()),
+                (
+                    FixTag::ShortQty,
+                    (-position.net_quantity).max(0).to_string(),
+                ),
+            ],
+        );
+        report.add_group_entry(
+            FixTag::NoPosAmt,
+            vec![
+                (FixTag::PosAmtType, TRADE_VARIATION.to_string()),
+                (FixTag::PosAmt, position.realized_pnl().to_string()),
+            ],
+        );
+        if let Some(mark_price) = mark_price {
+            report.add_field(FixTag::SettlPrice, &mark_price.to_string());
+            report.add_field(FixTag::SettlPriceType, THEORETICAL);
+            if let Some(average_cost) = position.average_cost() {
+                report.add_field(FixTag::PriorSettlPrice, &average_cost.to_string());
+            }
+            report.add_group_entry(
+                FixTag::NoPosAmt,
+                vec![
+                    (FixTag::PosAmtType, MARK_TO_MARKET.to_string()),
+                    (
+                        FixTag::PosAmt,
+                        position.unrealized_pnl(mark_price).to_string(),
+                    ),
+                ],
+            );
+        }
+        report
+    }
+
+    fn positions_header(request: &FixMessage, msg_type: MsgType) -> FixMessage {
+        let mut message = FixMessage::new();
+        message.add_field(FixTag::MsgType, &msg_type.to_string());
+        for tag in [
+            FixTag::PosReqID,
+            FixTag::PosReqType,
+            FixTag::Account,
+            FixTag::Symbol,
+        ] {
+            if let Some(value) = request.fields.get(&tag) {
+                message.add_field(tag, value);
+            }
+        }
+        message
+    }
+}
+
+#[test]
+fn test_position_report() {
+    use crate::exchange::position::Positions;
+    use crate::order::{Order, Side};
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::MsgType, "AN");
+    request.add_field(FixTag::PosReqID, "REQ1");
+    request.add_field(FixTag::PosReqType, "0");
+    request.add_field(FixTag::SenderCompID, "FIRM1");
+    assert_eq!(
+        request.to_position_request(),
+        Some(PositionRequest {
+            participant: "FIRM1".to_string(),
+            account: None,
+            symbol: None,
+        })
+    );
+
+    let mut sell = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    sell.participant = "FIRM1".to_string();
+    sell.account = "ACC1".to_string();
+    let mut positions = Positions::new();
+    positions.fill(&sell, Price::from(150), 100);
+    let position = positions.get("FIRM1", "ACC1", "AAPL").unwrap();
+    let report = FixMessage::position_report(&request, position, Some(Price::from(149)), 0, 1);
+    assert_eq!(report.msg_type(), Some(MsgType::PositionReport));
+    assert_eq!(report.fields.get(&FixTag::PosMaintRptID).unwrap(), "REQ1-1");
+    assert_eq!(report.fields.get(&FixTag::Account).unwrap(), "ACC1");
+    assert_eq!(report.fields.get(&FixTag::SettlPrice).unwrap(), "149");
+    assert_eq!(report.fields.get(&FixTag::PriorSettlPrice).unwrap(), "150");
+    let encoded = report.clone().encode();
+    assert!(encoded.contains("702=1|703=TOT|704=0|705=100|"));
+    assert!(encoded.contains("753=2|707=TVAR|708=0|707=FMTM|708=100|"));
+
+    assert_eq!(
+        FixMessage::positions_ack(&request, 0)
+            .fields
+            .get(&FixTag::PosReqResult)
+            .unwrap(),
+        "2"
+    );
+    request.modify_field(FixTag::PosReqType, "1");
+    assert_eq!(request.to_position_request(), None);
+}
@@ -0,0 +1,295 @@
+use super::fixmessage::{FixGroupEntry, FixMessage};
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::quote::{QuoteCancel, QuoteEntry, QuoteSide};
+use crate::exchange::reject::RejectReason;
+use crate::price::Price;
+
+// QuoteCancelType (298) values the exchange supports, answered with the
+// QuoteStatus (297) of the same value
+const CANCEL_FOR_SYMBOLS: &str = "1";
+const CANCEL_ALL_QUOTES: &str = "4";
+
+// QuoteStatus (297) of an accepted or rejected message
+const ACCEPTED: &str = "0";
+const REJECTED: &str = "5";
+
+// QuoteRejectReason (300) and QuoteEntryRejectReason (368) of anything the
+// exchange has no code for
+const OTHER: &str = "99";
+
+// The QuoteEntryRejectReason (368) of an entry rejected for `reason`
+fn quote_entry_reject_reason(reason: &RejectReason) -> &'static str {
+    match reason {
+        RejectReason::UnknownSymbol(_) => "1",
+        RejectReason::TradingPhase(_) => "2",
+        RejectReason::RiskBreach(_) => "3",
+        RejectReason::CrossedQuote { .. } => "7",
+        RejectReason::OffTick { .. } | RejectReason::PriceOutOfBand { .. } => "8",
+        _ => OTHER,
+    }
+}
+
+impl FixMessage {
+    // The quote entries of a MassQuote (35=i) from the sending session, in
+    // the order they came, across every quote set. Each side is quoted with
+    // its price and a size above zero; a side without a price, or quoted for
+    // a size of zero, is pulled. None when a set has no entries, an entry
+    // has no symbol or a price or size does not parse.
+    pub fn to_mass_quote(&self) -> Option<Vec<QuoteEntry>> {
+        let field = |tag| self.fields.get(&tag).cloned().unwrap_or_default();
+        let mut entries = Vec::new();
+        for quote_set in self.groups.get(&FixTag::NoQuoteSets)? {
+            let quote_set_id = quote_set
+                .iter()
+                .find(|(tag, _)| *tag == FixTag::QuoteSetID)
+                .map_or("", |(_, value)| value.as_str());
+            // Each entry starts with its QuoteEntryID
+            let mut fields = quote_set
+                .iter()
+                .skip_while(|(tag, _)| *tag != FixTag::QuoteEntryID)
+                .peekable();
+            let entries_before = entries.len();
+            while let Some((_, quote_entry_id)) = fields.next() {
+                let mut entry: Vec<&(FixTag, String)> = Vec::new();
+                while let Some(next) = fields.next_if(|(tag, _)| *tag != FixTag::QuoteEntryID) {
+                    entry.push(next);
+                }
+                let get = |tag: FixTag| {
+                    entry
+                        .iter()
+                        .find(|(entry_tag, _)| *entry_tag == tag)
+                        .map(|(_, value)| value.as_str())
+                };
+                let quote_side = |price_tag, size_tag| -> Option<Option<QuoteSide>> {
+                    let size = match get(size_tag) {
+                        Some(size) => size.parse::<u32>().ok()?,
+                        None => 0,
+                    };
+                    match get(price_tag) {
+                        Some(price) if size > 0 => Some(Some((price.parse::<Price>().ok()?, size))),
+                        _ => Some(None),
+                    }
+                };
+                entries.push(QuoteEntry {
+                    participant: field(FixTag::SenderCompID),
+                    account: field(FixTag::Account),
+                    quote_set_id: quote_set_id.to_string(),
+                    quote_entry_id: quote_entry_id.to_owned(),
+                    symbol: get(FixTag::Symbol)?.to_string(),
+                    bid: quote_side(FixTag::BidPx, FixTag::BidSize)?,
+                    offer: quote_side(FixTag::OfferPx, FixTag::OfferSize)?,
+                });
+            }
+            if entries.len() == entries_before {
+                return None;
+            }
+        }
+        Some(entries)
+    }
+
+    // The quotes a QuoteCancel (35=Z) pulls: every quote of the sending
+    // session for QuoteCancelType=4, or its quotes in the symbols of the
+    // NoQuoteEntries (295) group for 1. None for any other cancel type, or a
+    // cancel for symbols naming none.
+    pub fn to_quote_cancels(&self) -> Option<Vec<QuoteCancel>> {
+        let participant = self
+            .fields
+            .get(&FixTag::SenderCompID)
+            .cloned()
+            .unwrap_or_default();
+        match self.fields.get(&FixTag::QuoteCancelType)?.as_str() {
+            CANCEL_FOR_SYMBOLS => {
+                let symbols: Vec<&String> = self
+                    .groups
+                    .get(&FixTag::NoQuoteEntries)?
+                    .iter()
+                    .flatten()
+                    .filter(|(tag, _)| *tag == FixTag::Symbol)
+                    .map(|(_, symbol)| symbol)
+                    .collect();
+                if symbols.is_empty() {
+                    return None;
+                }
+                Some(
+                    symbols
+                        .into_iter()
+                        .map(|symbol| QuoteCancel {
+                            participant: participant.to_owned(),
+                            symbol: Some(symbol.to_owned()),
+                        })
+                        .collect(),
+                )
+            }
+            CANCEL_ALL_QUOTES => Some(vec![QuoteCancel {
+                participant,
+                symbol: None,
+            }]),
+            _ => None,
+        }
+    }
+
+    // MassQuoteAcknowledgement (35=b) of a MassQuote, listing every entry by
+    // quote set with the QuoteEntryRejectReason (368) of those rejected
+    pub fn mass_quote_ack(
+        request: &FixMessage,
+        results: &[(QuoteEntry, Result<(), RejectReason>)],
+    ) -> FixMessage {
+        let mut ack = FixMessage::quote_ack_header(request, ACCEPTED);
+        let mut results = results.iter().peekable();
+        while let Some((first, _)) = results.peek() {
+            let quote_set_id = first.quote_set_id.to_owned();
+            let mut quote_entries: FixGroupEntry = Vec::new();
+            let mut count = 0;
+            while let Some((entry, result)) =
+                results.next_if(|(entry, _)| entry.quote_set_id == quote_set_id)
+            {
+                quote_entries.push((FixTag::QuoteEntryID, entry.quote_entry_id.to_owned()));
+                quote_entries.push((FixTag::Symbol, entry.symbol.to_owned()));
+                if let Err(reason) = result {
+                    quote_entries.push((
+                        FixTag::QuoteEntryRejectReason,
+                        quote_entry_reject_reason(reason).to_string(),
+                    ));
+                }
+                count += 1;
+            }
+            let mut quote_set = vec![
+                (FixTag::QuoteSetID, quote_set_id),
+                (FixTag::TotNoQuoteEntries, count.to_string()),
+                (FixTag::NoQuoteEntries, count.to_string()),
+            ];
+            quote_set.extend(quote_entries);
+            ack.add_group_entry(FixTag::NoQuoteSets, quote_set);
+        }
+        ack
+    }
+
+    // MassQuoteAcknowledgement (35=b) of a QuoteCancel, with the QuoteStatus
+    // (297) of the cancel type
+    pub fn quote_cancel_ack(request: &FixMessage) -> FixMessage {
+        let status = request
+            .fields
+            .get(&FixTag::QuoteCancelType)
+            .map_or(CANCEL_ALL_QUOTES, String::as_str);
+        FixMessage::quote_ack_header(request, status)
+    }
+
+    // MassQuoteAcknowledgement (35=b) rejecting a MassQuote or QuoteCancel
+    // as a whole
+    pub fn quote_reject(request: &FixMessage, text: &str) -> FixMessage {
+        let mut ack = FixMessage::quote_ack_header(request, REJECTED);
+        ack.add_field(FixTag::QuoteRejectReason, OTHER);
+        ack.add_field(FixTag::Text, text);
+        ack
+    }
+
+    fn quote_ack_header(request: &FixMessage, status: &str) -> FixMessage {
+        let mut ack = FixMessage::new();
+        ack.add_field(
+            FixTag::MsgType,
+            &MsgType::MassQuoteAcknowledgement.to_string(),
+        );
+        for tag in [FixTag::QuoteID, FixTag::Account] {
+            if let Some(value) = request.fields.get(&tag) {
+                ack.add_field(tag, value);
+            }
+        }
+        ack.add_field(FixTag::QuoteStatus, status);
+        ack
+    }
+}
+
+#[test]
+fn test_to_mass_quote() {
+    let request = FixMessage::decode(
+        "35=i|49=MM1|117=Q1|296=2|302=S1|295=2|299=E1|55=AAPL|132=149|133=151|134=100|135=200|299=E2|55=MSFT|132=300|134=0|133=301|135=50|302=S2|295=1|299=E3|55=GOOGL|\x01",
+        "|",
+    );
+    let entries = request.to_mass_quote().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].participant, "MM1");
+    assert_eq!(
+        (
+            entries[0].quote_set_id.as_str(),
+            entries[0].quote_entry_id.as_str()
+        ),
+        ("S1", "E1")
+    );
+    assert_eq!(entries[0].bid, Some((Price::from(149), 100)));
+    assert_eq!(entries[0].offer, Some((Price::from(151), 200)));
+    // A size of zero pulls the side
+    assert_eq!(entries[1].bid, None);
+    assert_eq!(entries[1].offer, Some((Price::from(301), 50)));
+    assert_eq!(entries[2].quote_set_id, "S2");
+    assert_eq!((entries[2].bid, entries[2].offer), (None, None));
+
+    let request = FixMessage::decode("35=i|49=MM1|296=1|302=S1|295=1|299=E1|132=149|\x01", "|");
+    assert_eq!(request.to_mass_quote(), None);
+    let request = FixMessage::decode("35=i|49=MM1|296=1|302=S1|295=0|\x01", "|");
+    assert_eq!(request.to_mass_quote(), None);
+    assert_eq!(FixMessage::new().to_mass_quote(), None);
+}
+
+#[test]
+fn test_to_quote_cancels() {
+    let request = FixMessage::decode("35=Z|49=MM1|298=1|295=2|55=AAPL|55=MSFT|\x01", "|");
+    let cancels = request.to_quote_cancels().unwrap();
+    assert_eq!(cancels.len(), 2);
+    assert_eq!(cancels[1].participant, "MM1");
+    assert_eq!(cancels[1].symbol, Some("MSFT".to_string()));
+    let request = FixMessage::decode("35=Z|49=MM1|298=4|\x01", "|");
+    assert_eq!(
+        request.to_quote_cancels(),
+        Some(vec![QuoteCancel {
+            participant: "MM1".to_string(),
+            symbol: None,
+        }])
+    );
+    let request = FixMessage::decode("35=Z|49=MM1|298=1|\x01", "|");
+    assert_eq!(request.to_quote_cancels(), None);
+    let request = FixMessage::decode("35=Z|49=MM1|298=3|\x01", "|");
+    assert_eq!(request.to_quote_cancels(), None);
+}
+
+#[test]
+fn test_mass_quote_ack() {
+    let request = FixMessage::decode(
+        "35=i|49=MM1|117=Q1|296=2|302=S1|295=2|299=E1|55=AAPL|132=149|134=100|299=E2|55=TSLA|132=10|134=100|302=S2|295=1|299=E3|55=MSFT|\x01",
+        "|",
+    );
+    let entries = request.to_mass_quote().unwrap();
+    let results: Vec<(QuoteEntry, Result<(), RejectReason>)> = entries
+        .into_iter()
+        .map(|entry| {
+            let result = match entry.symbol.as_str() {
+                "TSLA" => Err(RejectReason::UnknownSymbol("TSLA".to_string())),
+                _ => Ok(()),
+            };
+            (entry, result)
+        })
+        .collect();
+    let mut ack = FixMessage::mass_quote_ack(&request, &results);
+    assert_eq!(ack.msg_type(), Some(MsgType::MassQuoteAcknowledgement));
+    let encoded = ack.encode();
+    assert!(encoded.contains("|117=Q1|"));
+    assert!(encoded.contains("|297=0|"));
+    assert!(encoded.contains(
+        "|296=2|302=S1|304=2|295=2|299=E1|55=AAPL|299=E2|55=TSLA|368=1|302=S2|304=1|295=1|299=E3|55=MSFT|"
+    ));
+
+    let reject = FixMessage::quote_reject(&request, "Unsupported mass quote");
+    assert_eq!(reject.fields.get(&FixTag::QuoteStatus).unwrap(), "5");
+    assert_eq!(reject.fields.get(&FixTag::QuoteRejectReason).unwrap(), "99");
+    let request = FixMessage::decode("35=Z|49=MM1|117=C1|298=4|\x01", "|");
+    let ack = FixMessage::quote_cancel_ack(&request);
+    assert_eq!(ack.fields.get(&FixTag::QuoteID).unwrap(), "C1");
+    assert_eq!(ack.fields.get(&FixTag::QuoteStatus).unwrap(), "4");
+    assert_eq!(
+        quote_entry_reject_reason(&RejectReason::CrossedQuote {
+            bid: Price::from(150),
+            offer: Price::from(149),
+        }),
+        "7"
+    );
+}
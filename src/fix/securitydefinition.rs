@@ -0,0 +1,214 @@
+use super::fixmessage::{FixGroupEntry, FixMessage};
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::instrument::{Instrument, ReferenceData};
+
+impl FixMessage {
+    // Answers a SecurityDefinitionRequest (35=c) with the definition of the
+    // requested symbol, or SecurityResponseType=6 if it is not listed
+    pub fn security_definition(request: &FixMessage, reference_data: &ReferenceData) -> FixMessage {
+        let mut definition = FixMessage::new();
+        definition.add_field(FixTag::MsgType, &MsgType::SecurityDefinition.to_string());
+        copy_request_id(request, &mut definition);
+
+        let symbol = request.fields.get(&FixTag::Symbol);
+        match symbol.and_then(|symbol| reference_data.get_instrument(symbol)) {
+            Some(instrument) => {
+                definition.add_field(FixTag::SecurityResponseType, "4");
+                for (tag, value) in instrument_fields(instrument) {
+                    definition.add_field(tag, &value);
+                }
+                for rule in tick_rules(instrument) {
+                    definition.add_group_entry(FixTag::NoTickRules, rule);
+                }
+            }
+            None => {
+                definition.add_field(FixTag::SecurityResponseType, "6");
+                let symbol = symbol.map_or("", String::as_str);
+                definition.add_field(FixTag::Symbol, symbol);
+                definition.add_field(FixTag::Text, &format!("Unknown symbol {}", symbol));
+            }
+        }
+        definition
+    }
+
+    // Answers a SecurityListRequest (35=x) for a single symbol
+    // (SecurityListRequestType=0) or for every listed instrument (=4)
+    pub fn security_list(request: &FixMessage, reference_data: &ReferenceData) -> FixMessage {
+        let mut list = FixMessage::new();
+        list.add_field(FixTag::MsgType, &MsgType::SecurityList.to_string());
+        copy_request_id(request, &mut list);
+
+        let request_type = request.fields.get(&FixTag::SecurityListRequestType);
+        let instruments: Vec<&Instrument> = match request_type.map(String::as_str) {
+            Some("0") => request
+                .fields
+                .get(&FixTag::Symbol)
+                .and_then(|symbol| reference_data.get_instrument(symbol))
+                .into_iter()
+                .collect(),
+            Some("4") | None => reference_data.get_instruments(),
+            Some(_) => {
+                list.add_field(FixTag::SecurityRequestResult, "1");
+                return list;
+            }
+        };
+
+        let result = if instruments.is_empty() { "2" } else { "0" };
+        list.add_field(FixTag::SecurityRequestResult, result);
+        list.add_field(FixTag::TotNoRelatedSym, &instruments.len().to_string());
+        for instrument in instruments {
+            let mut entry = instrument_fields(instrument);
+            let rules = tick_rules(instrument);
+            entry.push((FixTag::NoTickRules, rules.len().to_string()));
+            entry.extend(rules.into_iter().flatten());
+            list.add_group_entry(FixTag::NoRelatedSym, entry);
+        }
+        list
+    }
+}
+
+fn copy_request_id(request: &FixMessage, response: &mut FixMessage) {
+    if let Some(request_id) = request.fields.get(&FixTag::SecurityReqID) {
+        response.add_field(FixTag::SecurityReqID, request_id);
+        response.add_field(FixTag::SecurityResponseID, request_id);
+    }
+}
+
+fn instrument_fields(instrument: &Instrument) -> FixGroupEntry {
+    let mut fields = vec![
+        (FixTag::Symbol, instrument.symbol.to_owned()),
+        (FixTag::SecurityID, instrument.security_id.to_owned()),
+        (FixTag::Currency, instrument.currency.to_owned()),
+        (FixTag::RoundLot, instrument.lot_size.to_string()),
+        (FixTag::MinTradeVol, instrument.min_quantity.to_string()),
+        (FixTag::MaxTradeVol, instrument.max_quantity.to_string()),
+    ];
+    if let Some((_, tick_size)) = instrument.tick_sizes.first() {
+        fields.push((FixTag::MinPriceIncrement, tick_size.to_string()));
+    }
+    if let Some(low_limit_price) = instrument.low_limit_price {
+        fields.push((FixTag::LowLimitPrice, low_limit_price.to_string()));
+    }
+    if let Some(high_limit_price) = instrument.high_limit_price {
+        fields.push((FixTag::HighLimitPrice, high_limit_price.to_string()));
+    }
+    fields
+}
+
+fn tick_rules(instrument: &Instrument) -> Vec<FixGroupEntry> {
+    instrument
+        .tick_sizes
+        .iter()
+        .enumerate()
+        .map(|(index, (start, tick_size))| {
+            let mut rule = vec![(FixTag::StartTickPriceRange, start.to_string())];
+            if let Some((end, _)) = instrument.tick_sizes.get(index + 1) {
+                rule.push((FixTag::EndTickPriceRange, end.to_string()));
+            }
+            rule.push((FixTag::TickIncrement, tick_size.to_string()));
+            rule
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn test_reference_data() -> ReferenceData {
+    ReferenceData::parse(
+        "[AAPL]
+        security_id = US0378331005
+        lot_size = 100
+        tick_sizes = 0:0.0001, 1:0.01
+        high_limit_price = 1000
+        [MSFT]",
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_security_definition() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::MsgType, "c");
+    request.add_field(FixTag::SecurityReqID, "REQ1");
+    request.add_field(FixTag::Symbol, "AAPL");
+    let definition = FixMessage::security_definition(&request, &test_reference_data());
+    assert_eq!(definition.msg_type(), Some(MsgType::SecurityDefinition));
+    assert_eq!(
+        definition.fields.get(&FixTag::SecurityReqID).unwrap(),
+        "REQ1"
+    );
+    assert_eq!(
+        definition
+            .fields
+            .get(&FixTag::SecurityResponseType)
+            .unwrap(),
+        "4"
+    );
+    assert_eq!(
+        definition.fields.get(&FixTag::SecurityID).unwrap(),
+        "US0378331005"
+    );
+    assert_eq!(definition.fields.get(&FixTag::RoundLot).unwrap(), "100");
+    assert_eq!(
+        definition.fields.get(&FixTag::HighLimitPrice).unwrap(),
+        "1000"
+    );
+    assert_eq!(definition.fields.get(&FixTag::LowLimitPrice), None);
+    let rules = definition.groups.get(&FixTag::NoTickRules).unwrap();
+    assert_eq!(
+        rules[0],
+        vec![
+            (FixTag::StartTickPriceRange, "0".to_string()),
+            (FixTag::EndTickPriceRange, "1".to_string()),
+            (FixTag::TickIncrement, "0.0001".to_string()),
+        ]
+    );
+    assert_eq!(rules[1].len(), 2);
+}
+
+#[test]
+fn test_security_definition_unknown_symbol() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::Symbol, "TSLA");
+    let definition = FixMessage::security_definition(&request, &test_reference_data());
+    assert_eq!(
+        definition
+            .fields
+            .get(&FixTag::SecurityResponseType)
+            .unwrap(),
+        "6"
+    );
+    assert_eq!(
+        definition.fields.get(&FixTag::Text).unwrap(),
+        "Unknown symbol TSLA"
+    );
+}
+
+#[test]
+fn test_security_list() {
+    let mut request = FixMessage::new();
+    request.add_field(FixTag::SecurityListRequestType, "4");
+    let list = FixMessage::security_list(&request, &test_reference_data());
+    assert_eq!(list.msg_type(), Some(MsgType::SecurityList));
+    assert_eq!(
+        list.fields.get(&FixTag::SecurityRequestResult).unwrap(),
+        "0"
+    );
+    assert_eq!(list.fields.get(&FixTag::TotNoRelatedSym).unwrap(), "2");
+    let entries = list.groups.get(&FixTag::NoRelatedSym).unwrap();
+    assert_eq!(entries[0][0], (FixTag::Symbol, "AAPL".to_string()));
+    assert_eq!(entries[1][0], (FixTag::Symbol, "MSFT".to_string()));
+
+    request.add_field(FixTag::SecurityListRequestType, "0");
+    request.add_field(FixTag::Symbol, "MSFT");
+    let list = FixMessage::security_list(&request, &test_reference_data());
+    assert_eq!(list.groups.get(&FixTag::NoRelatedSym).unwrap().len(), 1);
+
+    request.add_field(FixTag::Symbol, "TSLA");
+    let list = FixMessage::security_list(&request, &test_reference_data());
+    assert_eq!(
+        list.fields.get(&FixTag::SecurityRequestResult).unwrap(),
+        "2"
+    );
+    assert_eq!(list.groups.get(&FixTag::NoRelatedSym), None);
+}
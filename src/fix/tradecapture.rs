@@ -0,0 +1,148 @@
+use super::fill::{liquidity_indicator, parse_liquidity_indicator};
+use super::fixmessage::FixMessage;
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::trade::{TradeReport, TradeSide};
+use chrono::NaiveDate;
+
+// PartyRole (452) of the participant that traded
+const EXECUTING_FIRM: &str = "1";
+
+const DATE_FORMAT: &str = "%Y%m%d";
+
+impl FixMessage {
+    // TradeCaptureReport (35=AE) of one side of a trade, in a single NoSides
+    // (552) entry with the side's fee. Both sides carry the trade id as
+    // TrdMatchID (880).
+    pub fn trade_capture_report(report: &TradeReport) -> FixMessage {
+        let mut message = FixMessage::new();
+        message.add_field(FixTag::MsgType, &MsgType::TradeCaptureReport.to_string());
+        message.add_field(
+            FixTag::TradeReportID,
+            &format!("{}-{}", report.trade_id, report.side.side),
+        );
+        message.add_field(FixTag::TrdMatchID, &report.trade_id);
+        message.add_field(FixTag::Symbol, &report.symbol);
+        message.add_field(FixTag::Currency, &report.currency);
+        message.add_field(FixTag::LastQty, &report.quantity.to_string());
+        message.add_field(FixTag::LastPx, &report.price.to_string());
+        message.add_field(
+            FixTag::TradeDate,
+            &report.trade_date.format(DATE_FORMAT).to_string(),
+        );
+        message.add_field(
+            FixTag::SettlDate,
+            &report.settlement_date.format(DATE_FORMAT).to_string(),
+        );
+        let side = &report.side;
+        let mut entry = vec![
+            (FixTag::Side, side.side.to_string()),
+            (FixTag::OrderID, side.order_id.to_string()),
+        ];
+        if !side.cl_ord_id.is_empty() {
+            entry.push((FixTag::ClOrdID, side.cl_ord_id.to_owned()));
+        }
+        if !side.account.is_empty() {
+            entry.push((FixTag::Account, side.account.to_owned()));
+        }
+        entry.extend([
+            (FixTag::LeavesQty, side.leaves_quantity.to_string()),
+            (FixTag::Commission, side.fee.to_string()),
+            (
+                FixTag::LastLiquidityInd,
+                liquidity_indicator(side.liquidity).to_string(),
+            ),
+            (FixTag::NoPartyIDs, "1".to_string()),
+            (FixTag::PartyID, side.participant.to_owned()),
+            (FixTag::PartyRole, EXECUTING_FIRM.to_string()),
+        ]);
+        message.add_group_entry(FixTag::NoSides, entry);
+        message
+    }
+
+    // The side a decoded TradeCaptureReport reports. The single NoSides
+    // entry is decoded into the message's fields.
+    pub fn to_trade_report(&self) -> Option<TradeReport> {
+        let date = |tag| NaiveDate::parse_from_str(self.fields.get(&tag)?, DATE_FORMAT).ok();
+        Some(TradeReport {
+            trade_id: self.fields.get(&FixTag::TrdMatchID)?.to_owned(),
+            symbol: self.fields.get(&FixTag::Symbol)?.to_owned(),
+            currency: self
+                .fields
+                .get(&FixTag::Currency)
+                .cloned()
+                .unwrap_or_default(),
+            price: self.fields.get(&FixTag::LastPx)?.parse().ok()?,
+            quantity: self.fields.get(&FixTag::LastQty)?.parse().ok()?,
+            trade_date: date(FixTag::TradeDate)?,
+            settlement_date: date(FixTag::SettlDate)?,
+            side: TradeSide {
+                side: self.fields.get(&FixTag::Side)?.parse().ok()?,
+                participant: self.fields.get(&FixTag::PartyID)?.to_owned(),
+                account: self
+                    .fields
+                    .get(&FixTag::Account)
+                    .cloned()
+                    .unwrap_or_default(),
+                order_id: self.fields.get(&FixTag::OrderID)?.parse().ok()?,
+                cl_ord_id: self
+                    .fields
+                    .get(&FixTag::ClOrdID)
+                    .cloned()
+                    .unwrap_or_default(),
+                leaves_quantity: self.fields.get(&FixTag::LeavesQty)?.parse().ok()?,
+                liquidity: parse_liquidity_indicator(self.fields.get(&FixTag::LastLiquidityInd)?)?,
+                fee: self.fields.get(&FixTag::Commission)?.parse().ok()?,
+            },
+        })
+    }
+}
+
+#[test]
+fn test_trade_capture_report() {
+    use crate::exchange::fee::Liquidity;
+    use crate::exchange::trade::Trade;
+    use crate::order::Side;
+    let side = |side, participant: &str, order_id| TradeSide {
+        side,
+        participant: participant.to_string(),
+        account: String::new(),
+        order_id,
+        cl_ord_id: String::new(),
+        leaves_quantity: 0,
+        liquidity: Liquidity::Added,
+        fee: "-0.2".parse().unwrap(),
+    };
+    let mut trade = Trade {
+        trade_id: "AAPL-7".to_string(),
+        symbol: "AAPL".to_string(),
+        currency: "USD".to_string(),
+        price: "150.25".parse().unwrap(),
+        quantity: 100,
+        trade_date: NaiveDate::from_ymd_opt(2024, 3, 7).unwrap(),
+        settlement_date: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+        buyer: side(Side::Buy, "FIRM1", 3),
+        seller: side(Side::Sell, "FIRM2", 1),
+    };
+    trade.buyer.account = "ACC1".to_string();
+    trade.buyer.cl_ord_id = "ORD3".to_string();
+
+    let [buy, sell] = trade.reports();
+    let mut message = FixMessage::trade_capture_report(&buy);
+    assert_eq!(message.msg_type(), Some(MsgType::TradeCaptureReport));
+    assert_eq!(
+        message.fields.get(&FixTag::TradeReportID).unwrap(),
+        "AAPL-7-1"
+    );
+    let encoded = message.encode();
+    assert!(encoded.contains("31=150.25|32=100|"));
+    assert!(encoded.contains("75=20240307|"));
+    assert!(encoded
+        .contains("552=1|54=1|37=3|11=ORD3|1=ACC1|151=0|12=-0.2|851=1|453=1|448=FIRM1|452=1|"));
+    let decoded = FixMessage::decode(&encoded, "|");
+    assert_eq!(decoded.to_trade_report(), Some(buy));
+
+    let decoded = FixMessage::decode(&FixMessage::trade_capture_report(&sell).encode(), "|");
+    assert_eq!(decoded.to_trade_report(), Some(sell));
+    assert_eq!(FixMessage::new().to_trade_report(), None);
+}
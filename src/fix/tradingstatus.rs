@@ -0,0 +1,94 @@
+use super::fixmessage::FixMessage;
+use super::fixtag::FixTag;
+use super::fixvalue::MsgType;
+use crate::exchange::phase::TradingPhase;
+
+// TradingSessionID (336) of the regular trading day
+const DAY_SESSION: &str = "1";
+
+impl FixMessage {
+    // SecurityStatus (35=f) announcing the trading phase of one instrument
+    pub fn security_status(symbol: &str, phase: TradingPhase) -> FixMessage {
+        let mut status = FixMessage::new();
+        status.add_field(FixTag::MsgType, &MsgType::SecurityStatus.to_string());
+        status.add_field(FixTag::Symbol, symbol);
+        status.add_field(
+            FixTag::SecurityTradingStatus,
+            security_trading_status(phase),
+        );
+        status.add_field(FixTag::TradingSessionID, DAY_SESSION);
+        status.add_field(FixTag::TradingSessionSubID, trading_session_sub_id(phase));
+        status
+    }
+
+    // TradingSessionStatus (35=h) announcing a phase the whole market is in
+    pub fn trading_session_status(phase: TradingPhase) -> FixMessage {
+        let mut status = FixMessage::new();
+        status.add_field(FixTag::MsgType, &MsgType::TradingSessionStatus.to_string());
+        status.add_field(FixTag::TradingSessionID, DAY_SESSION);
+        status.add_field(FixTag::TradSesStatus, trad_ses_status(phase));
+        status.add_field(FixTag::TradingSessionSubID, trading_session_sub_id(phase));
+        status
+    }
+}
+
+fn security_trading_status(phase: TradingPhase) -> &'static str {
+    match phase {
+        TradingPhase::PreOpen => "21",
+        TradingPhase::OpeningAuction => "22",
+        TradingPhase::Continuous => "17",
+        TradingPhase::ClosingAuction => "24",
+        TradingPhase::VolatilityAuction => "23",
+        TradingPhase::Halted => "2",
+        TradingPhase::Closed => "18",
+    }
+}
+
+fn trad_ses_status(phase: TradingPhase) -> &'static str {
+    match phase {
+        TradingPhase::PreOpen | TradingPhase::OpeningAuction => "4",
+        TradingPhase::Continuous | TradingPhase::VolatilityAuction => "2",
+        TradingPhase::ClosingAuction => "5",
+        TradingPhase::Halted => "1",
+        TradingPhase::Closed => "3",
+    }
+}
+
+fn trading_session_sub_id(phase: TradingPhase) -> &'static str {
+    match phase {
+        TradingPhase::PreOpen => "1",
+        TradingPhase::OpeningAuction => "2",
+        TradingPhase::Continuous => "3",
+        TradingPhase::ClosingAuction => "4",
+        TradingPhase::VolatilityAuction => "6",
+        TradingPhase::Closed => "5",
+        TradingPhase::Halted => "7",
+    }
+}
+
+#[test]
+fn test_security_status() {
+    let status = FixMessage::security_status("AAPL", TradingPhase::Halted);
+    assert_eq!(status.msg_type(), Some(MsgType::SecurityStatus));
+    assert_eq!(status.fields.get(&FixTag::Symbol).unwrap(), "AAPL");
+    assert_eq!(
+        status.fields.get(&FixTag::SecurityTradingStatus).unwrap(),
+        "2"
+    );
+    assert_eq!(
+        status.fields.get(&FixTag::TradingSessionSubID).unwrap(),
+        "7"
+    );
+}
+
+#[test]
+fn test_trading_session_status() {
+    let status = FixMessage::trading_session_status(TradingPhase::ClosingAuction);
+    assert_eq!(status.msg_type(), Some(MsgType::TradingSessionStatus));
+    assert_eq!(status.fields.get(&FixTag::TradingSessionID).unwrap(), "1");
+    assert_eq!(status.fields.get(&FixTag::TradSesStatus).unwrap(), "5");
+    assert_eq!(
+        status.fields.get(&FixTag::TradingSessionSubID).unwrap(),
+        "4"
+    );
+}
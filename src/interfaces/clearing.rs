@@ -0,0 +1,126 @@
+use super::{connector::FixMsgConnector, session::SessionConfig};
+use crate::clearing::clearinghouse::ClearingHouse;
+use crate::fix::{fixmessage::FixMessage, fixtag::FixTag, fixvalue::MsgType};
+use chrono::Utc;
+use std::{collections::VecDeque, sync::Arc};
+use tokio::sync::Mutex;
+
+// TradSesStatus (340) of a closed market
+const CLOSED: &str = "3";
+
+// The node after the exchange: a CCP clearing the trades the exchange
+// reports, which writes the day's settlement obligations once it closes
+pub struct ClearingNode {
+    receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
+    clearing_house: ClearingHouse,
+    obligations_dir: String,
+}
+
+impl ClearingNode {
+    pub fn new(ccp: &str, obligations_dir: &str) -> Self {
+        ClearingNode {
+            receiver_queue: Arc::new(Mutex::new(VecDeque::new())),
+            clearing_house: ClearingHouse::new(ccp),
+            obligations_dir: obligations_dir.to_owned(),
+        }
+    }
+
+    // Clears TradeCaptureReports until the exchange announces the close or
+    // disconnects, then writes the obligations to
+    // `obligations-YYYYMMDD.csv` for the clearing business date
+    pub async fn start(&mut self, address: &str, receiver_port: u16) {
+        if let Err(e) = FixMsgConnector::receiver_thread(
+            address,
+            receiver_port,
+            Arc::clone(&self.receiver_queue),
+            Arc::new(SessionConfig::new()),
+        )
+        .await
+        {
+            log_error!("{}", e);
+            return;
+        }
+
+        while !self.handle_process().await {
+            tokio::task::yield_now().await;
+        }
+        self.end_of_day();
+    }
+
+    // Clears the messages received since the last call, returning whether
+    // the clearing day is over
+    async fn handle_process(&mut self) -> bool {
+        let received_messages = std::mem::take(&mut *self.receiver_queue.lock().await);
+        for message in received_messages {
+            match message.msg_type() {
+                Some(MsgType::TradeCaptureReport) => self.process_trade_report(&message),
+                Some(MsgType::TradingSessionStatus)
+                    if message
+                        .fields
+                        .get(&FixTag::TradSesStatus)
+                        .map(String::as_str)
+                        == Some(CLOSED) =>
+                {
+                    return true;
+                }
+                Some(MsgType::Logout) => {
+                    log_warn!("Exchange disconnected before the close");
+                    return true;
+                }
+                _ => log_debug!("Ignoring message: {:?}", message),
+            }
+        }
+        false
+    }
+
+    fn process_trade_report(&mut self, message: &FixMessage) {
+        let report = match message.to_trade_report() {
+            Some(report) => report,
+            None => {
+                log_error!("Error converting message to trade report");
+                return;
+            }
+        };
+        match self.clearing_house.register(report) {
+            Ok(contracts) => {
+                for contract in contracts {
+                    log_info!(
+                        "Novated trade {}: {} {} {} {} at {} with {}",
+                        contract.trade_id,
+                        contract.participant,
+                        contract.side,
+                        contract.quantity,
+                        contract.symbol,
+                        contract.price,
+                        contract.counterparty
+                    );
+                }
+            }
+            Err(reason) => log_warn!("Rejected trade report: {}", reason),
+        }
+    }
+
+    fn end_of_day(&self) {
+        for report in self.clearing_house.get_pending() {
+            log_warn!(
+                "Trade {} was not cleared, only one side was reported",
+                report.trade_id
+            );
+        }
+        let file_path = format!(
+            "{}/obligations-{}.csv",
+            self.obligations_dir,
+            Utc::now().format("%Y%m%d")
+        );
+        let obligations = self.clearing_house.get_obligations();
+        match obligations.save(&file_path) {
+            Ok(()) => log_info!(
+                "{} wrote {} settlement obligations to {}",
+                self.clearing_house.get_ccp(),
+                obligations.get_obligations().len(),
+                file_path
+            ),
+            Err(e) => log_error!("{}", e),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{receiver::FixMsgReceiver, sender::FixMsgSender};
+use super::{receiver::FixMsgReceiver, sender::FixMsgSender, session::SessionConfig};
 use crate::fix::fixmessage::FixMessage;
 use std::{collections::VecDeque, sync::Arc};
 use tokio::{
@@ -9,11 +9,13 @@ use tokio::{
 pub struct FixMsgConnector {}
 
 impl FixMsgConnector {
+    // Listens on `receiver_port`, failing if it can't be bound
     pub async fn receiver_thread(
         address: &str,
         receiver_port: u16,
         receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
-    ) {
+        sessions: Arc<SessionConfig>,
+    ) -> Result<(), String> {
         let address = address.to_owned();
         match TcpListener::bind(format!("{}:{}", address, receiver_port)).await {
             Ok(receiver) => {
@@ -28,8 +30,12 @@ impl FixMsgConnector {
                                     addr.ip(),
                                     addr.port()
                                 );
-                                FixMsgReceiver::create_receiver(receive_socket, receiver_queue)
-                                    .await;
+                                FixMsgReceiver::create_receiver(
+                                    receive_socket,
+                                    receiver_queue,
+                                    Arc::clone(&sessions),
+                                )
+                                .await;
                             }
                             Err(e) => {
                                 log_error!("Failed to accept: {}", e);
@@ -38,12 +44,10 @@ impl FixMsgConnector {
                         }
                     }
                 });
+                Ok(())
             }
-            Err(e) => {
-                log_error!("Failed to bind to port: {}", e);
-                return;
-            }
-        };
+            Err(e) => Err(format!("Failed to bind to port {}: {}", receiver_port, e)),
+        }
     }
 
     pub async fn sender_thread(
@@ -60,7 +64,6 @@ impl FixMsgConnector {
             }
             Err(e) => {
                 log_warn!("Failed to create sender: {}", e);
-                return;
             }
         }
     }
@@ -71,9 +74,13 @@ impl FixMsgConnector {
         receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
         sender_queue: Arc<Mutex<VecDeque<String>>>,
         sender_port: u16,
+        sessions: Arc<SessionConfig>,
     ) {
-        let receiver = FixMsgConnector::receiver_thread(address, receiver_port, receiver_queue);
+        let receiver =
+            FixMsgConnector::receiver_thread(address, receiver_port, receiver_queue, sessions);
         let sender = FixMsgConnector::sender_thread(address, sender_port, sender_queue);
-        tokio::join!(receiver, sender);
+        if let (Err(e), ()) = tokio::join!(receiver, sender) {
+            log_error!("{}", e);
+        }
     }
 }
@@ -1,6 +1,10 @@
+pub mod clearing;
 pub mod client;
 mod connector;
 mod processor;
 mod receiver;
+mod router;
 mod sender;
 pub mod server;
+pub mod session;
+mod subscriptions;
@@ -1,50 +1,1051 @@
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Instant,
+};
 
 use crate::{
-    exchange::exchange::Exchange,
-    fix::{fixmessage::FixMessage, fixtag::FixTag},
+    exchange::{
+        cancellation::MassCancel,
+        exchange::{Exchange, ExchangeConfig},
+        fee::Fees,
+        journal::{Command, Journal},
+        phase::{PhaseChange, TradingPhase},
+        position::{Position, PositionRequest},
+        quote::{QuoteCancel, QuoteEntry},
+        reject::RejectReason,
+        risk::RiskLimits,
+        shard::ShardPlan,
+        snapshot::Snapshot,
+        trade::{AmendmentType, Trade},
+    },
+    fix::{
+        fixmessage::FixMessage,
+        fixtag::FixTag,
+        fixvalue::{ExecType, MsgType, OrdStatus},
+    },
     order::Order,
+    price::Price,
 };
-use tokio::sync::Mutex;
+
+use super::router::{FixMsgRouter, ShardHandle};
+use super::session::SessionConfig;
+use super::subscriptions::MarketDataSubscriptions;
+use tokio::sync::{oneshot, Mutex};
+
+// Operator requests applied by the processor between batches of messages
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminCommand {
+    SetTradingPhase {
+        symbol: String,
+        phase: TradingPhase,
+    },
+    Halt {
+        symbol: String,
+    },
+    Resume {
+        symbol: String,
+    },
+    BustTrade {
+        trade_id: String,
+    },
+    CorrectTrade {
+        trade_id: String,
+        price: Price,
+        quantity: u32,
+    },
+    SetRiskLimits {
+        participant: String,
+        symbol: String,
+        limits: RiskLimits,
+    },
+}
+
+// A position with the last trade price it is marked at, if any
+pub type MarkedPosition = (Position, Option<Price>);
+
+// A quote entry with whether it was accepted
+pub type QuoteResult = (QuoteEntry, Result<(), RejectReason>);
+
+// What the router hands a shard, in the order it was received
+#[derive(Debug)]
+pub enum ShardMessage {
+    Fix(FixMessage),
+    // This shard's part of a mass cancel across every shard, answered with
+    // the orders it cancelled
+    MassCancel(MassCancel, oneshot::Sender<Vec<Order>>),
+    // A request for positions across every shard, answered with this
+    // shard's positions and the prices they are marked at
+    Positions(PositionRequest, oneshot::Sender<Vec<MarkedPosition>>),
+    // The entries of a MassQuote across shards in this shard's symbols,
+    // answered with whether each was accepted
+    Quotes(
+        FixMessage,
+        Vec<QuoteEntry>,
+        oneshot::Sender<Vec<QuoteResult>>,
+    ),
+    // A QuoteCancel across every shard, answered with the quote legs this
+    // shard pulled
+    QuoteCancels(Vec<QuoteCancel>, oneshot::Sender<Vec<Order>>),
+}
+
+// The phase of every instrument across the shards, so that a phase the whole
+// market moves into is announced once
+#[derive(Debug, Default)]
+pub struct MarketStatus {
+    phases: HashMap<String, TradingPhase>,
+    market_phase: Option<TradingPhase>,
+}
+
+impl MarketStatus {
+    fn add_instruments(&mut self, exchange: &Exchange) {
+        for instrument in exchange.get_reference_data().get_instruments() {
+            self.phases.insert(
+                instrument.symbol.to_owned(),
+                exchange.get_trading_phase(&instrument.symbol),
+            );
+        }
+        self.market_phase = self.get_market_phase();
+    }
+
+    fn get_market_phase(&self) -> Option<TradingPhase> {
+        let mut phases = self.phases.values();
+        let phase = *phases.next()?;
+        phases.all(|other| *other == phase).then_some(phase)
+    }
+
+    // Records `changes`, returning the phase the market has just moved into
+    // as a whole, if any
+    fn apply(&mut self, changes: &[PhaseChange]) -> Option<TradingPhase> {
+        for change in changes {
+            self.phases.insert(change.symbol.to_owned(), change.to);
+        }
+        let phase = self.get_market_phase();
+        let entered = phase.filter(|_| phase != self.market_phase);
+        self.market_phase = phase;
+        entered
+    }
+}
 
 #[derive(Debug)]
 pub struct FixMsgProcessor {}
 
 impl FixMsgProcessor {
+    // Processes the messages routed to one shard. The queues are shared, so
+    // they are only locked to take the batch and to hand over the responses.
     pub async fn handle_process(
-        receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
-        sender_queue: Arc<Mutex<VecDeque<String>>>,
+        exchange: &mut Exchange,
+        subscriptions: &mut MarketDataSubscriptions,
+        sessions: &SessionConfig,
+        shard_queue: &Mutex<VecDeque<ShardMessage>>,
+        sender_queue: &Mutex<VecDeque<String>>,
     ) {
-        let mut received_messages = receiver_queue.lock().await;
-        let mut messages_to_send = sender_queue.lock().await;
-        let mut exchange = Exchange::new();
-        while let Some(mut message) = received_messages.pop_front() {
-            log_info!("Processing message: {:?}", message);
-            let order: Order = match message.to_order() {
-                Some(order) => order,
-                None => {
-                    log_error!("Error converting message to order");
+        let received_messages = std::mem::take(&mut *shard_queue.lock().await);
+        if received_messages.is_empty() {
+            return;
+        }
+        let mut messages_to_send = VecDeque::new();
+        for received in received_messages {
+            let message = match received {
+                ShardMessage::Fix(message) => message,
+                ShardMessage::MassCancel(mass_cancel, reply) => {
+                    if reply.send(exchange.mass_cancel(&mass_cancel)).is_err() {
+                        log_error!("Mass cancel finished after its report");
+                    }
+                    FixMsgProcessor::publish_market_data(
+                        exchange,
+                        subscriptions,
+                        &mut messages_to_send,
+                    );
+                    continue;
+                }
+                ShardMessage::Quotes(request, entries, reply) => {
+                    if reply
+                        .send(FixMsgProcessor::apply_quotes(exchange, entries))
+                        .is_err()
+                    {
+                        log_error!("Mass quote applied after its acknowledgement");
+                    }
+                    FixMsgProcessor::report_cancellations(
+                        exchange,
+                        &request,
+                        &mut messages_to_send,
+                    );
+                    FixMsgProcessor::publish_market_data(
+                        exchange,
+                        subscriptions,
+                        &mut messages_to_send,
+                    );
+                    continue;
+                }
+                ShardMessage::QuoteCancels(cancels, reply) => {
+                    if reply
+                        .send(FixMsgProcessor::cancel_quotes(exchange, &cancels))
+                        .is_err()
+                    {
+                        log_error!("Quote cancel finished after its acknowledgement");
+                    }
+                    FixMsgProcessor::publish_market_data(
+                        exchange,
+                        subscriptions,
+                        &mut messages_to_send,
+                    );
                     continue;
                 }
+                ShardMessage::Positions(request, reply) => {
+                    let positions = FixMsgProcessor::marked_positions(exchange, &request);
+                    if reply.send(positions).is_err() {
+                        log_error!("Positions collected after their report");
+                    }
+                    continue;
+                }
+            };
+            log_info!("Processing message: {:?}", message);
+            match message.msg_type() {
+                Some(MsgType::OrderCancelReplaceRequest) => {
+                    let mut response = FixMsgProcessor::process_replace(exchange, &message);
+                    messages_to_send.push_back(response.encode());
+                    FixMsgProcessor::report_cancellations(
+                        exchange,
+                        &message,
+                        &mut messages_to_send,
+                    );
+                }
+                Some(MsgType::OrderMassCancelRequest) => {
+                    FixMsgProcessor::process_mass_cancel(exchange, &message, &mut messages_to_send);
+                }
+                Some(MsgType::MassQuote) => {
+                    FixMsgProcessor::process_mass_quote(exchange, &message, &mut messages_to_send);
+                }
+                Some(MsgType::QuoteCancel) => {
+                    FixMsgProcessor::process_quote_cancel(
+                        exchange,
+                        &message,
+                        &mut messages_to_send,
+                    );
+                }
+                Some(MsgType::Logout) => {
+                    let session = message
+                        .fields
+                        .get(&FixTag::SenderCompID)
+                        .map_or("CLIENT", String::as_str);
+                    FixMsgProcessor::cancel_on_disconnect(
+                        exchange,
+                        sessions,
+                        session,
+                        &mut messages_to_send,
+                    );
+                }
+                Some(MsgType::RequestForPositions) => {
+                    FixMsgProcessor::process_position_request(
+                        exchange,
+                        &message,
+                        &mut messages_to_send,
+                    );
+                }
+                Some(MsgType::OrderStatusRequest) => {
+                    let mut response = FixMsgProcessor::process_status_request(exchange, &message);
+                    FixMsgProcessor::address_response(&message, &mut response);
+                    messages_to_send.push_back(response.encode());
+                }
+                Some(MsgType::MarketDataRequest) => {
+                    if let Some(mut response) = FixMsgProcessor::process_market_data_request(
+                        exchange,
+                        subscriptions,
+                        &message,
+                    ) {
+                        FixMsgProcessor::address_response(&message, &mut response);
+                        messages_to_send.push_back(response.encode());
+                    }
+                }
+                _ => {
+                    let mut order: Order = match message.to_order() {
+                        Some(order) => order,
+                        None => {
+                            log_error!("Error converting message to order");
+                            continue;
+                        }
+                    };
+                    exchange.assign_order_id(&mut order);
+                    match exchange.execute_order(order.clone()) {
+                        Ok(()) => {
+                            // The order is acknowledged by echoing it back
+                            let mut ack = message.clone();
+                            FixMsgProcessor::address_response(&message, &mut ack);
+                            messages_to_send.push_back(ack.encode());
+                            FixMsgProcessor::report_cancellations(
+                                exchange,
+                                &message,
+                                &mut messages_to_send,
+                            );
+                        }
+                        Err(reason) => {
+                            log_warn!("Rejected order {}: {}", order.id, reason);
+                            let mut report = FixMessage::execution_report(
+                                &order,
+                                ExecType::Rejected,
+                                OrdStatus::Rejected,
+                            );
+                            report.add_field(FixTag::Text, &reason.to_string());
+                            FixMsgProcessor::address_response(&message, &mut report);
+                            messages_to_send.push_back(report.encode());
+                        }
+                    }
+                }
+            }
+            FixMsgProcessor::publish_market_data(exchange, subscriptions, &mut messages_to_send);
+        }
+        sender_queue.lock().await.append(&mut messages_to_send);
+    }
+
+    // Sends the market data updates since the last call to every session
+    // subscribed to their symbols
+    fn publish_market_data(
+        exchange: &mut Exchange,
+        subscriptions: &MarketDataSubscriptions,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        for update in exchange.take_market_data() {
+            for (session, request_id) in subscriptions.get_subscribers(&update.symbol) {
+                let mut refresh = FixMessage::market_data_incremental(&update, request_id);
+                FixMsgProcessor::address_to(session, &mut refresh);
+                messages_to_send.push_back(refresh.encode());
+            }
+        }
+    }
+
+    fn process_replace(exchange: &mut Exchange, message: &FixMessage) -> FixMessage {
+        let Some(replacement) = message.to_order() else {
+            log_error!("Error converting message to order");
+            let mut reject =
+                FixMessage::order_cancel_reject(message, "Error converting message to order");
+            FixMsgProcessor::address_response(message, &mut reject);
+            return reject;
+        };
+        let result = match FixMsgProcessor::resolve_order_id(exchange, message) {
+            Some(order_id) => exchange.replace_order(order_id, replacement),
+            None => Err(RejectReason::UnknownOrder),
+        };
+
+        let mut response = match result {
+            Ok(order) => {
+                let mut report =
+                    FixMessage::execution_report(&order, ExecType::Replaced, OrdStatus::Replaced);
+                if let Some(orig_cl_ord_id) = message.fields.get(&FixTag::OrigClOrdID) {
+                    report.add_field(FixTag::OrigClOrdID, orig_cl_ord_id);
+                }
+                // The order holds what is left open of the new OrderQty
+                if let Some(order_qty) = message.fields.get(&FixTag::OrderQty) {
+                    report.modify_field(FixTag::OrderQty, order_qty);
+                }
+                report
+            }
+            Err(reason) => {
+                log_warn!("Rejected replace request: {}", reason);
+                FixMessage::order_cancel_reject(message, &reason.to_string())
+            }
+        };
+        FixMsgProcessor::address_response(message, &mut response);
+        response
+    }
+
+    // Cancels the orders of the sending session an OrderMassCancelRequest
+    // applies to, answering with an OrderMassCancelReport followed by an
+    // ExecutionReport for each cancelled order
+    fn process_mass_cancel(
+        exchange: &mut Exchange,
+        request: &FixMessage,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        let mass_cancel = match request.to_mass_cancel() {
+            Some(mass_cancel) => mass_cancel,
+            None => {
+                let mut reject =
+                    FixMessage::mass_cancel_reject(request, "0", "Unsupported mass cancel request");
+                FixMsgProcessor::address_response(request, &mut reject);
+                messages_to_send.push_back(reject.encode());
+                return;
+            }
+        };
+        if let Some(symbol) = &mass_cancel.symbol {
+            if exchange
+                .get_reference_data()
+                .get_instrument(symbol)
+                .is_none()
+            {
+                let mut reject = FixMessage::mass_cancel_reject(
+                    request,
+                    "1",
+                    &RejectReason::UnknownSymbol(symbol.to_owned()).to_string(),
+                );
+                FixMsgProcessor::address_response(request, &mut reject);
+                messages_to_send.push_back(reject.encode());
+                return;
+            }
+        }
+
+        let cancelled = exchange.mass_cancel(&mass_cancel);
+        FixMsgProcessor::report_mass_cancel(request, &cancelled, messages_to_send);
+    }
+
+    pub(super) fn report_mass_cancel(
+        request: &FixMessage,
+        cancelled: &[Order],
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        log_info!(
+            "Mass cancel by {} cancelled {} orders",
+            request
+                .fields
+                .get(&FixTag::SenderCompID)
+                .map_or("CLIENT", String::as_str),
+            cancelled.len()
+        );
+        let mut report = FixMessage::mass_cancel_report(request, cancelled.len());
+        FixMsgProcessor::address_response(request, &mut report);
+        messages_to_send.push_back(report.encode());
+        for order in cancelled {
+            let mut report =
+                FixMessage::execution_report(order, ExecType::Canceled, OrdStatus::Canceled);
+            FixMsgProcessor::address_response(request, &mut report);
+            messages_to_send.push_back(report.encode());
+        }
+    }
+
+    // Applies the entries of a MassQuote, each replacing the sender's quote
+    // in its symbol, and answers with a MassQuoteAcknowledgement
+    fn process_mass_quote(
+        exchange: &mut Exchange,
+        request: &FixMessage,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        let Some(entries) = request.to_mass_quote() else {
+            let mut reject = FixMessage::quote_reject(request, "Unsupported mass quote");
+            FixMsgProcessor::address_response(request, &mut reject);
+            messages_to_send.push_back(reject.encode());
+            return;
+        };
+        let results = FixMsgProcessor::apply_quotes(exchange, entries);
+        FixMsgProcessor::report_mass_quote(request, &results, messages_to_send);
+        FixMsgProcessor::report_cancellations(exchange, request, messages_to_send);
+    }
+
+    fn apply_quotes(exchange: &mut Exchange, entries: Vec<QuoteEntry>) -> Vec<QuoteResult> {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let result = exchange.quote(entry.clone());
+                if let Err(reason) = &result {
+                    log_warn!(
+                        "Rejected quote {} of {} in {}: {}",
+                        entry.quote_entry_id,
+                        entry.participant,
+                        entry.symbol,
+                        reason
+                    );
+                }
+                (entry, result)
+            })
+            .collect()
+    }
+
+    pub(super) fn report_mass_quote(
+        request: &FixMessage,
+        results: &[QuoteResult],
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        log_info!(
+            "Mass quote by {} accepted {} of {} quotes",
+            request
+                .fields
+                .get(&FixTag::SenderCompID)
+                .map_or("CLIENT", String::as_str),
+            results.iter().filter(|(_, result)| result.is_ok()).count(),
+            results.len()
+        );
+        let mut ack = FixMessage::mass_quote_ack(request, results);
+        FixMsgProcessor::address_response(request, &mut ack);
+        messages_to_send.push_back(ack.encode());
+    }
+
+    // Pulls the sender's quotes a QuoteCancel applies to, answering with a
+    // MassQuoteAcknowledgement
+    fn process_quote_cancel(
+        exchange: &mut Exchange,
+        request: &FixMessage,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        let Some(cancels) = request.to_quote_cancels() else {
+            let mut reject = FixMessage::quote_reject(request, "Unsupported quote cancel");
+            FixMsgProcessor::address_response(request, &mut reject);
+            messages_to_send.push_back(reject.encode());
+            return;
+        };
+        let cancelled = FixMsgProcessor::cancel_quotes(exchange, &cancels);
+        FixMsgProcessor::report_quote_cancel(request, &cancelled, messages_to_send);
+    }
+
+    fn cancel_quotes(exchange: &mut Exchange, cancels: &[QuoteCancel]) -> Vec<Order> {
+        cancels
+            .iter()
+            .flat_map(|quote_cancel| exchange.cancel_quotes(quote_cancel))
+            .collect()
+    }
+
+    pub(super) fn report_quote_cancel(
+        request: &FixMessage,
+        cancelled: &[Order],
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        log_info!(
+            "Quote cancel by {} pulled {} quote legs",
+            request
+                .fields
+                .get(&FixTag::SenderCompID)
+                .map_or("CLIENT", String::as_str),
+            cancelled.len()
+        );
+        let mut ack = FixMessage::quote_cancel_ack(request);
+        FixMsgProcessor::address_response(request, &mut ack);
+        messages_to_send.push_back(ack.encode());
+    }
+
+    // Pulls the resting orders of a session that logged out or disconnected,
+    // if it asked for cancel on disconnect. The reports go out in case it
+    // reconnects.
+    fn cancel_on_disconnect(
+        exchange: &mut Exchange,
+        sessions: &SessionConfig,
+        session: &str,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        if !sessions.get_settings(session).cancel_on_disconnect {
+            return;
+        }
+        let cancelled = exchange.mass_cancel(&MassCancel {
+            participant: session.to_string(),
+            symbol: None,
+            side: None,
+        });
+        if !cancelled.is_empty() {
+            log_info!(
+                "Cancelled {} orders of disconnected session {}",
+                cancelled.len(),
+                session
+            );
+        }
+        for order in &cancelled {
+            let mut report =
+                FixMessage::execution_report(order, ExecType::Canceled, OrdStatus::Canceled);
+            report.add_field(FixTag::Text, "Cancel on disconnect");
+            FixMsgProcessor::address_broadcast(&mut report);
+            report.modify_field(FixTag::TargetCompID, session);
+            messages_to_send.push_back(report.encode());
+        }
+    }
+
+    // Reports the current state of the order named by OrderID or ClOrdID,
+    // whether it is still on the book or not, to the participant it belongs to
+    fn process_status_request(exchange: &Exchange, request: &FixMessage) -> FixMessage {
+        let participant = request
+            .fields
+            .get(&FixTag::SenderCompID)
+            .map_or("", String::as_str);
+        let order_id = match request.fields.get(&FixTag::OrderID) {
+            Some(order_id) => order_id.parse::<u32>().ok(),
+            None => request
+                .fields
+                .get(&FixTag::ClOrdID)
+                .and_then(|cl_ord_id| exchange.get_order_id(participant, cl_ord_id)),
+        };
+        let record = order_id
+            .and_then(|order_id| exchange.get_participant_order_record(participant, order_id));
+        match record {
+            Some(record) => FixMessage::order_status_report(request, record),
+            None => {
+                log_warn!("Status requested for unknown order");
+                FixMessage::order_status_unknown(request)
+            }
+        }
+    }
+
+    // Answers a RequestForPositions with a RequestForPositionsAck followed by
+    // a PositionReport for each position of the session it asks for
+    fn process_position_request(
+        exchange: &Exchange,
+        request: &FixMessage,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        match request.to_position_request() {
+            Some(position_request) => {
+                let positions = FixMsgProcessor::marked_positions(exchange, &position_request);
+                FixMsgProcessor::report_positions(request, &positions, messages_to_send);
+            }
+            None => {
+                let mut reject =
+                    FixMessage::positions_reject(request, "Unsupported position request");
+                FixMsgProcessor::address_response(request, &mut reject);
+                messages_to_send.push_back(reject.encode());
+            }
+        }
+    }
+
+    // The positions `request` asks for, each with the last trade price of its
+    // symbol to mark it at
+    fn marked_positions(exchange: &Exchange, request: &PositionRequest) -> Vec<MarkedPosition> {
+        exchange
+            .get_positions(
+                &request.participant,
+                request.account.as_deref(),
+                request.symbol.as_deref(),
+            )
+            .into_iter()
+            .map(|position| {
+                (
+                    position.clone(),
+                    exchange.get_last_trade_price(&position.symbol),
+                )
+            })
+            .collect()
+    }
+
+    pub(super) fn report_positions(
+        request: &FixMessage,
+        positions: &[MarkedPosition],
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        let mut ack = FixMessage::positions_ack(request, positions.len());
+        FixMsgProcessor::address_response(request, &mut ack);
+        messages_to_send.push_back(ack.encode());
+        for (index, (position, mark_price)) in positions.iter().enumerate() {
+            let mut report =
+                FixMessage::position_report(request, position, *mark_price, index, positions.len());
+            FixMsgProcessor::address_response(request, &mut report);
+            messages_to_send.push_back(report.encode());
+        }
+    }
+
+    // Answers a MarketDataRequest for a snapshot (SubscriptionRequestType=0)
+    // or a subscription (=1) with a snapshot of the book, whose RptSeq is the
+    // sequence number incremental updates continue from. Unsubscribing (=2)
+    // is not answered.
+    fn process_market_data_request(
+        exchange: &Exchange,
+        subscriptions: &mut MarketDataSubscriptions,
+        request: &FixMessage,
+    ) -> Option<FixMessage> {
+        let session = request
+            .fields
+            .get(&FixTag::SenderCompID)
+            .map_or("CLIENT", String::as_str);
+        let request_id = request
+            .fields
+            .get(&FixTag::MDReqID)
+            .map_or("", String::as_str);
+        let request_type = request
+            .fields
+            .get(&FixTag::SubscriptionRequestType)
+            .map(String::as_str);
+        match request_type {
+            None | Some("0") => Some(FixMsgProcessor::market_data_snapshot(exchange, request)),
+            Some("1") => {
+                let mut snapshot = FixMsgProcessor::market_data_snapshot(exchange, request);
+                if snapshot.msg_type() == Some(MsgType::MarketDataSnapshotFullRefresh) {
+                    let symbol = request.fields.get(&FixTag::Symbol)?;
+                    let sequence = exchange.get_market_data_sequence(symbol);
+                    snapshot.add_field(FixTag::RptSeq, &sequence.to_string());
+                    subscriptions.subscribe(symbol, session, request_id);
+                    log_info!("{} subscribed to {} as {}", session, symbol, request_id);
+                }
+                Some(snapshot)
+            }
+            Some("2") => {
+                if !subscriptions.unsubscribe(session, request_id) {
+                    log_warn!("{} has no subscription {}", session, request_id);
+                }
+                None
+            }
+            Some(request_type) => Some(FixMessage::market_data_request_reject(
+                request,
+                "4",
+                &format!("Unsupported SubscriptionRequestType {}", request_type),
+            )),
+        }
+    }
+
+    // A snapshot of the book: every order for MDBookType=3, otherwise price
+    // levels up to MarketDepth (264), where 0 is the full book and 1 the top
+    // of book
+    fn market_data_snapshot(exchange: &Exchange, request: &FixMessage) -> FixMessage {
+        let symbol = request
+            .fields
+            .get(&FixTag::Symbol)
+            .map_or("", String::as_str);
+        if exchange
+            .get_reference_data()
+            .get_instrument(symbol)
+            .is_none()
+        {
+            return FixMessage::market_data_request_reject(
+                request,
+                "0",
+                &RejectReason::UnknownSymbol(symbol.to_string()).to_string(),
+            );
+        }
+
+        let book_type = request.fields.get(&FixTag::MDBookType).map(String::as_str);
+        let levels = match book_type {
+            Some("3") => {
+                return FixMessage::market_data_order_snapshot(
+                    request,
+                    symbol,
+                    &exchange.get_order_depth(symbol),
+                )
+            }
+            Some("1") => Some(1),
+            Some("2") | None => match request.fields.get(&FixTag::MarketDepth) {
+                Some(depth) => depth.parse::<usize>().ok(),
+                None => Some(0),
+            },
+            Some(_) => None,
+        };
+        match levels {
+            Some(levels) => FixMessage::market_data_snapshot(
+                request,
+                symbol,
+                &exchange.get_depth(symbol, levels),
+            ),
+            None => {
+                FixMessage::market_data_request_reject(request, "5", "Unsupported market depth")
+            }
+        }
+    }
+
+    // Orders cancelled by the exchange while matching are reported to their
+    // owners as Canceled, or as Restated when part of the order is left
+    fn report_cancellations(
+        exchange: &mut Exchange,
+        request: &FixMessage,
+        messages_to_send: &mut VecDeque<String>,
+    ) {
+        for cancellation in exchange.take_cancellations() {
+            log_info!(
+                "Cancelled {} of order {}: {}",
+                cancellation.quantity,
+                cancellation.order.id,
+                cancellation.reason
+            );
+            let mut report = match cancellation.remaining_order() {
+                Some(remaining) => {
+                    FixMessage::execution_report(&remaining, ExecType::Restated, OrdStatus::New)
+                }
+                None => FixMessage::execution_report(
+                    &cancellation.order,
+                    ExecType::Canceled,
+                    OrdStatus::Canceled,
+                ),
             };
-            exchange.execute_order(order);
-            message.modify_field(FixTag::SenderCompID, "SERVER");
-            message.modify_field(FixTag::TargetCompID, "CLIENT");
+            report.add_field(FixTag::Text, &cancellation.reason.to_string());
+            FixMsgProcessor::address_response(request, &mut report);
+            messages_to_send.push_back(report.encode());
+        }
+    }
+
+    // Clients may identify the order by exchange OrderID or by the
+    // OrigClOrdID they gave it
+    fn resolve_order_id(exchange: &Exchange, message: &FixMessage) -> Option<u32> {
+        match message.fields.get(&FixTag::OrderID) {
+            Some(order_id) => order_id.parse::<u32>().ok(),
+            None => {
+                let participant = message
+                    .fields
+                    .get(&FixTag::SenderCompID)
+                    .map_or("", String::as_str);
+                exchange.get_order_id(participant, message.fields.get(&FixTag::OrigClOrdID)?)
+            }
+        }
+    }
+
+    async fn handle_admin(
+        exchange: &mut Exchange,
+        admin_queue: &Mutex<VecDeque<AdminCommand>>,
+    ) -> Vec<PhaseChange> {
+        let mut changes = Vec::new();
+        while let Some(command) = admin_queue.lock().await.pop_front() {
+            let result = match command {
+                AdminCommand::SetTradingPhase { symbol, phase } => {
+                    exchange.set_trading_phase(&symbol, phase)
+                }
+                AdminCommand::Halt { symbol } => exchange.halt(&symbol),
+                AdminCommand::Resume { symbol } => exchange.resume(&symbol),
+                AdminCommand::BustTrade { trade_id } => {
+                    exchange.bust_trade(&trade_id).map(|()| Vec::new())
+                }
+                AdminCommand::CorrectTrade {
+                    trade_id,
+                    price,
+                    quantity,
+                } => exchange
+                    .correct_trade(&trade_id, price, quantity)
+                    .map(|()| Vec::new()),
+                AdminCommand::SetRiskLimits {
+                    participant,
+                    symbol,
+                    limits,
+                } => {
+                    exchange.set_risk_limits(&participant, &symbol, limits);
+                    Ok(Vec::new())
+                }
+            };
+            match result {
+                Ok(applied) => changes.extend(applied),
+                Err(reason) => log_warn!("Rejected admin command: {}", reason),
+            }
+        }
+        changes
+    }
+
+    // Announces each instrument's new phase with a SecurityStatus, and the
+    // market's with a TradingSessionStatus once every instrument, on any
+    // shard, shares it
+    async fn broadcast_phase_changes(
+        changes: Vec<PhaseChange>,
+        market_status: &Mutex<MarketStatus>,
+        sender_queue: &Mutex<VecDeque<String>>,
+    ) {
+        if changes.is_empty() {
+            return;
+        }
+        let market_phase = market_status.lock().await.apply(&changes);
+        let mut messages_to_send = sender_queue.lock().await;
+        for change in changes {
+            log_info!(
+                "{} moved from {} to {}",
+                change.symbol,
+                change.from,
+                change.to
+            );
+            if let Some(equilibrium) = change.equilibrium {
+                log_info!(
+                    "{} uncrossed {} at {}",
+                    change.symbol,
+                    equilibrium.volume,
+                    equilibrium.price
+                );
+            }
+            let mut status = FixMessage::security_status(&change.symbol, change.to);
+            FixMsgProcessor::address_broadcast(&mut status);
+            messages_to_send.push_back(status.encode());
+        }
+        if let Some(phase) = market_phase {
+            let mut status = FixMessage::trading_session_status(phase);
+            FixMsgProcessor::address_broadcast(&mut status);
+            messages_to_send.push_back(status.encode());
+        }
+    }
+
+    // Reports both sides of the trades made since the last call: a fill to
+    // the session of each side, and a trade capture report to the clearing
+    // node, if there is one. Busted and corrected trades are reported to
+    // both sides as well.
+    async fn report_trades(
+        exchange: &mut Exchange,
+        sender_queue: &Mutex<VecDeque<String>>,
+        clearing_queue: Option<&Mutex<VecDeque<String>>>,
+    ) {
+        let trades = exchange.take_trades();
+        let amendments = exchange.take_amendments();
+        if trades.is_empty() && amendments.is_empty() {
+            return;
+        }
+        let mut messages_to_send = sender_queue.lock().await;
+        for trade in &trades {
+            for side in [&trade.buyer, &trade.seller] {
+                let mut report = FixMessage::fill_report(trade, side);
+                FixMsgProcessor::address_to(&side.participant, &mut report);
+                messages_to_send.push_back(report.encode());
+            }
+        }
+        for amendment in &amendments {
+            let trade = &amendment.trade;
+            match amendment.amendment_type {
+                AmendmentType::Bust => log_info!("Busted trade {}", trade.trade_id),
+                AmendmentType::Correction => log_info!(
+                    "Corrected trade {} to {} at {}",
+                    trade.trade_id,
+                    trade.quantity,
+                    trade.price
+                ),
+            }
+            for (side, record) in [
+                (&trade.buyer, &amendment.buy_record),
+                (&trade.seller, &amendment.sell_record),
+            ] {
+                let mut report = FixMessage::trade_amendment_report(amendment, side, record);
+                FixMsgProcessor::address_to(&side.participant, &mut report);
+                messages_to_send.push_back(report.encode());
+            }
+        }
+        drop(messages_to_send);
+
+        let Some(clearing_queue) = clearing_queue else {
+            return;
+        };
+        let mut messages_to_send = clearing_queue.lock().await;
+        for report in trades.iter().flat_map(Trade::reports) {
+            let mut message = FixMessage::trade_capture_report(&report);
+            FixMsgProcessor::address_broadcast(&mut message);
+            message.modify_field(FixTag::TargetCompID, "CLEARING");
             messages_to_send.push_back(message.encode());
         }
     }
 
+    // Addresses `message` to the session of `participant`, or to every
+    // client for an order without one
+    fn address_to(participant: &str, message: &mut FixMessage) {
+        FixMsgProcessor::address_broadcast(message);
+        if !participant.is_empty() {
+            message.modify_field(FixTag::TargetCompID, participant);
+        }
+    }
+
+    // Addresses `message` to every client, for what is not meant for one
+    // session alone
+    pub(super) fn address_broadcast(message: &mut FixMessage) {
+        message.add_field(FixTag::BeginString, "FIX.4.2");
+        message.add_field(FixTag::SenderCompID, "SERVER");
+        message.add_field(FixTag::TargetCompID, "CLIENT");
+    }
+
+    // Addresses `response` back to the session that sent `request`
+    pub(super) fn address_response(request: &FixMessage, response: &mut FixMessage) {
+        if let Some(begin_string) = request.fields.get(&FixTag::BeginString) {
+            response.add_field(FixTag::BeginString, begin_string);
+        }
+        response.add_field(FixTag::SenderCompID, "SERVER");
+        response.add_field(
+            FixTag::TargetCompID,
+            request
+                .fields
+                .get(&FixTag::SenderCompID)
+                .map_or("CLIENT", String::as_str),
+        );
+    }
+
+    // Splits the instruments across up to `shard_count` shards, each with its
+    // own exchange on its own task, and routes messages to them until every
+    // shard's market has closed. Trades are reported on `clearing_queue`.
+    // Returns the fees charged over the day.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_processor(
         receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
         sender_queue: Arc<Mutex<VecDeque<String>>>,
-    ) {
-        tokio::spawn(async move {
-            log_debug!("Created processor thread");
-            loop {
-                let receiver_queue = Arc::clone(&receiver_queue);
-                let sender_queue = Arc::clone(&sender_queue);
-                FixMsgProcessor::handle_process(receiver_queue, sender_queue).await;
+        clearing_queue: Option<Arc<Mutex<VecDeque<String>>>>,
+        admin_queue: Arc<Mutex<VecDeque<AdminCommand>>>,
+        config: ExchangeConfig,
+        sessions: Arc<SessionConfig>,
+        shard_count: usize,
+        journal: Option<Journal>,
+        snapshot: &Snapshot,
+    ) -> Fees {
+        let plan = ShardPlan::new(&config, shard_count);
+        log_info!("Running {} matching shards", plan.len());
+        if let Some(journal) = &journal {
+            if let Err(e) = journal.append(Command::Start { shard_count }) {
+                log_error!("{}", e);
             }
-        });
+        }
+        let market_status = Arc::new(Mutex::new(MarketStatus::default()));
+        // Every shard follows the schedule from the same start
+        let start = Instant::now();
+        let mut shards = Vec::new();
+        let shard_snapshots = snapshot.split(&plan);
+        for (index, shard_config) in plan.get_configs().iter().enumerate() {
+            let mut exchange = Exchange::with_config(shard_config.clone());
+            exchange.restore(&shard_snapshots[index]);
+            if let Some(journal) = &journal {
+                exchange.set_journal(journal.for_shard(index));
+            }
+            market_status.lock().await.add_instruments(&exchange);
+            let shard_queue = Arc::new(Mutex::new(VecDeque::new()));
+            let shard_admin_queue = Arc::new(Mutex::new(VecDeque::new()));
+            let task = tokio::spawn(FixMsgProcessor::run_shard(
+                index,
+                exchange,
+                start,
+                Arc::clone(&shard_queue),
+                Arc::clone(&shard_admin_queue),
+                Arc::clone(&sender_queue),
+                clearing_queue.clone(),
+                Arc::clone(&market_status),
+                Arc::clone(&sessions),
+            ));
+            shards.push(ShardHandle {
+                queue: shard_queue,
+                admin_queue: shard_admin_queue,
+                task,
+            });
+        }
+
+        // Runs until the market closes
+        FixMsgRouter::route(
+            &plan,
+            &config.reference_data,
+            shards,
+            &receiver_queue,
+            &admin_queue,
+            &sender_queue,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_shard(
+        index: usize,
+        mut exchange: Exchange,
+        start: Instant,
+        shard_queue: Arc<Mutex<VecDeque<ShardMessage>>>,
+        admin_queue: Arc<Mutex<VecDeque<AdminCommand>>>,
+        sender_queue: Arc<Mutex<VecDeque<String>>>,
+        clearing_queue: Option<Arc<Mutex<VecDeque<String>>>>,
+        market_status: Arc<Mutex<MarketStatus>>,
+        sessions: Arc<SessionConfig>,
+    ) -> Fees {
+        log_debug!("Created processor thread for shard {}", index);
+        let mut subscriptions = MarketDataSubscriptions::new();
+        while !exchange.is_closed() {
+            let mut changes = exchange.apply_schedule(start.elapsed());
+            changes.extend(FixMsgProcessor::handle_admin(&mut exchange, &admin_queue).await);
+            changes.extend(exchange.take_phase_changes());
+            FixMsgProcessor::broadcast_phase_changes(changes, &market_status, &sender_queue).await;
+            // Trades from auctions uncrossed by the phase changes
+            let mut messages_to_send = VecDeque::new();
+            FixMsgProcessor::publish_market_data(
+                &mut exchange,
+                &subscriptions,
+                &mut messages_to_send,
+            );
+            if !messages_to_send.is_empty() {
+                sender_queue.lock().await.append(&mut messages_to_send);
+            }
+
+            FixMsgProcessor::handle_process(
+                &mut exchange,
+                &mut subscriptions,
+                &sessions,
+                &shard_queue,
+                &sender_queue,
+            )
+            .await;
+
+            // Volatility interruptions triggered by the orders just processed
+            let changes = exchange.take_phase_changes();
+            FixMsgProcessor::broadcast_phase_changes(changes, &market_status, &sender_queue).await;
+            FixMsgProcessor::report_trades(&mut exchange, &sender_queue, clearing_queue.as_deref())
+                .await;
+            tokio::task::yield_now().await;
+        }
+        log_info!("Market closed, stopping shard {}", index);
+        exchange.get_fees().clone()
     }
 }
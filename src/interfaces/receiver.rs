@@ -1,4 +1,7 @@
+use super::session::SessionConfig;
 use crate::fix::fixmessage::FixMessage;
+use crate::fix::fixtag::FixTag;
+use crate::fix::fixvalue::MsgType;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::io::AsyncReadExt;
@@ -12,25 +15,52 @@ impl FixMsgReceiver {
     pub async fn create_receiver(
         receive_socket: Arc<Mutex<TcpStream>>,
         receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
+        sessions: Arc<SessionConfig>,
     ) {
         tokio::spawn(async move {
             log_debug!("Created receiver thread");
             let receiver_queue = Arc::clone(&receiver_queue);
-            FixMsgReceiver::handle_receive(receiver_queue, receive_socket).await;
+            FixMsgReceiver::handle_receive(receiver_queue, receive_socket, sessions).await;
         });
     }
 
+    // Reads messages until the socket closes, fails or the session misses its
+    // heartbeats, then queues a Logout on behalf of the session so that it is
+    // processed after everything the session sent
     pub async fn handle_receive(
         receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
         receive_socket: Arc<Mutex<TcpStream>>,
+        sessions: Arc<SessionConfig>,
     ) {
         let mut buffer = Vec::new();
         let mut stream = receive_socket.lock().await;
+        // Known from the SenderCompID of the first message on the connection
+        let mut session: Option<String> = None;
 
         loop {
             let mut chunk = vec![0u8; 1024];
 
-            match stream.read(&mut chunk).await {
+            let heartbeat_timeout = sessions
+                .get_settings(session.as_deref().unwrap_or_default())
+                .heartbeat_timeout();
+            let read = match heartbeat_timeout {
+                Some(timeout) => {
+                    match tokio::time::timeout(timeout, stream.read(&mut chunk)).await {
+                        Ok(read) => read,
+                        Err(_) => {
+                            log_warn!(
+                                "No heartbeat from {} in {:?}, disconnecting",
+                                session.as_deref().unwrap_or("unknown session"),
+                                timeout
+                            );
+                            break;
+                        }
+                    }
+                }
+                None => stream.read(&mut chunk).await,
+            };
+
+            match read {
                 Ok(bytes_read) => {
                     if bytes_read == 0 {
                         break;
@@ -57,6 +87,9 @@ impl FixMsgReceiver {
                         );
 
                         let decoded_message = FixMessage::decode(&message_str, "|");
+                        if session.is_none() {
+                            session = decoded_message.fields.get(&FixTag::SenderCompID).cloned();
+                        }
 
                         receiver_queue.lock().await.push_back(decoded_message);
                     }
@@ -67,5 +100,14 @@ impl FixMsgReceiver {
                 }
             }
         }
+
+        if let Some(session) = session {
+            log_info!("Session {} disconnected", session);
+            let mut logout = FixMessage::new();
+            logout.add_field(FixTag::MsgType, &MsgType::Logout.to_string());
+            logout.add_field(FixTag::SenderCompID, &session);
+            logout.add_field(FixTag::Text, "Disconnected");
+            receiver_queue.lock().await.push_back(logout);
+        }
     }
 }
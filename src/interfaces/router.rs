@@ -0,0 +1,319 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use tokio::{
+    sync::{oneshot, Mutex},
+    task::JoinHandle,
+};
+
+use super::processor::{AdminCommand, FixMsgProcessor, MarkedPosition, QuoteResult, ShardMessage};
+use crate::config::ANY;
+use crate::exchange::{
+    fee::Fees, instrument::ReferenceData, quote::QuoteEntry, shard::ShardPlan, trade,
+};
+use crate::fix::{fixmessage::FixMessage, fixtag::FixTag, fixvalue::MsgType};
+use crate::order::Order;
+
+// A matching shard as seen from the router: its queues and its task, which
+// ends with the fees charged on the shard
+pub struct ShardHandle {
+    pub queue: Arc<Mutex<VecDeque<ShardMessage>>>,
+    pub admin_queue: Arc<Mutex<VecDeque<AdminCommand>>>,
+    pub task: JoinHandle<Fees>,
+}
+
+// Hands the received messages to the shard trading their symbol. Messages are
+// taken off the receiver queue in order and each shard processes its queue in
+// order, so the messages of a session for one symbol keep their order.
+pub struct FixMsgRouter;
+
+impl FixMsgRouter {
+    // Routes until every shard has stopped, returning the fees charged on
+    // all of them
+    pub async fn route(
+        plan: &ShardPlan,
+        reference_data: &ReferenceData,
+        mut shards: Vec<ShardHandle>,
+        receiver_queue: &Mutex<VecDeque<FixMessage>>,
+        admin_queue: &Mutex<VecDeque<AdminCommand>>,
+        sender_queue: &Arc<Mutex<VecDeque<String>>>,
+    ) -> Fees {
+        log_debug!("Created router thread");
+        while shards.iter().any(|shard| !shard.task.is_finished()) {
+            FixMsgRouter::route_admin(plan, &shards, admin_queue).await;
+            FixMsgRouter::route_messages(
+                plan,
+                reference_data,
+                &shards,
+                receiver_queue,
+                sender_queue,
+            )
+            .await;
+            tokio::task::yield_now().await;
+        }
+        let mut fees = Fees::new();
+        for shard in shards.iter_mut() {
+            match (&mut shard.task).await {
+                Ok(shard_fees) => fees.merge(shard_fees),
+                Err(error) => log_error!("Matching shard failed: {}", error),
+            }
+        }
+        fees
+    }
+
+    async fn route_admin(
+        plan: &ShardPlan,
+        shards: &[ShardHandle],
+        admin_queue: &Mutex<VecDeque<AdminCommand>>,
+    ) {
+        let commands = std::mem::take(&mut *admin_queue.lock().await);
+        for command in commands {
+            let symbol = match &command {
+                AdminCommand::SetTradingPhase { symbol, .. }
+                | AdminCommand::Halt { symbol }
+                | AdminCommand::Resume { symbol }
+                | AdminCommand::SetRiskLimits { symbol, .. } => symbol.as_str(),
+                // A trade is amended on the shard trading its symbol
+                AdminCommand::BustTrade { trade_id }
+                | AdminCommand::CorrectTrade { trade_id, .. } => {
+                    trade::parse_trade_id(trade_id).map_or(trade_id.as_str(), |(symbol, _)| symbol)
+                }
+            };
+            if symbol == ANY {
+                for shard in shards {
+                    shard.admin_queue.lock().await.push_back(command.clone());
+                }
+            } else {
+                let shard = &shards[plan.get_shard(symbol)];
+                shard.admin_queue.lock().await.push_back(command);
+            }
+        }
+    }
+
+    async fn route_messages(
+        plan: &ShardPlan,
+        reference_data: &ReferenceData,
+        shards: &[ShardHandle],
+        receiver_queue: &Mutex<VecDeque<FixMessage>>,
+        sender_queue: &Arc<Mutex<VecDeque<String>>>,
+    ) {
+        let received_messages = std::mem::take(&mut *receiver_queue.lock().await);
+        if received_messages.is_empty() {
+            return;
+        }
+        // Batched per shard so that each shard queue is locked once
+        let mut batches: Vec<VecDeque<ShardMessage>> =
+            shards.iter().map(|_| VecDeque::new()).collect();
+        let mut messages_to_send = VecDeque::new();
+        for message in received_messages {
+            let symbol = message.fields.get(&FixTag::Symbol);
+            match message.msg_type() {
+                // Reference data is answered here, from every instrument
+                Some(MsgType::SecurityDefinitionRequest) => {
+                    let mut response = FixMessage::security_definition(&message, reference_data);
+                    FixMsgProcessor::address_response(&message, &mut response);
+                    messages_to_send.push_back(response.encode());
+                }
+                Some(MsgType::SecurityListRequest) => {
+                    let mut response = FixMessage::security_list(&message, reference_data);
+                    FixMsgProcessor::address_response(&message, &mut response);
+                    messages_to_send.push_back(response.encode());
+                }
+                Some(MsgType::OrderMassCancelRequest) if symbol.is_none() && shards.len() > 1 => {
+                    match message.to_mass_cancel() {
+                        Some(mass_cancel) => {
+                            let mut replies = Vec::new();
+                            for batch in batches.iter_mut() {
+                                let (reply, cancelled) = oneshot::channel();
+                                batch.push_back(ShardMessage::MassCancel(
+                                    mass_cancel.clone(),
+                                    reply,
+                                ));
+                                replies.push(cancelled);
+                            }
+                            tokio::spawn(FixMsgRouter::report_mass_cancel(
+                                message,
+                                replies,
+                                Arc::clone(sender_queue),
+                            ));
+                        }
+                        // Rejected by the shard
+                        None => batches[0].push_back(ShardMessage::Fix(message)),
+                    }
+                }
+                // The entries of a mass quote go to the shards of their
+                // symbols, and are acknowledged together
+                Some(MsgType::MassQuote) if shards.len() > 1 => match message.to_mass_quote() {
+                    Some(entries) => {
+                        let entry_shards: Vec<usize> = entries
+                            .iter()
+                            .map(|entry| plan.get_shard(&entry.symbol))
+                            .collect();
+                        let mut shard_entries: Vec<Vec<QuoteEntry>> =
+                            shards.iter().map(|_| Vec::new()).collect();
+                        for (entry, shard) in entries.into_iter().zip(&entry_shards) {
+                            shard_entries[*shard].push(entry);
+                        }
+                        let mut replies = Vec::new();
+                        for (batch, entries) in batches.iter_mut().zip(shard_entries) {
+                            let (reply, results) = oneshot::channel();
+                            batch.push_back(ShardMessage::Quotes(message.clone(), entries, reply));
+                            replies.push(results);
+                        }
+                        tokio::spawn(FixMsgRouter::report_mass_quote(
+                            message,
+                            entry_shards,
+                            replies,
+                            Arc::clone(sender_queue),
+                        ));
+                    }
+                    // Rejected by the shard
+                    None => batches[0].push_back(ShardMessage::Fix(message)),
+                },
+                Some(MsgType::QuoteCancel) if shards.len() > 1 => {
+                    match message.to_quote_cancels() {
+                        Some(cancels) => {
+                            let mut replies = Vec::new();
+                            for batch in batches.iter_mut() {
+                                let (reply, cancelled) = oneshot::channel();
+                                batch.push_back(ShardMessage::QuoteCancels(cancels.clone(), reply));
+                                replies.push(cancelled);
+                            }
+                            tokio::spawn(FixMsgRouter::report_quote_cancel(
+                                message,
+                                replies,
+                                Arc::clone(sender_queue),
+                            ));
+                        }
+                        // Rejected by the shard
+                        None => batches[0].push_back(ShardMessage::Fix(message)),
+                    }
+                }
+                Some(MsgType::RequestForPositions) if symbol.is_none() && shards.len() > 1 => {
+                    match message.to_position_request() {
+                        Some(request) => {
+                            let mut replies = Vec::new();
+                            for batch in batches.iter_mut() {
+                                let (reply, positions) = oneshot::channel();
+                                batch.push_back(ShardMessage::Positions(request.clone(), reply));
+                                replies.push(positions);
+                            }
+                            tokio::spawn(FixMsgRouter::report_positions(
+                                message,
+                                replies,
+                                Arc::clone(sender_queue),
+                            ));
+                        }
+                        // Rejected by the shard
+                        None => batches[0].push_back(ShardMessage::Fix(message)),
+                    }
+                }
+                // Every shard holds orders of the session, and an
+                // unsubscribe names no symbol
+                Some(MsgType::Logout) | Some(MsgType::MarketDataRequest) if symbol.is_none() => {
+                    for batch in batches.iter_mut() {
+                        batch.push_back(ShardMessage::Fix(message.clone()));
+                    }
+                }
+                _ => {
+                    let shard = symbol.map_or(0, |symbol| plan.get_shard(symbol));
+                    batches[shard].push_back(ShardMessage::Fix(message));
+                }
+            }
+        }
+        for (shard, mut batch) in shards.iter().zip(batches) {
+            if !batch.is_empty() {
+                shard.queue.lock().await.append(&mut batch);
+            }
+        }
+        if !messages_to_send.is_empty() {
+            sender_queue.lock().await.append(&mut messages_to_send);
+        }
+    }
+
+    // Reports a mass cancel across the shards once each has done its part
+    async fn report_mass_cancel(
+        request: FixMessage,
+        replies: Vec<oneshot::Receiver<Vec<Order>>>,
+        sender_queue: Arc<Mutex<VecDeque<String>>>,
+    ) {
+        let mut cancelled = Vec::new();
+        for reply in replies {
+            match reply.await {
+                Ok(orders) => cancelled.extend(orders),
+                Err(_) => log_error!("Matching shard stopped during a mass cancel"),
+            }
+        }
+        let mut messages_to_send = VecDeque::new();
+        FixMsgProcessor::report_mass_cancel(&request, &cancelled, &mut messages_to_send);
+        sender_queue.lock().await.append(&mut messages_to_send);
+    }
+
+    // Acknowledges a mass quote across the shards once each has applied its
+    // entries, listing them in the order they came in
+    async fn report_mass_quote(
+        request: FixMessage,
+        entry_shards: Vec<usize>,
+        replies: Vec<oneshot::Receiver<Vec<QuoteResult>>>,
+        sender_queue: Arc<Mutex<VecDeque<String>>>,
+    ) {
+        let mut shard_results = Vec::new();
+        for reply in replies {
+            let results = match reply.await {
+                Ok(results) => results,
+                Err(_) => {
+                    log_error!("Matching shard stopped during a mass quote");
+                    Vec::new()
+                }
+            };
+            shard_results.push(results.into_iter());
+        }
+        let results: Vec<QuoteResult> = entry_shards
+            .iter()
+            .filter_map(|shard| shard_results[*shard].next())
+            .collect();
+        let mut messages_to_send = VecDeque::new();
+        FixMsgProcessor::report_mass_quote(&request, &results, &mut messages_to_send);
+        sender_queue.lock().await.append(&mut messages_to_send);
+    }
+
+    // Acknowledges a quote cancel across the shards once each has pulled its
+    // quotes
+    async fn report_quote_cancel(
+        request: FixMessage,
+        replies: Vec<oneshot::Receiver<Vec<Order>>>,
+        sender_queue: Arc<Mutex<VecDeque<String>>>,
+    ) {
+        let mut cancelled = Vec::new();
+        for reply in replies {
+            match reply.await {
+                Ok(legs) => cancelled.extend(legs),
+                Err(_) => log_error!("Matching shard stopped during a quote cancel"),
+            }
+        }
+        let mut messages_to_send = VecDeque::new();
+        FixMsgProcessor::report_quote_cancel(&request, &cancelled, &mut messages_to_send);
+        sender_queue.lock().await.append(&mut messages_to_send);
+    }
+
+    // Reports the positions across the shards once each has sent its own, by
+    // account and then symbol
+    async fn report_positions(
+        request: FixMessage,
+        replies: Vec<oneshot::Receiver<Vec<MarkedPosition>>>,
+        sender_queue: Arc<Mutex<VecDeque<String>>>,
+    ) {
+        let mut positions = Vec::new();
+        for reply in replies {
+            match reply.await {
+                Ok(shard_positions) => positions.extend(shard_positions),
+                Err(_) => log_error!("Matching shard stopped during a request for positions"),
+            }
+        }
+        positions.sort_by(|(position, _), (other, _)| {
+            (&position.account, &position.symbol).cmp(&(&other.account, &other.symbol))
+        });
+        let mut messages_to_send = VecDeque::new();
+        FixMsgProcessor::report_positions(&request, &positions, &mut messages_to_send);
+        sender_queue.lock().await.append(&mut messages_to_send);
+    }
+}
@@ -1,20 +1,145 @@
-use super::{connector::FixMsgConnector, processor::FixMsgProcessor};
-use crate::fix::fixmessage::FixMessage;
+use super::{
+    connector::FixMsgConnector,
+    processor::{AdminCommand, FixMsgProcessor},
+    session::SessionConfig,
+};
+use crate::exchange::{
+    exchange::ExchangeConfig, journal::Journal, phase::TradingPhase, risk::RiskLimits,
+    snapshot::Snapshot,
+};
+use crate::fix::{fixmessage::FixMessage, fixtag::FixTag};
+use crate::price::Price;
 use std::{collections::VecDeque, sync::Arc};
 use tokio::sync::Mutex;
 pub struct FixMsgServer {
     receiver_queue: Arc<Mutex<VecDeque<FixMessage>>>,
     sender_queue: Arc<Mutex<VecDeque<String>>>,
+    clearing_queue: Arc<Mutex<VecDeque<String>>>,
+    admin_queue: Arc<Mutex<VecDeque<AdminCommand>>>,
+    config: ExchangeConfig,
+    sessions: Arc<SessionConfig>,
+    shard_count: usize,
+    journal: Option<Journal>,
+    snapshot: Snapshot,
+    // The address and port of the clearing node, if trades are cleared
+    clearing: Option<(String, u16)>,
+    // Where the day's fee summary is written, if anywhere
+    fee_report: Option<String>,
 }
 
 impl FixMsgServer {
-    pub fn new() -> Self {
+    pub fn new(config: ExchangeConfig, sessions: SessionConfig) -> Self {
         FixMsgServer {
             receiver_queue: Arc::new(Mutex::new(VecDeque::new())),
             sender_queue: Arc::new(Mutex::new(VecDeque::new())),
+            clearing_queue: Arc::new(Mutex::new(VecDeque::new())),
+            admin_queue: Arc::new(Mutex::new(VecDeque::new())),
+            config,
+            sessions: Arc::new(sessions),
+            // A matching shard per core by default
+            shard_count: std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            journal: None,
+            snapshot: Snapshot::default(),
+            clearing: None,
+            fee_report: None,
         }
     }
 
+    // Journals every command applied by the matching shards, each run
+    // starting with the number of shards it is split into
+    pub fn set_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    // Starts the matching shards from the books, orders and executions of
+    // `snapshot` rather than empty
+    pub fn set_snapshot(&mut self, snapshot: Snapshot) {
+        self.snapshot = snapshot;
+    }
+
+    // Reports every trade to the clearing node listening on `port`. The
+    // day's trades are handed over once the market has closed.
+    pub fn set_clearing(&mut self, address: &str, port: u16) {
+        self.clearing = Some((address.to_string(), port));
+    }
+
+    // Writes what every participant was charged over the day to `file_path`
+    // once the market has closed
+    pub fn set_fee_report(&mut self, file_path: &str) {
+        self.fee_report = Some(file_path.to_string());
+    }
+
+    // Splits the instruments across at most `shard_count` matching shards
+    pub fn set_shard_count(&mut self, shard_count: usize) {
+        self.shard_count = shard_count.max(1);
+    }
+
+    // Moves `symbol`, or every instrument for '*', into `phase` outside of
+    // the schedule
+    pub async fn set_trading_phase(&self, symbol: &str, phase: TradingPhase) {
+        self.admin_queue
+            .lock()
+            .await
+            .push_back(AdminCommand::SetTradingPhase {
+                symbol: symbol.to_string(),
+                phase,
+            });
+    }
+
+    // Halts `symbol`, or the whole market for '*'
+    pub async fn halt(&self, symbol: &str) {
+        self.admin_queue.lock().await.push_back(AdminCommand::Halt {
+            symbol: symbol.to_string(),
+        });
+    }
+
+    // Resumes `symbol`, or every halted instrument for '*', in the phase it
+    // was halted in
+    pub async fn resume(&self, symbol: &str) {
+        self.admin_queue
+            .lock()
+            .await
+            .push_back(AdminCommand::Resume {
+                symbol: symbol.to_string(),
+            });
+    }
+
+    // Busts trade `trade_id`, the ExecID its fills were reported with
+    pub async fn bust_trade(&self, trade_id: &str) {
+        self.admin_queue
+            .lock()
+            .await
+            .push_back(AdminCommand::BustTrade {
+                trade_id: trade_id.to_string(),
+            });
+    }
+
+    // Corrects trade `trade_id` to `quantity` at `price`
+    pub async fn correct_trade(&self, trade_id: &str, price: Price, quantity: u32) {
+        self.admin_queue
+            .lock()
+            .await
+            .push_back(AdminCommand::CorrectTrade {
+                trade_id: trade_id.to_string(),
+                price,
+                quantity,
+            });
+    }
+
+    // Replaces the risk limits of `participant` in `symbol`, either of which
+    // may be '*'
+    pub async fn set_risk_limits(&self, participant: &str, symbol: &str, limits: RiskLimits) {
+        self.admin_queue
+            .lock()
+            .await
+            .push_back(AdminCommand::SetRiskLimits {
+                participant: participant.to_string(),
+                symbol: symbol.to_string(),
+                limits,
+            });
+    }
+
+    // Returns once the market has closed
     pub async fn start(&self, address: &str, receiver_port: u16) {
         let receiver_queue = Arc::clone(&self.receiver_queue);
         let sender_queue = Arc::clone(&self.sender_queue);
@@ -30,9 +155,46 @@ impl FixMsgServer {
             receiver_queue,
             sender_queue,
             sender_port,
+            Arc::clone(&self.sessions),
         )
         .await;
 
-        FixMsgProcessor::create_processor(processor_receiver_queue, processor_sender_queue).await;
+        let fees = FixMsgProcessor::create_processor(
+            processor_receiver_queue,
+            processor_sender_queue,
+            self.clearing
+                .as_ref()
+                .map(|_| Arc::clone(&self.clearing_queue)),
+            Arc::clone(&self.admin_queue),
+            self.config.clone(),
+            Arc::clone(&self.sessions),
+            self.shard_count,
+            self.journal.clone(),
+            &self.snapshot,
+        )
+        .await;
+        for summary in fees.get_summaries() {
+            log_info!(
+                "{} paid {} in fees on {} fills of {}",
+                summary.participant,
+                summary.total(),
+                summary.fills,
+                summary.quantity
+            );
+        }
+        if let Some(file_path) = &self.fee_report {
+            if let Err(e) = fees.save(file_path) {
+                log_error!("{}", e);
+            }
+        }
+
+        // The close follows the last trade and ends the clearing day
+        if let Some((address, port)) = &self.clearing {
+            let mut status = FixMessage::trading_session_status(TradingPhase::Closed);
+            FixMsgProcessor::address_broadcast(&mut status);
+            status.modify_field(FixTag::TargetCompID, "CLEARING");
+            self.clearing_queue.lock().await.push_back(status.encode());
+            FixMsgConnector::sender_thread(address, *port, Arc::clone(&self.clearing_queue)).await;
+        }
     }
 }
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::{self, ConfigSection, ANY};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionSettings {
+    // Pull the session's resting orders when its connection drops
+    pub cancel_on_disconnect: bool,
+    // How often the session sends at least a heartbeat, if it has to
+    pub heartbeat_interval: Option<Duration>,
+}
+
+impl SessionSettings {
+    // A session that sends nothing for two heartbeat intervals is gone
+    pub fn heartbeat_timeout(&self) -> Option<Duration> {
+        self.heartbeat_interval.map(|interval| interval * 2)
+    }
+}
+
+// Settings of the client sessions, by SenderCompID
+#[derive(Debug, Clone, Default)]
+pub struct SessionConfig {
+    sessions: HashMap<String, SessionSettings>,
+    default: SessionSettings,
+}
+
+impl SessionConfig {
+    pub fn new() -> SessionConfig {
+        SessionConfig::default()
+    }
+
+    pub fn load(file_path: &str) -> Result<SessionConfig, String> {
+        SessionConfig::from_sections(config::read_sections(file_path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<SessionConfig, String> {
+        SessionConfig::from_sections(config::parse_sections(contents)?)
+    }
+
+    // Builds the settings from [SENDERCOMPID] sections, where '*' applies to
+    // every session without one of its own:
+    //
+    //   [BOT1]
+    //   cancel_on_disconnect = true
+    //   heartbeat_seconds = 30
+    fn from_sections(sections: Vec<ConfigSection>) -> Result<SessionConfig, String> {
+        let mut config = SessionConfig::new();
+        for section in sections {
+            let mut settings = SessionSettings::default();
+            for entry in &section.entries {
+                match entry.key.as_str() {
+                    "cancel_on_disconnect" => settings.cancel_on_disconnect = entry.parse()?,
+                    "heartbeat_seconds" => {
+                        let seconds: f64 = entry.parse()?;
+                        if !seconds.is_finite() || seconds <= 0.0 {
+                            return Err(entry.invalid());
+                        }
+                        settings.heartbeat_interval = Some(Duration::from_secs_f64(seconds));
+                    }
+                    _ => return Err(entry.unknown()),
+                }
+            }
+            config.set_settings(&section.name, settings);
+        }
+        Ok(config)
+    }
+
+    pub fn set_settings(&mut self, session: &str, settings: SessionSettings) {
+        if session == ANY {
+            self.default = settings;
+        } else {
+            self.sessions.insert(session.to_string(), settings);
+        }
+    }
+
+    pub fn get_settings(&self, session: &str) -> &SessionSettings {
+        self.sessions.get(session).unwrap_or(&self.default)
+    }
+}
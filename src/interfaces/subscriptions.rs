@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+// Market data subscriptions by symbol. Each is held by a session, the
+// SenderCompID it came from, under the MDReqID it was made with.
+#[derive(Debug, Default)]
+pub struct MarketDataSubscriptions {
+    subscriptions: HashMap<String, Vec<(String, String)>>,
+}
+
+impl MarketDataSubscriptions {
+    pub fn new() -> MarketDataSubscriptions {
+        MarketDataSubscriptions::default()
+    }
+
+    pub fn subscribe(&mut self, symbol: &str, session: &str, request_id: &str) {
+        let subscribers = self.subscriptions.entry(symbol.to_string()).or_default();
+        let subscriber = (session.to_string(), request_id.to_string());
+        if !subscribers.contains(&subscriber) {
+            subscribers.push(subscriber);
+        }
+    }
+
+    // Returns false if the session had no subscription under `request_id`
+    pub fn unsubscribe(&mut self, session: &str, request_id: &str) -> bool {
+        let mut found = false;
+        for subscribers in self.subscriptions.values_mut() {
+            subscribers.retain(|(subscriber, subscribed_id)| {
+                let matches = subscriber == session && subscribed_id == request_id;
+                found |= matches;
+                !matches
+            });
+        }
+        self.subscriptions
+            .retain(|_, subscribers| !subscribers.is_empty());
+        found
+    }
+
+    // (session, MDReqID) of every subscription to `symbol`
+    pub fn get_subscribers(&self, symbol: &str) -> &[(String, String)] {
+        self.subscriptions
+            .get(symbol)
+            .map_or(&[], |subscribers| subscribers.as_slice())
+    }
+}
@@ -0,0 +1,9 @@
+#[macro_use]
+pub mod log;
+pub mod clearing;
+pub mod config;
+pub mod exchange;
+pub mod fix;
+pub mod interfaces;
+pub mod order;
+pub mod price;
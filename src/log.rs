@@ -27,7 +27,7 @@ impl<T: Write> Logger<T> {
 
     pub fn log(&self, level: LogLevel, args: Arguments, module: &'static str) {
         let log_level = get_log_level();
-        let module = module.splitn(2, "::").nth(1).unwrap_or(module);
+        let module = module.split_once("::").map_or(module, |(_, rest)| rest);
 
         if level as u8 >= log_level as u8 {
             let elapsed = self.start_time.elapsed();
@@ -68,27 +68,31 @@ lazy_static::lazy_static! {
     pub static ref LOGGER: Logger<std::io::Stdout> = Logger::new(std::io::stdout());
 }
 
+#[macro_export]
 macro_rules! log_info {
     ($($arg:tt)*) => {
-        crate::log::LOGGER.log(crate::log::LogLevel::Info, format_args!($($arg)*), module_path!())
+        $crate::log::LOGGER.log($crate::log::LogLevel::Info, format_args!($($arg)*), module_path!())
     };
 }
 
+#[macro_export]
 macro_rules! log_warn {
     ($($arg:tt)*) => {
-        crate::log::LOGGER.log(crate::log::LogLevel::Warn, format_args!($($arg)*), module_path!())
+        $crate::log::LOGGER.log($crate::log::LogLevel::Warn, format_args!($($arg)*), module_path!())
     };
 }
 
+#[macro_export]
 macro_rules! log_error {
     ($($arg:tt)*) => {
-        crate::log::LOGGER.log(crate::log::LogLevel::Error, format_args!($($arg)*), module_path!())
+        $crate::log::LOGGER.log($crate::log::LogLevel::Error, format_args!($($arg)*), module_path!())
     };
 }
 
+#[macro_export]
 macro_rules! log_debug {
     ($($arg:tt)*) => {
-        crate::log::LOGGER.log(crate::log::LogLevel::Debug, format_args!($($arg)*), module_path!())
+        $crate::log::LOGGER.log($crate::log::LogLevel::Debug, format_args!($($arg)*), module_path!())
     };
 }
 
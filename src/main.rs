@@ -1,26 +1,35 @@
 #[macro_use]
-mod log;
-mod exchange;
-mod fix;
-mod interfaces;
-mod order;
-use std::sync::Arc;
-
-use interfaces::client::FixMsgClient;
-use interfaces::server::FixMsgServer;
+extern crate rusty_prism;
+use chrono::Utc;
+use rusty_prism::exchange::exchange::ExchangeConfig;
+use rusty_prism::exchange::journal::Journal;
+use rusty_prism::exchange::snapshot::Snapshot;
+use rusty_prism::interfaces::clearing::ClearingNode;
+use rusty_prism::interfaces::client::FixMsgClient;
+use rusty_prism::interfaces::server::FixMsgServer;
+use rusty_prism::interfaces::session::SessionConfig;
 use std::env;
 use tokio::task;
 
-async fn run_server_task(seconds: u64) {
-    let server = Arc::new(FixMsgServer::new());
-    let server_task = tokio::spawn({
-        let server = Arc::clone(&server);
-        async move {
-            server.start("127.0.0.1", 8080).await;
-        }
-    });
-    tokio::time::sleep(tokio::time::Duration::from_secs(seconds)).await;
-    drop(server_task);
+// Runs until every instrument has closed according to the schedule
+async fn run_server_task(
+    config: ExchangeConfig,
+    sessions: SessionConfig,
+    journal: Journal,
+    snapshot: Snapshot,
+) {
+    let mut server = FixMsgServer::new(config, sessions);
+    server.set_journal(journal);
+    server.set_snapshot(snapshot);
+    server.set_clearing("127.0.0.1", 8082);
+    server.set_fee_report(&format!("./fees-{}.csv", Utc::now().format("%Y%m%d")));
+    server.start("127.0.0.1", 8080).await;
+}
+
+// Runs until the exchange has handed over the day's trades
+async fn run_clearing_task() {
+    let mut clearing = ClearingNode::new("CCP", ".");
+    clearing.start("127.0.0.1", 8082).await;
 }
 
 async fn run_client_task(messages_file: &str, server_receiver_port: u16) {
@@ -32,7 +41,57 @@ async fn run_client_task(messages_file: &str, server_receiver_port: u16) {
 async fn main() {
     env::set_var("APP_LOGLEVEL", "debug");
 
-    let server_task = task::spawn(run_server_task(11));
+    let config = match ExchangeConfig::load(
+        "./instruments.cfg",
+        "./risk.cfg",
+        "./schedule.cfg",
+        "./fees.cfg",
+    ) {
+        Ok(config) => config,
+        Err(e) => {
+            log_error!("{}", e);
+            return;
+        }
+    };
+
+    let sessions = match SessionConfig::load("./sessions.cfg") {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            log_error!("Failed to load sessions: {}", e);
+            return;
+        }
+    };
+
+    // Picks up where the last run left off. The recovered state is saved
+    // straight away, so the next start only replays the journal from here.
+    let snapshot = match Snapshot::recover("./snapshot.dat", "./journal.log", &config) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            log_error!("Failed to recover the last run: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = snapshot.save("./snapshot.dat") {
+        log_error!("{}", e);
+        return;
+    }
+
+    // Every order, cancel, amendment and phase change, for `replay`
+    let journal = match Journal::open("./journal.log") {
+        Ok(journal) => journal,
+        Err(e) => {
+            log_error!("Failed to open journal: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = journal.continue_after(snapshot.sequence) {
+        log_error!("{}", e);
+        return;
+    }
+
+    let clearing_task = task::spawn(run_clearing_task());
+
+    let server_task = task::spawn(run_server_task(config, sessions, journal, snapshot));
 
     let client1_task = task::spawn(run_client_task("./messages.txt", 8080));
 
@@ -40,7 +99,13 @@ async fn main() {
 
     let client3_task = task::spawn(run_client_task("./messages2.txt", 8080));
 
-    match tokio::try_join!(server_task, client1_task, client2_task, client3_task,) {
+    match tokio::try_join!(
+        clearing_task,
+        server_task,
+        client1_task,
+        client2_task,
+        client3_task,
+    ) {
         Ok(_) => log_debug!("All tasks completed successfully"),
         Err(e) => log_error!("Error: {}", e),
     };
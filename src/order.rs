@@ -1,12 +1,37 @@
+use std::fmt::Display;
 use std::hash::Hash;
+use std::str::FromStr;
 use std::sync::atomic::AtomicU32;
 
+use crate::price::Price;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Side {
     Buy = 1,
     Sell = 2,
 }
 
+impl FromStr for Side {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Side::Buy),
+            "2" => Ok(Side::Sell),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Buy => write!(f, "1"),
+            Side::Sell => write!(f, "2"),
+        }
+    }
+}
+
 #[test]
 fn test_side_eq() {
     let buy_side = Side::Buy;
@@ -23,27 +48,261 @@ fn test_side_clone() {
     assert_eq!(sell_side.clone(), Side::Sell);
 }
 
+#[test]
+fn test_side_from_str() {
+    assert_eq!("1".parse::<Side>(), Ok(Side::Buy));
+    assert_eq!("2".parse::<Side>(), Ok(Side::Sell));
+    assert!("3".parse::<Side>().is_err());
+    assert_eq!(Side::Sell.to_string().parse::<Side>(), Ok(Side::Sell));
+}
+
+// What happens when an order would trade against another order from the same
+// participant or STP group, mapped from SelfMatchPreventionInstruction (2964)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTradePrevention {
+    CancelNewest,
+    CancelOldest,
+    CancelBoth,
+    DecrementAndCancel,
+}
+
+impl FromStr for SelfTradePrevention {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(SelfTradePrevention::CancelNewest),
+            "2" => Ok(SelfTradePrevention::CancelOldest),
+            "3" => Ok(SelfTradePrevention::CancelBoth),
+            "4" => Ok(SelfTradePrevention::DecrementAndCancel),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for SelfTradePrevention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SelfTradePrevention::CancelNewest => "1",
+            SelfTradePrevention::CancelOldest => "2",
+            SelfTradePrevention::CancelBoth => "3",
+            SelfTradePrevention::DecrementAndCancel => "4",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// The price a pegged order follows, from PegPriceType (1094)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PegType {
+    // The best price on the order's own side
+    Primary,
+    // The best price on the opposite side
+    Market,
+    // Halfway between the best bid and offer
+    Midpoint,
+}
+
+impl FromStr for PegType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2" => Ok(PegType::Midpoint),
+            "4" => Ok(PegType::Market),
+            "5" => Ok(PegType::Primary),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for PegType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PegType::Midpoint => "2",
+            PegType::Market => "4",
+            PegType::Primary => "5",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// A price derived from the best bid and offer, moved by `offset`
+// (PegOffsetValue, 211) and capped at `limit` if the order has a price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Peg {
+    pub peg_type: PegType,
+    pub offset: Price,
+    pub limit: Option<Price>,
+}
+
+impl Peg {
+    // The best bid, offer or midpoint a `side` order with this peg follows.
+    // A midpoint off the instrument's tick grid, with `tick_size` giving the
+    // tick at a price, is rounded to the tick away from the other side. There
+    // is no reference while the touch it needs is missing.
+    pub fn reference(
+        &self,
+        side: &Side,
+        bid: Option<Price>,
+        ask: Option<Price>,
+        tick_size: impl Fn(Price) -> Price,
+    ) -> Option<Price> {
+        match (self.peg_type, side) {
+            (PegType::Primary, Side::Buy) | (PegType::Market, Side::Sell) => bid,
+            (PegType::Primary, Side::Sell) | (PegType::Market, Side::Buy) => ask,
+            (PegType::Midpoint, _) => {
+                let sum = bid?.raw() as i128 + ask?.raw() as i128;
+                let midpoint = Price::from_raw(sum.div_euclid(2) as i64);
+                let tick = tick_size(midpoint).raw().max(1) as i128;
+                let ticks = match side {
+                    Side::Buy => sum.div_euclid(2 * tick),
+                    Side::Sell => -(-sum).div_euclid(2 * tick),
+                };
+                i64::try_from(ticks * tick).ok().map(Price::from_raw)
+            }
+        }
+    }
+
+    // The price of a `side` order with this peg, given the best bid and
+    // offer. There is no price while the reference is missing, or when the
+    // offset takes it out of range.
+    pub fn price(
+        &self,
+        side: &Side,
+        bid: Option<Price>,
+        ask: Option<Price>,
+        tick_size: impl Fn(Price) -> Price,
+    ) -> Option<Price> {
+        self.price_at(side, self.reference(side, bid, ask, tick_size)?)
+    }
+
+    // The price of a `side` order with this peg, offset from `reference`
+    pub fn price_at(&self, side: &Side, reference: Price) -> Option<Price> {
+        let price = reference.checked_add(self.offset)?;
+        let price = match (self.limit, side) {
+            (Some(limit), Side::Buy) => price.min(limit),
+            (Some(limit), Side::Sell) => price.max(limit),
+            (None, _) => price,
+        };
+        (price > Price::ZERO).then_some(price)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Order {
     pub id: u32,
     pub symbol: String,
     pub quantity: u32,
-    pub price: f64,
+    pub price: Price,
     pub side: Side,
+    pub sequence: u32,
+    pub cl_ord_id: String,
+    pub participant: String,
+    // The participant's account the order is for, empty for none
+    pub account: String,
+    pub stp_group: String,
+    pub self_trade_prevention: Option<SelfTradePrevention>,
+    // Set for pegged orders, whose price is kept at the peg by the exchange
+    pub peg: Option<Peg>,
+    // Hidden orders rest and trade like any other, but are left out of the
+    // depth and market data, and queue behind the displayed orders at their
+    // price
+    pub hidden: bool,
+    // The least the order will trade at once, 0 for no minimum
+    pub min_quantity: u32,
+    // All-or-none orders only trade when they can be filled completely
+    pub all_or_none: bool,
 }
 
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
 impl Order {
-    pub fn new(symbol: &str, quantity: u32, price: f64, side: Side) -> Order {
-        static NEXT_ID: AtomicU32 = AtomicU32::new(0);
-        let id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    pub fn new(symbol: &str, quantity: u32, price: Price, side: Side) -> Order {
+        let id = Order::next_sequence();
         Order {
             id,
             symbol: symbol.to_string(),
             quantity,
             price,
             side,
+            sequence: id,
+            cl_ord_id: String::new(),
+            participant: String::new(),
+            account: String::new(),
+            stp_group: String::new(),
+            self_trade_prevention: None,
+            peg: None,
+            hidden: false,
+            min_quantity: 0,
+            all_or_none: false,
+        }
+    }
+
+    // Orders are checked for self-trades by STP group, or by participant when
+    // no group is given
+    pub fn stp_key(&self) -> &str {
+        if self.stp_group.is_empty() {
+            &self.participant
+        } else {
+            &self.stp_group
         }
     }
+
+    // The smallest fill the order takes: all of it for all-or-none, otherwise
+    // its minimum quantity, up to what is left
+    pub fn minimum_fill(&self) -> u32 {
+        if self.all_or_none {
+            self.quantity
+        } else {
+            self.min_quantity.min(self.quantity)
+        }
+    }
+
+    // Time priority is drawn from the same counter as order ids, so an order
+    // that loses priority queues behind every order created before it
+    pub fn next_sequence() -> u32 {
+        NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+// The order ids and time priorities handed out by one exchange: `next`, then
+// every `step` after it, so exchanges numbered 1 to `step` never share an id.
+// Unlike the process-wide counter behind `Order::new`, the same orders always
+// get the same ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderIds {
+    next: u32,
+    step: u32,
+}
+
+impl OrderIds {
+    pub fn new(first: u32, step: u32) -> OrderIds {
+        OrderIds {
+            next: first,
+            step: step.max(1),
+        }
+    }
+
+    pub fn next_id(&mut self) -> u32 {
+        let id = self.next;
+        self.next += self.step;
+        id
+    }
+
+    // Moves past `id`, so orders numbered elsewhere keep their priority
+    // over the ones numbered after them
+    pub fn skip_past(&mut self, id: u32) {
+        if id >= self.next {
+            self.next += ((id - self.next) / self.step + 1) * self.step;
+        }
+    }
+}
+
+impl Default for OrderIds {
+    fn default() -> Self {
+        OrderIds::new(1, 1)
+    }
 }
 
 impl Eq for Order {}
@@ -62,21 +321,12 @@ impl PartialOrd for Order {
 
 impl Ord for Order {
     fn cmp(&self, other: &Order) -> std::cmp::Ordering {
-        if self.side == Side::Buy {
-            match other.price.partial_cmp(&self.price) {
-                Some(std::cmp::Ordering::Equal) => self.id.cmp(&other.id),
-                Some(std::cmp::Ordering::Greater) => std::cmp::Ordering::Greater,
-                Some(std::cmp::Ordering::Less) => std::cmp::Ordering::Less,
-                None => std::cmp::Ordering::Less,
-            }
+        let price_priority = if self.side == Side::Buy {
+            other.price.cmp(&self.price)
         } else {
-            match self.price.partial_cmp(&other.price) {
-                Some(std::cmp::Ordering::Equal) => self.id.cmp(&other.id),
-                Some(std::cmp::Ordering::Greater) => std::cmp::Ordering::Greater,
-                Some(std::cmp::Ordering::Less) => std::cmp::Ordering::Less,
-                None => std::cmp::Ordering::Less,
-            }
-        }
+            self.price.cmp(&other.price)
+        };
+        price_priority.then_with(|| self.sequence.cmp(&other.sequence))
     }
 }
 
@@ -88,16 +338,124 @@ impl Hash for Order {
 
 #[test]
 fn test_order_cmp() {
-    let order1 = Order::new("AAPL", 100, 100.0, Side::Buy);
-    let order2 = Order::new("AAPL", 100, 150.0, Side::Sell);
-    let order3 = Order::new("AAPL", 100, 200.0, Side::Sell);
-    let order4 = Order::new("AAPL", 100, 200.0, Side::Buy);
+    let order1 = Order::new("AAPL", 100, Price::from(100), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Sell);
+    let order3 = Order::new("AAPL", 100, Price::from(200), Side::Sell);
+    let order4 = Order::new("AAPL", 100, Price::from(200), Side::Buy);
     assert_eq!(order1.cmp(&order4), std::cmp::Ordering::Greater);
     assert_eq!(order3.cmp(&order2), std::cmp::Ordering::Greater);
 }
 
 #[test]
 fn test_order_clone() {
-    let order1 = Order::new("AAPL", 100, 150.0, Side::Buy);
+    let order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
     assert_eq!(order1.clone(), order1);
 }
+
+#[test]
+fn test_order_sequence_priority() {
+    let mut order1 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    let order2 = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    assert_eq!(order1.cmp(&order2), std::cmp::Ordering::Less);
+    order1.sequence = Order::next_sequence();
+    assert_eq!(order1.cmp(&order2), std::cmp::Ordering::Greater);
+}
+
+#[test]
+fn test_order_stp_key() {
+    let mut order = Order::new("AAPL", 100, Price::from(150), Side::Buy);
+    order.participant = "FIRM1".to_string();
+    assert_eq!(order.stp_key(), "FIRM1");
+    order.stp_group = "DESK1".to_string();
+    assert_eq!(order.stp_key(), "DESK1");
+    assert_eq!(
+        "4".parse::<SelfTradePrevention>(),
+        Ok(SelfTradePrevention::DecrementAndCancel)
+    );
+    assert!("5".parse::<SelfTradePrevention>().is_err());
+    assert_eq!(
+        SelfTradePrevention::CancelBoth.to_string().parse(),
+        Ok(SelfTradePrevention::CancelBoth)
+    );
+}
+
+#[test]
+fn test_order_ids() {
+    let mut ids = OrderIds::new(2, 3);
+    assert_eq!(ids.next_id(), 2);
+    assert_eq!(ids.next_id(), 5);
+    ids.skip_past(9);
+    assert_eq!(ids.next_id(), 11);
+    ids.skip_past(4);
+    assert_eq!(ids.next_id(), 14);
+    let mut ids = OrderIds::default();
+    ids.skip_past(0);
+    assert_eq!(ids.next_id(), 1);
+}
+
+#[test]
+fn test_peg_price() {
+    let (bid, ask) = (Some(Price::from(100)), Some(Price::from(101)));
+    let raw_tick = |_| Price::from_raw(1);
+    let peg = |peg_type, offset: &str| Peg {
+        peg_type,
+        offset: offset.parse().unwrap(),
+        limit: None,
+    };
+    let primary = peg(PegType::Primary, "-0.01");
+    assert_eq!(
+        primary.price(&Side::Buy, bid, ask, raw_tick),
+        Some("99.99".parse().unwrap())
+    );
+    assert_eq!(
+        primary.price(&Side::Sell, bid, ask, raw_tick),
+        Some("100.99".parse().unwrap())
+    );
+    let market = peg(PegType::Market, "0");
+    assert_eq!(market.price(&Side::Buy, bid, ask, raw_tick), ask);
+    assert_eq!(market.price(&Side::Buy, bid, None, raw_tick), None);
+    let midpoint = peg(PegType::Midpoint, "0");
+    assert_eq!(
+        midpoint.price(&Side::Sell, bid, ask, raw_tick),
+        Some("100.5".parse().unwrap())
+    );
+    assert_eq!(midpoint.price(&Side::Buy, bid, None, raw_tick), None);
+    let odd = Some(Price::from_raw(101_000_001));
+    assert_eq!(
+        midpoint.price(&Side::Buy, bid, odd, raw_tick),
+        Some(Price::from_raw(100_500_000))
+    );
+    assert_eq!(
+        midpoint.price(&Side::Sell, bid, odd, raw_tick),
+        Some(Price::from_raw(100_500_001))
+    );
+
+    // On a coarser tick, an odd number of ticks apart rounds the midpoint
+    // down for a buy and up for a sell
+    let cent = |_| "0.01".parse().unwrap();
+    let wide_ask = Some("100.03".parse().unwrap());
+    assert_eq!(
+        midpoint.price(&Side::Buy, bid, wide_ask, cent),
+        Some("100.01".parse().unwrap())
+    );
+    assert_eq!(
+        midpoint.price(&Side::Sell, bid, wide_ask, cent),
+        Some("100.02".parse().unwrap())
+    );
+
+    let capped = Peg {
+        limit: Some(Price::from(100)),
+        ..market
+    };
+    assert_eq!(
+        capped.price(&Side::Buy, bid, ask, raw_tick),
+        Some(Price::from(100))
+    );
+    let wide = peg(PegType::Primary, "9223372036854");
+    assert_eq!(wide.price(&Side::Buy, bid, ask, raw_tick), None);
+    let top = Some(Price::from_raw(i64::MAX));
+    assert_eq!(midpoint.price(&Side::Sell, top, top, raw_tick), top);
+    assert_eq!("2".parse::<PegType>(), Ok(PegType::Midpoint));
+    assert_eq!(PegType::Primary.to_string().parse(), Ok(PegType::Primary));
+    assert!("3".parse::<PegType>().is_err());
+}
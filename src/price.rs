@@ -0,0 +1,177 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+pub const PRICE_DECIMALS: u32 = 6;
+const PRICE_SCALE: i64 = 10i64.pow(PRICE_DECIMALS);
+
+// Fixed-point price stored as an integer number of 10^-PRICE_DECIMALS units,
+// so comparisons and matching never see floating point drift
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Price(i64);
+
+impl Price {
+    pub const ZERO: Price = Price(0);
+
+    pub fn from_raw(raw: i64) -> Price {
+        Price(raw)
+    }
+
+    pub fn raw(&self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_mul(&self, quantity: u32) -> Option<Price> {
+        self.0.checked_mul(quantity as i64).map(Price)
+    }
+
+    pub fn checked_add(&self, other: Price) -> Option<Price> {
+        self.0.checked_add(other.0).map(Price)
+    }
+
+    pub fn checked_sub(&self, other: Price) -> Option<Price> {
+        self.0.checked_sub(other.0).map(Price)
+    }
+
+    pub fn is_on_tick(&self, tick_size: Price) -> bool {
+        tick_size.0 > 0 && self.0 % tick_size.0 == 0
+    }
+}
+
+// The operators saturate at the ends of the range rather than wrap. Where
+// an out of range price has to be caught, use the checked methods instead.
+impl From<i64> for Price {
+    fn from(units: i64) -> Self {
+        Price(units.saturating_mul(PRICE_SCALE))
+    }
+}
+
+impl Add for Price {
+    type Output = Price;
+    fn add(self, other: Price) -> Price {
+        Price(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+    fn sub(self, other: Price) -> Price {
+        Price(self.0.saturating_sub(other.0))
+    }
+}
+
+impl FromStr for Price {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(());
+        }
+        if !whole.bytes().all(|byte| byte.is_ascii_digit())
+            || !fraction.bytes().all(|byte| byte.is_ascii_digit())
+        {
+            return Err(());
+        }
+
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > PRICE_DECIMALS as usize {
+            return Err(());
+        }
+
+        let whole = match whole {
+            "" => 0,
+            whole => whole.parse::<i64>().map_err(|_| ())?,
+        };
+        let fraction = match fraction {
+            "" => 0,
+            digits => {
+                digits.parse::<i64>().map_err(|_| ())?
+                    * 10i64.pow(PRICE_DECIMALS - digits.len() as u32)
+            }
+        };
+        let raw = whole
+            .checked_mul(PRICE_SCALE)
+            .and_then(|raw| raw.checked_add(fraction))
+            .ok_or(())?;
+
+        Ok(Price(if negative { -raw } else { raw }))
+    }
+}
+
+impl Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let units = self.0.unsigned_abs();
+        let whole = units / PRICE_SCALE as u64;
+        let fraction = units % PRICE_SCALE as u64;
+
+        if fraction == 0 {
+            write!(f, "{}{}", sign, whole)
+        } else {
+            let fraction = format!("{:0width$}", fraction, width = PRICE_DECIMALS as usize);
+            write!(f, "{}{}.{}", sign, whole, fraction.trim_end_matches('0'))
+        }
+    }
+}
+
+#[test]
+fn test_price_from_str() {
+    assert_eq!("100".parse::<Price>().unwrap(), Price::from(100));
+    assert_eq!("100.00".parse::<Price>().unwrap(), Price::from(100));
+    assert_eq!("0.1".parse::<Price>().unwrap(), Price::from_raw(100_000));
+    assert_eq!(".5".parse::<Price>().unwrap(), Price::from_raw(500_000));
+    assert_eq!(
+        "-1.25".parse::<Price>().unwrap(),
+        Price::from_raw(-1_250_000)
+    );
+    assert_eq!(
+        "150.000001".parse::<Price>().unwrap(),
+        Price::from_raw(150_000_001)
+    );
+    assert!("150.0000001".parse::<Price>().is_err());
+    assert!("".parse::<Price>().is_err());
+    assert!(".".parse::<Price>().is_err());
+    assert!("1e5".parse::<Price>().is_err());
+    assert!("NaN".parse::<Price>().is_err());
+    assert!("1.2.3".parse::<Price>().is_err());
+}
+
+#[test]
+fn test_price_to_string() {
+    assert_eq!(Price::from(100).to_string(), "100");
+    assert_eq!(Price::from_raw(100_500_000).to_string(), "100.5");
+    assert_eq!(Price::from_raw(1).to_string(), "0.000001");
+    assert_eq!(Price::from_raw(-1_250_000).to_string(), "-1.25");
+    assert_eq!("0.1".parse::<Price>().unwrap().to_string(), "0.1");
+}
+
+#[test]
+fn test_price_arithmetic_is_exact() {
+    let tenth = "0.1".parse::<Price>().unwrap();
+    let fifth = "0.2".parse::<Price>().unwrap();
+    assert_eq!(tenth + fifth, "0.3".parse::<Price>().unwrap());
+    assert_eq!(fifth - tenth, tenth);
+    assert!(fifth > tenth);
+    assert_eq!(tenth.checked_mul(30), Some(Price::from(3)));
+    assert_eq!(Price::from_raw(i64::MAX).checked_mul(2), None);
+    assert_eq!(fifth.checked_sub(tenth), Some(tenth));
+    assert_eq!(Price::from_raw(i64::MAX).checked_add(tenth), None);
+    assert_eq!(Price::from_raw(i64::MIN).checked_sub(tenth), None);
+    assert_eq!(Price::from_raw(i64::MAX) + tenth, Price::from_raw(i64::MAX));
+    assert_eq!(Price::from(i64::MIN), Price::from_raw(i64::MIN));
+}
+
+#[test]
+fn test_price_is_on_tick() {
+    let tick = "0.05".parse::<Price>().unwrap();
+    assert!("100.05".parse::<Price>().unwrap().is_on_tick(tick));
+    assert!("100".parse::<Price>().unwrap().is_on_tick(tick));
+    assert!(!"100.01".parse::<Price>().unwrap().is_on_tick(tick));
+    assert!(!Price::from(100).is_on_tick(Price::ZERO));
+}